@@ -0,0 +1,116 @@
+use crate::code::AutoIndent;
+use crate::config::NewlineKey;
+use crate::window::{focused_window, FocusedWindow};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+/// Settings that change while typing into one app, see
+/// [`AppSettings`](crate::config::AppSettings).
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppProfile {
+    /// Speed factor, on top of the speed set otherwise.
+    pub speed: Option<f64>,
+    pub newline_key: Option<NewlineKey>,
+    pub auto_indent: Option<AutoIndent>,
+    /// Types every character as written, like
+    /// [`TypingConfig::exact`](crate::config::TypingConfig::exact).
+    pub no_mistakes: bool,
+}
+
+/// Profiles by the text an app's window title or name contains; the first that matches
+/// is used.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AppProfiles(pub Vec<(String, AppProfile)>);
+
+impl AppProfiles {
+    /// The name and profile of the app `window` belongs to, if it has one.
+    pub fn find(&self, window: &FocusedWindow) -> Option<(&str, &AppProfile)> {
+        self.0
+            .iter()
+            .find(|(name, _)| window.matches(name))
+            .map(|(name, profile)| (name.as_str(), profile))
+    }
+}
+
+/// The app profile the typist is to switch to, handed over from whoever watches the
+/// focused window.
+#[derive(Default)]
+pub struct AppSwitch {
+    pending: Mutex<Option<Option<AppProfile>>>,
+}
+
+impl AppSwitch {
+    pub fn new() -> Arc<Self> {
+        Arc::new(AppSwitch::default())
+    }
+
+    /// Makes the typist use `profile` from the next character on, or its own settings
+    /// again for `None`.
+    pub fn switch(&self, profile: Option<AppProfile>) {
+        *self.pending.lock().unwrap() = Some(profile);
+    }
+
+    /// The profile to switch to, if there was a switch since the last call.
+    pub fn take(&self) -> Option<Option<AppProfile>> {
+        self.pending.lock().unwrap().take()
+    }
+}
+
+/// Polls the focused window on a background thread and switches to the profile of the
+/// app it belongs to whenever that changes. Stops when dropped.
+pub struct AppWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl AppWatcher {
+    /// Switches to the profile of `focused` right away, then watches for other apps
+    /// taking the focus. `on_switch` is called with the name of the new profile, if
+    /// any, and the window that brought it.
+    pub fn spawn<F>(
+        profiles: AppProfiles,
+        focused: Option<&FocusedWindow>,
+        interval: Duration,
+        switch: Arc<AppSwitch>,
+        on_switch: F,
+    ) -> Self
+    where
+        F: Fn(Option<&str>, &FocusedWindow) + Send + 'static,
+    {
+        let mut current = None;
+        if let Some(window) = focused {
+            if let Some((name, profile)) = profiles.find(window) {
+                switch.switch(Some(profile.clone()));
+                current = Some(name.to_string());
+                on_switch(Some(name), window);
+            }
+        }
+
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watched = Arc::clone(&stopped);
+        thread::spawn(move || {
+            while !watched.load(Ordering::SeqCst) {
+                thread::sleep(interval);
+                let Some(focused) = focused_window() else {
+                    continue;
+                };
+                let found = profiles.find(&focused);
+                if found.map(|(name, _)| name) == current.as_deref() {
+                    continue;
+                }
+                switch.switch(found.map(|(_, profile)| profile.clone()));
+                on_switch(found.map(|(name, _)| name), &focused);
+                current = found.map(|(name, _)| name.to_string());
+            }
+        });
+
+        AppWatcher { stopped }
+    }
+}
+
+impl Drop for AppWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
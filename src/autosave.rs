@@ -0,0 +1,27 @@
+use crate::events::Chord;
+use crate::timing::Delay;
+use std::time::Duration;
+
+/// Presses a save shortcut every few paragraphs or minutes, the way writers save their
+/// work during a long document.
+#[derive(Clone, Debug)]
+pub struct AutosaveConfig {
+    pub chord: Chord,
+    /// Saves after this many paragraphs.
+    pub every_paragraphs: Option<usize>,
+    /// Saves at the first word break once this much time has passed since the last save.
+    pub every: Option<Duration>,
+    /// Pause after saving, before typing on.
+    pub pause: Delay,
+}
+
+impl Default for AutosaveConfig {
+    fn default() -> Self {
+        AutosaveConfig {
+            chord: Chord::ctrl('s'),
+            every_paragraphs: Some(3),
+            every: None,
+            pause: Delay::Uniform(300..1200),
+        }
+    }
+}
@@ -0,0 +1,764 @@
+use crate::events::{Key, KeyAction};
+use enigo::{Enigo, KeyboardControllable, MouseButton, MouseControllable};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// Sends key events to the system on behalf of a [`HumanTypist`](crate::HumanTypist).
+pub trait KeyboardBackend: Send {
+    /// Types `c` however the backend sees fit, picking the key and modifiers itself.
+    fn send_char(&mut self, c: char) -> Result<(), Box<dyn std::error::Error>>;
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>>;
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>>;
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Moves the mouse pointer to `x`, `y` in screen pixels.
+    fn mouse_move(&mut self, _x: i32, _y: i32) -> Result<(), Box<dyn std::error::Error>> {
+        Err("this keyboard backend cannot use the mouse".into())
+    }
+
+    /// Clicks the left mouse button.
+    fn mouse_click(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Err("this keyboard backend cannot use the mouse".into())
+    }
+
+    /// Turns the mouse wheel by `lines`, down for positive ones.
+    fn mouse_scroll(&mut self, _lines: i32) -> Result<(), Box<dyn std::error::Error>> {
+        Err("this keyboard backend cannot use the mouse".into())
+    }
+
+    /// Moves the mouse pointer by `dx`, `dy` pixels from where it is.
+    fn mouse_nudge(&mut self, _dx: i32, _dy: i32) -> Result<(), Box<dyn std::error::Error>> {
+        Err("this keyboard backend cannot use the mouse".into())
+    }
+}
+
+/// Which [`KeyboardBackend`] to type with.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BackendKind {
+    /// ydotool on Wayland, enigo everywhere else.
+    #[default]
+    Auto,
+    Enigo,
+    /// Runs the `xdotool` command for every key event.
+    Xdotool,
+    /// Runs the `ydotool` command, which writes to `/dev/uinput` and so also works
+    /// on Wayland. Needs `ydotoold` running.
+    Ydotool,
+    /// Windows only: sends hardware scan codes with `SendInput`, for apps and games
+    /// that ignore the Unicode input enigo sends.
+    Scancode,
+}
+
+pub fn create_backend(
+    kind: BackendKind,
+) -> Result<Box<dyn KeyboardBackend>, Box<dyn std::error::Error>> {
+    match kind {
+        BackendKind::Auto
+            if cfg!(target_os = "linux") && env::var_os("WAYLAND_DISPLAY").is_some() =>
+        {
+            Ok(Box::new(YdotoolBackend::new()?))
+        }
+        BackendKind::Auto | BackendKind::Enigo => Ok(Box::new(EnigoBackend::new())),
+        BackendKind::Xdotool => Ok(Box::new(XdotoolBackend::new()?)),
+        BackendKind::Ydotool => Ok(Box::new(YdotoolBackend::new()?)),
+        #[cfg(windows)]
+        BackendKind::Scancode => Ok(Box::new(ScancodeBackend)),
+        #[cfg(not(windows))]
+        BackendKind::Scancode => Err("The scancode backend only works on Windows".into()),
+    }
+}
+
+pub struct EnigoBackend {
+    enigo: Enigo,
+}
+
+impl EnigoBackend {
+    pub fn new() -> Self {
+        EnigoBackend {
+            enigo: Enigo::new(),
+        }
+    }
+}
+
+impl Default for EnigoBackend {
+    fn default() -> Self {
+        EnigoBackend::new()
+    }
+}
+
+impl KeyboardBackend for EnigoBackend {
+    fn send_char(&mut self, c: char) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.key_sequence(&c.to_string());
+        Ok(())
+    }
+
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.key_click(enigo_key(key));
+        Ok(())
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.key_down(enigo_key(key));
+        Ok(())
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.key_up(enigo_key(key));
+        Ok(())
+    }
+
+    fn mouse_move(&mut self, x: i32, y: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.mouse_move_to(x, y);
+        Ok(())
+    }
+
+    fn mouse_click(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.mouse_click(MouseButton::Left);
+        Ok(())
+    }
+
+    fn mouse_scroll(&mut self, lines: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.mouse_scroll_y(lines);
+        Ok(())
+    }
+
+    fn mouse_nudge(&mut self, dx: i32, dy: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.enigo.mouse_move_relative(dx, dy);
+        Ok(())
+    }
+}
+
+fn enigo_key(key: Key) -> enigo::Key {
+    match key {
+        Key::Return => enigo::Key::Return,
+        Key::Backspace => enigo::Key::Backspace,
+        Key::Tab => enigo::Key::Tab,
+        Key::Escape => enigo::Key::Escape,
+        Key::Delete => enigo::Key::Delete,
+        Key::Home => enigo::Key::Home,
+        Key::End => enigo::Key::End,
+        Key::PageUp => enigo::Key::PageUp,
+        Key::PageDown => enigo::Key::PageDown,
+        Key::Left => enigo::Key::LeftArrow,
+        Key::Right => enigo::Key::RightArrow,
+        Key::Up => enigo::Key::UpArrow,
+        Key::Down => enigo::Key::DownArrow,
+        Key::Shift => enigo::Key::Shift,
+        Key::Control => enigo::Key::Control,
+        Key::Alt => enigo::Key::Alt,
+        Key::Meta => enigo::Key::Meta,
+        Key::Char(c) => enigo::Key::Layout(c),
+        // enigo has no keypad keys, but Windows takes their virtual key codes as raw keys
+        Key::Keypad(c) if cfg!(windows) => enigo::Key::Raw(keypad_virtual_key(c)),
+        Key::Keypad(c) => enigo::Key::Layout(c),
+    }
+}
+
+/// The Windows virtual key code of the keypad key that types `c`.
+fn keypad_virtual_key(c: char) -> u16 {
+    match c {
+        '*' => 0x6A,
+        '+' => 0x6B,
+        '-' => 0x6D,
+        '.' => 0x6E,
+        '/' => 0x6F,
+        digit => 0x60 + digit.to_digit(10).unwrap_or(0) as u16,
+    }
+}
+
+/// Records key actions instead of sending them, for tests.
+///
+/// Clones share the recording, so one can be handed to a typist and the other kept to
+/// look at what was typed.
+#[derive(Clone, Default)]
+pub struct MockBackend {
+    actions: Arc<Mutex<Vec<KeyAction>>>,
+}
+
+impl MockBackend {
+    pub fn new() -> Self {
+        MockBackend::default()
+    }
+
+    pub fn actions(&self) -> Vec<KeyAction> {
+        self.actions.lock().unwrap().clone()
+    }
+
+    /// The text the recorded actions would leave in a plain text editor.
+    pub fn typed(&self) -> String {
+        let mut text = String::new();
+        let mut held = Vec::new();
+        // Start of the text selected with Shift+Home, replaced by whatever comes next
+        let mut selection: Option<usize> = None;
+        for action in self.actions() {
+            let key = match action {
+                KeyAction::Type { char } => {
+                    if let Some(start) = selection.take() {
+                        text.truncate(start);
+                    }
+                    text.push(char);
+                    continue;
+                }
+                KeyAction::Up { key } => {
+                    held.retain(|&k| k != key);
+                    continue;
+                }
+                KeyAction::Down { key } => {
+                    held.push(key);
+                    key
+                }
+                KeyAction::Click { key } => key,
+                // A click could put the cursor anywhere, so it is taken to stay put
+                KeyAction::MouseMove { .. }
+                | KeyAction::MouseClick
+                | KeyAction::MouseScroll { .. }
+                | KeyAction::MouseNudge { .. } => continue,
+            };
+            if matches!(key, Key::Shift | Key::Control | Key::Alt | Key::Meta) {
+                continue;
+            }
+            // Shortcuts like Ctrl+C type nothing
+            if matches!(key, Key::Char(_)) && held.contains(&Key::Control) {
+                continue;
+            }
+            // Moving the cursor drops the selection
+            if key.moves_cursor() && !held.contains(&Key::Shift) {
+                selection = None;
+                continue;
+            }
+            if let Some(start) = selection.take() {
+                text.truncate(start);
+                if matches!(key, Key::Backspace | Key::Delete) {
+                    continue;
+                }
+            }
+            // Keys type when they go down
+            match key {
+                Key::Return => text.push('\n'),
+                Key::Tab => text.push('\t'),
+                Key::Home if held.contains(&Key::Shift) => {
+                    selection = Some(text.rfind('\n').map_or(0, |i| i + 1));
+                }
+                Key::End if held.contains(&Key::Shift) => selection = Some(text.len()),
+                // The cursor stays at the end of the text, with nothing after it to
+                // delete, and Escape leaves the text alone
+                Key::Home
+                | Key::End
+                | Key::PageUp
+                | Key::PageDown
+                | Key::Left
+                | Key::Right
+                | Key::Up
+                | Key::Down
+                | Key::Delete
+                | Key::Escape => {}
+                Key::Backspace if held.contains(&Key::Control) => {
+                    // Whitespace before the cursor goes along with the word
+                    while text.ends_with(char::is_whitespace) {
+                        text.pop();
+                    }
+                    while text.ends_with(|c: char| !c.is_whitespace()) {
+                        text.pop();
+                    }
+                }
+                Key::Backspace => {
+                    text.pop();
+                }
+                Key::Char(c) if held.contains(&Key::Shift) => text.extend(c.to_uppercase()),
+                Key::Char(c) | Key::Keypad(c) => text.push(c),
+                Key::Shift | Key::Control | Key::Alt | Key::Meta => {}
+            }
+        }
+        text
+    }
+
+    fn record(&self, action: KeyAction) -> Result<(), Box<dyn std::error::Error>> {
+        self.actions.lock().unwrap().push(action);
+        Ok(())
+    }
+}
+
+impl KeyboardBackend for MockBackend {
+    fn send_char(&mut self, char: char) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::Type { char })
+    }
+
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::Click { key })
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::Down { key })
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::Up { key })
+    }
+
+    fn mouse_move(&mut self, x: i32, y: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::MouseMove { x, y })
+    }
+
+    fn mouse_click(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::MouseClick)
+    }
+
+    fn mouse_scroll(&mut self, lines: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::MouseScroll { lines })
+    }
+
+    fn mouse_nudge(&mut self, dx: i32, dy: i32) -> Result<(), Box<dyn std::error::Error>> {
+        self.record(KeyAction::MouseNudge { dx, dy })
+    }
+}
+
+/// Types through the `xdotool` command line tool, for X servers where enigo's input
+/// is not picked up.
+pub struct XdotoolBackend;
+
+impl XdotoolBackend {
+    /// Fails if `xdotool` is not installed.
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        run("xdotool", &["version"])?;
+        Ok(XdotoolBackend)
+    }
+}
+
+impl KeyboardBackend for XdotoolBackend {
+    fn send_char(&mut self, c: char) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["type", "--delay", "0", "--", &c.to_string()])
+    }
+
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["key", "--", &xdotool_key(key)])
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["keydown", "--", &xdotool_key(key)])
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["keyup", "--", &xdotool_key(key)])
+    }
+
+    fn mouse_move(&mut self, x: i32, y: i32) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["mousemove", &x.to_string(), &y.to_string()])
+    }
+
+    fn mouse_click(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        run("xdotool", &["click", "1"])
+    }
+
+    fn mouse_scroll(&mut self, lines: i32) -> Result<(), Box<dyn std::error::Error>> {
+        // Buttons 4 and 5 are the wheel up and down
+        let button = if lines < 0 { "4" } else { "5" };
+        let repeat = lines.unsigned_abs().to_string();
+        run("xdotool", &["click", "--repeat", &repeat, button])
+    }
+
+    fn mouse_nudge(&mut self, dx: i32, dy: i32) -> Result<(), Box<dyn std::error::Error>> {
+        let (dx, dy) = (dx.to_string(), dy.to_string());
+        run("xdotool", &["mousemove_relative", "--", &dx, &dy])
+    }
+}
+
+fn run(program: &str, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let output = Command::new(program)
+        .args(args)
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{} {} failed: {}",
+            program,
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into())
+    }
+}
+
+/// The X keysym name of `key`.
+pub(crate) fn xdotool_key(key: Key) -> String {
+    match key {
+        Key::Return => "Return".to_string(),
+        Key::Backspace => "BackSpace".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::Escape => "Escape".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "Prior".to_string(),
+        Key::PageDown => "Next".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Shift => "shift".to_string(),
+        Key::Control => "ctrl".to_string(),
+        Key::Alt => "alt".to_string(),
+        Key::Meta => "super".to_string(),
+        Key::Keypad(c) => match c {
+            '*' => "KP_Multiply".to_string(),
+            '+' => "KP_Add".to_string(),
+            '-' => "KP_Subtract".to_string(),
+            '.' => "KP_Decimal".to_string(),
+            '/' => "KP_Divide".to_string(),
+            digit => format!("KP_{}", digit),
+        },
+        Key::Char(c) if c.is_ascii_alphanumeric() => c.to_string(),
+        // Any other character by its Unicode keysym
+        Key::Char(c) => format!("U{:04X}", c as u32),
+    }
+}
+
+/// Types through `ydotool`, which injects input below the display server and so works
+/// on Wayland. Keys are pressed by their US layout position.
+pub struct YdotoolBackend;
+
+impl YdotoolBackend {
+    /// Fails if `ydotool` is not installed.
+    pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
+        Command::new("ydotool")
+            .arg("help")
+            .output()
+            .map_err(|e| format!("Failed to run ydotool: {}", e))?;
+        Ok(YdotoolBackend)
+    }
+
+    fn keys(&self, events: &[(Key, bool)]) -> Result<(), Box<dyn std::error::Error>> {
+        let mut args = vec!["key".to_string()];
+        for &(key, down) in events {
+            args.push(format!("{}:{}", evdev_code(key)?, u8::from(down)));
+        }
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        run("ydotool", &args)
+    }
+}
+
+impl KeyboardBackend for YdotoolBackend {
+    fn send_char(&mut self, c: char) -> Result<(), Box<dyn std::error::Error>> {
+        run(
+            "ydotool",
+            &["type", "--key-delay", "0", "--", &c.to_string()],
+        )
+    }
+
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.keys(&[(key, true), (key, false)])
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.keys(&[(key, true)])
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        self.keys(&[(key, false)])
+    }
+
+    fn mouse_move(&mut self, x: i32, y: i32) -> Result<(), Box<dyn std::error::Error>> {
+        run(
+            "ydotool",
+            &[
+                "mousemove",
+                "--absolute",
+                "-x",
+                &x.to_string(),
+                "-y",
+                &y.to_string(),
+            ],
+        )
+    }
+
+    fn mouse_click(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Left button down and up
+        run("ydotool", &["click", "0xC0"])
+    }
+
+    fn mouse_scroll(&mut self, lines: i32) -> Result<(), Box<dyn std::error::Error>> {
+        // The wheel goes down for negative values
+        let lines = (-lines).to_string();
+        run(
+            "ydotool",
+            &["mousemove", "--wheel", "-x", "0", "-y", &lines],
+        )
+    }
+
+    fn mouse_nudge(&mut self, dx: i32, dy: i32) -> Result<(), Box<dyn std::error::Error>> {
+        // Moves are relative without --absolute
+        let (dx, dy) = (dx.to_string(), dy.to_string());
+        run("ydotool", &["mousemove", "-x", &dx, "-y", &dy])
+    }
+}
+
+/// The Linux input event code of the key that types `key` on a US layout.
+fn evdev_code(key: Key) -> Result<u16, String> {
+    let code = match key {
+        Key::Return => 28,
+        Key::Backspace => 14,
+        Key::Tab => 15,
+        Key::Escape => 1,
+        Key::Delete => 111,
+        Key::Home => 102,
+        Key::End => 107,
+        Key::PageUp => 104,
+        Key::PageDown => 109,
+        Key::Left => 105,
+        Key::Right => 106,
+        Key::Up => 103,
+        Key::Down => 108,
+        Key::Shift => 42,
+        Key::Control => 29,
+        Key::Alt => 56,
+        Key::Meta => 125,
+        Key::Keypad(c) => match c {
+            '7' => 71,
+            '8' => 72,
+            '9' => 73,
+            '-' => 74,
+            '4' => 75,
+            '5' => 76,
+            '6' => 77,
+            '+' => 78,
+            '1' => 79,
+            '2' => 80,
+            '3' => 81,
+            '0' => 82,
+            '.' => 83,
+            '/' => 98,
+            '*' => 55,
+            _ => return Err(format!("ydotool has no keypad key for {:?}", c)),
+        },
+        Key::Char(c) => {
+            const ROWS: [(&str, u16); 4] = [
+                ("1234567890-=", 2),
+                ("qwertyuiop[]", 16),
+                ("asdfghjkl;'`", 30),
+                ("zxcvbnm,./", 44),
+            ];
+            let lower = c.to_ascii_lowercase();
+            match lower {
+                ' ' => 57,
+                '\\' => 43,
+                _ => ROWS
+                    .iter()
+                    .find_map(|(row, first)| {
+                        row.chars()
+                            .position(|k| k == lower)
+                            .map(|i| first + i as u16)
+                    })
+                    .ok_or_else(|| format!("ydotool has no key for {:?}", c))?,
+            }
+        }
+    };
+    Ok(code)
+}
+
+/// Types with `SendInput` scan codes, the way a physical keyboard would, holding Shift
+/// and friends for characters that need them. Characters that have no key on the
+/// current layout fall back to Unicode input.
+#[cfg(windows)]
+pub struct ScancodeBackend;
+
+#[cfg(windows)]
+impl ScancodeBackend {
+    fn send(&self, inputs: &[KeyInput]) -> Result<(), Box<dyn std::error::Error>> {
+        use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+            SendInput, INPUT, INPUT_0, INPUT_KEYBOARD, KEYBDINPUT, KEYEVENTF_EXTENDEDKEY,
+            KEYEVENTF_KEYUP, KEYEVENTF_SCANCODE, KEYEVENTF_UNICODE, VK_DELETE, VK_DOWN, VK_END,
+            VK_HOME, VK_LEFT, VK_LWIN, VK_NEXT, VK_PRIOR, VK_RIGHT, VK_UP,
+        };
+
+        let inputs: Vec<INPUT> = inputs
+            .iter()
+            .map(|input| {
+                let (scan, mut flags) = match input.key {
+                    WindowsKey::Virtual(vk) => {
+                        // Keys of the navigation block share scan codes with the keypad
+                        let extended = [
+                            VK_LWIN, VK_DELETE, VK_HOME, VK_END, VK_PRIOR, VK_NEXT, VK_LEFT,
+                            VK_RIGHT, VK_UP, VK_DOWN,
+                        ];
+                        let flags = if extended.contains(&vk) {
+                            KEYEVENTF_SCANCODE | KEYEVENTF_EXTENDEDKEY
+                        } else {
+                            KEYEVENTF_SCANCODE
+                        };
+                        (scan_code(vk), flags)
+                    }
+                    WindowsKey::Unicode(unit) => (unit, KEYEVENTF_UNICODE),
+                };
+                if !input.down {
+                    flags |= KEYEVENTF_KEYUP;
+                }
+                INPUT {
+                    r#type: INPUT_KEYBOARD,
+                    Anonymous: INPUT_0 {
+                        ki: KEYBDINPUT {
+                            wVk: 0,
+                            wScan: scan,
+                            dwFlags: flags,
+                            time: 0,
+                            dwExtraInfo: 0,
+                        },
+                    },
+                }
+            })
+            .collect();
+
+        // SAFETY: `inputs` is a valid slice of initialized INPUT structs
+        let sent = unsafe {
+            SendInput(
+                inputs.len() as u32,
+                inputs.as_ptr(),
+                std::mem::size_of::<INPUT>() as i32,
+            )
+        };
+        if sent as usize == inputs.len() {
+            Ok(())
+        } else {
+            Err("SendInput was blocked by another application".into())
+        }
+    }
+}
+
+#[cfg(windows)]
+impl KeyboardBackend for ScancodeBackend {
+    fn send_char(&mut self, c: char) -> Result<(), Box<dyn std::error::Error>> {
+        if c == '\n' {
+            return self.key_click(Key::Return);
+        }
+
+        let Some((vk, modifiers)) = key_for_char(c) else {
+            let mut inputs = Vec::new();
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                let key = WindowsKey::Unicode(*unit);
+                inputs.push(KeyInput { key, down: true });
+                inputs.push(KeyInput { key, down: false });
+            }
+            return self.send(&inputs);
+        };
+
+        let modifiers: Vec<WindowsKey> = modifiers.into_iter().map(WindowsKey::Virtual).collect();
+        let mut inputs: Vec<KeyInput> = modifiers
+            .iter()
+            .map(|&key| KeyInput { key, down: true })
+            .collect();
+        inputs.push(KeyInput {
+            key: WindowsKey::Virtual(vk),
+            down: true,
+        });
+        inputs.push(KeyInput {
+            key: WindowsKey::Virtual(vk),
+            down: false,
+        });
+        inputs.extend(
+            modifiers
+                .iter()
+                .rev()
+                .map(|&key| KeyInput { key, down: false }),
+        );
+        self.send(&inputs)
+    }
+
+    fn key_click(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        let key = windows_key(key)?;
+        self.send(&[KeyInput { key, down: true }, KeyInput { key, down: false }])
+    }
+
+    fn key_down(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        let key = windows_key(key)?;
+        self.send(&[KeyInput { key, down: true }])
+    }
+
+    fn key_up(&mut self, key: Key) -> Result<(), Box<dyn std::error::Error>> {
+        let key = windows_key(key)?;
+        self.send(&[KeyInput { key, down: false }])
+    }
+}
+
+#[cfg(windows)]
+#[derive(Clone, Copy)]
+enum WindowsKey {
+    /// A virtual key code, sent as its scan code.
+    Virtual(u16),
+    /// A UTF-16 code unit, for characters without a key.
+    Unicode(u16),
+}
+
+#[cfg(windows)]
+struct KeyInput {
+    key: WindowsKey,
+    down: bool,
+}
+
+#[cfg(windows)]
+fn windows_key(key: Key) -> Result<WindowsKey, String> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        VK_BACK, VK_CONTROL, VK_DELETE, VK_DOWN, VK_END, VK_ESCAPE, VK_HOME, VK_LEFT, VK_LWIN,
+        VK_MENU, VK_NEXT, VK_PRIOR, VK_RETURN, VK_RIGHT, VK_SHIFT, VK_TAB, VK_UP,
+    };
+
+    let vk = match key {
+        Key::Return => VK_RETURN,
+        Key::Backspace => VK_BACK,
+        Key::Tab => VK_TAB,
+        Key::Escape => VK_ESCAPE,
+        Key::Delete => VK_DELETE,
+        Key::Home => VK_HOME,
+        Key::End => VK_END,
+        Key::PageUp => VK_PRIOR,
+        Key::PageDown => VK_NEXT,
+        Key::Left => VK_LEFT,
+        Key::Right => VK_RIGHT,
+        Key::Up => VK_UP,
+        Key::Down => VK_DOWN,
+        Key::Shift => VK_SHIFT,
+        Key::Control => VK_CONTROL,
+        Key::Alt => VK_MENU,
+        Key::Meta => VK_LWIN,
+        Key::Keypad(c) => keypad_virtual_key(c),
+        Key::Char(c) => {
+            key_for_char(c)
+                .ok_or_else(|| format!("No key for {:?} on this keyboard layout", c))?
+                .0
+        }
+    };
+    Ok(WindowsKey::Virtual(vk))
+}
+
+/// The virtual key that types `c` on the current layout and the modifiers it needs.
+#[cfg(windows)]
+fn key_for_char(c: char) -> Option<(u16, Vec<u16>)> {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{
+        VkKeyScanW, VK_CONTROL, VK_MENU, VK_SHIFT,
+    };
+
+    let unit = u16::try_from(u32::from(c)).ok()?;
+    // SAFETY: VkKeyScanW only reads its argument
+    let scan = unsafe { VkKeyScanW(unit) };
+    if scan == -1 {
+        return None;
+    }
+
+    let [vk, state] = (scan as u16).to_le_bytes();
+    let modifiers = [(1, VK_SHIFT), (2, VK_CONTROL), (4, VK_MENU)]
+        .into_iter()
+        .filter(|(bit, _)| state & bit != 0)
+        .map(|(_, modifier)| modifier)
+        .collect();
+    Some((u16::from(vk), modifiers))
+}
+
+#[cfg(windows)]
+fn scan_code(vk: u16) -> u16 {
+    use windows_sys::Win32::UI::Input::KeyboardAndMouse::{MapVirtualKeyW, MAPVK_VK_TO_VSC};
+
+    // SAFETY: MapVirtualKeyW only reads its arguments
+    unsafe { MapVirtualKeyW(u32::from(vk), MAPVK_VK_TO_VSC) as u16 }
+}
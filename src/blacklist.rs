@@ -0,0 +1,111 @@
+use crate::hotkeys::TypingControls;
+use crate::window::{focused_window, FocusedWindow};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Windows typer refuses to type into, see
+/// [`BlacklistSettings`](crate::config::BlacklistSettings).
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Blacklist {
+    /// Text in the title or app name of the windows not to type into.
+    pub apps: Vec<String>,
+    /// Also refuses while the system says a password is being entered.
+    pub secure_input: bool,
+}
+
+impl Blacklist {
+    /// Why typing into `window` is refused, if it is.
+    pub fn check(&self, window: Option<&FocusedWindow>) -> Option<String> {
+        if self.secure_input && secure_input_enabled() {
+            return Some("a password field has the focus".to_string());
+        }
+        let window = window?;
+        self.apps
+            .iter()
+            .find(|app| window.matches(app))
+            .map(|app| format!("{} is blacklisted (\"{}\")", window.describe(), app))
+    }
+}
+
+/// Whether the system says a password is being entered. macOS tells when an app keeps
+/// keystrokes away from the others for one; on Windows the focused edit control has the
+/// password style, which browsers and most newer apps do not use for theirs. Other
+/// systems never tell.
+#[cfg(target_os = "macos")]
+pub fn secure_input_enabled() -> bool {
+    #[link(name = "Carbon", kind = "framework")]
+    extern "C" {
+        fn IsSecureEventInputEnabled() -> u8;
+    }
+    unsafe { IsSecureEventInputEnabled() != 0 }
+}
+
+#[cfg(windows)]
+pub fn secure_input_enabled() -> bool {
+    use windows_sys::Win32::UI::WindowsAndMessaging::{
+        GetClassNameW, GetGUIThreadInfo, GetWindowLongW, ES_PASSWORD, GUITHREADINFO, GWL_STYLE,
+    };
+
+    let mut info: GUITHREADINFO = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<GUITHREADINFO>() as u32;
+    if unsafe { GetGUIThreadInfo(0, &mut info) } == 0 || info.hwndFocus.is_null() {
+        return false;
+    }
+    let mut class = [0u16; 64];
+    let len = unsafe { GetClassNameW(info.hwndFocus, class.as_mut_ptr(), class.len() as i32) };
+    let class = String::from_utf16_lossy(&class[..len.max(0) as usize]).to_lowercase();
+    // Edit, RichEdit and WinForms text boxes; the style bit means something else for
+    // other controls
+    if !class.contains("edit") {
+        return false;
+    }
+    unsafe { GetWindowLongW(info.hwndFocus, GWL_STYLE) & ES_PASSWORD != 0 }
+}
+
+#[cfg(not(any(target_os = "macos", windows)))]
+pub fn secure_input_enabled() -> bool {
+    false
+}
+
+/// Polls the focused window on a background thread and aborts typing as soon as the
+/// blacklist refuses it. Stops when dropped.
+pub struct BlacklistWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl BlacklistWatcher {
+    /// Calls `on_block` with the reason once typing has been aborted.
+    pub fn spawn<F>(
+        blacklist: Blacklist,
+        interval: Duration,
+        controls: Arc<TypingControls>,
+        on_block: F,
+    ) -> Self
+    where
+        F: Fn(String) + Send + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watched = Arc::clone(&stopped);
+        thread::spawn(move || loop {
+            thread::sleep(interval);
+            if watched.load(Ordering::SeqCst) || controls.is_aborted() {
+                return;
+            }
+            if let Some(reason) = blacklist.check(focused_window().as_ref()) {
+                controls.abort();
+                on_block(reason);
+                return;
+            }
+        });
+
+        BlacklistWatcher { stopped }
+    }
+}
+
+impl Drop for BlacklistWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,29 @@
+use crate::timing::Delay;
+use std::time::Duration;
+
+/// Takes a break of a few minutes at the end of a paragraph once `every` has passed
+/// since the last one, for long texts typed over hours.
+#[derive(Clone, Debug)]
+pub struct BreakConfig {
+    pub every: Duration,
+    /// How long each break lasts, in milliseconds.
+    pub duration: Delay,
+}
+
+impl Default for BreakConfig {
+    fn default() -> Self {
+        BreakConfig {
+            every: Duration::from_secs(50 * 60),
+            duration: Delay::Uniform(5 * 60_000..10 * 60_000),
+        }
+    }
+}
+
+/// Start or end of a break, reported through
+/// [`HumanTypistBuilder::on_break`](crate::HumanTypistBuilder::on_break).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BreakEvent {
+    /// A break this long starts. Progress up to it has been reported already.
+    Started(Duration),
+    Ended,
+}
@@ -0,0 +1,217 @@
+use serde_json::json;
+use std::time::Duration;
+
+/// Gaps between keystrokes at least this long count as thinking pauses.
+const THINKING_THRESHOLD_MS: u64 = 1000;
+const MIN_INTERVALS: usize = 20;
+
+/// A key pressed during a typing test.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Keystroke {
+    Char(char),
+    Backspace,
+}
+
+/// Typing habits measured from a typing test.
+#[derive(Clone, Debug)]
+pub struct Calibration {
+    pub characters: usize,
+    pub keystroke: DelayFit,
+    pub thinking: Option<DelayFit>,
+    pub mistakes: usize,
+    pub corrections: usize,
+    /// Pause before the first Backspace of a correction.
+    pub correction_delay: Option<DelayFit>,
+    /// Characters typed after a mistake before going back to fix it.
+    pub correction_lag: Option<(usize, usize)>,
+}
+
+/// A delay distribution fitted to measured delays, in milliseconds.
+#[derive(Clone, Debug)]
+pub struct DelayFit {
+    pub mean: f64,
+    pub std_dev: f64,
+    /// 5th percentile.
+    pub min: u64,
+    /// 95th percentile, always above `min`.
+    pub max: u64,
+}
+
+impl DelayFit {
+    /// Fits `delays`, which get sorted, or returns `None` if there are none.
+    pub fn new(delays: &mut [u64]) -> Option<DelayFit> {
+        if delays.is_empty() {
+            return None;
+        }
+        delays.sort_unstable();
+
+        let n = delays.len() as f64;
+        let mean = delays.iter().sum::<u64>() as f64 / n;
+        let variance = delays
+            .iter()
+            .map(|&d| (d as f64 - mean).powi(2))
+            .sum::<f64>()
+            / n;
+        let min = percentile(delays, 0.05);
+        let max = percentile(delays, 0.95).max(min + 1);
+        Some(DelayFit {
+            mean,
+            std_dev: variance.sqrt(),
+            min,
+            max,
+        })
+    }
+}
+
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    let index = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[index]
+}
+
+impl Calibration {
+    /// Measures `keystrokes`, pressed at the given times since the test started, while
+    /// typing `target`.
+    pub fn fit(target: &str, keystrokes: &[(Duration, Keystroke)]) -> Result<Calibration, String> {
+        let target: Vec<char> = target.chars().collect();
+        let mut typed: Vec<char> = Vec::new();
+        let mut keystroke_delays = Vec::new();
+        let mut thinking_delays = Vec::new();
+        let mut correction_delays = Vec::new();
+        let mut lags = Vec::new();
+        let (mut mistakes, mut corrections) = (0, 0);
+        // Characters typed since the first wrong one, while it is still on screen
+        let mut since_mistake: Option<usize> = None;
+        let mut erasing = false;
+
+        for (index, &(at, keystroke)) in keystrokes.iter().enumerate() {
+            let delay = index
+                .checked_sub(1)
+                .map(|previous| at.saturating_sub(keystrokes[previous].0).as_millis() as u64);
+
+            match keystroke {
+                Keystroke::Char(c) => {
+                    if let (Some(delay), Some((_, Keystroke::Char(_)))) =
+                        (delay, index.checked_sub(1).map(|i| keystrokes[i]))
+                    {
+                        if delay >= THINKING_THRESHOLD_MS {
+                            thinking_delays.push(delay);
+                        } else {
+                            keystroke_delays.push(delay);
+                        }
+                    }
+
+                    match &mut since_mistake {
+                        Some(count) => *count += 1,
+                        None if target.get(typed.len()) != Some(&c) => {
+                            mistakes += 1;
+                            since_mistake = Some(0);
+                        }
+                        None => {}
+                    }
+                    typed.push(c);
+                    erasing = false;
+                }
+                Keystroke::Backspace => {
+                    if let (Some(count), false) = (since_mistake, erasing) {
+                        correction_delays.extend(delay);
+                        lags.push(count);
+                        erasing = true;
+                    }
+                    typed.pop();
+                    if since_mistake.is_some() && target.starts_with(&typed) {
+                        corrections += 1;
+                        since_mistake = None;
+                        erasing = false;
+                    }
+                }
+            }
+        }
+
+        if keystroke_delays.len() < MIN_INTERVALS {
+            return Err(format!(
+                "Not enough keystrokes to calibrate, type at least {} characters",
+                MIN_INTERVALS + 1
+            ));
+        }
+
+        Ok(Calibration {
+            characters: typed.len(),
+            keystroke: DelayFit::new(&mut keystroke_delays).unwrap(),
+            thinking: DelayFit::new(&mut thinking_delays),
+            mistakes,
+            corrections,
+            correction_delay: DelayFit::new(&mut correction_delays),
+            correction_lag: lags
+                .iter()
+                .min()
+                .zip(lags.iter().max())
+                .map(|(&min, &max)| (min, max)),
+        })
+    }
+
+    /// Average typing speed, counting five characters as a word.
+    pub fn words_per_minute(&self) -> f64 {
+        60_000.0 / (self.keystroke.mean * 5.0)
+    }
+
+    /// The fitted settings, in the shape of a config profile.
+    pub fn to_profile(&self) -> serde_json::Value {
+        let keystroke = &self.keystroke;
+        let mut delays = json!({
+            "distribution": "log_normal",
+            "base_min": keystroke.min,
+            "base_max": keystroke.max,
+            "base_mean": keystroke.mean.round(),
+            "base_std_dev": keystroke.std_dev.round(),
+        });
+        if let Some(thinking) = &self.thinking {
+            delays["thinking_min"] = json!(thinking.min);
+            delays["thinking_max"] = json!(thinking.max);
+            delays["thinking_mean"] = json!(thinking.mean.round());
+            delays["thinking_std_dev"] = json!(thinking.std_dev.round());
+        }
+
+        // One mistake in this many characters
+        let probability = (self.characters as f64 / self.mistakes.max(1) as f64)
+            .round()
+            .max(1.0);
+        let mut mistakes = json!({ "probability": probability as u64 });
+        if self.mistakes > 0 {
+            let corrected = self.corrections as f64 / self.mistakes as f64 * 100.0;
+            mistakes["correction_probability"] = json!(corrected.round() as u64);
+        }
+        if let Some(delay) = &self.correction_delay {
+            mistakes["correction_delay_min"] = json!(delay.min);
+            mistakes["correction_delay_max"] = json!(delay.max);
+        }
+        if let Some((min, max)) = self.correction_lag {
+            mistakes["correction_lag_chars_min"] = json!(min);
+            mistakes["correction_lag_chars_max"] = json!(max);
+        }
+
+        json!({ "delays": delays, "mistakes": mistakes })
+    }
+
+    pub fn print(&self) {
+        println!("Calibration:");
+        println!("  Characters:      {}", self.characters);
+        println!("  Speed:           {:.0} WPM", self.words_per_minute());
+        println!(
+            "  Keystroke delay: {:.0} ms on average (±{:.0}, mostly {}-{} ms)",
+            self.keystroke.mean, self.keystroke.std_dev, self.keystroke.min, self.keystroke.max
+        );
+        if let Some(thinking) = &self.thinking {
+            println!("  Thinking pauses: {:.0} ms on average", thinking.mean);
+        }
+        println!(
+            "  Mistakes:        {} ({} corrected)",
+            self.mistakes, self.corrections
+        );
+        if let Some(delay) = &self.correction_delay {
+            println!(
+                "  Noticing:        {:.0} ms before erasing on average",
+                delay.mean
+            );
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use crate::timing::Delay;
+
+/// Types blank-line separated blocks as separate chat messages: each one is sent with
+/// Enter and followed by a pause before the next, while the newlines inside a message
+/// are typed with Shift+Enter so they do not send it early.
+#[derive(Clone, Debug)]
+pub struct ChatConfig {
+    /// Pause after sending a message, before starting the next one.
+    pub message_delay: Delay,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        ChatConfig {
+            message_delay: Delay::Uniform(2000..8000),
+        }
+    }
+}
+
+/// Where the next message starts if the newline at `i` ends one: when only whitespace
+/// follows up to a blank line or the end of the text.
+pub fn next_message(chars: &[char], i: usize) -> Option<usize> {
+    let gap = chars[i + 1..]
+        .iter()
+        .take_while(|c| c.is_whitespace())
+        .count();
+    let end = i + 1 + gap;
+    if end == chars.len() {
+        return Some(end);
+    }
+    chars[i + 1..end]
+        .iter()
+        .rposition(|&c| c == '\n')
+        .map(|last| i + 2 + last)
+}
+
+/// Whether the last message of `chars` still needs to be sent once it is typed, that is
+/// whether the text does not already end with a newline the message was sent with.
+pub fn ends_unsent(chars: &[char]) -> bool {
+    let trailing = chars.iter().rev().take_while(|c| c.is_whitespace());
+    chars.iter().any(|c| !c.is_whitespace()) && !trailing.clone().any(|&c| c == '\n')
+}
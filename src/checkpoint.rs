@@ -0,0 +1,73 @@
+use crate::paths;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Progress of an interrupted run, saved so `typer resume` can pick it up again.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Checkpoint {
+    /// The texts of the run along with where each came from.
+    pub texts: Vec<(String, String)>,
+    /// Index of the text being typed.
+    pub current: usize,
+    /// Number of characters of the current text already typed correctly.
+    pub position: usize,
+    /// Number of wrong characters after `position` that must be erased first.
+    pub erase: usize,
+    /// Config file, profile and `--set` overrides the run was started with.
+    pub config: PathBuf,
+    pub profile: Option<String>,
+    pub overrides: Vec<(String, String)>,
+}
+
+impl Checkpoint {
+    pub fn new(
+        texts: Vec<(String, String)>,
+        config: PathBuf,
+        profile: Option<String>,
+        overrides: Vec<(String, String)>,
+    ) -> Self {
+        Checkpoint {
+            texts,
+            current: 0,
+            position: 0,
+            erase: 0,
+            config,
+            profile,
+            overrides,
+        }
+    }
+
+    /// Loads the checkpoint at `path`, or `None` when there is no checkpoint.
+    pub fn load(path: &Path) -> Result<Option<Checkpoint>, Box<dyn std::error::Error>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(path)?;
+        let checkpoint = serde_json::from_str(&content)
+            .map_err(|e| format!("Invalid checkpoint {}: {}", path.display(), e))?;
+        Ok(Some(checkpoint))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write to a temporary file first so a crash never leaves half a checkpoint
+        let temp_path = path.with_extension("tmp");
+        fs::write(&temp_path, serde_json::to_string(self)?)?;
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    }
+
+    pub fn remove(path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        if path.exists() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn get_checkpoint_path() -> PathBuf {
+    paths::data_dir().join("checkpoint.json")
+}
@@ -0,0 +1,293 @@
+use super::load_run_config;
+use super::session::SnippetSession;
+use crate::SnippetTypingArgs;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use typer::config::get_config_path;
+#[cfg(feature = "midi")]
+use typer::config::load_config;
+use typer::daemon::{self, DaemonEvent, DaemonState, DaemonStatus, Events, Request, Response};
+use typer::directives::strip_directives;
+use typer::http;
+use typer::snippets::SnippetLibrary;
+use typer::text::InlineSource;
+use typer::triggers::{StreamDeckPlugin, TriggerAction};
+use typer::websocket;
+use typer::TypingControls;
+
+/// The text loaded into `typer daemon` and the run typing it.
+pub struct DaemonRun {
+    text: Option<(String, String)>,
+    /// The `--set` overrides and those set since, for the next run.
+    overrides: Vec<(String, String)>,
+    controls: Option<Arc<TypingControls>>,
+    status: DaemonStatus,
+    events: Events,
+}
+
+impl DaemonRun {
+    /// The status, with the pause state of the run, which the pause hotkey changes too.
+    fn status(&self) -> DaemonStatus {
+        let mut status = self.status.clone();
+        if let (DaemonState::Typing | DaemonState::Paused, Some(controls)) =
+            (status.state, &self.controls)
+        {
+            status.state = match controls.is_paused() {
+                true => DaemonState::Paused,
+                false => DaemonState::Typing,
+            };
+        }
+        status
+    }
+}
+
+/// Answers `typer ctl`, and the REST API when serving one, until killed, typing one
+/// loaded text at a time.
+pub fn run(
+    mut typing: SnippetTypingArgs,
+    config_path: Option<PathBuf>,
+    serve: Option<String>,
+    websocket: Option<String>,
+    token: Option<String>,
+    allow_origins: Vec<String>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+    // Whoever sends `start` has already picked the window to type into
+    typing.countdown.get_or_insert(0);
+    let vars = typing.vars.to_vars()?;
+    let events = Events::default();
+    let run = Arc::new(Mutex::new(DaemonRun {
+        text: None,
+        overrides: typing.overrides.clone(),
+        controls: None,
+        status: DaemonStatus {
+            speed: 1.0,
+            ..DaemonStatus::default()
+        },
+        events: events.clone(),
+    }));
+
+    let handle = Arc::new(move |request| {
+        daemon_request(&run, &config_path, &typing, &vars, request).unwrap_or_else(Response::error)
+    });
+
+    // No one else on the computer, nor a web page, may type through the daemon
+    let token = match token {
+        Some(token) => token,
+        None if serve.is_some() || websocket.is_some() => {
+            let token = http::generate_token();
+            println!("Token for the REST API and WebSocket: {}", token);
+            token
+        }
+        None => String::new(),
+    };
+    if let Some(addr) = &serve {
+        let http_handle = Arc::clone(&handle);
+        http::spawn(addr, token.clone(), move |request| http_handle(request))?;
+        println!("REST API at http://{}", addr);
+    }
+    if let Some(addr) = &websocket {
+        let websocket_handle = Arc::clone(&handle);
+        websocket::spawn(addr, token, allow_origins, events, move |request| {
+            websocket_handle(request)
+        })?;
+        println!("WebSocket at ws://{}", addr);
+    }
+    let path = daemon::socket_path();
+    println!("typer daemon listening at {}", path.display());
+    daemon::serve(&path, move |request| handle(request))
+}
+
+pub fn daemon_request(
+    daemon: &Arc<Mutex<DaemonRun>>,
+    config_path: &Path,
+    typing: &SnippetTypingArgs,
+    vars: &Option<HashMap<String, String>>,
+    request: Request,
+) -> Result<Response, String> {
+    let mut run = daemon.lock().unwrap();
+    let state = run.status().state;
+    let changes = request != Request::Status;
+    match request {
+        Request::Load { .. } | Request::Start if state != DaemonState::Idle => {
+            return Err("Still typing; abort first".to_string())
+        }
+        Request::Pause | Request::Resume | Request::Abort if state == DaemonState::Idle => {
+            return Err("Not typing".to_string())
+        }
+        Request::Load { text, origin } => {
+            run.status.total = strip_directives(&text).chars().count();
+            run.status.position = 0;
+            run.status.loaded = Some(origin.clone());
+            run.text = Some((text, origin));
+        }
+        Request::Start => {
+            let (text, origin) = run
+                .text
+                .clone()
+                .ok_or("No text loaded, send `typer ctl load` first")?;
+            // Loaded for every run, so changes to the config apply without a restart
+            let typing = SnippetTypingArgs {
+                overrides: run.overrides.clone(),
+                ..typing.clone()
+            };
+            let mut session =
+                SnippetSession::new(config_path, typing).map_err(|e| e.to_string())?;
+            session.controls.set_speed(run.status.speed);
+            let progress = Arc::clone(daemon);
+            session.on_progress = Some(Arc::new(move |position, total| {
+                let mut run = progress.lock().unwrap();
+                run.status.position = position;
+                run.status.total = total;
+                run.events.send(DaemonEvent::Progress { position, total });
+            }));
+            let finished = Arc::clone(daemon);
+            let controls = Arc::clone(&session.controls);
+            let mut vars = vars.clone();
+            thread::spawn(move || {
+                let source = InlineSource { origin, text };
+                if let Err(e) = session.type_source(Box::new(source), &mut vars) {
+                    println!("Could not type: {}", e);
+                }
+                // Also stops the hotkey listener of the run
+                session.controls.abort();
+                let mut run = finished.lock().unwrap();
+                run.status.state = DaemonState::Idle;
+                run.events.send(DaemonEvent::Status {
+                    status: run.status(),
+                });
+            });
+            run.controls = Some(controls);
+            run.status.position = 0;
+            run.status.state = DaemonState::Typing;
+        }
+        Request::Pause | Request::Resume | Request::Abort => {
+            let controls = run.controls.as_ref().ok_or("Not typing")?;
+            match request {
+                Request::Pause => controls.set_paused(true),
+                Request::Resume => controls.set_paused(false),
+                _ => controls.abort(),
+            }
+        }
+        Request::Speed { speed } => {
+            if !(speed.is_finite() && speed > 0.0) {
+                return Err(format!("Speed must be a positive number, not {}", speed));
+            }
+            run.status.speed = speed;
+            if let Some(controls) = &run.controls {
+                controls.set_speed(speed);
+            }
+        }
+        Request::Set { overrides } => {
+            daemon::check_settable(&overrides)?;
+            let mut all = run.overrides.clone();
+            all.extend(overrides);
+            // Better found out now than on the next start
+            load_run_config(config_path, typing.profile.as_deref(), &all)
+                .map_err(|e| e.to_string())?;
+            run.overrides = all;
+        }
+        Request::Status => {}
+    }
+    let status = run.status();
+    if changes {
+        run.events.send(DaemonEvent::Status {
+            status: status.clone(),
+        });
+    }
+    Ok(Response::ok(status))
+}
+
+/// The actions of `triggers.bindings` and the snippets they type.
+pub struct Triggers {
+    bindings: HashMap<String, TriggerAction>,
+    library: SnippetLibrary,
+}
+
+impl Triggers {
+    fn load(config_path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = load_run_config(config_path, None, &[])?;
+        let bindings = config
+            .triggers
+            .bindings
+            .iter()
+            .map(|(trigger, action)| Ok((trigger.clone(), TriggerAction::parse(action)?)))
+            .collect::<Result<HashMap<_, _>, String>>()?;
+        if bindings.is_empty() {
+            return Err("No triggers bound to actions, add some to triggers.bindings".into());
+        }
+        Ok(Triggers {
+            bindings,
+            library: SnippetLibrary::new(config.snippets.dir()),
+        })
+    }
+
+    /// Has the daemon do what `trigger` is bound to, if anything.
+    fn pull(&self, trigger: &str) {
+        let Some(action) = self.bindings.get(trigger) else {
+            // Tells which notes the device sends while setting it up
+            println!("{} is not bound to anything", trigger);
+            return;
+        };
+        if let Err(e) = self.act(action) {
+            println!("{}: {}", trigger, e);
+        }
+    }
+
+    fn act(&self, action: &TriggerAction) -> Result<(), Box<dyn std::error::Error>> {
+        let path = daemon::socket_path();
+        let requests = match action {
+            TriggerAction::Snippet(name) => vec![
+                Request::Load {
+                    text: self.library.read(name)?,
+                    origin: format!("snippet {}", name),
+                },
+                Request::Start,
+            ],
+            TriggerAction::Pause => {
+                let status = daemon::send(&path, &Request::Status)?.status;
+                match status.map(|status| status.state) {
+                    Some(DaemonState::Paused) => vec![Request::Resume],
+                    _ => vec![Request::Pause],
+                }
+            }
+            TriggerAction::Abort => vec![Request::Abort],
+            &TriggerAction::Speed(speed) => vec![Request::Speed { speed }],
+        };
+        for request in requests {
+            if let Some(error) = daemon::send(&path, &request)?.error {
+                return Err(error.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Listens to the MIDI input of `triggers.midi_port` until killed.
+pub fn triggers(config_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+    let triggers = Triggers::load(&config_path)?;
+    #[cfg(feature = "midi")]
+    {
+        let port = load_config(&config_path)?.triggers.midi_port;
+        let (name, _connection) =
+            typer::triggers::listen_midi(port.as_deref(), move |trigger| triggers.pull(&trigger))?;
+        println!("Listening to {}, press Ctrl+C to stop.", name);
+        loop {
+            thread::park();
+        }
+    }
+    #[cfg(not(feature = "midi"))]
+    {
+        drop(triggers);
+        Err("MIDI triggers need a build with --features midi".into())
+    }
+}
+
+/// Runs as a Stream Deck plugin until the Stream Deck app closes it.
+pub fn stream_deck(plugin: &StreamDeckPlugin) -> Result<(), Box<dyn std::error::Error>> {
+    let triggers = Triggers::load(&get_config_path())?;
+    plugin.listen(|trigger| triggers.pull(&trigger))
+}
@@ -0,0 +1,115 @@
+use super::session::say;
+use indicatif::ProgressBar;
+use std::time::Duration;
+use typer::config::Config;
+use typer::hooks::{run_hook, HookEvent, HookPayload, HookWorker};
+use typer::notifications::{notify_desktop, play_sound};
+use typer::{HumanTypist, HumanTypistBuilder, TypingOutcome};
+
+/// Runs the hook of `payload.event`, if there is one, and waits for it to finish.
+pub fn fire_hook(config: &Config, payload: HookPayload, bar: &ProgressBar) {
+    let Some(hook) = config.hooks.hook(payload.event) else {
+        return;
+    };
+    if let Err(e) = run_hook(hook, &payload) {
+        say(bar, format!("Warning: {}", e));
+    }
+}
+
+/// Shows a desktop notification and plays a sound for the end of a run, as far as
+/// `[notifications]` asks for them and the run was long enough.
+pub fn notify_finished(
+    config: &Config,
+    payload: &HookPayload,
+    elapsed: Duration,
+    bar: &ProgressBar,
+) {
+    let settings = &config.notifications;
+    if elapsed < Duration::from_secs(settings.min_seconds) {
+        return;
+    }
+    if settings.desktop {
+        let message = match payload.event {
+            HookEvent::Abort => format!(
+                "Typing aborted at character {} of {}",
+                payload.position, payload.total
+            ),
+            _ => format!("Finished typing {}", payload.source),
+        };
+        if let Err(e) = notify_desktop(&message) {
+            say(bar, format!("Warning: {}", e));
+        }
+    }
+    if settings.sound {
+        if let Err(e) = play_sound(settings.sound_file.as_deref()) {
+            say(bar, format!("Warning: {}", e));
+        }
+    }
+}
+
+/// Types with the typist of `builder` through `type_all` and runs the hooks around it,
+/// for typing outside a session such as `typer form`, `replay` and `diff`: the start
+/// hook first, the mistake hook for each typo and the complete or abort hook at the end.
+pub fn with_hooks<F>(
+    config: &Config,
+    source: &str,
+    total: usize,
+    builder: HumanTypistBuilder,
+    type_all: F,
+) -> TypingOutcome
+where
+    F: FnOnce(&mut HumanTypist) -> TypingOutcome,
+{
+    let bar = ProgressBar::hidden();
+    let payload = |event, position, elapsed: Duration, mistakes| HookPayload {
+        event,
+        source: source.to_string(),
+        position,
+        total,
+        elapsed_ms: elapsed.as_millis() as u64,
+        mistakes,
+    };
+    fire_hook(
+        config,
+        payload(HookEvent::Start, 0, Duration::ZERO, 0),
+        &bar,
+    );
+
+    let hook_worker = (config.hooks.on_mistake.as_ref())
+        .map(|_| HookWorker::spawn(|e| println!("Warning: {}", e)));
+    let builder = match (&config.hooks.on_mistake, &hook_worker) {
+        (Some(hook), Some(worker)) => {
+            let (hook, queue, source) = (hook.clone(), worker.queue(), source.to_string());
+            let mut mistakes = 0;
+            builder.on_mistake(move |position, elapsed| {
+                mistakes += 1;
+                let payload = HookPayload {
+                    event: HookEvent::Mistake,
+                    source: source.clone(),
+                    position,
+                    total,
+                    elapsed_ms: elapsed.as_millis() as u64,
+                    mistakes,
+                };
+                queue.send(hook.clone(), payload);
+            })
+        }
+        _ => builder,
+    };
+    let mut typist = builder.build();
+    let outcome = type_all(&mut typist);
+    drop(hook_worker);
+
+    let event = match outcome {
+        TypingOutcome::Completed => HookEvent::Complete,
+        TypingOutcome::Aborted => HookEvent::Abort,
+    };
+    let position = match outcome {
+        TypingOutcome::Completed => total,
+        TypingOutcome::Aborted => typist.position(),
+    };
+    let payload = payload(event, position, typist.elapsed(), typist.stats().mistakes);
+    notify_finished(config, &payload, typist.elapsed(), &bar);
+    fire_hook(config, payload, &bar);
+    outcome
+}
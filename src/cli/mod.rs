@@ -0,0 +1,93 @@
+//! What the commands of the `typer` binary share beyond their arguments: loading the
+//! config, typing a batch of texts, the watchers and hooks around a run, and the daemon.
+
+pub mod daemon;
+pub mod hooks;
+pub mod session;
+pub mod texts;
+pub mod watchers;
+
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+use typer::config::{env_overrides, load_config, Config};
+use typer::hotkeys::{Hotkey, HotkeyEvent, HotkeyListener, Jump, SpeedHotkeys};
+use typer::permissions;
+use typer::TypingControls;
+
+/// Loads the config file and applies the profile, environment and `--set` overrides.
+pub fn load_run_config(
+    config_path: &Path,
+    profile: Option<&str>,
+    overrides: &[(String, String)],
+) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut config = load_config(config_path)?;
+    if let Some(profile) = profile {
+        config = config.with_profile(profile)?;
+    }
+    // Environment variables win over the file and command line flags win over both
+    let mut all_overrides = env_overrides()?;
+    all_overrides.extend_from_slice(overrides);
+    let config = config.with_overrides(&all_overrides)?;
+    config.validate()?;
+    Ok(config)
+}
+
+pub fn spawn_hotkey_listener(
+    config: &Config,
+    controls: &Arc<TypingControls>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let pause_hotkey = Hotkey::parse(&config.hotkeys.pause)?;
+    let abort_hotkey = Hotkey::parse(&config.hotkeys.abort)?;
+    let speed = SpeedHotkeys {
+        slower: Hotkey::parse(&config.hotkeys.slower)?,
+        faster: Hotkey::parse(&config.hotkeys.faster)?,
+        step: config.hotkeys.speed_step,
+    };
+    HotkeyListener::new(pause_hotkey, abort_hotkey)
+        .with_speed(speed)
+        .with_skip(
+            Hotkey::parse(&config.hotkeys.skip_sentence)?,
+            Jump::Sentence,
+        )
+        .with_skip(
+            Hotkey::parse(&config.hotkeys.skip_paragraph)?,
+            Jump::Paragraph,
+        )
+        .spawn(controls.clone(), |event| match event {
+            HotkeyEvent::Paused => println!("Paused."),
+            HotkeyEvent::Resumed => println!("Resumed."),
+            HotkeyEvent::Aborted => println!("Aborting..."),
+            HotkeyEvent::Speed(speed) => println!("Speed: {:.0}%", speed * 100.0),
+            HotkeyEvent::Jumped(Jump::Sentence) => println!("Skipping the rest of the sentence."),
+            HotkeyEvent::Jumped(Jump::Paragraph) => println!("Skipping the rest of the paragraph."),
+            HotkeyEvent::Jumped(Jump::Line(line)) => println!("Jumping to line {}.", line),
+        });
+    Ok(())
+}
+
+/// Explains how to grant the permission to send keystrokes if it is missing, instead of
+/// typing into the void.
+pub fn check_permissions() -> Result<(), Box<dyn std::error::Error>> {
+    if permissions::can_send_input() {
+        return Ok(());
+    }
+
+    println!("{}", permissions::permission_instructions());
+    let answer = ask("Open the Accessibility settings now?", "Y/n")?;
+    if !answer.eq_ignore_ascii_case("n") {
+        permissions::open_permission_settings()?;
+    }
+    Err("Missing the Accessibility permission".into())
+}
+
+/// Prints `question` and returns the trimmed answer, or `default` when it is left empty.
+pub fn ask(question: &str, default: &str) -> io::Result<String> {
+    print!("{} [{}]: ", question, default);
+    io::stdout().flush()?;
+
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    Ok(if answer.is_empty() { default } else { answer }.to_string())
+}
@@ -0,0 +1,723 @@
+use super::hooks::{fire_hook, notify_finished};
+use super::texts::{fill_placeholders, read_texts, sheet_texts};
+use super::watchers::watch_apps;
+use super::{check_permissions, load_run_config, spawn_hotkey_listener};
+use crate::SnippetTypingArgs;
+use indicatif::{HumanDuration, ProgressBar, ProgressStyle};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+use typer::apps::AppSwitch;
+use typer::backend::create_backend;
+use typer::breaks::BreakEvent;
+use typer::checkpoint::{get_checkpoint_path, Checkpoint};
+use typer::code::Language;
+use typer::config::{BetweenFiles, Config};
+use typer::directives::{strip_directives, Script};
+use typer::guards::{GuardSettings, Guards};
+use typer::hooks::{HookEvent, HookPayload, HookWorker};
+use typer::hotkeys::{Jump, PauseReason};
+use typer::key_sounds::KeySounds;
+use typer::notifications::notify_desktop;
+#[cfg(feature = "overlay")]
+use typer::overlay::{Overlay, OverlayStatus};
+use typer::report::SessionReport;
+use typer::screen;
+use typer::snippets::SnippetLibrary;
+use typer::text::FileSource;
+#[cfg(feature = "tui")]
+use typer::tui::{Tui, TuiStatus};
+use typer::window::{activate_window, focused_window, FocusWatcher, FocusedWindow};
+use typer::{
+    HumanTypist, HumanTypistBuilder, KeyboardLayout, Step, TextSource, TypingControls,
+    TypingOutcome,
+};
+
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(2);
+
+pub type ProgressHook = Arc<dyn Fn(usize, usize) + Send + Sync>;
+
+/// Everything needed to type a batch of texts.
+#[derive(Clone)]
+pub struct Session<'a> {
+    pub config: &'a Config,
+    pub keyboard: &'a KeyboardLayout,
+    pub controls: &'a Arc<TypingControls>,
+    pub countdown: Option<u64>,
+    pub dry_run: bool,
+    pub target: Option<String>,
+    pub focus_window: Option<String>,
+    pub ignore_blacklist: bool,
+    pub report: Option<PathBuf>,
+    pub keystroke_log: Option<PathBuf>,
+    pub finish_in: Option<Duration>,
+    /// Told the characters typed and the total as typing goes on, for whoever follows
+    /// the run from outside.
+    pub on_progress: Option<ProgressHook>,
+    #[cfg(feature = "overlay")]
+    pub overlay: Option<Overlay>,
+    /// Whether to take over the terminal with a control panel while typing.
+    #[cfg(feature = "tui")]
+    pub tui: bool,
+}
+
+impl Session<'_> {
+    /// Whether the terminal is taken over by the control panel while typing.
+    fn shows_panel(&self) -> bool {
+        #[cfg(feature = "tui")]
+        return self.tui;
+        #[cfg(not(feature = "tui"))]
+        false
+    }
+
+    /// Counts down and types the texts of `run`, or simulates them in a dry run.
+    ///
+    /// Real runs are checkpointed regularly; the checkpoint is removed once all texts
+    /// are typed and kept for `typer resume` when typing is aborted.
+    pub fn start(&self, run: Checkpoint) -> Result<TypingOutcome, Box<dyn std::error::Error>> {
+        let started_at = (run.current, run.position);
+        let run = Arc::new(Mutex::new(run));
+
+        if self.dry_run {
+            println!("\nDry run:");
+            let mut typist = self.typist_builder()?.dry_run(true).build();
+            self.pace(&mut typist, &run.lock().unwrap());
+            let outcome = type_texts(
+                &mut typist,
+                &run,
+                self.config,
+                true,
+                &ProgressBar::hidden(),
+                None,
+            );
+            self.report(&typist, &run.lock().unwrap(), started_at)?;
+            return Ok(outcome);
+        }
+
+        check_permissions()?;
+        let backend = create_backend(self.config.keyboard.backend)?;
+        let focused = match &self.focus_window {
+            Some(name) => {
+                count_down(Some(self.countdown.unwrap_or(0)), self.config)?;
+                activate_window(name)?;
+                check_focus(Some(self.target.as_deref().unwrap_or(name)))?
+            }
+            None => {
+                count_down(self.countdown, self.config)?;
+                check_focus(self.target.as_deref())?
+            }
+        };
+        let bar = match self.shows_panel() {
+            true => ProgressBar::hidden(),
+            false => progress_bar(&run.lock().unwrap()),
+        };
+        let message_bar = bar.clone();
+        let guards = GuardSettings::new(self.config, self.ignore_blacklist)?.start(
+            self.controls,
+            focused.clone(),
+            move |message| say(&message_bar, message),
+        )?;
+
+        if io::stdin().is_terminal() && !self.shows_panel() {
+            spawn_command_reader(self.controls);
+        }
+        let viewport = self.config.viewport.config_for(focused.as_ref());
+        let checkpoint_path = get_checkpoint_path();
+        #[cfg(feature = "tui")]
+        let tui = match self.tui {
+            true => {
+                let texts: Vec<String> = run
+                    .lock()
+                    .unwrap()
+                    .texts
+                    .iter()
+                    .map(|(text, _)| text.clone())
+                    .collect();
+                Some(Arc::new(Tui::spawn(
+                    self.controls.clone(),
+                    &texts,
+                    self.config.hotkeys.speed_step,
+                )?))
+            }
+            false => None,
+        };
+        #[cfg(feature = "tui")]
+        let progress_tui = tui.clone();
+        let app_switch = AppSwitch::new();
+        let apps = (!self.config.apps.is_empty())
+            .then(|| watch_apps(self.config, focused.as_ref(), &app_switch, &bar));
+        let progress_bar = bar.clone();
+        let progress_run = Arc::clone(&run);
+        let progress_path = checkpoint_path.clone();
+        let break_bar = bar.clone();
+        let break_run = Arc::clone(&run);
+        let break_path = checkpoint_path.clone();
+        let notify = self.config.breaks.notify;
+        // Characters typed before each text, which does not change while typing
+        let offsets: Vec<usize> = run
+            .lock()
+            .unwrap()
+            .texts
+            .iter()
+            .scan(0, |offset, (text, _)| {
+                let before = *offset;
+                *offset += strip_directives(text).chars().count();
+                Some(before)
+            })
+            .collect();
+        let total: usize = run
+            .lock()
+            .unwrap()
+            .texts
+            .iter()
+            .map(|(text, _)| strip_directives(text).chars().count())
+            .sum();
+        let hook = self.on_progress.clone();
+        let hook_payload = |event, position, elapsed: Duration, mistakes| {
+            let run = run.lock().unwrap();
+            HookPayload {
+                event,
+                source: run.texts[run.current].1.clone(),
+                position: offsets[run.current] + position,
+                total,
+                elapsed_ms: elapsed.as_millis() as u64,
+                mistakes,
+            }
+        };
+        let position = run.lock().unwrap().position;
+        fire_hook(
+            self.config,
+            hook_payload(HookEvent::Start, position, Duration::ZERO, 0),
+            &bar,
+        );
+        let mistake_hook = self.config.hooks.on_mistake.clone();
+        let mistake_run = Arc::clone(&run);
+        let mistake_offsets = offsets.clone();
+        let progress_offsets = offsets.clone();
+        let mut mistakes = 0;
+        // Dropped before the hook of the end of the run, which waits for these to be done
+        let hook_bar = bar.clone();
+        let hook_worker = mistake_hook
+            .as_ref()
+            .map(|_| HookWorker::spawn(move |e| say(&hook_bar, format!("Warning: {}", e))));
+        let mistake_queue = hook_worker.as_ref().map(HookWorker::queue);
+        let mut last_saved = Instant::now();
+        let mut first = None;
+        #[cfg(feature = "overlay")]
+        let overlay = self.overlay.clone();
+        // Kept for the whole run, as on X11 the copied text goes away with it
+        let mut clipboard = arboard::Clipboard::new().ok();
+        let builder = self
+            .typist_builder()?
+            .boxed_backend(backend)
+            .app_switch(app_switch)
+            .clipboard(|| arboard::Clipboard::new().ok()?.get_text().ok())
+            .clipboard_writer(move |text| {
+                clipboard
+                    .as_mut()
+                    .is_some_and(|clipboard| clipboard.set_text(text).is_ok())
+            })
+            .on_progress(move |progress| {
+                let mut run = progress_run.lock().unwrap();
+                run.position = progress.position;
+                run.erase = progress.erase;
+
+                let done = progress_offsets[run.current] + progress.position;
+                let first = *first.get_or_insert(done);
+                let wpm = words_per_minute(done - first, progress.elapsed);
+                update_progress_bar(&progress_bar, done, wpm);
+                if let Some(hook) = &hook {
+                    hook(done, total);
+                }
+                #[cfg(feature = "overlay")]
+                if let Some(overlay) = &overlay {
+                    overlay.update(OverlayStatus {
+                        position: done,
+                        total: progress_bar.length().unwrap_or(0) as usize,
+                        wpm,
+                    });
+                }
+                #[cfg(feature = "tui")]
+                if let Some(tui) = &progress_tui {
+                    tui.update(TuiStatus {
+                        position: done,
+                        wpm,
+                    });
+                }
+
+                if last_saved.elapsed() >= CHECKPOINT_INTERVAL {
+                    if let Err(e) = run.save(&progress_path) {
+                        println!("Warning: Could not save checkpoint: {}", e);
+                    }
+                    last_saved = Instant::now();
+                }
+            })
+            .on_mistake(move |position, elapsed| {
+                mistakes += 1;
+                let (Some(hook), Some(queue)) = (&mistake_hook, &mistake_queue) else {
+                    return;
+                };
+                let run = mistake_run.lock().unwrap();
+                let payload = HookPayload {
+                    event: HookEvent::Mistake,
+                    source: run.texts[run.current].1.clone(),
+                    position: mistake_offsets[run.current] + position,
+                    total,
+                    elapsed_ms: elapsed.as_millis() as u64,
+                    mistakes,
+                };
+                queue.send(hook.clone(), payload);
+            })
+            .on_break(move |event| match event {
+                BreakEvent::Started(duration) => {
+                    break_bar.println(format!(
+                        "Taking a {} minute break.",
+                        (duration.as_secs_f64() / 60.0).round()
+                    ));
+                    // Saved now so the run can be resumed if it is not picked up again
+                    if let Err(e) = break_run.lock().unwrap().save(&break_path) {
+                        break_bar.println(format!("Warning: Could not save checkpoint: {}", e));
+                    }
+                }
+                BreakEvent::Ended => {
+                    break_bar.println("Break over, typing again.");
+                    if notify {
+                        if let Err(e) = notify_desktop("Break over, typing again.") {
+                            break_bar.println(format!("Warning: Could not notify: {}", e));
+                        }
+                    }
+                }
+            });
+        let output_bar = bar.clone();
+        let builder = builder.on_output(move |line| say(&output_bar, line.to_string()));
+        #[cfg(feature = "tui")]
+        let builder = match &tui {
+            Some(tui) => {
+                let (pausing, output) = (Arc::clone(tui), Arc::clone(tui));
+                builder
+                    .on_pause(move |reason, duration| pausing.pausing(reason, duration))
+                    .on_output(move |line| output.say(line))
+            }
+            None => builder,
+        };
+        let mut typist = builder.build();
+        typist.config.viewport = viewport;
+        self.pace(&mut typist, &run.lock().unwrap());
+        let outcome = type_texts(&mut typist, &run, self.config, false, &bar, guards.focus());
+        drop(guards);
+        drop(apps);
+        drop(hook_worker);
+        #[cfg(feature = "tui")]
+        if let Some(tui) = &tui {
+            tui.close();
+        }
+        bar.finish();
+        let event = match outcome {
+            TypingOutcome::Completed => HookEvent::Complete,
+            TypingOutcome::Aborted => HookEvent::Abort,
+        };
+        let payload = hook_payload(
+            event,
+            typist.position(),
+            typist.elapsed(),
+            typist.stats().mistakes,
+        );
+        notify_finished(self.config, &payload, typist.elapsed(), &bar);
+        fire_hook(self.config, payload, &bar);
+        self.report(&typist, &run.lock().unwrap(), started_at)?;
+
+        match outcome {
+            TypingOutcome::Completed => Checkpoint::remove(&checkpoint_path)?,
+            TypingOutcome::Aborted => {
+                run.lock().unwrap().save(&checkpoint_path)?;
+                println!("Run `typer resume` to continue where you left off.");
+            }
+        }
+        Ok(outcome)
+    }
+
+    /// Paces `typist` to type what is left of `run` within `--finish-in`, if given.
+    fn pace(&self, typist: &mut HumanTypist, run: &Checkpoint) {
+        let Some(budget) = self.finish_in else {
+            return;
+        };
+        let chars: usize = run.texts[run.current..]
+            .iter()
+            .map(|(text, _)| strip_directives(text).chars().count())
+            .sum();
+        typist.finish_in(budget, chars.saturating_sub(run.position));
+    }
+
+    fn typist_builder(&self) -> Result<HumanTypistBuilder, Box<dyn std::error::Error>> {
+        let mut builder = HumanTypist::builder()
+            .config(self.config.to_typing_config()?)
+            .keyboard(self.keyboard.clone())
+            .controls(self.controls.clone());
+        if let Some(region) = self.config.shell.prompt_region()? {
+            if self.config.shell.enabled && !self.dry_run {
+                builder = builder.screen(screen::region_capture(region)?);
+            }
+        }
+        if let Some(path) = &self.keystroke_log {
+            let log = File::create(path)
+                .map_err(|e| format!("Failed to create {}: {}", path.display(), e))?;
+            builder = builder.keystroke_log(BufWriter::new(log));
+        }
+        if !self.dry_run {
+            builder = with_key_sounds(builder, self.config)?;
+        }
+        Ok(builder)
+    }
+
+    /// Prints the session report for what `typist` typed of `run` since `started_at`,
+    /// a text index and character position, and saves it if asked to.
+    fn report(
+        &self,
+        typist: &HumanTypist,
+        run: &Checkpoint,
+        started_at: (usize, usize),
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let mut typed = String::new();
+        for index in started_at.0..=run.current {
+            let text = strip_directives(&run.texts[index].0);
+            let from = if index == started_at.0 {
+                started_at.1
+            } else {
+                0
+            };
+            let to = if index == run.current {
+                typist.position()
+            } else {
+                usize::MAX
+            };
+            typed.extend(text.chars().take(to).skip(from));
+        }
+
+        let report = SessionReport::new(&typed, typist.stats(), typist.elapsed());
+        println!();
+        report.print();
+        if let Some(path) = &self.report {
+            report.save(path)?;
+            println!("Report saved to {}", path.display());
+        }
+        Ok(())
+    }
+}
+
+/// The config and controls snippets are typed with, loaded once for all of them.
+pub struct SnippetSession {
+    pub config_path: PathBuf,
+    pub config: Config,
+    pub keyboard: KeyboardLayout,
+    pub controls: Arc<TypingControls>,
+    pub typing: SnippetTypingArgs,
+    pub on_progress: Option<ProgressHook>,
+}
+
+impl SnippetSession {
+    pub fn new(
+        config_path: &Path,
+        typing: SnippetTypingArgs,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let config = load_run_config(config_path, typing.profile.as_deref(), &typing.overrides)?;
+        let keyboard = config.keyboard_layout()?;
+        let controls = TypingControls::new();
+        if !typing.dry_run {
+            spawn_hotkey_listener(&config, &controls)?;
+        }
+        Ok(SnippetSession {
+            config_path: config_path.to_path_buf(),
+            config,
+            keyboard,
+            controls,
+            typing,
+            on_progress: None,
+        })
+    }
+
+    /// Types the snippet called `name` into the focused window.
+    pub fn type_snippet(
+        &self,
+        library: &SnippetLibrary,
+        name: &str,
+        vars: &mut Option<HashMap<String, String>>,
+    ) -> Result<TypingOutcome, Box<dyn std::error::Error>> {
+        let source = FileSource {
+            path: library.path(name)?,
+        };
+        self.type_source(Box::new(source), vars)
+    }
+
+    /// Types the text of `source` into the focused window.
+    pub fn type_source(
+        &self,
+        source: Box<dyn TextSource>,
+        vars: &mut Option<HashMap<String, String>>,
+    ) -> Result<TypingOutcome, Box<dyn std::error::Error>> {
+        let texts = fill_placeholders(read_texts(&[source])?, vars)?;
+        let texts = sheet_texts(&self.config, texts)?;
+        let session = Session {
+            config: &self.config,
+            keyboard: &self.keyboard,
+            controls: &self.controls,
+            countdown: self.typing.countdown,
+            dry_run: self.typing.dry_run,
+            target: None,
+            focus_window: None,
+            ignore_blacklist: false,
+            report: None,
+            keystroke_log: None,
+            finish_in: None,
+            on_progress: self.on_progress.clone(),
+            #[cfg(feature = "overlay")]
+            overlay: None,
+            #[cfg(feature = "tui")]
+            tui: false,
+        };
+        let run = Checkpoint::new(
+            texts,
+            self.config_path.clone(),
+            self.typing.profile.clone(),
+            self.typing.overrides.clone(),
+        );
+        session.start(run)
+    }
+}
+
+/// Types the texts of `run` in turn, starting where it left off and doing what the
+/// playlist settings say in between. Keeps `run` up to date with the current text.
+pub fn type_texts(
+    typist: &mut HumanTypist,
+    run: &Mutex<Checkpoint>,
+    config: &Config,
+    dry_run: bool,
+    bar: &ProgressBar,
+    focus: Option<&FocusWatcher>,
+) -> TypingOutcome {
+    let (texts, first, position, erase) = {
+        let run = run.lock().unwrap();
+        (run.texts.clone(), run.current, run.position, run.erase)
+    };
+
+    for (index, (text, text_source)) in texts.iter().enumerate().skip(first) {
+        if index > first {
+            match config.playlist.between_files {
+                BetweenFiles::Pause => typist.pause(config.playlist.pause_ms, "next file"),
+                BetweenFiles::WaitForKey if dry_run => say(
+                    bar,
+                    format!("Waiting for {} before the next file", config.hotkeys.pause),
+                ),
+                BetweenFiles::WaitForKey => {
+                    typist.controls().pause(PauseReason::Step);
+                    say(
+                        bar,
+                        format!(
+                            "Finished {}. Press {} to continue with {}.",
+                            texts[index - 1].1,
+                            config.hotkeys.pause,
+                            text_source
+                        ),
+                    );
+                }
+                BetweenFiles::SwitchWindow => {
+                    if let Some(focus) = focus {
+                        focus.retarget();
+                    }
+                    typist.switch_window();
+                    typist.pause(config.playlist.pause_ms, "window switch");
+                }
+            }
+        }
+        if texts.len() > 1 {
+            say(bar, format!("Typing {}", text_source));
+        }
+
+        let start = if index == first {
+            typist.erase(erase);
+            position
+        } else {
+            0
+        };
+        run.lock().unwrap().current = index;
+        let script = Script::parse(text).unwrap_or_else(|_| Script::plain(text));
+        if let (Some(code), None) = (&mut typist.config.code, config.code.language) {
+            code.language = Language::detect(Path::new(text_source));
+        }
+        if typist.type_script_from(&script, start) == TypingOutcome::Aborted {
+            say(
+                bar,
+                format!(
+                    "Typing aborted at character {} of {}.",
+                    typist.position(),
+                    text_source
+                ),
+            );
+            return TypingOutcome::Aborted;
+        }
+    }
+
+    TypingOutcome::Completed
+}
+
+/// Prints a line without breaking the progress bar, or directly when there is none.
+pub fn say(bar: &ProgressBar, message: String) {
+    if bar.is_hidden() {
+        println!("{}", message);
+    } else {
+        bar.println(message);
+    }
+}
+
+/// Creates a progress bar over all characters of `run`, drawn on stderr.
+fn progress_bar(run: &Checkpoint) -> ProgressBar {
+    let total: usize = run
+        .texts
+        .iter()
+        .map(|(text, _)| strip_directives(text).chars().count())
+        .sum();
+    let bar = ProgressBar::new(total as u64);
+    bar.set_style(
+        ProgressStyle::with_template("{bar:40.cyan/blue} {pos}/{len} chars  {msg}")
+            .expect("valid progress bar template")
+            .progress_chars("=> "),
+    );
+    bar
+}
+
+/// Typing speed over `chars` characters typed in `elapsed`, if there is enough to tell.
+fn words_per_minute(chars: usize, elapsed: Duration) -> Option<f64> {
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    // A word is five characters by convention
+    (minutes > 0.0 && chars > 0).then(|| chars as f64 / 5.0 / minutes)
+}
+
+/// Moves the progress bar to `done` characters and updates the speed and time left.
+fn update_progress_bar(bar: &ProgressBar, done: usize, wpm: Option<f64>) {
+    bar.set_position(done as u64);
+
+    if let Some(wpm) = wpm {
+        let remaining = bar.length().unwrap_or(0).saturating_sub(done as u64) as f64;
+        let time_left = Duration::from_secs_f64(remaining / (wpm * 5.0) * 60.0);
+        bar.set_message(format!("{:.0} WPM, {} left", wpm, HumanDuration(time_left)));
+    }
+}
+
+/// Plays a key click for every keystroke `builder` makes, when `key_sounds` is enabled.
+pub fn with_key_sounds(
+    builder: HumanTypistBuilder,
+    config: &Config,
+) -> Result<HumanTypistBuilder, Box<dyn std::error::Error>> {
+    let settings = &config.key_sounds;
+    if !settings.enabled {
+        return Ok(builder);
+    }
+    let sounds = KeySounds::start(&settings.samples, settings.variation())?;
+    Ok(builder.on_key_event(move |event| sounds.play(&event.action)))
+}
+
+/// Waits `secs` seconds, or as many as the user enters when `None`, so they can focus
+/// the target window.
+pub fn count_down(secs: Option<u64>, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let delay_secs = match secs {
+        Some(secs) => secs,
+        None => {
+            print!("\nEnter the number of seconds to wait before starting: ");
+            io::stdout().flush()?;
+
+            let mut delay_secs = String::new();
+            io::stdin().read_line(&mut delay_secs)?;
+            delay_secs.trim().parse().unwrap_or(5)
+        }
+    };
+
+    println!("\nStarting in...");
+    for i in (1..=delay_secs).rev() {
+        println!("{i}...");
+        thread::sleep(Duration::from_secs(1));
+    }
+    println!(
+        "Go! Press {} to pause or resume, {} or hold Escape to abort.",
+        config.hotkeys.pause, config.hotkeys.abort
+    );
+    if let Some(step) = config.pauses.step {
+        let boundary = match step {
+            Step::Line => "line",
+            Step::Paragraph => "paragraph",
+        };
+        println!(
+            "Typing stops after every {}, press {} to type the next one.",
+            boundary, config.hotkeys.pause
+        );
+    }
+    Ok(())
+}
+
+/// Shows which window is about to receive the keystrokes and, with a `target`, refuses
+/// to go on unless it matches.
+fn check_focus(target: Option<&str>) -> Result<Option<FocusedWindow>, Box<dyn std::error::Error>> {
+    let focused = focused_window();
+    match &focused {
+        Some(window) => println!("Typing into {}", window.describe()),
+        None => println!("Could not detect the focused window."),
+    }
+
+    match (target, focused) {
+        (Some(target), Some(window)) if !window.matches(target) => Err(format!(
+            "The focused window {} does not match --target \"{}\", not typing",
+            window.describe(),
+            target
+        )
+        .into()),
+        (Some(target), None) => Err(format!(
+            "Cannot check the focused window against --target \"{}\", not typing",
+            target
+        )
+        .into()),
+        (_, focused) => Ok(focused),
+    }
+}
+
+/// Checks the focused window and starts the [`Guards`] for typing outside a session,
+/// such as `typer form`, `replay` and `diff`, right before it starts.
+pub fn guard(
+    config: &Config,
+    controls: &Arc<TypingControls>,
+    ignore_blacklist: bool,
+) -> Result<Guards, Box<dyn std::error::Error>> {
+    let settings = GuardSettings::new(config, ignore_blacklist)?;
+    let focused = check_focus(None)?;
+    Ok(settings.start(controls, focused, |message| println!("{}", message))?)
+}
+
+/// Reads jump commands typed into the terminal while typing: a line number to go on
+/// from, `sentence` or `paragraph`. Only one reader is ever started.
+fn spawn_command_reader(controls: &Arc<TypingControls>) {
+    static STARTED: Once = Once::new();
+    let controls = controls.clone();
+    STARTED.call_once(|| {
+        println!("Type a line number and Enter here to jump to that line.");
+        thread::spawn(move || {
+            for line in io::stdin().lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                let jump = match line.trim() {
+                    "sentence" => Jump::Sentence,
+                    "paragraph" => Jump::Paragraph,
+                    command => match command.trim_start_matches("line").trim().parse() {
+                        Ok(line) => Jump::Line(line),
+                        Err(_) => {
+                            println!("Expected a line number, sentence or paragraph.");
+                            continue;
+                        }
+                    },
+                };
+                controls.request_jump(jump);
+            }
+        });
+    });
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+use std::io::{self, IsTerminal, Write};
+use typer::config::Config;
+use typer::directives::Script;
+use typer::template;
+use typer::TextSource;
+
+/// Fills the placeholders of `texts` with `vars`, asking for the values of any others
+/// and adding them to `vars` so they are asked only once. Without `vars` the texts are
+/// no templates and are kept as they are.
+pub fn fill_placeholders(
+    texts: Vec<(String, String)>,
+    vars: &mut Option<HashMap<String, String>>,
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let Some(vars) = vars else {
+        return Ok(texts);
+    };
+    for (text, _) in &texts {
+        for name in template::placeholders(text) {
+            if vars.contains_key(&name) {
+                continue;
+            }
+            // Piped text leaves no one to ask
+            if !io::stdin().is_terminal() {
+                return Err(format!(
+                    "No value for {{{{{}}}}}, pass it with --var {}=VALUE",
+                    name, name
+                )
+                .into());
+            }
+            print!("Value for {{{{{}}}}}: ", name);
+            io::stdout().flush()?;
+            let mut value = String::new();
+            io::stdin().read_line(&mut value)?;
+            vars.insert(name, value.trim_end_matches(['\r', '\n']).to_string());
+        }
+    }
+
+    texts
+        .into_iter()
+        .map(|(text, text_source)| {
+            let text =
+                template::fill(&text, vars).map_err(|e| format!("{}: {}", text_source, e))?;
+            Ok((text, text_source))
+        })
+        .collect()
+}
+
+/// Turns CSV `texts` into cell by cell typing when `sheet.enabled` is on.
+pub fn sheet_texts(
+    config: &Config,
+    texts: Vec<(String, String)>,
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    if !config.sheet.enabled {
+        return Ok(texts);
+    }
+    let layout = config.sheet.layout()?;
+    texts
+        .into_iter()
+        .map(|(text, text_source)| {
+            let text = layout
+                .to_text(&text)
+                .map_err(|e| format!("{}: invalid CSV: {}", text_source, e))?;
+            Ok((text, text_source))
+        })
+        .collect()
+}
+
+/// Reads every source up front so a missing file is reported before typing starts.
+/// Returns each text along with where it came from.
+pub fn read_texts(
+    sources: &[Box<dyn TextSource>],
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    sources
+        .iter()
+        .map(|source| {
+            let text = source.read()?;
+            // Malformed directives are reported before anything gets typed
+            Script::parse(&text).map_err(|e| format!("{}: {}", source.describe(), e))?;
+            Ok((text, source.describe()))
+        })
+        .collect()
+}
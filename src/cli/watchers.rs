@@ -0,0 +1,37 @@
+use super::session::say;
+use indicatif::ProgressBar;
+use std::sync::Arc;
+use std::time::Duration;
+use typer::apps::{AppSwitch, AppWatcher};
+use typer::config::Config;
+use typer::window::FocusedWindow;
+
+/// Hands the `[apps]` settings of whichever app has the focus to `switch` while typing.
+pub fn watch_apps(
+    config: &Config,
+    focused: Option<&FocusedWindow>,
+    switch: &Arc<AppSwitch>,
+    bar: &ProgressBar,
+) -> AppWatcher {
+    let bar = bar.clone();
+    AppWatcher::spawn(
+        config.app_profiles(),
+        focused,
+        Duration::from_millis(config.focus.poll_ms),
+        Arc::clone(switch),
+        move |name, window| match name {
+            Some(name) => say(
+                &bar,
+                format!(
+                    "Typing into {} with the [apps.{}] settings.",
+                    window.describe(),
+                    name
+                ),
+            ),
+            None => say(
+                &bar,
+                format!("Typing into {} with the usual settings.", window.describe()),
+            ),
+        },
+    )
+}
@@ -0,0 +1,376 @@
+use crate::timing::Delay;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Programming language of a source file, which decides what counts as a string
+/// literal, a comment and the start of a function.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Language {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Go,
+    C,
+    Cpp,
+    Java,
+    CSharp,
+    Ruby,
+    Shell,
+    /// Anything else, handled with rules common to most languages.
+    #[default]
+    Other,
+}
+
+/// First words of a line that cannot start a C-style function definition.
+const CONTROL_WORDS: &[&str] = &[
+    "if", "else", "for", "while", "do", "switch", "case", "catch", "return", "new", "throw",
+    "using", "try", "sizeof", "delete",
+];
+
+impl Language {
+    pub fn from_extension(extension: &str) -> Option<Language> {
+        let language = match extension.to_ascii_lowercase().as_str() {
+            "rs" => Language::Rust,
+            "py" | "pyw" => Language::Python,
+            "js" | "mjs" | "cjs" | "jsx" => Language::JavaScript,
+            "ts" | "tsx" => Language::TypeScript,
+            "go" => Language::Go,
+            "c" | "h" => Language::C,
+            "cc" | "cpp" | "cxx" | "hpp" | "hh" | "hxx" => Language::Cpp,
+            "java" | "kt" => Language::Java,
+            "cs" => Language::CSharp,
+            "rb" => Language::Ruby,
+            "sh" | "bash" | "zsh" => Language::Shell,
+            _ => return None,
+        };
+        Some(language)
+    }
+
+    /// The language of the file at `path`, by its extension.
+    pub fn detect(path: &Path) -> Language {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .and_then(Language::from_extension)
+            .unwrap_or_default()
+    }
+
+    /// Whether indentation changes what the code means, as in Python.
+    pub fn indentation_sensitive(self) -> bool {
+        self == Language::Python
+    }
+
+    fn line_comment(self) -> Option<&'static str> {
+        match self {
+            Language::Python | Language::Ruby | Language::Shell => Some("#"),
+            Language::Other => None,
+            _ => Some("//"),
+        }
+    }
+
+    fn block_comment(self) -> Option<(&'static str, &'static str)> {
+        match self {
+            Language::Python | Language::Ruby | Language::Shell | Language::Other => None,
+            _ => Some(("/*", "*/")),
+        }
+    }
+
+    /// Characters that open and close a string literal. Rust's `'` also starts
+    /// lifetimes, so it is left out.
+    fn quotes(self) -> &'static [char] {
+        match self {
+            Language::Rust | Language::Other => &['"'],
+            Language::JavaScript | Language::TypeScript | Language::Go => &['"', '\'', '`'],
+            _ => &['"', '\''],
+        }
+    }
+
+    /// Whether a string opened with `quote` may go on past the end of the line.
+    fn multiline(self, quote: char) -> bool {
+        match self {
+            Language::Rust | Language::Shell => true,
+            Language::JavaScript | Language::TypeScript | Language::Go => quote == '`',
+            _ => false,
+        }
+    }
+
+    /// Whether `line` starts the definition of a function, method or class.
+    pub fn starts_function(self, line: &str) -> bool {
+        let (modifiers, keywords): (&[&str], &[&str]) = match self {
+            Language::Rust => (
+                &[
+                    "pub",
+                    "pub(crate)",
+                    "pub(super)",
+                    "async",
+                    "unsafe",
+                    "const",
+                    "extern",
+                    "default",
+                ],
+                &["fn"],
+            ),
+            Language::Python => (&["async"], &["def", "class"]),
+            Language::JavaScript | Language::TypeScript => (
+                &["export", "default", "async", "public", "private", "static"],
+                &["function", "class"],
+            ),
+            Language::Go => (&[], &["func"]),
+            Language::Ruby => (&[], &["def", "class", "module"]),
+            Language::Shell => (&[], &["function"]),
+            Language::C | Language::Cpp | Language::Java | Language::CSharp => (
+                &[
+                    "public",
+                    "private",
+                    "protected",
+                    "static",
+                    "abstract",
+                    "final",
+                ],
+                &["class", "struct", "interface"],
+            ),
+            Language::Other => (&[], &["fn", "def", "function", "func", "class"]),
+        };
+        let line = line.trim();
+        let first = line
+            .split_whitespace()
+            .find(|word| !modifiers.contains(word));
+        if first.is_some_and(|word| keywords.contains(&word)) {
+            return true;
+        }
+
+        match self {
+            Language::Shell => first.is_some_and(|word| word.ends_with("()")),
+            Language::C | Language::Cpp | Language::Java | Language::CSharp => {
+                starts_c_function(line)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Guesses whether `line` is the head of a C-style function, like `int main(void) {`:
+/// a return type and a name before the parameters, and no statement.
+fn starts_c_function(line: &str) -> bool {
+    let Some((head, _)) = line.split_once('(') else {
+        return false;
+    };
+    let words: Vec<&str> = head.split_whitespace().collect();
+    line.starts_with(|c: char| c.is_alphabetic() || c == '_')
+        && (line.ends_with('{') || line.ends_with(')'))
+        && !line.contains(';')
+        && !head.contains('=')
+        && words.len() >= 2
+        && !CONTROL_WORDS.contains(&words[0])
+}
+
+/// Marks the characters of `chars` that belong to a string literal, quotes included.
+/// Quotes inside comments do not start a string.
+pub fn string_literals(chars: &[char], language: Language) -> Vec<bool> {
+    let mut inside = vec![false; chars.len()];
+    let at = |i: usize, pattern: &str| {
+        pattern
+            .chars()
+            .enumerate()
+            .all(|(k, c)| chars.get(i + k) == Some(&c))
+    };
+    let mut i = 0;
+
+    while i < chars.len() {
+        if language.line_comment().is_some_and(|prefix| at(i, prefix)) {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+            continue;
+        }
+        if let Some((open, close)) = language.block_comment().filter(|(open, _)| at(i, open)) {
+            i += open.len();
+            while i < chars.len() && !at(i, close) {
+                i += 1;
+            }
+            i += close.len();
+            continue;
+        }
+        let quote = chars[i];
+        // Rust's '"' is a character, not the start of a string
+        let char_literal = i > 0 && chars[i - 1] == '\'' && chars.get(i + 1) == Some(&'\'');
+        if !language.quotes().contains(&quote) || char_literal {
+            i += 1;
+            continue;
+        }
+
+        let triple: String = [quote; 3].iter().collect();
+        let delimiter = match language == Language::Python && at(i, &triple) {
+            true => triple,
+            false => quote.to_string(),
+        };
+        let multiline = delimiter.len() == 3 || language.multiline(quote);
+        let start = i;
+        i += delimiter.len();
+        while i < chars.len() && !at(i, &delimiter) {
+            match chars[i] {
+                '\\' => i += 1,
+                '\n' if !multiline => break,
+                _ => {}
+            }
+            i += 1;
+        }
+        if at(i, &delimiter) {
+            i += delimiter.len();
+        }
+        let end = i.min(chars.len());
+        inside[start..end].fill(true);
+    }
+    inside
+}
+
+/// Parameters of the code typing mode.
+#[derive(Clone, Debug)]
+pub struct CodeModeConfig {
+    pub language: Language,
+    /// Delay factor before symbols and punctuation, which take longer to find.
+    pub symbol_factor: f64,
+    /// Whether string literals get typos like the rest of the code.
+    pub mistakes_in_strings: bool,
+    /// Pause to think before a line that starts a function.
+    pub function_pause: Delay,
+    /// Keeps whitespace and the characters next to it free of mistakes and never leaves
+    /// indentation to the editor, for any language. Always on for indentation-sensitive
+    /// languages.
+    pub strict_whitespace: bool,
+    /// In strict mode, selects and copies every line once it is typed and retypes it
+    /// if it does not match the source.
+    pub verify_lines: bool,
+}
+
+impl Default for CodeModeConfig {
+    fn default() -> Self {
+        CodeModeConfig {
+            language: Language::Other,
+            symbol_factor: 1.5,
+            mistakes_in_strings: true,
+            function_pause: Delay::Uniform(1500..4000),
+            strict_whitespace: false,
+            verify_lines: false,
+        }
+    }
+}
+
+/// How to keep an editor's own indentation after Enter from doubling the source's.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoIndent {
+    /// Type every line's indentation, for editors that do not indent by themselves.
+    #[default]
+    Off,
+    /// Leave out the indentation of each line and let the editor indent it.
+    Strip,
+    /// Select whatever the editor indented with Shift+Home and type the source's
+    /// indentation over it, so every line comes out exactly as in the source.
+    Home,
+}
+
+/// Brackets and quotes editors close by themselves, opener first.
+pub const AUTO_CLOSE_PAIRS: &[(char, char)] = &[
+    ('(', ')'),
+    ('[', ']'),
+    ('{', '}'),
+    ('"', '"'),
+    ('\'', '\''),
+    ('`', '`'),
+];
+
+/// What to do about the closer an editor inserts after an opening bracket or quote.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum AutoClose {
+    /// The editor does not close this opener, type everything as written.
+    #[default]
+    Off,
+    /// The editor types over its closer when the same character is typed, so the
+    /// closer is typed as usual.
+    TypeOver,
+    /// Press Right to get past the editor's closer instead of typing it.
+    Skip,
+    /// Press End to get past the editor's closers when nothing else follows them on
+    /// the line, and Right otherwise.
+    End,
+}
+
+/// The [`AutoClose`] rule of every opener in [`AUTO_CLOSE_PAIRS`].
+#[derive(Clone, Debug, Default)]
+pub struct AutoCloseRules {
+    rules: HashMap<char, AutoClose>,
+}
+
+impl AutoCloseRules {
+    /// The same rule for every opener.
+    pub fn new(rule: AutoClose) -> Self {
+        AutoCloseRules {
+            rules: AUTO_CLOSE_PAIRS
+                .iter()
+                .map(|&(opener, _)| (opener, rule))
+                .collect(),
+        }
+    }
+
+    /// Changes the rule of `opener`, which has to be one of [`AUTO_CLOSE_PAIRS`].
+    pub fn set(&mut self, opener: char, rule: AutoClose) -> Result<(), String> {
+        if closer(opener).is_none() {
+            return Err(format!(
+                "{:?} is not an opening bracket or quote the editor closes",
+                opener
+            ));
+        }
+        self.rules.insert(opener, rule);
+        Ok(())
+    }
+
+    pub fn rule(&self, opener: char) -> AutoClose {
+        self.rules.get(&opener).copied().unwrap_or_default()
+    }
+}
+
+/// The character an editor closes `opener` with.
+pub fn closer(opener: char) -> Option<char> {
+    AUTO_CLOSE_PAIRS
+        .iter()
+        .find(|&&(open, _)| open == opener)
+        .map(|&(_, close)| close)
+}
+
+/// What kind of text `typer run --mode` types.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TypingMode {
+    #[default]
+    Text,
+    /// Source code, see [`CodeModeConfig`].
+    Code,
+    /// Chat messages separated by blank lines, see [`ChatConfig`](crate::chat::ChatConfig).
+    Chat,
+    /// Terminal commands, one per line, see [`ShellConfig`](crate::shell::ShellConfig).
+    Shell,
+    /// A CSV table typed into a spreadsheet, see [`SheetLayout`](crate::sheet::SheetLayout).
+    Sheet,
+}
+
+impl FromStr for TypingMode {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "text" | "prose" => Ok(TypingMode::Text),
+            "code" => Ok(TypingMode::Code),
+            "chat" => Ok(TypingMode::Chat),
+            "shell" => Ok(TypingMode::Shell),
+            "sheet" => Ok(TypingMode::Sheet),
+            _ => Err(format!(
+                "Unknown typing mode: {} (expected text, code, chat, shell or sheet)",
+                name
+            )),
+        }
+    }
+}
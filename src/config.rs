@@ -0,0 +1,2424 @@
+use crate::apps::{AppProfile, AppProfiles};
+use crate::autosave::AutosaveConfig;
+use crate::backend::BackendKind;
+use crate::blacklist::Blacklist;
+use crate::breaks::BreakConfig;
+use crate::chat::ChatConfig;
+use crate::code::{self, AutoClose, AutoCloseRules, AutoIndent, CodeModeConfig, Language};
+use crate::distraction::{DistractionAction, DistractionConfig};
+use crate::events::{Chord, Key};
+use crate::fatigue::{FatigueConfig, WarmUpConfig};
+use crate::form::{FieldOptions, FormField};
+use crate::hooks::HookEvent;
+use crate::hotkeys::Hotkey;
+use crate::key_sounds::ClickVariation;
+use crate::keyboard::{FingerTimingConfig, KeyboardLayout};
+use crate::mishaps::{Mishap, MishapConfig};
+use crate::paste::PasteConfig;
+use crate::paths;
+use crate::profile::{CharClass, PersonalProfile};
+use crate::reading::ReadAheadConfig;
+use crate::screen::ScreenRegion;
+use crate::sheet::{ColumnRule, SheetLayout};
+use crate::shell::{PromptDetection, ShellConfig};
+use crate::snippets;
+use crate::time_of_day::{self, TimeOfDayConfig};
+use crate::timing::{BigramTable, BurstConfig, Delay, DelayDistribution, KeyHoldConfig};
+use crate::triggers::{check_trigger, TriggerAction};
+use crate::typos::{TypoDictionary, BUILTIN_WRONG_WORDS};
+use crate::viewport::{ViewportAction, ViewportConfig};
+use crate::window::FocusedWindow;
+use chrono::{Local, Timelike};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+type Range<T> = std::ops::Range<T>;
+type RangeInclusive<T> = std::ops::RangeInclusive<T>;
+
+/// How a noticed mistake gets erased.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum CorrectionStyle {
+    /// Backspace over the wrong characters only.
+    #[default]
+    Backspace,
+    /// Delete the whole word with a burst of backspaces and quickly retype it.
+    Word,
+    /// Delete the whole word with Ctrl+Backspace and quickly retype it.
+    CtrlBackspace,
+    /// Select back to the start of the line with Shift+Home and quickly retype the line
+    /// over the selection, for editors where backspacing looks out of place.
+    SelectAndRetype,
+}
+
+/// The key pressed for a newline in the text.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum NewlineKey {
+    #[default]
+    Enter,
+    /// For chat apps, where Enter sends the message.
+    ShiftEnter,
+    /// For apps where Ctrl+Enter starts a new line and Enter does something else.
+    CtrlEnter,
+}
+
+impl NewlineKey {
+    pub fn chord(self) -> Chord {
+        let enter = Chord::key(Key::Return);
+        match self {
+            NewlineKey::Enter => enter,
+            NewlineKey::ShiftEnter => enter.with(Key::Shift),
+            NewlineKey::CtrlEnter => enter.with(Key::Control),
+        }
+    }
+}
+
+/// Where typing stops until resumed, for typing in front of an audience one chunk at a
+/// time.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Step {
+    /// At the end of every line that is not blank.
+    Line,
+    /// At the end of every paragraph.
+    Paragraph,
+}
+
+/// One in how many characters of each class hit a nearby key instead.
+///
+/// In a config file this is either a table by class or a single number for all classes;
+/// classes left out of the table keep their default.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(from = "MistakeRatesSetting")]
+pub struct MistakeRates {
+    pub letter: u32,
+    pub digit: u32,
+    pub punctuation: u32,
+    pub symbol: u32,
+    pub whitespace: u32,
+}
+
+impl MistakeRates {
+    /// The same rate for every class.
+    pub fn uniform(one_in: u32) -> Self {
+        MistakeRates {
+            letter: one_in,
+            digit: one_in,
+            punctuation: one_in,
+            symbol: one_in,
+            whitespace: one_in,
+        }
+    }
+
+    pub fn get(&self, class: CharClass) -> u32 {
+        match class {
+            CharClass::Letter => self.letter,
+            CharClass::Digit => self.digit,
+            CharClass::Punctuation => self.punctuation,
+            CharClass::Symbol => self.symbol,
+            CharClass::Whitespace => self.whitespace,
+        }
+    }
+
+    /// Every rate multiplied by `factor`, e.g. 4.0 for four times fewer mistakes.
+    pub fn scaled(&self, factor: f64) -> Self {
+        let scale = |one_in: u32| ((one_in as f64 * factor).round() as u32).max(1);
+        MistakeRates {
+            letter: scale(self.letter),
+            digit: scale(self.digit),
+            punctuation: scale(self.punctuation),
+            symbol: scale(self.symbol),
+            whitespace: scale(self.whitespace),
+        }
+    }
+}
+
+impl Default for MistakeRates {
+    fn default() -> Self {
+        // Symbols sit far from the home row and mostly need Shift
+        MistakeRates {
+            letter: 10,
+            digit: 8,
+            punctuation: 10,
+            symbol: 5,
+            whitespace: 10,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MistakeRatesSetting {
+    Uniform(u32),
+    PerClass {
+        letter: Option<u32>,
+        digit: Option<u32>,
+        punctuation: Option<u32>,
+        symbol: Option<u32>,
+        whitespace: Option<u32>,
+    },
+}
+
+impl From<MistakeRatesSetting> for MistakeRates {
+    fn from(setting: MistakeRatesSetting) -> Self {
+        match setting {
+            MistakeRatesSetting::Uniform(one_in) => MistakeRates::uniform(one_in),
+            MistakeRatesSetting::PerClass {
+                letter,
+                digit,
+                punctuation,
+                symbol,
+                whitespace,
+            } => {
+                let default = MistakeRates::default();
+                MistakeRates {
+                    letter: letter.unwrap_or(default.letter),
+                    digit: digit.unwrap_or(default.digit),
+                    punctuation: punctuation.unwrap_or(default.punctuation),
+                    symbol: symbol.unwrap_or(default.symbol),
+                    whitespace: whitespace.unwrap_or(default.whitespace),
+                }
+            }
+        }
+    }
+}
+
+pub struct TypingConfig {
+    pub base_delay: Delay,
+    pub thinking_delay: Delay,
+    /// One in this many keystrokes hits a nearby key, by character class.
+    pub mistake_probability: MistakeRates,
+    /// Percentages of letters swapped, doubled, left out or typed in the wrong case.
+    pub transposition_probability: u32,
+    pub double_letter_probability: u32,
+    pub omission_probability: u32,
+    pub case_mistake_probability: u32,
+    /// Misspells whole words from this dictionary, e.g. "recieve", when set.
+    pub misspellings: Option<TypoDictionary>,
+    /// Percentage of words in `misspellings` that get misspelled.
+    pub misspelling_probability: u32,
+    /// Words typed in place of each other, e.g. "there" for "their".
+    pub wrong_words: Option<TypoDictionary>,
+    /// Percentage of words in `wrong_words` that get swapped for another.
+    pub wrong_word_probability: u32,
+    pub correction_delay: Range<u64>,
+    /// Percentage of mistakes that get corrected; the rest are left in the output.
+    pub correction_probability: u32,
+    /// How many more characters get typed before a wrong key is noticed.
+    pub correction_lag_chars: RangeInclusive<usize>,
+    pub correction_style: CorrectionStyle,
+    /// Types every character as written, without any kind of mistake.
+    pub exact: bool,
+    /// Types digits and `.+-*/` on the numeric keypad.
+    pub keypad: bool,
+    /// Key typed for newlines; Enter, or Shift+Enter inside chat messages, when not set.
+    pub newline_key: Option<NewlineKey>,
+    /// How to deal with editors that indent new lines by themselves.
+    pub auto_indent: AutoIndent,
+    /// How to deal with editors that close brackets and quotes by themselves.
+    pub auto_close: AutoCloseRules,
+    pub long_pause_probability: u32,
+    pub long_pause_delay: Delay,
+    /// Hesitates before complex words, as if reading them from the source, when set.
+    pub read_ahead: Option<ReadAheadConfig>,
+    /// Slows typing down and adds mistakes over time when set.
+    pub fatigue: Option<FatigueConfig>,
+    /// Starts out slower and with more mistakes when set, see [`WarmUpConfig`].
+    pub warm_up: Option<WarmUpConfig>,
+    /// Slows down and adds mistakes by the local time when set, see [`TimeOfDayConfig`].
+    pub time_of_day: Option<TimeOfDayConfig>,
+    /// Types in bursts separated by short pauses when set.
+    pub burst: Option<BurstConfig>,
+    /// Presses and releases keys separately, holding each one down, when set.
+    pub key_hold: Option<KeyHoldConfig>,
+    /// Scales the delay between two keystrokes by how easy the pair is to type.
+    pub bigrams: Option<BigramTable>,
+    /// Slows down same-finger pairs and speeds up hand alternation when set.
+    pub finger_timing: Option<FingerTimingConfig>,
+    /// Types source code the way programmers do when set, see [`CodeModeConfig`].
+    pub code: Option<CodeModeConfig>,
+    /// Types blank-line separated blocks as chat messages when set, see [`ChatConfig`].
+    pub chat: Option<ChatConfig>,
+    /// Types each line as a terminal command when set, see [`ShellConfig`].
+    pub shell: Option<ShellConfig>,
+    /// Scrolls along every few lines when set, see [`ViewportConfig`].
+    pub viewport: Option<ViewportConfig>,
+    /// Presses Ctrl+S now and then when set, see [`AutosaveConfig`].
+    pub autosave: Option<AutosaveConfig>,
+    /// Presses a wrong shortcut now and then when set, see [`MishapConfig`].
+    pub mishaps: Option<MishapConfig>,
+    /// Looks away for a while a few times an hour when set, see [`DistractionConfig`].
+    pub distraction: Option<DistractionConfig>,
+    /// Takes breaks of a few minutes in long texts when set, see [`BreakConfig`].
+    pub breaks: Option<BreakConfig>,
+    /// Pauses at the end of every line or paragraph until resumed when set.
+    pub step: Option<Step>,
+    /// Which blocks are pasted rather than typed.
+    pub paste: PasteConfig,
+    /// Measured delays and error rates of one person, preferred over the settings above.
+    pub personal: Option<PersonalProfile>,
+}
+
+impl Default for TypingConfig {
+    fn default() -> Self {
+        TypingConfig {
+            base_delay: Delay::Uniform(20..100),
+            thinking_delay: Delay::Uniform(500..1500),
+            mistake_probability: MistakeRates::default(),
+            transposition_probability: 0,
+            double_letter_probability: 0,
+            omission_probability: 0,
+            case_mistake_probability: 0,
+            misspellings: None,
+            misspelling_probability: 10,
+            wrong_words: None,
+            wrong_word_probability: 0,
+            correction_delay: 300..700,
+            correction_probability: 100,
+            correction_lag_chars: 0..=0,
+            correction_style: CorrectionStyle::Backspace,
+            exact: false,
+            keypad: false,
+            newline_key: None,
+            auto_indent: AutoIndent::Off,
+            auto_close: AutoCloseRules::default(),
+            long_pause_probability: 5,
+            long_pause_delay: Delay::Uniform(1000..3000),
+            read_ahead: None,
+            fatigue: None,
+            warm_up: None,
+            time_of_day: None,
+            burst: None,
+            key_hold: None,
+            bigrams: None,
+            finger_timing: None,
+            code: None,
+            chat: None,
+            shell: None,
+            viewport: None,
+            autosave: None,
+            mishaps: None,
+            distraction: None,
+            breaks: None,
+            step: None,
+            paste: PasteConfig::default(),
+            personal: None,
+        }
+    }
+}
+
+/// Version written to new config files. Older files are upgraded by [`migrate_config`].
+pub const CONFIG_VERSION: u32 = 2;
+
+/// Where each setting of the flat version 1 format moved to in version 2.
+const V1_SETTINGS: [(&str, &str, &str); 47] = [
+    ("base_delay_min", "delays", "base_min"),
+    ("base_delay_max", "delays", "base_max"),
+    ("base_delay_mean", "delays", "base_mean"),
+    ("base_delay_std_dev", "delays", "base_std_dev"),
+    ("thinking_delay_min", "delays", "thinking_min"),
+    ("thinking_delay_max", "delays", "thinking_max"),
+    ("thinking_delay_mean", "delays", "thinking_mean"),
+    ("thinking_delay_std_dev", "delays", "thinking_std_dev"),
+    ("delay_distribution", "delays", "distribution"),
+    ("mistake_probability", "mistakes", "probability"),
+    (
+        "transposition_probability",
+        "mistakes",
+        "transposition_probability",
+    ),
+    (
+        "double_letter_probability",
+        "mistakes",
+        "double_letter_probability",
+    ),
+    ("omission_probability", "mistakes", "omission_probability"),
+    (
+        "case_mistake_probability",
+        "mistakes",
+        "case_mistake_probability",
+    ),
+    (
+        "correction_probability",
+        "mistakes",
+        "correction_probability",
+    ),
+    ("correction_delay_min", "mistakes", "correction_delay_min"),
+    ("correction_delay_max", "mistakes", "correction_delay_max"),
+    (
+        "correction_lag_chars_min",
+        "mistakes",
+        "correction_lag_chars_min",
+    ),
+    (
+        "correction_lag_chars_max",
+        "mistakes",
+        "correction_lag_chars_max",
+    ),
+    ("correction_style", "mistakes", "correction_style"),
+    ("long_pause_probability", "pauses", "long_pause_probability"),
+    ("long_pause_delay_min", "pauses", "long_pause_min"),
+    ("long_pause_delay_max", "pauses", "long_pause_max"),
+    ("long_pause_delay_mean", "pauses", "long_pause_mean"),
+    ("long_pause_delay_std_dev", "pauses", "long_pause_std_dev"),
+    ("fatigue_enabled", "fatigue", "enabled"),
+    ("fatigue_onset_minutes", "fatigue", "onset_minutes"),
+    ("fatigue_ramp_minutes", "fatigue", "ramp_minutes"),
+    ("fatigue_max_slowdown", "fatigue", "max_slowdown"),
+    (
+        "fatigue_max_extra_mistakes",
+        "fatigue",
+        "max_extra_mistakes",
+    ),
+    (
+        "fatigue_recovery_min_pause_ms",
+        "fatigue",
+        "recovery_min_pause_ms",
+    ),
+    ("fatigue_recovery_rate", "fatigue", "recovery_rate"),
+    ("burst_enabled", "burst", "enabled"),
+    ("burst_length_min", "burst", "length_min"),
+    ("burst_length_max", "burst", "length_max"),
+    ("burst_speed_factor", "burst", "speed_factor"),
+    ("burst_pause_min", "burst", "pause_min"),
+    ("burst_pause_max", "burst", "pause_max"),
+    ("layout", "keyboard", "layout"),
+    ("layout_file", "keyboard", "layout_file"),
+    ("bigram_timing", "keyboard", "bigram_timing"),
+    ("bigram_file", "keyboard", "bigram_file"),
+    ("finger_timing", "keyboard", "finger_timing"),
+    ("same_finger_factor", "keyboard", "same_finger_factor"),
+    (
+        "hand_alternation_factor",
+        "keyboard",
+        "hand_alternation_factor",
+    ),
+    ("pause_hotkey", "hotkeys", "pause"),
+    ("abort_hotkey", "hotkeys", "abort"),
+];
+
+/// How error-prone a [`Config::tuned`] typist is.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Sloppiness {
+    Careful,
+    Normal,
+    Sloppy,
+}
+
+/// What happens between two texts of a playlist.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum BetweenFiles {
+    /// Wait for `pause_ms` and carry on.
+    #[default]
+    Pause,
+    /// Pause typing until the pause hotkey is pressed.
+    WaitForKey,
+    /// Press Alt+Tab, then wait for `pause_ms`.
+    SwitchWindow,
+}
+
+/// What happens when the focus leaves the window typer is typing into.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum FocusLoss {
+    /// Pause typing until the window has the focus again.
+    #[default]
+    Pause,
+    /// Stop typing; the run can be resumed later.
+    Abort,
+    /// Keep typing into whatever window has the focus.
+    Ignore,
+}
+
+/// What happens when the screen locks or the session becomes inactive while typing.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LockAction {
+    /// Stop typing; the run can be resumed later.
+    #[default]
+    Abort,
+    /// Pause typing until resumed with the pause hotkey.
+    Pause,
+    /// Keep typing.
+    Ignore,
+}
+
+/// User-facing settings, grouped into sections.
+///
+/// Every field has a default, so a config file only needs the values it changes.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Format version of the file, bumped whenever settings move or change meaning.
+    pub version: u32,
+    pub delays: DelaySettings,
+    pub mistakes: MistakeSettings,
+    pub pauses: PauseSettings,
+    pub fatigue: FatigueSettings,
+    pub warm_up: WarmUpSettings,
+    pub time_of_day: TimeOfDaySettings,
+    pub burst: BurstSettings,
+    pub hold: HoldSettings,
+    pub keyboard: KeyboardSettings,
+    pub hotkeys: HotkeySettings,
+    pub playlist: PlaylistSettings,
+    pub focus: FocusSettings,
+    pub blacklist: BlacklistSettings,
+    pub takeover: TakeoverSettings,
+    pub lock: LockSettings,
+    pub code: CodeSettings,
+    pub editor: EditorSettings,
+    pub chat: ChatSettings,
+    pub shell: ShellSettings,
+    pub viewport: ViewportSettings,
+    pub autosave: AutosaveSettings,
+    pub mishaps: MishapSettings,
+    pub distraction: DistractionSettings,
+    pub breaks: BreakSettings,
+    pub notifications: NotificationSettings,
+    pub key_sounds: KeySoundSettings,
+    pub paste: PasteSettings,
+    pub personal: PersonalSettings,
+    pub snippets: SnippetSettings,
+    pub form: FormSettings,
+    pub sheet: SheetSettings,
+    pub triggers: TriggerSettings,
+    pub hooks: HookSettings,
+    /// Settings for the apps whose window title or name contains the key, e.g.
+    /// `[apps.code]`, used whenever such a window has the focus while typing.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub apps: BTreeMap<String, AppSettings>,
+    /// Named sets of overrides, e.g. `[profiles.code]`, applied on top of the settings above.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub profiles: BTreeMap<String, serde_json::Value>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct DelaySettings {
+    /// Shape of the base, thinking and long pause delays. Uniform picks between the
+    /// min and max values, the others use the mean and standard deviation.
+    pub distribution: DelayDistribution,
+    pub base_min: u64,
+    pub base_max: u64,
+    pub base_mean: f64,
+    pub base_std_dev: f64,
+    pub thinking_min: u64,
+    pub thinking_max: u64,
+    pub thinking_mean: f64,
+    pub thinking_std_dev: f64,
+}
+
+impl Default for DelaySettings {
+    fn default() -> Self {
+        DelaySettings {
+            distribution: DelayDistribution::Uniform,
+            base_min: 20,
+            base_max: 100,
+            base_mean: 60.0,
+            base_std_dev: 20.0,
+            thinking_min: 500,
+            thinking_max: 1500,
+            thinking_mean: 1000.0,
+            thinking_std_dev: 250.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MistakeSettings {
+    /// One in this many keystrokes hits a nearby key, by character class.
+    pub probability: MistakeRates,
+    /// Percentage of letters swapped with the next one, unlike `probability`.
+    pub transposition_probability: u32,
+    /// Percentage of letters typed twice.
+    pub double_letter_probability: u32,
+    /// Percentage of letters left out.
+    pub omission_probability: u32,
+    /// Percentage of letters typed in the wrong case.
+    pub case_mistake_probability: u32,
+    /// Misspells common words the way people habitually do, e.g. "recieve".
+    pub misspellings: bool,
+    /// Extra misspellings by word, extending or overriding the built-in list.
+    pub misspellings_file: Option<PathBuf>,
+    /// Percentage of words with a known misspelling that get misspelled.
+    pub misspelling_probability: u32,
+    /// Percentage of words from `wrong_words` typed as another word of their group.
+    pub wrong_word_probability: u32,
+    /// Groups of words that get confused with each other, like their/there/they're.
+    pub wrong_words: Vec<Vec<String>>,
+    pub correction_probability: u32,
+    pub correction_delay_min: u64,
+    pub correction_delay_max: u64,
+    /// How many more characters get typed before a mistake is noticed, by default none.
+    pub correction_lag_chars_min: usize,
+    pub correction_lag_chars_max: usize,
+    pub correction_style: CorrectionStyle,
+}
+
+impl Default for MistakeSettings {
+    fn default() -> Self {
+        MistakeSettings {
+            probability: MistakeRates::default(),
+            transposition_probability: 0,
+            double_letter_probability: 0,
+            omission_probability: 0,
+            case_mistake_probability: 0,
+            misspellings: false,
+            misspellings_file: None,
+            misspelling_probability: 10,
+            wrong_word_probability: 0,
+            wrong_words: BUILTIN_WRONG_WORDS
+                .iter()
+                .map(|group| group.iter().map(|word| word.to_string()).collect())
+                .collect(),
+            correction_probability: 100,
+            correction_delay_min: 300,
+            correction_delay_max: 700,
+            correction_lag_chars_min: 0,
+            correction_lag_chars_max: 0,
+            correction_style: CorrectionStyle::Backspace,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PauseSettings {
+    pub long_pause_probability: u32,
+    pub long_pause_min: u64,
+    pub long_pause_max: u64,
+    pub long_pause_mean: f64,
+    pub long_pause_std_dev: f64,
+    /// Pauses before long, rare or symbol-heavy words, as if reading ahead.
+    pub read_ahead: bool,
+    /// Milliseconds of hesitation per point of word complexity above the threshold.
+    pub read_ahead_ms_per_point: f64,
+    /// Complexity a word needs before it causes hesitation; roughly its length, plus
+    /// points for rare words, digits, symbols and inner capitals.
+    pub read_ahead_threshold: f64,
+    pub read_ahead_max: u64,
+    /// Pause at the end of every `line` or `paragraph` until the pause hotkey is pressed.
+    pub step: Option<Step>,
+}
+
+impl Default for PauseSettings {
+    fn default() -> Self {
+        PauseSettings {
+            long_pause_probability: 5,
+            long_pause_min: 1000,
+            long_pause_max: 3000,
+            long_pause_mean: 2000.0,
+            long_pause_std_dev: 500.0,
+            read_ahead: false,
+            read_ahead_ms_per_point: 40.0,
+            read_ahead_threshold: 8.0,
+            read_ahead_max: 1500,
+            step: None,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FatigueSettings {
+    pub enabled: bool,
+    pub onset_minutes: f64,
+    pub ramp_minutes: f64,
+    pub max_slowdown: f64,
+    pub max_extra_mistakes: f64,
+    pub recovery_min_pause_ms: u64,
+    pub recovery_rate: f64,
+}
+
+impl Default for FatigueSettings {
+    fn default() -> Self {
+        FatigueSettings {
+            enabled: false,
+            onset_minutes: 10.0,
+            ramp_minutes: 30.0,
+            max_slowdown: 0.5,
+            max_extra_mistakes: 1.0,
+            recovery_min_pause_ms: 2000,
+            recovery_rate: 3.0,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct WarmUpSettings {
+    /// Starts out slower and sloppier, reaching full speed after `seconds` of typing.
+    pub enabled: bool,
+    pub seconds: f64,
+    pub slowdown: f64,
+    pub extra_mistakes: f64,
+}
+
+impl Default for WarmUpSettings {
+    fn default() -> Self {
+        WarmUpSettings {
+            enabled: false,
+            seconds: 60.0,
+            slowdown: 0.3,
+            extra_mistakes: 0.5,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TimeOfDaySettings {
+    /// Multiplies delays and mistake rates by `curve` at the local time.
+    pub enabled: bool,
+    /// Multipliers by hour of the day, like `"23" = 1.2`, interpolated in between.
+    pub curve: BTreeMap<String, f64>,
+}
+
+impl Default for TimeOfDaySettings {
+    fn default() -> Self {
+        TimeOfDaySettings {
+            enabled: false,
+            curve: time_of_day::default_curve()
+                .into_iter()
+                .map(|(hour, factor)| (hour.to_string(), factor))
+                .collect(),
+        }
+    }
+}
+
+impl TimeOfDaySettings {
+    /// The curve as hours and multipliers, sorted by hour.
+    pub fn curve(&self) -> Result<Vec<(f64, f64)>, String> {
+        let mut curve = Vec::new();
+        for (hour, &factor) in &self.curve {
+            let at = hour
+                .trim()
+                .parse::<f64>()
+                .ok()
+                .filter(|at| (0.0..24.0).contains(at))
+                .ok_or_else(|| format!("{:?} is not an hour from 0 to 23", hour))?;
+            if !(factor.is_finite() && factor > 0.0) {
+                return Err(format!(
+                    "the multiplier at {} ({}) must be positive",
+                    hour, factor
+                ));
+            }
+            curve.push((at, factor));
+        }
+        curve.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Ok(curve)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct BurstSettings {
+    pub enabled: bool,
+    pub length_min: usize,
+    pub length_max: usize,
+    pub speed_factor: f64,
+    pub pause_min: u64,
+    pub pause_max: u64,
+}
+
+impl Default for BurstSettings {
+    fn default() -> Self {
+        BurstSettings {
+            enabled: false,
+            length_min: 5,
+            length_max: 15,
+            speed_factor: 0.6,
+            pause_min: 150,
+            pause_max: 450,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct HoldSettings {
+    /// Sends separate key down and up events instead of typing each character at once.
+    pub enabled: bool,
+    pub duration_min: u64,
+    pub duration_max: u64,
+    /// Percentage of keys still held when the next one goes down.
+    pub rollover_probability: u32,
+    /// Releases keys at their own time, so they overlap the way they do when typing fast.
+    pub timeline: bool,
+    /// Percentage of letter pairs swapped by rolling over them in timeline mode.
+    pub swap_probability: u32,
+}
+
+impl Default for HoldSettings {
+    fn default() -> Self {
+        HoldSettings {
+            enabled: false,
+            duration_min: 50,
+            duration_max: 130,
+            rollover_probability: 10,
+            timeline: false,
+            swap_probability: 1,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyboardSettings {
+    pub layout: String,
+    /// Custom adjacency map that replaces `layout` when set.
+    pub layout_file: Option<PathBuf>,
+    pub bigram_timing: bool,
+    /// Bigram delay factors that extend or override the built-in table.
+    pub bigram_file: Option<PathBuf>,
+    pub finger_timing: bool,
+    pub same_finger_factor: f64,
+    pub hand_alternation_factor: f64,
+    /// How keystrokes are sent to the system.
+    pub backend: BackendKind,
+}
+
+impl Default for KeyboardSettings {
+    fn default() -> Self {
+        KeyboardSettings {
+            layout: "qwerty".to_string(),
+            layout_file: None,
+            bigram_timing: false,
+            bigram_file: None,
+            finger_timing: false,
+            same_finger_factor: 1.4,
+            hand_alternation_factor: 0.85,
+            backend: BackendKind::Auto,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct HotkeySettings {
+    pub pause: String,
+    pub abort: String,
+    /// Slow typing down or speed it up by `speed_step` while it runs.
+    pub slower: String,
+    pub faster: String,
+    pub speed_step: f64,
+    /// Skip the rest of the sentence or paragraph, to get back in step with the target.
+    pub skip_sentence: String,
+    pub skip_paragraph: String,
+}
+
+impl Default for HotkeySettings {
+    fn default() -> Self {
+        HotkeySettings {
+            pause: "F8".to_string(),
+            abort: "Ctrl+Shift+Q".to_string(),
+            slower: "F9".to_string(),
+            faster: "F10".to_string(),
+            speed_step: 0.25,
+            skip_sentence: "F6".to_string(),
+            skip_paragraph: "F7".to_string(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PlaylistSettings {
+    pub between_files: BetweenFiles,
+    pub pause_ms: u64,
+}
+
+impl Default for PlaylistSettings {
+    fn default() -> Self {
+        PlaylistSettings {
+            between_files: BetweenFiles::Pause,
+            pause_ms: 3000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FocusSettings {
+    pub on_loss: FocusLoss,
+    pub poll_ms: u64,
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        FocusSettings {
+            on_loss: FocusLoss::Pause,
+            poll_ms: 250,
+        }
+    }
+}
+
+/// Windows typer refuses to type into, checked before typing starts and all through
+/// the run.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct BlacklistSettings {
+    pub enabled: bool,
+    /// Text in the title or app name of the windows not to type into, ignoring case.
+    pub apps: Vec<String>,
+    /// Also refuses while the system says a password is being entered, which only macOS
+    /// and, for classic password boxes, Windows tell.
+    pub secure_input: bool,
+}
+
+impl Default for BlacklistSettings {
+    fn default() -> Self {
+        BlacklistSettings {
+            enabled: true,
+            apps: [
+                "1password",
+                "bitwarden",
+                "dashlane",
+                "enpass",
+                "keepass",
+                "keychain access",
+                "lastpass",
+            ]
+            .map(String::from)
+            .to_vec(),
+            secure_input: true,
+        }
+    }
+}
+
+impl BlacklistSettings {
+    pub fn blacklist(&self) -> Option<Blacklist> {
+        self.enabled.then(|| Blacklist {
+            apps: self.apps.clone(),
+            secure_input: self.secure_input,
+        })
+    }
+}
+
+/// Pausing while the user types or moves the mouse themselves.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct TakeoverSettings {
+    pub enabled: bool,
+    /// Resumes typing once the keyboard and mouse were left alone this long.
+    pub idle_ms: u64,
+    /// Whether moving the mouse or pressing its buttons counts too.
+    pub mouse: bool,
+}
+
+impl Default for TakeoverSettings {
+    fn default() -> Self {
+        TakeoverSettings {
+            enabled: false,
+            idle_ms: 3000,
+            mouse: true,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockSettings {
+    pub on_lock: LockAction,
+    /// How often to check whether the screen is locked.
+    pub poll_ms: u64,
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        LockSettings {
+            on_lock: LockAction::Abort,
+            poll_ms: 1000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct CodeSettings {
+    /// Types source code: slower on symbols, thinking at function boundaries and
+    /// never slipping on whitespace. `typer run --mode code` turns it on.
+    pub enabled: bool,
+    /// Language of the code; detected from the file extension when not set.
+    pub language: Option<Language>,
+    /// Delay factor before symbols and punctuation.
+    pub symbol_factor: f64,
+    /// Whether typos happen inside string literals too.
+    pub mistakes_in_strings: bool,
+    pub function_pause_min: u64,
+    pub function_pause_max: u64,
+    /// Types whitespace exactly and never slips next to it, whatever the language;
+    /// always on for Python.
+    pub strict_whitespace: bool,
+    /// In strict mode, checks every typed line by selecting and copying it.
+    pub verify_lines: bool,
+}
+
+impl Default for CodeSettings {
+    fn default() -> Self {
+        CodeSettings {
+            enabled: false,
+            language: None,
+            symbol_factor: 1.5,
+            mistakes_in_strings: true,
+            function_pause_min: 1500,
+            function_pause_max: 4000,
+            strict_whitespace: false,
+            verify_lines: false,
+        }
+    }
+}
+
+/// Compensation for what the editor being typed into does by itself.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct EditorSettings {
+    /// Key to type newlines with: `enter`, `shift_enter` or `ctrl_enter`. Enter when not
+    /// set, except inside chat messages, where it would send the message.
+    pub newline_key: Option<NewlineKey>,
+    pub auto_indent: AutoIndent,
+    /// What to do about the closing brackets and quotes the editor inserts.
+    pub auto_close: AutoClose,
+    /// Rules for single openers that differ from `auto_close`, e.g. `"'" = "off"`.
+    pub auto_close_rules: BTreeMap<String, AutoClose>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatSettings {
+    /// Types blank-line separated blocks as messages sent with Enter. `typer run --mode
+    /// chat` turns it on.
+    pub enabled: bool,
+    /// Pause after sending a message.
+    pub delay_min: u64,
+    pub delay_max: u64,
+}
+
+impl Default for ChatSettings {
+    fn default() -> Self {
+        ChatSettings {
+            enabled: false,
+            delay_min: 2000,
+            delay_max: 8000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShellSettings {
+    /// Types each line as a command run with Enter. `typer run --mode shell` turns it on.
+    pub enabled: bool,
+    /// Time given to each command to finish, unless a `{{wait:...}}` on its line says
+    /// otherwise.
+    pub delay_min: u64,
+    pub delay_max: u64,
+    /// Part of the screen showing the prompt, as `x,y,width,height`. When set, typer
+    /// waits for it to change and settle down instead of the delay above.
+    pub prompt_region: Option<String>,
+    /// How long the prompt region has to stay the same for a command to be done.
+    pub prompt_settle_ms: u64,
+    /// Longest wait for the prompt before typing the next command anyway.
+    pub prompt_timeout_ms: u64,
+}
+
+impl Default for ShellSettings {
+    fn default() -> Self {
+        ShellSettings {
+            enabled: false,
+            delay_min: 1000,
+            delay_max: 3000,
+            prompt_region: None,
+            prompt_settle_ms: 800,
+            prompt_timeout_ms: 60_000,
+        }
+    }
+}
+
+impl ShellSettings {
+    pub fn prompt_region(&self) -> Result<Option<ScreenRegion>, String> {
+        self.prompt_region
+            .as_deref()
+            .map(ScreenRegion::parse)
+            .transpose()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewportSettings {
+    /// Scrolls along every few lines, for editors that do not keep the caret in view.
+    pub enabled: bool,
+    pub every_lines: usize,
+    /// `scroll` to turn the mouse wheel or `ctrl_end` to jump to the end.
+    pub action: ViewportAction,
+    /// Lines scrolled at a time with `scroll`.
+    pub scroll_lines: u32,
+    /// Settings for the apps whose window title or name contains the key, e.g.
+    /// `[viewport.apps.notepad]`, which apply there even when `enabled` is off.
+    pub apps: BTreeMap<String, ViewportAppSettings>,
+}
+
+impl Default for ViewportSettings {
+    fn default() -> Self {
+        let defaults = ViewportConfig::default();
+        ViewportSettings {
+            enabled: false,
+            every_lines: defaults.every_lines,
+            action: defaults.action,
+            scroll_lines: defaults.scroll_lines,
+            apps: BTreeMap::new(),
+        }
+    }
+}
+
+impl ViewportSettings {
+    /// The viewport behaviour for typing into `window`: its app's settings if there
+    /// are any, the general ones otherwise.
+    pub fn config_for(&self, window: Option<&FocusedWindow>) -> Option<ViewportConfig> {
+        let app = window.and_then(|window| {
+            self.apps
+                .iter()
+                .find(|(name, _)| window.matches(name))
+                .map(|(_, app)| app)
+        });
+        match app {
+            Some(app) => app.enabled.then(|| ViewportConfig {
+                every_lines: app.every_lines.unwrap_or(self.every_lines),
+                action: app.action.unwrap_or(self.action),
+                scroll_lines: app.scroll_lines.unwrap_or(self.scroll_lines),
+            }),
+            None => self.enabled.then_some(ViewportConfig {
+                every_lines: self.every_lines,
+                action: self.action,
+                scroll_lines: self.scroll_lines,
+            }),
+        }
+    }
+}
+
+/// Viewport settings for one app; those not given are taken from the `viewport` section.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewportAppSettings {
+    pub enabled: bool,
+    pub every_lines: Option<usize>,
+    pub action: Option<ViewportAction>,
+    pub scroll_lines: Option<u32>,
+}
+
+impl Default for ViewportAppSettings {
+    fn default() -> Self {
+        ViewportAppSettings {
+            enabled: true,
+            every_lines: None,
+            action: None,
+            scroll_lines: None,
+        }
+    }
+}
+
+/// Settings that change while typing into one app; those not given stay as they are.
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct AppSettings {
+    /// Speed factor, on top of the speed set otherwise.
+    pub speed: Option<f64>,
+    pub newline_key: Option<NewlineKey>,
+    pub auto_indent: Option<AutoIndent>,
+    /// Types every character as written, without any kind of mistake.
+    pub no_mistakes: bool,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutosaveSettings {
+    /// Presses `key` every few paragraphs or seconds while typing.
+    pub enabled: bool,
+    pub key: String,
+    pub every_paragraphs: Option<usize>,
+    pub every_seconds: Option<u64>,
+    /// Pause after saving.
+    pub pause_min: u64,
+    pub pause_max: u64,
+}
+
+impl Default for AutosaveSettings {
+    fn default() -> Self {
+        AutosaveSettings {
+            enabled: false,
+            key: "ctrl+s".to_string(),
+            every_paragraphs: Some(3),
+            every_seconds: None,
+            pause_min: 300,
+            pause_max: 1200,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct MishapSettings {
+    /// Presses a wrong shortcut once in `one_in_words` words and takes it back.
+    pub enabled: bool,
+    pub one_in_words: u32,
+    /// Mishaps that may happen: undo, bold or italic.
+    pub allowed: Vec<Mishap>,
+    pub redo_key: String,
+    /// Flustered pause before taking the mishap back.
+    pub pause_min: u64,
+    pub pause_max: u64,
+}
+
+impl Default for MishapSettings {
+    fn default() -> Self {
+        MishapSettings {
+            enabled: false,
+            one_in_words: 500,
+            allowed: vec![Mishap::Undo],
+            redo_key: "ctrl+y".to_string(),
+            pause_min: 800,
+            pause_max: 2500,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct DistractionSettings {
+    /// Stops typing for a while about `per_hour` times an hour.
+    pub enabled: bool,
+    pub per_hour: f64,
+    pub duration_min: u64,
+    pub duration_max: u64,
+    /// What happens during a distraction, picked at random: idle, wiggle or switch_window.
+    pub actions: Vec<DistractionAction>,
+}
+
+impl Default for DistractionSettings {
+    fn default() -> Self {
+        DistractionSettings {
+            enabled: false,
+            per_hour: 3.0,
+            duration_min: 10_000,
+            duration_max: 60_000,
+            actions: vec![DistractionAction::Idle],
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct BreakSettings {
+    /// Takes a break at the end of a paragraph every `every_minutes` minutes.
+    pub enabled: bool,
+    pub every_minutes: u64,
+    /// Length of a break, in minutes.
+    pub duration_min: u64,
+    pub duration_max: u64,
+    /// Shows a desktop notification when a break is over.
+    pub notify: bool,
+}
+
+impl Default for BreakSettings {
+    fn default() -> Self {
+        BreakSettings {
+            enabled: false,
+            every_minutes: 50,
+            duration_min: 5,
+            duration_max: 10,
+            notify: false,
+        }
+    }
+}
+
+/// How typer lets you know a run is over, for long runs typed while away.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    /// Shows a desktop notification when typing finishes or is aborted.
+    pub desktop: bool,
+    /// Plays a sound when typing finishes or is aborted.
+    pub sound: bool,
+    /// Sound file to play instead of the system's notification sound.
+    pub sound_file: Option<PathBuf>,
+    /// Only for runs that took at least this long, in seconds.
+    pub min_seconds: u64,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        NotificationSettings {
+            desktop: false,
+            sound: false,
+            sound_file: None,
+            min_seconds: 60,
+        }
+    }
+}
+
+/// Key clicks played along with the keystrokes, for screen recordings of typer typing.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeySoundSettings {
+    pub enabled: bool,
+    /// Sound files to pick each click from, or directories of `.wav` and `.ogg` files.
+    /// When empty, typer makes up its own clicks.
+    pub samples: Vec<PathBuf>,
+    /// Percentage of the loudness of the samples.
+    pub volume: u32,
+    /// Percentage the volume of each click may stray from `volume`.
+    pub volume_variation: u32,
+    /// Percentage each click may be played faster or slower, and so higher or lower.
+    pub pitch_variation: u32,
+}
+
+impl Default for KeySoundSettings {
+    fn default() -> Self {
+        KeySoundSettings {
+            enabled: false,
+            samples: Vec::new(),
+            volume: 50,
+            volume_variation: 20,
+            pitch_variation: 8,
+        }
+    }
+}
+
+impl KeySoundSettings {
+    pub fn variation(&self) -> ClickVariation {
+        ClickVariation {
+            volume: self.volume,
+            volume_variation: self.volume_variation,
+            pitch_variation: self.pitch_variation,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct PasteSettings {
+    /// Pastes paragraphs at least this long instead of typing them.
+    pub min_block_chars: Option<usize>,
+    /// Pause to find a block before pasting it.
+    pub find_delay_min: u64,
+    pub find_delay_max: u64,
+}
+
+impl Default for PasteSettings {
+    fn default() -> Self {
+        PasteSettings {
+            min_block_chars: None,
+            find_delay_min: 1500,
+            find_delay_max: 4000,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct PersonalSettings {
+    /// Personal typing profile, e.g. `profile.json`, see [`PersonalProfile`].
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct SnippetSettings {
+    /// Directory of the snippet library; `snippets` in the data directory when not set.
+    pub dir: Option<PathBuf>,
+    /// Snippets typed by `typer snippets listen` by hotkey, e.g. `"Ctrl+Alt+S" = "signature"`.
+    pub bindings: BTreeMap<String, String>,
+}
+
+impl SnippetSettings {
+    pub fn dir(&self) -> PathBuf {
+        self.dir
+            .clone()
+            .unwrap_or_else(snippets::default_snippets_dir)
+    }
+}
+
+/// How `typer form` moves through the fields of a form.
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FormSettings {
+    /// Key that moves on to the next field, e.g. `tab` or `down`.
+    pub next_key: String,
+    /// Key pressed after the last field, e.g. `enter` to submit the form.
+    pub submit_key: Option<String>,
+    /// Options by field name, e.g. `[form.fields.email]`.
+    pub fields: BTreeMap<String, FieldSettings>,
+}
+
+impl Default for FormSettings {
+    fn default() -> Self {
+        FormSettings {
+            next_key: "tab".to_string(),
+            submit_key: None,
+            fields: BTreeMap::new(),
+        }
+    }
+}
+
+impl FormSettings {
+    pub fn next_key(&self) -> Result<Chord, String> {
+        Chord::parse(&self.next_key)
+    }
+
+    pub fn submit_key(&self) -> Result<Option<Chord>, String> {
+        self.submit_key.as_deref().map(Chord::parse).transpose()
+    }
+
+    /// The fields of `record` with their options from these settings.
+    pub fn fields(&self, record: Vec<(String, String)>) -> Result<Vec<FormField>, String> {
+        record
+            .into_iter()
+            .map(|(name, value)| {
+                let options = match self.fields.get(&name) {
+                    Some(field) => FieldOptions {
+                        mistakes: field.mistakes,
+                        keypad: field.keypad,
+                        next: field.next_key.as_deref().map(Chord::parse).transpose()?,
+                    },
+                    None => FieldOptions::default(),
+                };
+                Ok(FormField {
+                    name,
+                    value,
+                    options,
+                })
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct FieldSettings {
+    /// Whether the field may get typos; turn it off for emails and other exact values.
+    pub mistakes: bool,
+    /// Types the digits of the field on the numeric keypad.
+    pub keypad: bool,
+    /// Key that moves on from this field instead of `form.next_key`.
+    pub next_key: Option<String>,
+}
+
+impl Default for FieldSettings {
+    fn default() -> Self {
+        FieldSettings {
+            mistakes: true,
+            keypad: false,
+            next_key: None,
+        }
+    }
+}
+
+/// How a CSV table is typed into a spreadsheet, see [`SheetLayout`].
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct SheetSettings {
+    /// Types CSV texts cell by cell. `typer run --mode sheet` turns it on.
+    pub enabled: bool,
+    /// Key that moves to the next cell, e.g. `tab` or `right`.
+    pub next_cell: String,
+    /// Key that moves to the next row, e.g. `enter` or `down`.
+    pub next_row: String,
+    /// Key pressed after `next_row` to get back to the first column, e.g. `home`.
+    pub row_start: Option<String>,
+    /// Key that types a newline inside a cell.
+    pub cell_newline: String,
+    /// Types the header row of the CSV too.
+    pub header: bool,
+    /// Formatting by column name, e.g. `[sheet.columns.price]` with `decimals = 2`.
+    pub columns: BTreeMap<String, ColumnRule>,
+}
+
+impl Default for SheetSettings {
+    fn default() -> Self {
+        SheetSettings {
+            enabled: false,
+            next_cell: "tab".to_string(),
+            next_row: "enter".to_string(),
+            row_start: None,
+            cell_newline: "alt+enter".to_string(),
+            header: true,
+            columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl SheetSettings {
+    pub fn layout(&self) -> Result<SheetLayout, String> {
+        Ok(SheetLayout {
+            next_cell: Chord::parse(&self.next_cell)?,
+            next_row: Chord::parse(&self.next_row)?,
+            row_start: self.row_start.as_deref().map(Chord::parse).transpose()?,
+            cell_newline: Chord::parse(&self.cell_newline)?,
+            header: self.header,
+            columns: self.columns.clone(),
+        })
+    }
+}
+
+/// What `typer triggers` does when a MIDI note or controller or a Stream Deck key is
+/// pressed, see [`TriggerAction`].
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct TriggerSettings {
+    /// MIDI input to listen to, by part of its name; the first one when not set.
+    pub midi_port: Option<String>,
+    /// Actions by trigger, e.g. `"note 36" = "snippet intro"` or `"deck 0,1" = "pause"`.
+    pub bindings: BTreeMap<String, String>,
+}
+
+/// Shell commands, or URLs to POST to, run as typing goes on, see
+/// [`run_hook`](crate::hooks::run_hook).
+#[derive(Serialize, Deserialize, Default, Clone)]
+#[serde(default)]
+pub struct HookSettings {
+    /// Run once the countdown is over, before the first key is typed.
+    pub on_start: Option<String>,
+    /// Run for every mistake, without waiting for it to finish.
+    pub on_mistake: Option<String>,
+    pub on_complete: Option<String>,
+    pub on_abort: Option<String>,
+}
+
+impl HookSettings {
+    pub fn hook(&self, event: HookEvent) -> Option<&str> {
+        match event {
+            HookEvent::Start => self.on_start.as_deref(),
+            HookEvent::Mistake => self.on_mistake.as_deref(),
+            HookEvent::Complete => self.on_complete.as_deref(),
+            HookEvent::Abort => self.on_abort.as_deref(),
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            version: CONFIG_VERSION,
+            delays: DelaySettings::default(),
+            mistakes: MistakeSettings::default(),
+            pauses: PauseSettings::default(),
+            fatigue: FatigueSettings::default(),
+            warm_up: WarmUpSettings::default(),
+            time_of_day: TimeOfDaySettings::default(),
+            burst: BurstSettings::default(),
+            hold: HoldSettings::default(),
+            keyboard: KeyboardSettings::default(),
+            hotkeys: HotkeySettings::default(),
+            playlist: PlaylistSettings::default(),
+            focus: FocusSettings::default(),
+            blacklist: BlacklistSettings::default(),
+            takeover: TakeoverSettings::default(),
+            lock: LockSettings::default(),
+            code: CodeSettings::default(),
+            editor: EditorSettings::default(),
+            chat: ChatSettings::default(),
+            shell: ShellSettings::default(),
+            viewport: ViewportSettings::default(),
+            autosave: AutosaveSettings::default(),
+            mishaps: MishapSettings::default(),
+            distraction: DistractionSettings::default(),
+            breaks: BreakSettings::default(),
+            notifications: NotificationSettings::default(),
+            key_sounds: KeySoundSettings::default(),
+            paste: PasteSettings::default(),
+            personal: PersonalSettings::default(),
+            snippets: SnippetSettings::default(),
+            form: FormSettings::default(),
+            sheet: SheetSettings::default(),
+            triggers: TriggerSettings::default(),
+            hooks: HookSettings::default(),
+            apps: BTreeMap::new(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Builds settings for a typist averaging roughly `wpm` words per minute.
+    pub fn tuned(wpm: u32, sloppiness: Sloppiness, layout: &str) -> Config {
+        // A word is five characters by convention
+        let keystroke_ms = 12_000 / u64::from(wpm.max(1));
+
+        let mut config = Config::default();
+        config.delays.base_min = keystroke_ms / 2;
+        config.delays.base_max = keystroke_ms * 3 / 2 + 1;
+        config.delays.base_mean = keystroke_ms as f64;
+        config.delays.base_std_dev = (keystroke_ms / 3) as f64;
+        config.keyboard.layout = layout.to_string();
+
+        let mistakes = &mut config.mistakes;
+        match sloppiness {
+            Sloppiness::Careful => {
+                mistakes.probability = mistakes.probability.scaled(4.0);
+                mistakes.transposition_probability = 0;
+                mistakes.double_letter_probability = 0;
+                mistakes.omission_probability = 0;
+                mistakes.case_mistake_probability = 0;
+            }
+            Sloppiness::Normal => {}
+            Sloppiness::Sloppy => {
+                mistakes.probability = mistakes.probability.scaled(0.4);
+                mistakes.transposition_probability = 3;
+                mistakes.double_letter_probability = 3;
+                mistakes.omission_probability = 3;
+                mistakes.case_mistake_probability = 3;
+                mistakes.correction_probability = 90;
+                mistakes.correction_lag_chars_max = 4;
+            }
+        }
+
+        config
+    }
+
+    /// Returns these settings with the named profile applied on top.
+    pub fn with_profile(&self, name: &str) -> Result<Config, Box<dyn std::error::Error>> {
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            format!(
+                "Unknown profile {:?}, available profiles: {}",
+                name,
+                if known.is_empty() {
+                    "none".to_string()
+                } else {
+                    known.join(", ")
+                }
+            )
+        })?;
+
+        let mut settings = serde_json::to_value(self)?;
+        merge_values(&mut settings, profile.clone());
+        let mut config: Config = serde_json::from_value(settings)
+            .map_err(|e| format!("Invalid profile {:?}: {}", name, e))?;
+        config.profiles = self.profiles.clone();
+        Ok(config)
+    }
+
+    /// Returns these settings with `section.field` keys set to the given values.
+    ///
+    /// Values are parsed as JSON when the field is not a string, so `20`, `true` and
+    /// `2.5` work as expected.
+    pub fn with_overrides(
+        &self,
+        overrides: &[(String, String)],
+    ) -> Result<Config, Box<dyn std::error::Error>> {
+        let mut settings = serde_json::to_value(self)?;
+
+        for (key, value) in overrides {
+            let (section, field) = key
+                .split_once('.')
+                .ok_or_else(|| format!("Invalid setting {:?}, expected section.field", key))?;
+            let slot = settings
+                .get_mut(section)
+                .filter(|_| section != "profiles")
+                .and_then(|section| {
+                    field
+                        .split('.')
+                        .try_fold(section, |value, part| value.get_mut(part))
+                })
+                .ok_or_else(|| format!("Unknown setting {:?}", key))?;
+            *slot = match slot {
+                serde_json::Value::String(_) | serde_json::Value::Null => {
+                    serde_json::Value::String(value.clone())
+                }
+                _ => serde_json::from_str(value)
+                    .unwrap_or_else(|_| serde_json::Value::String(value.clone())),
+            };
+
+            // Check each value on its own so the error names the setting at fault
+            serde_json::from_value::<Config>(settings.clone())
+                .map_err(|e| format!("Invalid value {:?} for {}: {}", value, key, e))?;
+        }
+
+        Ok(serde_json::from_value(settings)?)
+    }
+
+    /// Checks that every setting is usable, listing each invalid field and why.
+    pub fn validate(&self) -> Result<(), Box<dyn std::error::Error>> {
+        let mut problems = Vec::new();
+        let mut check = |ok: bool, problem: String| {
+            if !ok {
+                problems.push(problem);
+            }
+        };
+
+        let ranges = [
+            ("delays.base", self.delays.base_min, self.delays.base_max),
+            (
+                "delays.thinking",
+                self.delays.thinking_min,
+                self.delays.thinking_max,
+            ),
+            (
+                "mistakes.correction_delay",
+                self.mistakes.correction_delay_min,
+                self.mistakes.correction_delay_max,
+            ),
+            (
+                "pauses.long_pause",
+                self.pauses.long_pause_min,
+                self.pauses.long_pause_max,
+            ),
+            ("burst.pause", self.burst.pause_min, self.burst.pause_max),
+            (
+                "hold.duration",
+                self.hold.duration_min,
+                self.hold.duration_max,
+            ),
+            (
+                "code.function_pause",
+                self.code.function_pause_min,
+                self.code.function_pause_max,
+            ),
+            ("chat.delay", self.chat.delay_min, self.chat.delay_max),
+            ("shell.delay", self.shell.delay_min, self.shell.delay_max),
+            (
+                "autosave.pause",
+                self.autosave.pause_min,
+                self.autosave.pause_max,
+            ),
+            (
+                "mishaps.pause",
+                self.mishaps.pause_min,
+                self.mishaps.pause_max,
+            ),
+            (
+                "distraction.duration",
+                self.distraction.duration_min,
+                self.distraction.duration_max,
+            ),
+            (
+                "breaks.duration",
+                self.breaks.duration_min,
+                self.breaks.duration_max,
+            ),
+            (
+                "paste.find_delay",
+                self.paste.find_delay_min,
+                self.paste.find_delay_max,
+            ),
+        ];
+        for (name, min, max) in ranges {
+            check(
+                min < max,
+                format!("{name}_min ({min}) must be less than {name}_max ({max})"),
+            );
+        }
+        check(
+            self.mistakes.correction_lag_chars_min <= self.mistakes.correction_lag_chars_max,
+            format!(
+                "mistakes.correction_lag_chars_min ({}) must not be greater than mistakes.correction_lag_chars_max ({})",
+                self.mistakes.correction_lag_chars_min, self.mistakes.correction_lag_chars_max
+            ),
+        );
+        check(
+            self.burst.length_min >= 1 && self.burst.length_min <= self.burst.length_max,
+            format!(
+                "burst.length_min ({}) must be at least 1 and not greater than burst.length_max ({})",
+                self.burst.length_min, self.burst.length_max
+            ),
+        );
+
+        let rates = &self.mistakes.probability;
+        for (class, one_in) in [
+            ("letter", rates.letter),
+            ("digit", rates.digit),
+            ("punctuation", rates.punctuation),
+            ("symbol", rates.symbol),
+            ("whitespace", rates.whitespace),
+        ] {
+            check(
+                one_in >= 1,
+                format!("mistakes.probability.{class} must be at least 1 (it means one mistake in that many keystrokes)"),
+            );
+        }
+        let percentages = [
+            (
+                "mistakes.transposition_probability",
+                self.mistakes.transposition_probability,
+            ),
+            (
+                "mistakes.double_letter_probability",
+                self.mistakes.double_letter_probability,
+            ),
+            (
+                "mistakes.omission_probability",
+                self.mistakes.omission_probability,
+            ),
+            (
+                "mistakes.case_mistake_probability",
+                self.mistakes.case_mistake_probability,
+            ),
+            (
+                "mistakes.misspelling_probability",
+                self.mistakes.misspelling_probability,
+            ),
+            (
+                "mistakes.wrong_word_probability",
+                self.mistakes.wrong_word_probability,
+            ),
+            (
+                "mistakes.correction_probability",
+                self.mistakes.correction_probability,
+            ),
+            (
+                "pauses.long_pause_probability",
+                self.pauses.long_pause_probability,
+            ),
+            ("hold.rollover_probability", self.hold.rollover_probability),
+            ("hold.swap_probability", self.hold.swap_probability),
+            ("key_sounds.volume", self.key_sounds.volume),
+            (
+                "key_sounds.volume_variation",
+                self.key_sounds.volume_variation,
+            ),
+            (
+                "key_sounds.pitch_variation",
+                self.key_sounds.pitch_variation,
+            ),
+        ];
+        for (name, percent) in percentages {
+            let unlike = match name.starts_with("mistakes.") {
+                true => ", unlike mistakes.probability, which is one in that many keystrokes",
+                false => "",
+            };
+            check(
+                percent <= 100,
+                format!("{name} ({percent}) is a percentage and must be between 0 and 100{unlike}"),
+            );
+        }
+
+        let distributions = [
+            (
+                "delays.base",
+                self.delays.base_mean,
+                self.delays.base_std_dev,
+            ),
+            (
+                "delays.thinking",
+                self.delays.thinking_mean,
+                self.delays.thinking_std_dev,
+            ),
+            (
+                "pauses.long_pause",
+                self.pauses.long_pause_mean,
+                self.pauses.long_pause_std_dev,
+            ),
+        ];
+        for (name, mean, std_dev) in distributions {
+            check(mean > 0.0, format!("{name}_mean ({mean}) must be positive"));
+            check(
+                std_dev >= 0.0,
+                format!("{name}_std_dev ({std_dev}) must not be negative"),
+            );
+        }
+
+        let factors = [
+            ("burst.speed_factor", self.burst.speed_factor),
+            (
+                "keyboard.same_finger_factor",
+                self.keyboard.same_finger_factor,
+            ),
+            (
+                "keyboard.hand_alternation_factor",
+                self.keyboard.hand_alternation_factor,
+            ),
+            ("code.symbol_factor", self.code.symbol_factor),
+            ("hotkeys.speed_step", self.hotkeys.speed_step),
+        ];
+        for (name, factor) in factors {
+            check(factor > 0.0, format!("{name} ({factor}) must be positive"));
+        }
+
+        for (name, value) in [
+            (
+                "pauses.read_ahead_ms_per_point",
+                self.pauses.read_ahead_ms_per_point,
+            ),
+            (
+                "pauses.read_ahead_threshold",
+                self.pauses.read_ahead_threshold,
+            ),
+            ("distraction.per_hour", self.distraction.per_hour),
+        ] {
+            check(
+                value.is_finite() && value >= 0.0,
+                format!("{name} ({value}) must not be negative"),
+            );
+        }
+
+        for group in &self.mistakes.wrong_words {
+            check(
+                group.len() >= 2
+                    && group
+                        .iter()
+                        .all(|word| !word.is_empty() && !word.contains(char::is_whitespace)),
+                format!(
+                    "mistakes.wrong_words group {:?} needs at least two single words",
+                    group
+                ),
+            );
+        }
+
+        for opener in self.editor.auto_close_rules.keys() {
+            let mut chars = opener.chars();
+            let valid = match (chars.next(), chars.next()) {
+                (Some(c), None) => code::closer(c).is_some(),
+                _ => false,
+            };
+            check(
+                valid,
+                format!(
+                    "editor.auto_close_rules: {:?} is not one of ( [ {{ \" ' `",
+                    opener
+                ),
+            );
+        }
+
+        check(
+            self.focus.poll_ms > 0,
+            "focus.poll_ms must be positive".to_string(),
+        );
+        check(
+            self.lock.poll_ms > 0,
+            "lock.poll_ms must be positive".to_string(),
+        );
+        check(
+            self.breaks.every_minutes > 0,
+            "breaks.every_minutes must be positive".to_string(),
+        );
+
+        let fatigue = [
+            ("fatigue.onset_minutes", self.fatigue.onset_minutes),
+            ("fatigue.ramp_minutes", self.fatigue.ramp_minutes),
+            ("fatigue.max_slowdown", self.fatigue.max_slowdown),
+            (
+                "fatigue.max_extra_mistakes",
+                self.fatigue.max_extra_mistakes,
+            ),
+            ("fatigue.recovery_rate", self.fatigue.recovery_rate),
+            ("warm_up.seconds", self.warm_up.seconds),
+            ("warm_up.slowdown", self.warm_up.slowdown),
+            ("warm_up.extra_mistakes", self.warm_up.extra_mistakes),
+        ];
+        for (name, value) in fatigue {
+            check(
+                value.is_finite() && value >= 0.0,
+                format!("{name} ({value}) must not be negative"),
+            );
+        }
+
+        for (name, app) in &self.apps {
+            if let Some(speed) = app.speed {
+                check(
+                    speed > 0.0,
+                    format!("apps.{name}.speed ({speed}) must be positive"),
+                );
+            }
+        }
+
+        if self.keyboard.layout_file.is_none() {
+            if let Err(e) = KeyboardLayout::named(&self.keyboard.layout) {
+                problems.push(format!(
+                    "keyboard.layout: {e} (expected qwerty, dvorak, colemak, azerty or qwertz)"
+                ));
+            }
+        }
+        for (name, hotkey) in [
+            ("hotkeys.pause", &self.hotkeys.pause),
+            ("hotkeys.abort", &self.hotkeys.abort),
+            ("hotkeys.slower", &self.hotkeys.slower),
+            ("hotkeys.faster", &self.hotkeys.faster),
+            ("hotkeys.skip_sentence", &self.hotkeys.skip_sentence),
+            ("hotkeys.skip_paragraph", &self.hotkeys.skip_paragraph),
+        ] {
+            if let Err(e) = Hotkey::parse(hotkey) {
+                problems.push(format!("{name}: {e}"));
+            }
+        }
+        let field_keys = self.form.fields.iter().map(|(name, field)| {
+            (
+                format!("form.fields.{name}.next_key"),
+                field.next_key.as_ref(),
+            )
+        });
+        let form_keys = [
+            ("form.next_key".to_string(), Some(&self.form.next_key)),
+            ("form.submit_key".to_string(), self.form.submit_key.as_ref()),
+            ("sheet.next_cell".to_string(), Some(&self.sheet.next_cell)),
+            ("sheet.next_row".to_string(), Some(&self.sheet.next_row)),
+            ("sheet.row_start".to_string(), self.sheet.row_start.as_ref()),
+            (
+                "sheet.cell_newline".to_string(),
+                Some(&self.sheet.cell_newline),
+            ),
+            ("autosave.key".to_string(), Some(&self.autosave.key)),
+            ("mishaps.redo_key".to_string(), Some(&self.mishaps.redo_key)),
+        ];
+        for (name, key) in form_keys.into_iter().chain(field_keys) {
+            if let Some(Err(e)) = key.map(|key| Chord::parse(key)) {
+                problems.push(format!("{name}: {e}"));
+            }
+        }
+        if let Err(e) = self.time_of_day.curve() {
+            problems.push(format!("time_of_day.curve: {e}"));
+        }
+        if let Err(e) = self.shell.prompt_region() {
+            problems.push(format!("shell.prompt_region: {e}"));
+        }
+        for hotkey in self.snippets.bindings.keys() {
+            if let Err(e) = Hotkey::parse(hotkey) {
+                problems.push(format!("snippets.bindings: {e}"));
+            }
+        }
+        for (trigger, action) in &self.triggers.bindings {
+            if let Err(e) = check_trigger(trigger).and(TriggerAction::parse(action).map(|_| ())) {
+                problems.push(format!("triggers.bindings: {e}"));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(format!("Invalid config:\n  {}", problems.join("\n  ")).into())
+        }
+    }
+
+    pub fn keyboard_layout(&self) -> Result<KeyboardLayout, Box<dyn std::error::Error>> {
+        match &self.keyboard.layout_file {
+            Some(path) => KeyboardLayout::from_file(path),
+            None => Ok(KeyboardLayout::named(&self.keyboard.layout)?),
+        }
+    }
+
+    /// The `[apps]` settings, for [`AppWatcher`](crate::apps::AppWatcher).
+    pub fn app_profiles(&self) -> AppProfiles {
+        AppProfiles(
+            self.apps
+                .iter()
+                .map(|(name, app)| {
+                    let profile = AppProfile {
+                        speed: app.speed,
+                        newline_key: app.newline_key,
+                        auto_indent: app.auto_indent,
+                        no_mistakes: app.no_mistakes,
+                    };
+                    (name.clone(), profile)
+                })
+                .collect(),
+        )
+    }
+
+    pub fn to_typing_config(&self) -> Result<TypingConfig, Box<dyn std::error::Error>> {
+        self.validate()?;
+
+        let Config {
+            delays,
+            mistakes,
+            pauses,
+            fatigue,
+            warm_up,
+            time_of_day,
+            burst,
+            hold,
+            keyboard,
+            code,
+            editor,
+            chat,
+            shell,
+            viewport,
+            autosave,
+            mishaps,
+            distraction,
+            breaks,
+            paste,
+            personal,
+            ..
+        } = self;
+
+        let misspellings = match &mistakes.misspellings_file {
+            Some(path) => Some(TypoDictionary::from_file(path)?),
+            None => mistakes.misspellings.then(TypoDictionary::builtin),
+        };
+        let mut auto_close = AutoCloseRules::new(editor.auto_close);
+        for (opener, &rule) in &editor.auto_close_rules {
+            for c in opener.chars() {
+                auto_close.set(c, rule)?;
+            }
+        }
+        let bigrams = match &keyboard.bigram_file {
+            Some(path) => Some(BigramTable::from_file(path)?),
+            None => keyboard.bigram_timing.then(BigramTable::builtin),
+        };
+
+        Ok(TypingConfig {
+            base_delay: Delay::new(
+                delays.distribution,
+                delays.base_min..delays.base_max,
+                delays.base_mean,
+                delays.base_std_dev,
+            ),
+            thinking_delay: Delay::new(
+                delays.distribution,
+                delays.thinking_min..delays.thinking_max,
+                delays.thinking_mean,
+                delays.thinking_std_dev,
+            ),
+            mistake_probability: mistakes.probability,
+            transposition_probability: mistakes.transposition_probability,
+            double_letter_probability: mistakes.double_letter_probability,
+            omission_probability: mistakes.omission_probability,
+            case_mistake_probability: mistakes.case_mistake_probability,
+            misspellings,
+            misspelling_probability: mistakes.misspelling_probability,
+            wrong_words: (mistakes.wrong_word_probability > 0)
+                .then(|| TypoDictionary::from_groups(&mistakes.wrong_words)),
+            wrong_word_probability: mistakes.wrong_word_probability,
+            correction_delay: mistakes.correction_delay_min..mistakes.correction_delay_max,
+            correction_probability: mistakes.correction_probability,
+            correction_lag_chars: mistakes.correction_lag_chars_min
+                ..=mistakes.correction_lag_chars_max,
+            correction_style: mistakes.correction_style,
+            exact: false,
+            keypad: false,
+            newline_key: editor.newline_key,
+            auto_indent: editor.auto_indent,
+            auto_close,
+            long_pause_probability: pauses.long_pause_probability,
+            long_pause_delay: Delay::new(
+                delays.distribution,
+                pauses.long_pause_min..pauses.long_pause_max,
+                pauses.long_pause_mean,
+                pauses.long_pause_std_dev,
+            ),
+            read_ahead: pauses.read_ahead.then_some(ReadAheadConfig {
+                ms_per_point: pauses.read_ahead_ms_per_point,
+                threshold: pauses.read_ahead_threshold,
+                max_pause: pauses.read_ahead_max,
+            }),
+            step: pauses.step,
+            warm_up: warm_up.enabled.then(|| WarmUpConfig {
+                duration: Duration::from_secs_f64(warm_up.seconds),
+                slowdown: warm_up.slowdown,
+                extra_mistakes: warm_up.extra_mistakes,
+            }),
+            time_of_day: match time_of_day.enabled {
+                true => Some(TimeOfDayConfig {
+                    curve: time_of_day.curve()?,
+                    start_hour: {
+                        let now = Local::now();
+                        now.hour() as f64 + now.minute() as f64 / 60.0
+                    },
+                }),
+                false => None,
+            },
+            fatigue: fatigue.enabled.then(|| FatigueConfig {
+                onset: Duration::from_secs_f64(fatigue.onset_minutes * 60.0),
+                ramp: Duration::from_secs_f64(fatigue.ramp_minutes * 60.0),
+                max_slowdown: fatigue.max_slowdown,
+                max_extra_mistakes: fatigue.max_extra_mistakes,
+                recovery_min_pause: Duration::from_millis(fatigue.recovery_min_pause_ms),
+                recovery_rate: fatigue.recovery_rate,
+            }),
+            burst: burst.enabled.then_some(BurstConfig {
+                length: burst.length_min..=burst.length_max,
+                speed_factor: burst.speed_factor,
+                pause: burst.pause_min..burst.pause_max,
+            }),
+            key_hold: hold.enabled.then_some(KeyHoldConfig {
+                duration: Delay::Uniform(hold.duration_min..hold.duration_max),
+                rollover_probability: hold.rollover_probability,
+                timeline: hold.timeline,
+                swap_probability: hold.swap_probability,
+            }),
+            bigrams,
+            finger_timing: keyboard.finger_timing.then_some(FingerTimingConfig {
+                same_finger_factor: keyboard.same_finger_factor,
+                hand_alternation_factor: keyboard.hand_alternation_factor,
+            }),
+            code: code.enabled.then(|| CodeModeConfig {
+                language: code.language.unwrap_or_default(),
+                symbol_factor: code.symbol_factor,
+                mistakes_in_strings: code.mistakes_in_strings,
+                function_pause: Delay::Uniform(code.function_pause_min..code.function_pause_max),
+                strict_whitespace: code.strict_whitespace,
+                verify_lines: code.verify_lines,
+            }),
+            chat: chat.enabled.then_some(ChatConfig {
+                message_delay: Delay::Uniform(chat.delay_min..chat.delay_max),
+            }),
+            shell: shell.enabled.then_some(ShellConfig {
+                command_delay: Delay::Uniform(shell.delay_min..shell.delay_max),
+                prompt: shell.prompt_region.is_some().then_some(PromptDetection {
+                    settle: Duration::from_millis(shell.prompt_settle_ms),
+                    timeout: Duration::from_millis(shell.prompt_timeout_ms),
+                }),
+            }),
+            viewport: viewport.config_for(None),
+            autosave: match autosave.enabled {
+                true => Some(AutosaveConfig {
+                    chord: Chord::parse(&autosave.key)?,
+                    every_paragraphs: autosave.every_paragraphs,
+                    every: autosave.every_seconds.map(Duration::from_secs),
+                    pause: Delay::Uniform(autosave.pause_min..autosave.pause_max),
+                }),
+                false => None,
+            },
+            mishaps: match mishaps.enabled {
+                true => Some(MishapConfig {
+                    one_in_words: mishaps.one_in_words,
+                    allowed: mishaps.allowed.clone(),
+                    redo: Chord::parse(&mishaps.redo_key)?,
+                    pause: Delay::Uniform(mishaps.pause_min..mishaps.pause_max),
+                }),
+                false => None,
+            },
+            distraction: distraction.enabled.then(|| DistractionConfig {
+                per_hour: distraction.per_hour,
+                duration: Delay::Uniform(distraction.duration_min..distraction.duration_max),
+                actions: distraction.actions.clone(),
+            }),
+            breaks: breaks.enabled.then(|| BreakConfig {
+                every: Duration::from_secs(breaks.every_minutes * 60),
+                duration: Delay::Uniform(
+                    breaks.duration_min * 60_000..breaks.duration_max * 60_000,
+                ),
+            }),
+            paste: PasteConfig {
+                min_block_chars: paste.min_block_chars,
+                find_delay: Delay::Uniform(paste.find_delay_min..paste.find_delay_max),
+            },
+            personal: personal
+                .file
+                .as_deref()
+                .map(PersonalProfile::from_file)
+                .transpose()?,
+        })
+    }
+}
+
+/// Collects overrides from `TYPER_<SECTION>_<FIELD>` environment variables, such as
+/// `TYPER_DELAYS_BASE_MIN=30` or `TYPER_MISTAKES_PROBABILITY=20`.
+pub fn env_overrides() -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let mut overrides = Vec::new();
+    let defaults = serde_json::to_value(Config::default())?;
+
+    if let Some(sections) = defaults.as_object() {
+        for (section, fields) in sections {
+            let Some(fields) = fields.as_object() else {
+                continue;
+            };
+            for field in fields.keys() {
+                let var = format!("TYPER_{}_{}", section, field).to_ascii_uppercase();
+                if let Ok(value) = env::var(&var) {
+                    overrides.push((format!("{}.{}", section, field), value));
+                }
+            }
+        }
+    }
+
+    Ok(overrides)
+}
+
+/// Splits a `section.field=value` command line override.
+pub fn parse_override(arg: &str) -> Result<(String, String), String> {
+    arg.split_once('=')
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .ok_or_else(|| format!("Expected section.field=value, got {:?}", arg))
+}
+
+/// Recursively overwrites the fields of `base` with the ones set in `overlay`.
+fn merge_values(base: &mut serde_json::Value, overlay: serde_json::Value) {
+    match (base, overlay) {
+        (serde_json::Value::Object(base), serde_json::Value::Object(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_values(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+/// Returns `config.toml` when it exists, `config.json` otherwise.
+pub fn get_config_path() -> PathBuf {
+    let dir = paths::config_dir();
+    let toml_path = dir.join("config.toml");
+    if toml_path.exists() {
+        return toml_path;
+    }
+    dir.join("config.json")
+}
+
+pub fn ensure_config_exists() -> Result<Config, Box<dyn std::error::Error>> {
+    load_config(&get_config_path())
+}
+
+fn is_toml(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "toml")
+}
+
+/// Parses a config in the format given by the extension of `config_path`, upgrading
+/// it to the current version if needed.
+pub fn parse_config(
+    config_path: &Path,
+    content: &str,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    let mut settings = parse_settings(config_path, content)?;
+    migrate_config(&mut settings)?;
+    Ok(serde_json::from_value(settings)?)
+}
+
+fn parse_settings(
+    config_path: &Path,
+    content: &str,
+) -> Result<serde_json::Value, Box<dyn std::error::Error>> {
+    if is_toml(config_path) {
+        Ok(toml::from_str(content)?)
+    } else {
+        Ok(serde_json::from_str(content)?)
+    }
+}
+
+/// Upgrades raw config settings to [`CONFIG_VERSION`] in place, keeping every value
+/// the user set. Returns the version the settings had before.
+///
+/// Files without a `version` field are treated as version 1, the original flat format.
+pub fn migrate_config(settings: &mut serde_json::Value) -> Result<u32, Box<dyn std::error::Error>> {
+    let settings = settings
+        .as_object_mut()
+        .ok_or("The config must be a table of settings")?;
+    let version = match settings.get("version") {
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| format!("Invalid config version: {}", version))?,
+        None => 1,
+    };
+    if version > CONFIG_VERSION {
+        return Err(format!(
+            "The config has version {} but this typer only understands up to version {}; please upgrade typer",
+            version, CONFIG_VERSION
+        )
+        .into());
+    }
+
+    if version < 2 {
+        // Version 2 grouped the flat settings into sections
+        for (old, section, field) in V1_SETTINGS {
+            if let Some(value) = settings.remove(old) {
+                let section = settings
+                    .entry(section)
+                    .or_insert_with(|| serde_json::Value::Object(Default::default()));
+                if let Some(section) = section.as_object_mut() {
+                    section.insert(field.to_string(), value);
+                }
+            }
+        }
+    }
+
+    settings.insert("version".to_string(), CONFIG_VERSION.into());
+    Ok(version)
+}
+
+/// Serializes a config in the format given by the extension of `config_path`.
+pub fn format_config(
+    config_path: &Path,
+    config: &Config,
+) -> Result<String, Box<dyn std::error::Error>> {
+    if is_toml(config_path) {
+        Ok(toml::to_string_pretty(config)?)
+    } else {
+        Ok(serde_json::to_string_pretty(config)?)
+    }
+}
+
+/// Loads the config at `config_path`, writing the defaults there if it is missing or invalid.
+pub fn load_config(config_path: &Path) -> Result<Config, Box<dyn std::error::Error>> {
+    if !config_path.exists() {
+        let config = Config::default();
+        save_config(config_path, &config)?;
+        return Ok(config);
+    }
+
+    let config_str = fs::read_to_string(config_path)?;
+    let mut settings = match parse_settings(config_path, &config_str) {
+        Ok(settings) => settings,
+        Err(e) => return reset_invalid_config(config_path, &e.to_string()),
+    };
+    let version = migrate_config(&mut settings)?;
+
+    match serde_json::from_value::<Config>(settings) {
+        Ok(config) => {
+            if version < CONFIG_VERSION {
+                let backup = backup_path(config_path);
+                fs::copy(config_path, &backup)?;
+                save_config(config_path, &config)?;
+                println!(
+                    "Upgraded {} from version {} to {}, the old file was backed up to {}.",
+                    config_path.display(),
+                    version,
+                    CONFIG_VERSION,
+                    backup.display()
+                );
+            }
+            Ok(config)
+        }
+        Err(e) => reset_invalid_config(config_path, &e.to_string()),
+    }
+}
+
+/// Backs up a config that cannot be read and replaces it with the defaults.
+fn reset_invalid_config(
+    config_path: &Path,
+    reason: &str,
+) -> Result<Config, Box<dyn std::error::Error>> {
+    // Keep the broken file around so the user's settings aren't lost
+    let backup = backup_path(config_path);
+    fs::copy(config_path, &backup)?;
+    println!(
+        "Warning: Could not parse {}: {}",
+        config_path.display(),
+        reason
+    );
+    println!(
+        "The invalid file was backed up to {}. Creating new config...",
+        backup.display()
+    );
+    let config = Config::default();
+    save_config(config_path, &config)?;
+    Ok(config)
+}
+
+/// Returns a timestamped sibling of `path` such as `config.json.20240101-120000.bak`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(Local::now().format(".%Y%m%d-%H%M%S.bak").to_string());
+    path.with_file_name(name)
+}
+
+pub fn save_config(config_path: &Path, config: &Config) -> Result<(), Box<dyn std::error::Error>> {
+    let config_str = format_config(config_path, config)?;
+    if let Some(dir) = config_path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(config_path, config_str)?;
+    Ok(())
+}
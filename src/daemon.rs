@@ -0,0 +1,341 @@
+use serde::{Deserialize, Serialize};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// A command for `typer daemon`, sent as one line of JSON like
+/// `{"command":"speed","speed":1.5}`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// Replaces the text the next `start` types.
+    Load {
+        text: String,
+        origin: String,
+    },
+    Start,
+    Pause,
+    Resume,
+    Abort,
+    /// Sets the speed factor, also while typing.
+    Speed {
+        speed: f64,
+    },
+    /// Changes config settings, as `--set` does, for the runs started from then on.
+    Set {
+        overrides: Vec<(String, String)>,
+    },
+    Status,
+}
+
+/// Config sections a [`Request::Set`] may change, which only shape how typing goes.
+const SETTABLE_SECTIONS: [&str; 9] = [
+    "delays",
+    "mistakes",
+    "pauses",
+    "fatigue",
+    "warm_up",
+    "time_of_day",
+    "burst",
+    "hold",
+    "keyboard",
+];
+
+/// Settings of those sections naming a file, for no client to point elsewhere.
+const UNSETTABLE: [&str; 3] = [
+    "mistakes.misspellings_file",
+    "keyboard.layout_file",
+    "keyboard.bigram_file",
+];
+
+/// Checks that a [`Request::Set`] only changes how typing goes. Anyone with the token
+/// may send one, so no setting that runs commands, like `hooks.on_start`, or names a
+/// file can be changed this way.
+pub fn check_settable(overrides: &[(String, String)]) -> Result<(), String> {
+    for (key, _) in overrides {
+        let section = key.split('.').next().unwrap_or(key);
+        let settable = SETTABLE_SECTIONS.contains(&section)
+            && !UNSETTABLE
+                .iter()
+                .any(|unsettable| key.starts_with(unsettable));
+        if !settable {
+            return Err(format!("{} cannot be changed through the daemon", key));
+        }
+    }
+    Ok(())
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DaemonState {
+    #[default]
+    Idle,
+    Typing,
+    Paused,
+}
+
+/// What the daemon is doing, sent along with every answer.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonStatus {
+    pub state: DaemonState,
+    /// Where the loaded text came from, when there is one.
+    pub loaded: Option<String>,
+    /// Characters typed of the text being typed, or of the last one.
+    pub position: usize,
+    pub total: usize,
+    pub speed: f64,
+}
+
+/// The answer to a [`Request`], one line of JSON.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<DaemonStatus>,
+}
+
+impl Response {
+    pub fn ok(status: DaemonStatus) -> Self {
+        Response {
+            ok: true,
+            error: None,
+            status: Some(status),
+        }
+    }
+
+    pub fn error(error: impl Into<String>) -> Self {
+        Response {
+            ok: false,
+            error: Some(error.into()),
+            status: None,
+        }
+    }
+}
+
+/// Something that happened in the daemon, streamed to whoever follows it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum DaemonEvent {
+    /// Another character was typed.
+    Progress { position: usize, total: usize },
+    /// A run started, paused, resumed or ended, or the text or speed changed.
+    Status { status: DaemonStatus },
+}
+
+/// Hands every event sent to it to each subscriber still listening.
+#[derive(Clone, Default)]
+pub struct Events {
+    subscribers: Arc<Mutex<Vec<Sender<DaemonEvent>>>>,
+}
+
+impl Events {
+    pub fn subscribe(&self) -> Receiver<DaemonEvent> {
+        let (tx, rx) = mpsc::channel();
+        self.subscribers.lock().unwrap().push(tx);
+        rx
+    }
+
+    pub fn send(&self, event: DaemonEvent) {
+        self.subscribers
+            .lock()
+            .unwrap()
+            .retain(|subscriber| subscriber.send(event.clone()).is_ok());
+    }
+}
+
+/// Where the daemon listens: a Unix socket in the runtime directory, or a named pipe on
+/// Windows.
+pub fn socket_path() -> PathBuf {
+    #[cfg(windows)]
+    return PathBuf::from(r"\\.\pipe\typer");
+    #[cfg(not(windows))]
+    dirs::runtime_dir()
+        .unwrap_or_else(crate::paths::data_dir)
+        .join("typer.sock")
+}
+
+/// Answers every request line of every connection to `path` with `handle`, each
+/// connection on its own thread. Only returns when listening fails.
+pub fn serve<F>(path: &Path, handle: F) -> Result<(), Box<dyn std::error::Error>>
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let handle = Arc::new(handle);
+    let mut listener = Listener::bind(path)?;
+    loop {
+        let (reader, writer) = listener.accept()?;
+        let handle = Arc::clone(&handle);
+        thread::spawn(move || answer(reader, writer, &*handle));
+    }
+}
+
+fn answer<F>(reader: impl Read, mut writer: impl Write, handle: &F)
+where
+    F: Fn(Request) -> Response,
+{
+    for line in BufReader::new(reader).lines() {
+        let Ok(line) = line else {
+            return;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(request),
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        };
+        let Ok(mut json) = serde_json::to_string(&response) else {
+            return;
+        };
+        json.push('\n');
+        if writer.write_all(json.as_bytes()).is_err() || writer.flush().is_err() {
+            return;
+        }
+    }
+}
+
+/// Sends `request` to the daemon listening at `path` and waits for the answer.
+pub fn send(path: &Path, request: &Request) -> Result<Response, Box<dyn std::error::Error>> {
+    let (reader, mut writer) = connect(path).map_err(|e| {
+        format!(
+            "Could not reach typer daemon at {}: {} (is it running?)",
+            path.display(),
+            e
+        )
+    })?;
+    let mut json = serde_json::to_string(request)?;
+    json.push('\n');
+    writer.write_all(json.as_bytes())?;
+    writer.flush()?;
+
+    let mut line = String::new();
+    BufReader::new(reader).read_line(&mut line)?;
+    if line.is_empty() {
+        return Err("typer daemon closed the connection without answering".into());
+    }
+    Ok(serde_json::from_str(&line)?)
+}
+
+#[cfg(unix)]
+struct Listener(std::os::unix::net::UnixListener);
+
+#[cfg(unix)]
+impl Listener {
+    fn bind(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        use std::os::unix::net::{UnixListener, UnixStream};
+
+        if path.exists() {
+            // A socket nobody answers on is left over from a daemon that crashed
+            if UnixStream::connect(path).is_ok() {
+                return Err(
+                    format!("typer daemon is already running at {}", path.display()).into(),
+                );
+            }
+            std::fs::remove_file(path)?;
+        }
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let listener = UnixListener::bind(path)
+            .map_err(|e| format!("Failed to listen at {}: {}", path.display(), e))?;
+        Ok(Listener(listener))
+    }
+
+    fn accept(&mut self) -> Result<(impl Read, impl Write), Box<dyn std::error::Error>> {
+        let (stream, _) = self.0.accept()?;
+        Ok((stream.try_clone()?, stream))
+    }
+}
+
+#[cfg(unix)]
+fn connect(path: &Path) -> std::io::Result<(impl Read, impl Write)> {
+    let stream = std::os::unix::net::UnixStream::connect(path)?;
+    Ok((stream.try_clone()?, stream))
+}
+
+#[cfg(windows)]
+struct Listener {
+    name: Vec<u16>,
+    first: bool,
+}
+
+#[cfg(windows)]
+impl Listener {
+    fn bind(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        use std::os::windows::ffi::OsStrExt;
+
+        let name = path.as_os_str().encode_wide().chain([0]).collect();
+        Ok(Listener { name, first: true })
+    }
+
+    /// Creates an instance of the pipe and waits for a client to connect to it.
+    fn accept(&mut self) -> Result<(impl Read, impl Write), Box<dyn std::error::Error>> {
+        use std::fs::File;
+        use std::os::windows::io::FromRawHandle;
+        use windows_sys::Win32::Foundation::{
+            CloseHandle, GetLastError, ERROR_ACCESS_DENIED, ERROR_PIPE_CONNECTED,
+            INVALID_HANDLE_VALUE,
+        };
+        use windows_sys::Win32::Storage::FileSystem::{
+            FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+        };
+        use windows_sys::Win32::System::Pipes::{
+            ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+            PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+        };
+
+        // The first instance fails if another daemon already owns the pipe
+        let open_mode = match self.first {
+            true => PIPE_ACCESS_DUPLEX | FILE_FLAG_FIRST_PIPE_INSTANCE,
+            false => PIPE_ACCESS_DUPLEX,
+        };
+        let pipe_mode =
+            PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS;
+        let handle = unsafe {
+            CreateNamedPipeW(
+                self.name.as_ptr(),
+                open_mode,
+                pipe_mode,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return match unsafe { GetLastError() } {
+                ERROR_ACCESS_DENIED if self.first => Err("typer daemon is already running".into()),
+                _ => Err(format!(
+                    "Failed to create the pipe: {}",
+                    std::io::Error::last_os_error()
+                )
+                .into()),
+            };
+        }
+        self.first = false;
+
+        let connected = unsafe { ConnectNamedPipe(handle, std::ptr::null_mut()) } != 0;
+        // A client may connect between creating the pipe and waiting for one
+        if !connected && unsafe { GetLastError() } != ERROR_PIPE_CONNECTED {
+            let error = std::io::Error::last_os_error();
+            unsafe { CloseHandle(handle) };
+            return Err(format!("Failed to accept a connection: {}", error).into());
+        }
+        let pipe = unsafe { File::from_raw_handle(handle as _) };
+        Ok((pipe.try_clone()?, pipe))
+    }
+}
+
+#[cfg(windows)]
+fn connect(path: &Path) -> std::io::Result<(impl Read, impl Write)> {
+    let pipe = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(path)?;
+    Ok((pipe.try_clone()?, pipe))
+}
@@ -0,0 +1,265 @@
+use crate::calibrate::DelayFit;
+use crate::profile::{CharClass, CorrectionHabits, ErrorRates, Latency, PersonalProfile};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Longer gaps between presses are breaks between phrases or sessions, not typing.
+const MAX_GAP_MS: f64 = 2000.0;
+/// Bigrams measured fewer times than this are left to the overall keystroke latency.
+const MIN_BIGRAM_SAMPLES: usize = 5;
+/// Character classes typed fewer times than this get no error rate.
+const MIN_CLASS_SAMPLES: usize = 20;
+const MIN_INTERVALS: usize = 20;
+
+const KEY_COLUMNS: &[&str] = &["key", "keys", "letter", "char", "character", "keyname"];
+const PRESS_COLUMNS: &[&str] = &[
+    "press",
+    "press_time",
+    "presstime",
+    "keydown",
+    "key_down",
+    "down",
+    "down_time",
+];
+const RELEASE_COLUMNS: &[&str] = &[
+    "release",
+    "release_time",
+    "releasetime",
+    "keyup",
+    "key_up",
+    "up",
+    "up_time",
+];
+const EVENT_COLUMNS: &[&str] = &["event", "type", "action", "direction"];
+const TIME_COLUMNS: &[&str] = &["time", "timestamp", "time_ms", "t"];
+
+/// A key as named in a dataset.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DatasetKey {
+    Char(char),
+    Backspace,
+    /// Shift, arrows and other keys that do not type anything.
+    Other,
+}
+
+impl DatasetKey {
+    /// Reads the key names used by common datasets, e.g. `a`, `SPACE`, `Key.space`, `BKSP`.
+    pub fn parse(name: &str) -> DatasetKey {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return DatasetKey::Char(c);
+        }
+        let name = name.trim().to_lowercase();
+        let name = name.strip_prefix("key.").unwrap_or(&name);
+        match name {
+            "" | "space" | "spacebar" => DatasetKey::Char(' '),
+            "enter" | "return" => DatasetKey::Char('\n'),
+            "tab" => DatasetKey::Char('\t'),
+            "backspace" | "bksp" | "back" | "bs" => DatasetKey::Backspace,
+            "period" => DatasetKey::Char('.'),
+            "comma" => DatasetKey::Char(','),
+            _ => DatasetKey::Other,
+        }
+    }
+}
+
+/// One key press from a keystroke-dynamics dataset, with times in milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct KeyPress {
+    pub key: DatasetKey,
+    pub press: f64,
+    pub release: Option<f64>,
+}
+
+/// Reads a CSV (or tab or semicolon separated) keystroke dataset, sorted by press time.
+///
+/// Two layouts are understood, told apart by their header: one row per key with
+/// press and release columns, or one row per event with key, event (`down`/`up`) and
+/// time columns. `time_scale` converts the times to milliseconds, e.g. 1000 for seconds.
+pub fn read_dataset(
+    path: &Path,
+    time_scale: f64,
+) -> Result<Vec<KeyPress>, Box<dyn std::error::Error>> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    parse_dataset(&content, time_scale).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Parses the contents of a keystroke dataset, see [`read_dataset`].
+pub fn parse_dataset(content: &str, time_scale: f64) -> Result<Vec<KeyPress>, String> {
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = [b'\t', b';', b',']
+        .into_iter()
+        .max_by_key(|&d| header.bytes().filter(|&b| b == d).count())
+        .unwrap();
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|h| h.to_lowercase().replace([' ', '-'], "_"))
+        .collect();
+    let column = |names: &[&str]| headers.iter().position(|h| names.contains(&h.as_str()));
+
+    let key = column(KEY_COLUMNS).ok_or("No key column, expected one named \"key\"")?;
+    let mut presses = Vec::new();
+    let mut open: HashMap<String, Vec<usize>> = HashMap::new();
+
+    let layout = match (
+        column(PRESS_COLUMNS),
+        column(EVENT_COLUMNS),
+        column(TIME_COLUMNS),
+    ) {
+        (Some(press), _, _) => Ok((press, column(RELEASE_COLUMNS))),
+        (None, Some(event), Some(time)) => Err((event, time)),
+        _ => {
+            return Err("Expected press and release columns, or event and time columns".to_string())
+        }
+    };
+
+    for (row, record) in reader.records().enumerate() {
+        let record = record.map_err(|e| e.to_string())?;
+        let field = |index: usize| record.get(index).unwrap_or_default();
+        let time = |index: usize| -> Result<f64, String> {
+            field(index)
+                .parse::<f64>()
+                .map(|t| t * time_scale)
+                .map_err(|_| format!("Row {}: {:?} is not a time", row + 2, field(index)))
+        };
+        // A lone space is trimmed to an empty name, which parses as space
+        let name = record.get(key).unwrap_or_default();
+
+        match layout {
+            Ok((press, release)) => presses.push(KeyPress {
+                key: DatasetKey::parse(name),
+                press: time(press)?,
+                release: release
+                    .filter(|&index| !field(index).is_empty())
+                    .map(time)
+                    .transpose()?,
+            }),
+            Err((event, at)) => {
+                let event = field(event).to_lowercase();
+                if ["down", "keydown", "key_down", "press", "pressed", "kd"]
+                    .contains(&event.as_str())
+                {
+                    open.entry(name.to_string())
+                        .or_default()
+                        .push(presses.len());
+                    presses.push(KeyPress {
+                        key: DatasetKey::parse(name),
+                        press: time(at)?,
+                        release: None,
+                    });
+                } else if let Some(index) = open
+                    .get_mut(name)
+                    .and_then(|open| (!open.is_empty()).then(|| open.remove(0)))
+                {
+                    presses[index].release = Some(time(at)?);
+                }
+            }
+        }
+    }
+
+    if presses.is_empty() {
+        return Err("The dataset has no key presses".to_string());
+    }
+    presses.sort_by(|a, b| a.press.total_cmp(&b.press));
+    Ok(presses)
+}
+
+/// Fits a personal profile to the key presses of one typist.
+///
+/// Bigram latencies are the times between consecutive presses, hold times those from
+/// press to release of a character, error rates count the characters that were erased
+/// with Backspace right after being typed, and the correction delay is the pause before
+/// such a Backspace.
+pub fn fit_profile(name: &str, presses: &[KeyPress]) -> Result<PersonalProfile, String> {
+    let mut bigrams: BTreeMap<String, Vec<u64>> = BTreeMap::new();
+    let mut intervals = Vec::new();
+    let mut correction_delays = Vec::new();
+    let mut holds = Vec::new();
+    let mut typed: HashMap<CharClass, usize> = HashMap::new();
+    let mut erased: HashMap<CharClass, usize> = HashMap::new();
+
+    for (index, press) in presses.iter().enumerate() {
+        let previous = index.checked_sub(1).map(|i| presses[i]);
+        let gap = previous
+            .map(|previous| press.press - previous.press)
+            .filter(|gap| (0.0..=MAX_GAP_MS).contains(gap));
+
+        match (previous.map(|p| p.key), press.key) {
+            (_, DatasetKey::Char(c)) => {
+                holds.extend(
+                    press
+                        .release
+                        .map(|release| release - press.press)
+                        .filter(|hold| (0.0..=MAX_GAP_MS).contains(hold))
+                        .map(|hold| hold.round() as u64),
+                );
+                *typed.entry(CharClass::of(c)).or_default() += 1;
+                if let (Some(DatasetKey::Char(first)), Some(gap)) = (previous.map(|p| p.key), gap) {
+                    let pair: String = [first, c].iter().flat_map(|c| c.to_lowercase()).collect();
+                    bigrams.entry(pair).or_default().push(gap.round() as u64);
+                    intervals.push(gap.round() as u64);
+                }
+            }
+            (Some(DatasetKey::Char(wrong)), DatasetKey::Backspace) => {
+                *erased.entry(CharClass::of(wrong)).or_default() += 1;
+                correction_delays.extend(gap.map(|gap| gap.round() as u64));
+            }
+            _ => {}
+        }
+    }
+
+    if intervals.len() < MIN_INTERVALS {
+        return Err(format!(
+            "Not enough consecutive key presses, found {} intervals but need {}",
+            intervals.len(),
+            MIN_INTERVALS
+        ));
+    }
+
+    let rate = |class: CharClass| {
+        let total = typed.get(&class).copied().unwrap_or(0);
+        let errors = erased.get(&class).copied().unwrap_or(0);
+        (total >= MIN_CLASS_SAMPLES).then(|| (errors as f64 / total as f64 * 100.0).min(100.0))
+    };
+    let correction_delay = DelayFit::new(&mut correction_delays);
+
+    Ok(PersonalProfile {
+        name: name.to_string(),
+        bigrams: bigrams
+            .into_iter()
+            .filter(|(_, delays)| delays.len() >= MIN_BIGRAM_SAMPLES)
+            .filter_map(|(pair, mut delays)| Some((pair, latency(&mut delays)?)))
+            .collect(),
+        keystroke: latency(&mut intervals),
+        hold: latency(&mut holds),
+        error_rates: ErrorRates {
+            letter: rate(CharClass::Letter),
+            digit: rate(CharClass::Digit),
+            punctuation: rate(CharClass::Punctuation),
+            symbol: rate(CharClass::Symbol),
+            whitespace: rate(CharClass::Whitespace),
+        },
+        corrections: CorrectionHabits {
+            delay_min: correction_delay.as_ref().map(|fit| fit.min),
+            delay_max: correction_delay.as_ref().map(|fit| fit.max),
+            ..CorrectionHabits::default()
+        },
+    })
+}
+
+fn latency(delays: &mut [u64]) -> Option<Latency> {
+    DelayFit::new(delays)
+        .filter(|fit| fit.mean > 0.0)
+        .map(|fit| Latency {
+            mean: fit.mean,
+            std_dev: fit.std_dev,
+        })
+}
@@ -0,0 +1,279 @@
+use crate::events::{Chord, Key};
+use crate::typist::{HumanTypist, TypingOutcome};
+use std::fmt;
+use std::process::Command;
+
+/// What happened to one line between two versions of a text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineChange<'a> {
+    Keep(&'a str),
+    Remove(&'a str),
+    Add(&'a str),
+}
+
+/// The lines of `old` and `new` as kept, removed and added, with the removed lines of a
+/// change before the lines added in their place.
+///
+/// Texts are split at every newline, so a text ending in one has an empty last line,
+/// just like the document in an editor.
+pub fn diff_lines<'a>(old: &'a str, new: &'a str) -> Vec<LineChange<'a>> {
+    let old: Vec<&str> = old.split('\n').collect();
+    let new: Vec<&str> = new.split('\n').collect();
+
+    // Lines the texts start and end with alike are kept without a look at the rest
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let (a, b) = (
+        &old[prefix..old.len() - suffix],
+        &new[prefix..new.len() - suffix],
+    );
+
+    // Length of the longest common subsequence of a[i..] and b[j..]
+    let width = b.len() + 1;
+    let mut common = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i * width + j] = if a[i] == b[j] {
+                common[(i + 1) * width + j + 1] + 1
+            } else {
+                common[(i + 1) * width + j].max(common[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut changes: Vec<LineChange> = old[..prefix].iter().map(|&l| LineChange::Keep(l)).collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            changes.push(LineChange::Keep(a[i]));
+            i += 1;
+            j += 1;
+        } else if i < a.len()
+            && (j == b.len() || common[(i + 1) * width + j] >= common[i * width + j + 1])
+        {
+            changes.push(LineChange::Remove(a[i]));
+            i += 1;
+        } else {
+            changes.push(LineChange::Add(b[j]));
+            j += 1;
+        }
+    }
+    changes.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|&l| LineChange::Keep(l)),
+    );
+    changes
+}
+
+/// The lines a patch adds to one file.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PatchFile {
+    pub path: String,
+    /// The added lines, with an empty line between those of separate hunks.
+    pub added: String,
+}
+
+/// The files of a unified diff, like `git show` or a `.patch` file prints, with the
+/// lines added to each. Deleted files and files with nothing added are left out.
+pub fn parse_patch(patch: &str) -> Vec<PatchFile> {
+    let mut files: Vec<PatchFile> = Vec::new();
+    let mut in_hunk = false;
+    let mut hunk_start = false;
+    for line in patch.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            // Anything after a tab is a timestamp
+            let path = path.split('\t').next().unwrap_or_default().trim();
+            let path = path.strip_prefix("b/").unwrap_or(path);
+            in_hunk = false;
+            if path != "/dev/null" {
+                files.push(PatchFile {
+                    path: path.to_string(),
+                    added: String::new(),
+                });
+            }
+        } else if line.starts_with("@@") {
+            in_hunk = files.last().is_some();
+            hunk_start = true;
+        } else if line.starts_with("diff ") || line.starts_with("--- ") {
+            in_hunk = false;
+        } else if let (true, Some(added)) = (in_hunk, line.strip_prefix('+')) {
+            let file = files.last_mut().expect("hunks follow a file header");
+            if hunk_start && !file.added.is_empty() {
+                file.added.push('\n');
+            }
+            hunk_start = false;
+            file.added.push_str(added);
+            file.added.push('\n');
+        }
+    }
+    files.retain(|file| !file.added.is_empty());
+    files
+}
+
+/// The patch of a git commit, or of a range of them, from `git show`.
+pub fn git_patch(revision: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let output = Command::new("git")
+        .args(["show", "--format=", "--no-color", "--no-ext-diff", revision])
+        .output()
+        .map_err(|e| format!("Failed to run git: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "git show {} failed: {}",
+            revision,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+        .into());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// The lines added by `changes`, each followed by a newline.
+pub fn added_lines(changes: &[LineChange]) -> String {
+    changes
+        .iter()
+        .filter_map(|change| match change {
+            LineChange::Add(line) => Some(format!("{}\n", line)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// One step of turning the old text into the new one in an editor.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiffStep {
+    /// Presses a chord this many times.
+    Press(Chord, usize),
+    /// Types text.
+    Type(String),
+}
+
+impl fmt::Display for DiffStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DiffStep::Press(chord, 1) => write!(f, "press {}", chord),
+            DiffStep::Press(chord, count) => write!(f, "press {} x{}", chord, count),
+            DiffStep::Type(text) => write!(f, "type {:?}", text),
+        }
+    }
+}
+
+/// The key presses and typing that edit the old text of `changes` into the new one,
+/// starting anywhere in a document holding the old text.
+///
+/// The cursor goes to the start of the document and down line by line with the arrow
+/// keys, so this needs an editor that moves by lines of the text rather than wrapped
+/// lines on screen. Removed lines are selected with Shift+Down and deleted; added lines
+/// are typed in front of the line that follows them, or after the end of the document.
+pub fn plan(changes: &[LineChange]) -> Vec<DiffStep> {
+    let old_lines = changes
+        .iter()
+        .filter(|change| !matches!(change, LineChange::Add(_)))
+        .count();
+    let mut steps = vec![DiffStep::Press(Chord::key(Key::Home).with(Key::Control), 1)];
+    // Old lines passed so far, and those still to be passed with Down before an edit
+    let mut line = 0;
+    let mut pending_down = 0;
+    // Whether the cursor is at the end of the document, after removing its last lines
+    let mut at_end = false;
+    let mut empty = false;
+
+    let mut index = 0;
+    while index < changes.len() {
+        let run = changes[index..]
+            .iter()
+            .take_while(|change| same_kind(change, &changes[index]))
+            .count();
+        match changes[index] {
+            LineChange::Keep(_) => {
+                pending_down += run;
+                line += run;
+            }
+            LineChange::Remove(_) => {
+                flush_down(&mut steps, &mut pending_down);
+                if line + run < old_lines {
+                    push_press(&mut steps, Chord::key(Key::Down).with(Key::Shift), run);
+                    push_press(&mut steps, Chord::key(Key::Delete), 1);
+                } else {
+                    // No line left below to select up to: take the newline before instead
+                    if line > 0 {
+                        push_press(&mut steps, Chord::key(Key::Backspace), 1);
+                    }
+                    let to_end = Chord::key(Key::End).with(Key::Control).with(Key::Shift);
+                    push_press(&mut steps, to_end, 1);
+                    push_press(&mut steps, Chord::key(Key::Delete), 1);
+                    at_end = true;
+                    empty = line == 0;
+                }
+                line += run;
+            }
+            LineChange::Add(_) => {
+                let added: Vec<&str> = changes[index..index + run]
+                    .iter()
+                    .filter_map(|change| match change {
+                        LineChange::Add(line) => Some(*line),
+                        _ => None,
+                    })
+                    .collect();
+                let text = if line < old_lines {
+                    flush_down(&mut steps, &mut pending_down);
+                    added.iter().map(|line| format!("{}\n", line)).collect()
+                } else {
+                    if !at_end {
+                        push_press(&mut steps, Chord::key(Key::End).with(Key::Control), 1);
+                    }
+                    match empty {
+                        true => added.join("\n"),
+                        false => format!("\n{}", added.join("\n")),
+                    }
+                };
+                steps.push(DiffStep::Type(text));
+                empty = false;
+            }
+        }
+        index += run;
+    }
+    steps
+}
+
+/// Runs the steps of a [`plan`], typing text the way the typist types anything else.
+pub fn type_diff(typist: &mut HumanTypist, steps: &[DiffStep]) -> TypingOutcome {
+    for step in steps {
+        if typist.controls().is_aborted() {
+            return TypingOutcome::Aborted;
+        }
+        match step {
+            DiffStep::Press(chord, count) => {
+                for _ in 0..*count {
+                    typist.press(chord.clone());
+                }
+            }
+            DiffStep::Type(text) => {
+                if typist.type_text(text) == TypingOutcome::Aborted {
+                    return TypingOutcome::Aborted;
+                }
+            }
+        }
+    }
+    TypingOutcome::Completed
+}
+
+fn same_kind(a: &LineChange, b: &LineChange) -> bool {
+    std::mem::discriminant(a) == std::mem::discriminant(b)
+}
+
+fn flush_down(steps: &mut Vec<DiffStep>, pending: &mut usize) {
+    push_press(steps, Chord::key(Key::Down), *pending);
+    *pending = 0;
+}
+
+fn push_press(steps: &mut Vec<DiffStep>, chord: Chord, count: usize) {
+    if count > 0 {
+        steps.push(DiffStep::Press(chord, count));
+    }
+}
@@ -0,0 +1,217 @@
+use crate::events::Chord;
+use std::ops::Range;
+
+/// An instruction embedded in the text between double braces, like `{{pause:2000}}`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Directive {
+    /// Waits this many milliseconds.
+    Pause(u64),
+    /// Waits this many milliseconds after the newline ending its line, e.g. for the
+    /// command typed on it to finish, see [`ShellConfig`](crate::shell::ShellConfig).
+    Wait(u64),
+    /// Presses a key or chord, e.g. `{{key:tab}}` or `{{key:ctrl+s}}`.
+    Key(Chord),
+    /// Types this many times as fast as configured from here on, e.g. `{{speed:2x}}`.
+    Speed(f64),
+    /// Moves the mouse pointer to a point of the screen, e.g. `{{move:640,480}}`.
+    Move(i32, i32),
+    /// Clicks, or double-clicks, the left mouse button, after moving the pointer to
+    /// the point given, if any, e.g. `{{click:640,480}}` or `{{doubleclick}}`.
+    Click {
+        at: Option<(i32, i32)>,
+        double: bool,
+    },
+    /// Pastes the text up to this character index instead of typing it, from
+    /// `{{paste}}` to `{{/paste}}`, see [`PasteConfig`](crate::paste::PasteConfig).
+    Paste { end: usize },
+}
+
+/// A text to type with the directives taken out of it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Script {
+    /// The characters to type.
+    pub text: String,
+    /// Directives by the character index of `text` they come before, in order.
+    pub directives: Vec<(usize, Directive)>,
+}
+
+impl Script {
+    /// A script without directives, typing `text` as it is.
+    pub fn plain(text: &str) -> Self {
+        Script {
+            text: text.to_string(),
+            directives: Vec::new(),
+        }
+    }
+
+    /// Takes the directives out of `raw`.
+    ///
+    /// Only double braces around a known directive name count as a directive, so
+    /// `{{` elsewhere, as in templates or format strings, is typed as it is. Everything
+    /// after `{{stop}}` is left untyped.
+    pub fn parse(raw: &str) -> Result<Script, String> {
+        let mut script = Script::default();
+        let mut count = 0;
+        let mut rest = raw;
+        // Index in `directives` of a paste block still waiting for its end
+        let mut open_paste: Option<usize> = None;
+
+        while let Some(open) = rest.find("{{") {
+            let Some(close) = rest[open..].find("}}").map(|close| open + close) else {
+                break;
+            };
+            let (name, arg) = match rest[open + 2..close].split_once(':') {
+                Some((name, arg)) => (name.trim(), Some(arg.trim())),
+                None => (rest[open + 2..close].trim(), None),
+            };
+            let directive = match (name.to_ascii_lowercase().as_str(), arg) {
+                ("stop", None) => {
+                    script.text.push_str(&rest[..open]);
+                    if let Some(index) = open_paste {
+                        let end = script.text.chars().count();
+                        script.directives[index].1 = Directive::Paste { end };
+                    }
+                    return Ok(script);
+                }
+                ("pause", Some(arg)) => Directive::Pause(parse_millis(arg)?),
+                ("wait", Some(arg)) => Directive::Wait(parse_millis(arg)?),
+                ("key", Some(arg)) => Directive::Key(Chord::parse(arg)?),
+                ("speed", Some(arg)) => Directive::Speed(parse_speed(arg)?),
+                ("move", Some(arg)) => {
+                    let (x, y) = parse_point(arg)?;
+                    Directive::Move(x, y)
+                }
+                ("paste", None) if open_paste.is_none() => {
+                    open_paste = Some(script.directives.len());
+                    Directive::Paste { end: 0 }
+                }
+                ("/paste", None) => {
+                    let index = open_paste.take().ok_or("{{/paste}} without {{paste}}")?;
+                    script.text.push_str(&rest[..open]);
+                    count += rest[..open].chars().count();
+                    script.directives[index].1 = Directive::Paste { end: count };
+                    rest = &rest[close + 2..];
+                    continue;
+                }
+                (name @ ("click" | "doubleclick"), arg) => Directive::Click {
+                    at: arg.map(parse_point).transpose()?,
+                    double: name == "doubleclick",
+                },
+                ("stop" | "pause" | "wait" | "key" | "speed" | "move" | "paste", _) => {
+                    return Err(format!(
+                        "Malformed directive {}, expected {{{{pause:MS}}}}, {{{{wait:MS}}}}, {{{{key:KEY}}}}, {{{{speed:FACTOR}}}}, {{{{move:X,Y}}}}, {{{{click:X,Y}}}} or {{{{stop}}}}",
+                        &rest[open..close + 2]
+                    ))
+                }
+                // Not a directive, keep the braces as text
+                _ => {
+                    script.text.push_str(&rest[..open + 2]);
+                    count += rest[..open + 2].chars().count();
+                    rest = &rest[open + 2..];
+                    continue;
+                }
+            };
+
+            script.text.push_str(&rest[..open]);
+            count += rest[..open].chars().count();
+            script.directives.push((count, directive));
+            rest = &rest[close + 2..];
+        }
+
+        if open_paste.is_some() {
+            return Err("{{paste}} without {{/paste}}".to_string());
+        }
+        script.text.push_str(rest);
+        Ok(script)
+    }
+}
+
+/// Names of the directives, the only ones double braces are taken out for.
+const NAMES: [&str; 10] = [
+    "stop",
+    "pause",
+    "wait",
+    "key",
+    "speed",
+    "move",
+    "paste",
+    "/paste",
+    "click",
+    "doubleclick",
+];
+
+/// The character ranges of `raw` that directives take up, braces included, found the
+/// way [`Script::parse`] finds them.
+pub fn directive_spans(raw: &str) -> Vec<Range<usize>> {
+    let mut spans = Vec::new();
+    let (mut rest, mut offset) = (raw, 0);
+    while let Some(open) = rest.find("{{") {
+        let Some(close) = rest[open..].find("}}").map(|close| open + close) else {
+            break;
+        };
+        let inner = &rest[open + 2..close];
+        let name = inner.split_once(':').map_or(inner, |(name, _)| name);
+        let skip = match NAMES.contains(&name.trim().to_ascii_lowercase().as_str()) {
+            true => {
+                let start = raw[..offset + open].chars().count();
+                spans.push(start..start + rest[open..close + 2].chars().count());
+                close + 2
+            }
+            false => open + 2,
+        };
+        rest = &rest[skip..];
+        offset += skip;
+    }
+    spans
+}
+
+/// The text of `raw` as it gets typed, without directives. Texts with malformed
+/// directives are returned as they are.
+pub fn strip_directives(raw: &str) -> String {
+    Script::parse(raw).map_or_else(|_| raw.to_string(), |script| script.text)
+}
+
+/// Reads a duration like `2000`, `2000ms` or `1.5s` as milliseconds.
+fn parse_millis(arg: &str) -> Result<u64, String> {
+    let (number, scale) = match arg.strip_suffix("ms") {
+        Some(number) => (number, 1.0),
+        None => match arg.strip_suffix('s') {
+            Some(number) => (number, 1000.0),
+            None => (arg, 1.0),
+        },
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|millis| millis.is_finite() && *millis >= 0.0)
+        .map(|millis| (millis * scale).round() as u64)
+        .ok_or_else(|| {
+            format!(
+                "Invalid pause {:?}, expected milliseconds like 2000 or 1.5s",
+                arg
+            )
+        })
+}
+
+/// Reads a point of the screen like `640,480`.
+fn parse_point(arg: &str) -> Result<(i32, i32), String> {
+    arg.split_once(',')
+        .and_then(|(x, y)| Some((x.trim().parse().ok()?, y.trim().parse().ok()?)))
+        .ok_or_else(|| format!("Invalid point {:?}, expected X,Y like 640,480", arg))
+}
+
+/// Reads a speed factor like `2x`, `0.5` or `150%`.
+fn parse_speed(arg: &str) -> Result<f64, String> {
+    let (number, scale) = match arg.strip_suffix('%') {
+        Some(number) => (number, 0.01),
+        None => (arg.strip_suffix(['x', 'X']).unwrap_or(arg), 1.0),
+    };
+    number
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .map(|factor| factor * scale)
+        .filter(|factor| factor.is_finite() && *factor > 0.0)
+        .ok_or_else(|| format!("Invalid speed {:?}, expected a factor like 2x or 50%", arg))
+}
@@ -0,0 +1,38 @@
+use crate::timing::Delay;
+use serde::{Deserialize, Serialize};
+
+/// What the typist does at the start and end of a distraction, besides stopping.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DistractionAction {
+    /// Only stops typing.
+    Idle,
+    /// Moves the mouse a little back and forth.
+    Wiggle,
+    /// Switches to the previous window with Alt+Tab and back again.
+    SwitchWindow,
+}
+
+/// Stops typing for a while a few times an hour, the way people look at something else
+/// and come back.
+///
+/// Distractions happen at random times, on average `per_hour` times an hour of typing,
+/// and always between two words.
+#[derive(Clone, Debug)]
+pub struct DistractionConfig {
+    pub per_hour: f64,
+    /// How long each distraction lasts.
+    pub duration: Delay,
+    /// One of these is picked for each distraction.
+    pub actions: Vec<DistractionAction>,
+}
+
+impl Default for DistractionConfig {
+    fn default() -> Self {
+        DistractionConfig {
+            per_hour: 3.0,
+            duration: Delay::Uniform(10_000..60_000),
+            actions: vec![DistractionAction::Idle],
+        }
+    }
+}
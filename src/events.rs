@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// A key the typist presses, independent of how the key press is sent.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Key {
+    Return,
+    Backspace,
+    Tab,
+    Escape,
+    Delete,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Left,
+    Right,
+    Up,
+    Down,
+    Shift,
+    Control,
+    Alt,
+    Meta,
+    /// The key that produces this character on the current layout.
+    Char(char),
+    /// A key of the numeric keypad: a digit or one of `.+-*/`.
+    Keypad(char),
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Key::Char(c) => write!(f, "{:?}", c),
+            Key::Keypad(c) => write!(f, "Keypad({:?})", c),
+            key => write!(f, "{:?}", key),
+        }
+    }
+}
+
+impl Key {
+    /// Reads a key name such as `Tab`, `enter` or `a`, whatever its case.
+    pub fn parse(name: &str) -> Result<Key, String> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Ok(Key::Char(c.to_ascii_lowercase()));
+        }
+        match name.trim().to_ascii_lowercase().as_str() {
+            "enter" | "return" => Ok(Key::Return),
+            "backspace" => Ok(Key::Backspace),
+            "tab" => Ok(Key::Tab),
+            "escape" | "esc" => Ok(Key::Escape),
+            "delete" | "del" => Ok(Key::Delete),
+            "home" => Ok(Key::Home),
+            "end" => Ok(Key::End),
+            "pageup" | "pgup" => Ok(Key::PageUp),
+            "pagedown" | "pgdn" => Ok(Key::PageDown),
+            "left" => Ok(Key::Left),
+            "right" => Ok(Key::Right),
+            "up" => Ok(Key::Up),
+            "down" => Ok(Key::Down),
+            "space" => Ok(Key::Char(' ')),
+            "shift" => Ok(Key::Shift),
+            "ctrl" | "control" => Ok(Key::Control),
+            "alt" => Ok(Key::Alt),
+            "meta" | "super" | "cmd" | "win" => Ok(Key::Meta),
+            other => other
+                .strip_prefix("numpad")
+                .or_else(|| other.strip_prefix("kp"))
+                .and_then(|rest| {
+                    let mut chars = rest.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(c), None) => Key::keypad(c),
+                        _ => None,
+                    }
+                })
+                .ok_or_else(|| format!("Unknown key: {}", name)),
+        }
+    }
+
+    /// The name [`Key::parse`] reads back as this key.
+    pub fn name(self) -> String {
+        match self {
+            Key::Char(' ') => "space".to_string(),
+            Key::Char(c) => c.to_string(),
+            Key::Keypad(c) => format!("kp{}", c),
+            key => format!("{:?}", key).to_lowercase(),
+        }
+    }
+
+    /// The keypad key that types `c`, if there is one.
+    pub fn keypad(c: char) -> Option<Key> {
+        (c.is_ascii_digit() || ".+-*/".contains(c)).then_some(Key::Keypad(c))
+    }
+
+    /// Whether the key only moves the cursor, like the arrow keys.
+    pub fn moves_cursor(self) -> bool {
+        matches!(
+            self,
+            Key::Home
+                | Key::End
+                | Key::PageUp
+                | Key::PageDown
+                | Key::Left
+                | Key::Right
+                | Key::Up
+                | Key::Down
+        )
+    }
+
+    pub fn is_modifier(self) -> bool {
+        matches!(self, Key::Shift | Key::Control | Key::Alt | Key::Meta)
+    }
+}
+
+/// A key pressed while modifiers are held down, such as `Ctrl+S`, or a key on its own.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Chord {
+    pub modifiers: Vec<Key>,
+    pub key: Key,
+}
+
+impl Chord {
+    /// `key` on its own, without modifiers.
+    pub fn key(key: Key) -> Self {
+        Chord {
+            modifiers: Vec::new(),
+            key,
+        }
+    }
+
+    /// Ctrl and the key of `c`, like `Chord::ctrl('s')`.
+    pub fn ctrl(c: char) -> Self {
+        Chord::key(Key::Char(c)).with(Key::Control)
+    }
+
+    /// Alt and the key of `c`.
+    pub fn alt(c: char) -> Self {
+        Chord::key(Key::Char(c)).with(Key::Alt)
+    }
+
+    /// Meta and the key of `c`, Cmd on macOS.
+    pub fn meta(c: char) -> Self {
+        Chord::key(Key::Char(c)).with(Key::Meta)
+    }
+
+    /// This chord with `modifier` held down as well, like
+    /// `Chord::key(Key::Tab).with(Key::Shift)`.
+    pub fn with(mut self, modifier: Key) -> Self {
+        if !self.modifiers.contains(&modifier) {
+            self.modifiers.push(modifier);
+        }
+        self
+    }
+
+    /// Reads a chord such as `tab` or `ctrl+shift+s`; the last key is the one pressed.
+    pub fn parse(name: &str) -> Result<Chord, String> {
+        // A trailing "++" is the plus key itself
+        let (modifiers, key) = match name.strip_suffix("++") {
+            Some(modifiers) => (modifiers, "+"),
+            None => name.rsplit_once('+').unwrap_or(("", name)),
+        };
+        let key = Key::parse(key.trim())?;
+        let modifiers = modifiers
+            .split('+')
+            .filter(|part| !part.trim().is_empty())
+            .map(|part| match Key::parse(part.trim())? {
+                modifier if modifier.is_modifier() => Ok(modifier),
+                _ => Err(format!("{} is not a modifier in {}", part.trim(), name)),
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        Ok(Chord { modifiers, key })
+    }
+}
+
+impl From<Key> for Chord {
+    fn from(key: Key) -> Self {
+        Chord::key(key)
+    }
+}
+
+impl fmt::Display for Chord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for modifier in &self.modifiers {
+            write!(f, "{}+", modifier)?;
+        }
+        write!(f, "{}", self.key)
+    }
+}
+
+/// One synthesized keyboard action.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum KeyAction {
+    /// Types a character, whatever keys it takes.
+    Type {
+        char: char,
+    },
+    Click {
+        key: Key,
+    },
+    Down {
+        key: Key,
+    },
+    Up {
+        key: Key,
+    },
+    /// Moves the mouse pointer to a point of the screen, in pixels.
+    MouseMove {
+        x: i32,
+        y: i32,
+    },
+    /// Clicks the left mouse button wherever the pointer is.
+    MouseClick,
+    /// Turns the mouse wheel by this many lines, down for positive ones.
+    MouseScroll {
+        lines: i32,
+    },
+    /// Moves the mouse pointer by this many pixels from where it is.
+    MouseNudge {
+        dx: i32,
+        dy: i32,
+    },
+}
+
+impl fmt::Display for KeyAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyAction::Type { char } => write!(f, "type {:?}", char),
+            KeyAction::Click { key } => write!(f, "key {}", key),
+            KeyAction::Down { key } => write!(f, "key {} down", key),
+            KeyAction::Up { key } => write!(f, "key {} up", key),
+            KeyAction::MouseMove { x, y } => write!(f, "mouse move {},{}", x, y),
+            KeyAction::MouseClick => write!(f, "mouse click"),
+            KeyAction::MouseScroll { lines } => write!(f, "mouse scroll {}", lines),
+            KeyAction::MouseNudge { dx, dy } => write!(f, "mouse nudge {},{}", dx, dy),
+        }
+    }
+}
+
+/// A line of the keystroke log.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct KeyEvent {
+    /// Time since typing started.
+    pub at_ms: u64,
+    /// Time since the previous event.
+    pub delay_ms: u64,
+    #[serde(flatten)]
+    pub action: KeyAction,
+    /// Part of a typing mistake.
+    #[serde(default)]
+    pub mistake: bool,
+    /// Part of erasing a mistake.
+    #[serde(default)]
+    pub correction: bool,
+}
+
+/// Reads a keystroke log written with [`HumanTypistBuilder::keystroke_log`].
+///
+/// [`HumanTypistBuilder::keystroke_log`]: crate::HumanTypistBuilder::keystroke_log
+pub fn read_keystroke_log(path: &Path) -> Result<Vec<KeyEvent>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+
+    content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(index, line)| {
+            serde_json::from_str(line).map_err(|e| {
+                format!(
+                    "Invalid event on line {} of {}: {}",
+                    index + 1,
+                    path.display(),
+                    e
+                )
+                .into()
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,161 @@
+use crate::backend::xdotool_key;
+use crate::events::{Key, KeyAction, KeyEvent};
+use std::fmt::Write;
+use std::str::FromStr;
+
+/// A scripting tool that can play back key events on its own.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScriptFormat {
+    /// An AutoHotkey v2 script, for Windows.
+    Ahk,
+    /// A shell script calling `xdotool`, for X11.
+    Xdotool,
+}
+
+impl FromStr for ScriptFormat {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_lowercase().as_str() {
+            "ahk" | "autohotkey" => Ok(ScriptFormat::Ahk),
+            "xdotool" | "sh" => Ok(ScriptFormat::Xdotool),
+            _ => Err(format!(
+                "Unknown script format: {} (expected ahk or xdotool)",
+                name
+            )),
+        }
+    }
+}
+
+/// Turns `events` into a standalone script that sends them with the same timings,
+/// after waiting `countdown_ms` for the target window to be focused.
+pub fn export_script(events: &[KeyEvent], format: ScriptFormat, countdown_ms: u64) -> String {
+    match format {
+        ScriptFormat::Ahk => ahk_script(events, countdown_ms),
+        ScriptFormat::Xdotool => xdotool_script(events, countdown_ms),
+    }
+}
+
+fn ahk_script(events: &[KeyEvent], countdown_ms: u64) -> String {
+    let mut script = String::from(
+        "; Generated by typer. Press Escape to stop.\n\
+         #Requires AutoHotkey v2.0\n\
+         SendMode \"Event\"\n\
+         SetKeyDelay 0, 0\n\
+         CoordMode \"Mouse\", \"Screen\"\n\
+         Esc::ExitApp\n\n",
+    );
+    writeln!(script, "Sleep {}", countdown_ms).unwrap();
+
+    for event in events {
+        if event.delay_ms > 0 {
+            writeln!(script, "Sleep {}", event.delay_ms).unwrap();
+        }
+        let line = match event.action {
+            KeyAction::Type { char } => format!("SendText \"{}\"", ahk_escape(char)),
+            KeyAction::Click { key } => format!("Send \"{{{}}}\"", ahk_key(key)),
+            KeyAction::Down { key } => format!("Send \"{{{} down}}\"", ahk_key(key)),
+            KeyAction::Up { key } => format!("Send \"{{{} up}}\"", ahk_key(key)),
+            KeyAction::MouseMove { x, y } => format!("MouseMove {}, {}, 0", x, y),
+            KeyAction::MouseClick => "Click".to_string(),
+            KeyAction::MouseScroll { lines } => match lines < 0 {
+                true => format!("Send \"{{WheelUp {}}}\"", -lines),
+                false => format!("Send \"{{WheelDown {}}}\"", lines),
+            },
+            KeyAction::MouseNudge { dx, dy } => format!("MouseMove {}, {}, 0, \"R\"", dx, dy),
+        };
+        script.push_str(&line);
+        script.push('\n');
+    }
+
+    script.push_str("ExitApp\n");
+    script
+}
+
+/// Escapes `c` for a double-quoted AutoHotkey v2 string.
+fn ahk_escape(c: char) -> String {
+    match c {
+        '"' => "`\"".to_string(),
+        '`' => "``".to_string(),
+        '\n' => "`n".to_string(),
+        '\t' => "`t".to_string(),
+        c => c.to_string(),
+    }
+}
+
+/// The AutoHotkey key name of `key`, as used between braces.
+fn ahk_key(key: Key) -> String {
+    match key {
+        Key::Return => "Enter".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Tab => "Tab".to_string(),
+        Key::Escape => "Esc".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PgUp".to_string(),
+        Key::PageDown => "PgDn".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Shift => "Shift".to_string(),
+        Key::Control => "Ctrl".to_string(),
+        Key::Alt => "Alt".to_string(),
+        Key::Meta => "LWin".to_string(),
+        Key::Keypad(c) => match c {
+            '*' => "NumpadMult".to_string(),
+            '+' => "NumpadAdd".to_string(),
+            '-' => "NumpadSub".to_string(),
+            '.' => "NumpadDot".to_string(),
+            '/' => "NumpadDiv".to_string(),
+            digit => format!("Numpad{}", digit),
+        },
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char(c) => ahk_escape(c),
+    }
+}
+
+fn xdotool_script(events: &[KeyEvent], countdown_ms: u64) -> String {
+    let mut script =
+        String::from("#!/bin/sh\n# Generated by typer. Press Ctrl+C to stop.\nset -e\n\n");
+    writeln!(script, "sleep {}", seconds(countdown_ms)).unwrap();
+
+    for event in events {
+        if event.delay_ms > 0 {
+            writeln!(script, "sleep {}", seconds(event.delay_ms)).unwrap();
+        }
+        let line = match event.action {
+            KeyAction::Type { char } => {
+                format!(
+                    "xdotool type --delay 0 -- {}",
+                    shell_quote(&char.to_string())
+                )
+            }
+            KeyAction::Click { key } => format!("xdotool key -- {}", xdotool_key(key)),
+            KeyAction::Down { key } => format!("xdotool keydown -- {}", xdotool_key(key)),
+            KeyAction::Up { key } => format!("xdotool keyup -- {}", xdotool_key(key)),
+            KeyAction::MouseMove { x, y } => format!("xdotool mousemove {} {}", x, y),
+            KeyAction::MouseClick => "xdotool click 1".to_string(),
+            KeyAction::MouseScroll { lines } => format!(
+                "xdotool click --repeat {} {}",
+                lines.unsigned_abs(),
+                if lines < 0 { 4 } else { 5 }
+            ),
+            KeyAction::MouseNudge { dx, dy } => {
+                format!("xdotool mousemove_relative -- {} {}", dx, dy)
+            }
+        };
+        script.push_str(&line);
+        script.push('\n');
+    }
+    script
+}
+
+fn seconds(millis: u64) -> String {
+    format!("{}.{:03}", millis / 1000, millis % 1000)
+}
+
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', r"'\''"))
+}
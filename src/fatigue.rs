@@ -0,0 +1,107 @@
+use std::time::Duration;
+
+/// Parameters of the fatigue curve: after `onset` of typing, keystrokes slow down and
+/// mistakes get more likely, linearly over `ramp`, until the maximum is reached.
+#[derive(Clone, Debug)]
+pub struct FatigueConfig {
+    pub onset: Duration,
+    pub ramp: Duration,
+    /// How much slower keystrokes get at full fatigue, e.g. 0.5 for 50% slower.
+    pub max_slowdown: f64,
+    /// How much more likely mistakes get at full fatigue, e.g. 1.0 for twice as likely.
+    pub max_extra_mistakes: f64,
+    /// Only pauses at least this long let the typist recover.
+    pub recovery_min_pause: Duration,
+    /// Seconds of typing time recovered per second of pause.
+    pub recovery_rate: f64,
+}
+
+impl Default for FatigueConfig {
+    fn default() -> Self {
+        FatigueConfig {
+            onset: Duration::from_secs(10 * 60),
+            ramp: Duration::from_secs(30 * 60),
+            max_slowdown: 0.5,
+            max_extra_mistakes: 1.0,
+            recovery_min_pause: Duration::from_secs(2),
+            recovery_rate: 3.0,
+        }
+    }
+}
+
+/// Parameters of the warm-up at the start of typing: keystrokes start out slower and
+/// mistakes more likely, easing linearly to the configured speed over `duration`.
+#[derive(Clone, Debug)]
+pub struct WarmUpConfig {
+    pub duration: Duration,
+    /// How much slower the first keystrokes are, e.g. 0.3 for 30% slower.
+    pub slowdown: f64,
+    /// How much more likely the first mistakes are, e.g. 0.5 for 50% more likely.
+    pub extra_mistakes: f64,
+}
+
+impl Default for WarmUpConfig {
+    fn default() -> Self {
+        WarmUpConfig {
+            duration: Duration::from_secs(60),
+            slowdown: 0.3,
+            extra_mistakes: 0.5,
+        }
+    }
+}
+
+impl WarmUpConfig {
+    /// How cold the typist still is after typing for `elapsed`, between 1 at the start
+    /// and 0 once warmed up.
+    pub fn level(&self, elapsed: Duration) -> f64 {
+        if self.duration.is_zero() {
+            return 0.0;
+        }
+        1.0 - (elapsed.as_secs_f64() / self.duration.as_secs_f64()).min(1.0)
+    }
+
+    pub fn delay_factor(&self, elapsed: Duration) -> f64 {
+        1.0 + self.level(elapsed) * self.slowdown
+    }
+
+    pub fn mistake_factor(&self, elapsed: Duration) -> f64 {
+        1.0 + self.level(elapsed) * self.extra_mistakes
+    }
+}
+
+/// Tracks how tired the typist is.
+#[derive(Default)]
+pub(crate) struct Fatigue {
+    active: Duration,
+}
+
+impl Fatigue {
+    pub(crate) fn work(&mut self, duration: Duration) {
+        self.active += duration;
+    }
+
+    pub(crate) fn rest(&mut self, config: &FatigueConfig, duration: Duration) {
+        if duration >= config.recovery_min_pause {
+            self.active = self
+                .active
+                .saturating_sub(duration.mul_f64(config.recovery_rate));
+        }
+    }
+
+    /// Fatigue between 0 (fresh) and 1 (fully tired).
+    pub(crate) fn level(&self, config: &FatigueConfig) -> f64 {
+        let tired = self.active.saturating_sub(config.onset);
+        if config.ramp.is_zero() {
+            return if tired.is_zero() { 0.0 } else { 1.0 };
+        }
+        (tired.as_secs_f64() / config.ramp.as_secs_f64()).min(1.0)
+    }
+
+    pub(crate) fn delay_factor(&self, config: &FatigueConfig) -> f64 {
+        1.0 + self.level(config) * config.max_slowdown
+    }
+
+    pub(crate) fn mistake_factor(&self, config: &FatigueConfig) -> f64 {
+        1.0 + self.level(config) * config.max_extra_mistakes
+    }
+}
@@ -0,0 +1,185 @@
+use crate::events::Chord;
+use crate::typist::{HumanTypist, TypingOutcome};
+use serde::de::{self, Deserialize, Deserializer, MapAccess, Visitor};
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+/// How one field of a form is typed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldOptions {
+    /// Whether the field may get typos; off for values that must be exact, like emails.
+    pub mistakes: bool,
+    /// Types digits and `.+-*/` on the numeric keypad.
+    pub keypad: bool,
+    /// Key that moves on to the next field instead of the form's.
+    pub next: Option<Chord>,
+}
+
+impl Default for FieldOptions {
+    fn default() -> Self {
+        FieldOptions {
+            mistakes: true,
+            keypad: false,
+            next: None,
+        }
+    }
+}
+
+/// A value to type into one field of a form.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FormField {
+    pub name: String,
+    pub value: String,
+    pub options: FieldOptions,
+}
+
+/// Types the value of each field and presses `next`, or the field's own key, to move
+/// on to the following one. After the last field `submit` is pressed, if given.
+pub fn fill_form(
+    typist: &mut HumanTypist,
+    fields: &[FormField],
+    next: &Chord,
+    submit: Option<&Chord>,
+) -> TypingOutcome {
+    let (exact, keypad) = (typist.config.exact, typist.config.keypad);
+
+    for (index, field) in fields.iter().enumerate() {
+        typist.config.exact = exact || !field.options.mistakes;
+        typist.config.keypad = keypad || field.options.keypad;
+        let outcome = typist.type_text(&field.value);
+        typist.config.exact = exact;
+        typist.config.keypad = keypad;
+        if outcome == TypingOutcome::Aborted || typist.controls().is_aborted() {
+            return TypingOutcome::Aborted;
+        }
+
+        let key = match index + 1 == fields.len() {
+            true => submit,
+            false => Some(field.options.next.as_ref().unwrap_or(next)),
+        };
+        if let Some(key) = key {
+            typist.press(key.clone());
+        }
+    }
+
+    TypingOutcome::Completed
+}
+
+/// Reads the record at index `row` of a JSON or CSV file, picked by extension, as field
+/// names and values in the order of the file.
+///
+/// A JSON file holds one object of fields or an array of them; a CSV file has a header
+/// row with the field names and one record per row after it.
+pub fn read_record(
+    path: &Path,
+    row: usize,
+) -> Result<Vec<(String, String)>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let is_json = path.extension().is_some_and(|ext| ext == "json");
+    let records = if is_json {
+        parse_json_records(&content)
+    } else {
+        parse_csv_records(&content)
+    }
+    .map_err(|e| format!("Invalid record file {}: {}", path.display(), e))?;
+
+    let count = records.len();
+    records.into_iter().nth(row).ok_or_else(|| {
+        format!(
+            "{} has {} records, there is no record {}",
+            path.display(),
+            count,
+            row + 1
+        )
+        .into()
+    })
+}
+
+/// Reads an object of fields, or an array of them, keeping the fields in file order.
+pub fn parse_json_records(content: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    #[derive(serde::Deserialize)]
+    #[serde(untagged)]
+    enum Records {
+        One(Record),
+        Many(Vec<Record>),
+    }
+
+    match serde_json::from_str(content).map_err(|e| e.to_string())? {
+        Records::One(record) => Ok(vec![record.0]),
+        Records::Many(records) => Ok(records.into_iter().map(|record| record.0).collect()),
+    }
+}
+
+/// Reads comma, tab or semicolon separated rows under a header row of field names.
+pub fn parse_csv_records(content: &str) -> Result<Vec<Vec<(String, String)>>, String> {
+    let header = content.lines().next().unwrap_or_default();
+    let delimiter = [b'\t', b';']
+        .into_iter()
+        .find(|&delimiter| header.contains(delimiter as char))
+        .unwrap_or(b',');
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .from_reader(content.as_bytes());
+    let headers: Vec<String> = reader
+        .headers()
+        .map_err(|e| e.to_string())?
+        .iter()
+        .map(|header| header.trim().to_string())
+        .collect();
+
+    reader
+        .records()
+        .map(|record| {
+            let record = record.map_err(|e| e.to_string())?;
+            Ok(headers
+                .iter()
+                .cloned()
+                .zip(record.iter().map(str::to_string))
+                .collect())
+        })
+        .collect()
+}
+
+/// The fields of a JSON object in the order they appear, with numbers and booleans
+/// turned into the text they are typed as.
+struct Record(Vec<(String, String)>);
+
+impl<'de> Deserialize<'de> for Record {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct RecordVisitor;
+
+        impl<'de> Visitor<'de> for RecordVisitor {
+            type Value = Record;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an object of field values")
+            }
+
+            fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Record, A::Error> {
+                let mut fields = Vec::new();
+                while let Some((name, value)) = map.next_entry::<String, serde_json::Value>()? {
+                    let value = match value {
+                        serde_json::Value::String(text) => text,
+                        serde_json::Value::Null => String::new(),
+                        serde_json::Value::Number(_) | serde_json::Value::Bool(_) => {
+                            value.to_string()
+                        }
+                        _ => {
+                            return Err(de::Error::custom(format!(
+                                "field {:?} is not a string, number or boolean",
+                                name
+                            )))
+                        }
+                    };
+                    fields.push((name, value));
+                }
+                Ok(Record(fields))
+            }
+        }
+
+        deserializer.deserialize_map(RecordVisitor)
+    }
+}
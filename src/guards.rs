@@ -0,0 +1,175 @@
+use crate::blacklist::{Blacklist, BlacklistWatcher};
+use crate::config::{Config, FocusLoss, LockAction};
+use crate::hotkeys::{Hotkey, TypingControls};
+use crate::lock::{session_state, LockWatcher, SessionState};
+use crate::takeover::{TakeoverEvent, TakeoverWatcher};
+use crate::window::{FocusEvent, FocusWatcher, FocusedWindow};
+use device_query::Keycode;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// What is checked before typing starts and watched while it goes on: the blacklist,
+/// the screen lock, takeover by the user and the focus. Every way of typing starts its
+/// [`Guards`] from these.
+#[derive(Clone)]
+pub struct GuardSettings {
+    blacklist: Option<Blacklist>,
+    on_lock: LockAction,
+    lock_poll: Duration,
+    /// How long to wait before resuming, the keys that do not count and whether the
+    /// mouse does, when takeover is on.
+    takeover: Option<(Duration, Vec<Keycode>, bool)>,
+    on_loss: FocusLoss,
+    focus_poll: Duration,
+}
+
+/// The watchers started by [`GuardSettings::start`]. They stop when dropped, which is
+/// all the others are kept for.
+pub struct Guards {
+    focus: Option<FocusWatcher>,
+    _blacklist: Option<BlacklistWatcher>,
+    _takeover: Option<TakeoverWatcher>,
+    _lock: Option<LockWatcher>,
+}
+
+impl GuardSettings {
+    pub fn new(
+        config: &Config,
+        ignore_blacklist: bool,
+    ) -> Result<GuardSettings, Box<dyn std::error::Error>> {
+        let takeover = match config.takeover.enabled {
+            true => {
+                let hotkeys = &config.hotkeys;
+                let mut ignored = Vec::new();
+                for hotkey in [
+                    &hotkeys.pause,
+                    &hotkeys.abort,
+                    &hotkeys.slower,
+                    &hotkeys.faster,
+                    &hotkeys.skip_sentence,
+                    &hotkeys.skip_paragraph,
+                ] {
+                    ignored.extend(Hotkey::parse(hotkey)?.keys());
+                }
+                let idle = Duration::from_millis(config.takeover.idle_ms);
+                Some((idle, ignored, config.takeover.mouse))
+            }
+            false => None,
+        };
+        Ok(GuardSettings {
+            blacklist: match ignore_blacklist {
+                true => None,
+                false => config.blacklist.blacklist(),
+            },
+            on_lock: config.lock.on_lock,
+            lock_poll: Duration::from_millis(config.lock.poll_ms),
+            takeover,
+            on_loss: config.focus.on_loss,
+            focus_poll: Duration::from_millis(config.focus.poll_ms),
+        })
+    }
+
+    /// Refuses to type into `focused` when the session is locked or the window is
+    /// blacklisted, and starts the watchers otherwise, which tell `on_message` what
+    /// they do.
+    pub fn start<F>(
+        &self,
+        controls: &Arc<TypingControls>,
+        focused: Option<FocusedWindow>,
+        on_message: F,
+    ) -> Result<Guards, String>
+    where
+        F: Fn(String) + Clone + Send + 'static,
+    {
+        let on_lock = self.on_lock;
+        if let Some(state) = session_state().filter(|state| *state != SessionState::Active) {
+            if on_lock != LockAction::Ignore {
+                return Err(format!("Not typing, {}", state.describe()));
+            }
+        }
+        let blacklist = self.blacklist.as_ref();
+        if let Some(reason) = blacklist.and_then(|b| b.check(focused.as_ref())) {
+            return Err(format!(
+                "Not typing, {} (pass --ignore-blacklist to type anyway)",
+                reason
+            ));
+        }
+
+        let blacklist = blacklist.map(|blacklist| {
+            let on_message = on_message.clone();
+            BlacklistWatcher::spawn(
+                blacklist.clone(),
+                self.focus_poll,
+                Arc::clone(controls),
+                move |reason| on_message(format!("Stopped typing, {}.", reason)),
+            )
+        });
+        let takeover = self.takeover.clone().map(|(idle, ignored, mouse)| {
+            let on_message = on_message.clone();
+            TakeoverWatcher::spawn(idle, ignored, mouse, Arc::clone(controls), move |event| {
+                match event {
+                    TakeoverEvent::Paused => on_message("You took over, pausing.".to_string()),
+                    TakeoverEvent::Resumed => on_message(format!(
+                        "Left alone for {:.1}s, resuming.",
+                        idle.as_secs_f64()
+                    )),
+                }
+            })
+        });
+        let lock = (on_lock != LockAction::Ignore).then(|| {
+            let on_message = on_message.clone();
+            LockWatcher::spawn(
+                on_lock,
+                self.lock_poll,
+                Arc::clone(controls),
+                move |state| match (state, on_lock) {
+                    (SessionState::Active, _) => {
+                        on_message("The session is active again, resuming.".to_string())
+                    }
+                    (_, LockAction::Pause) => on_message(format!(
+                        "Paused, {}, until the session is active again.",
+                        state.describe()
+                    )),
+                    _ => on_message(format!("Aborting, {}...", state.describe())),
+                },
+            )
+        });
+        let on_loss = self.on_loss;
+        let focus = focused
+            .filter(|_| on_loss != FocusLoss::Ignore)
+            .map(|window| {
+                FocusWatcher::spawn(
+                    window,
+                    on_loss,
+                    self.focus_poll,
+                    Arc::clone(controls),
+                    move |event| match (event, on_loss) {
+                        (FocusEvent::Lost(window), FocusLoss::Abort) => {
+                            on_message(format!("Focus moved to {}, aborting...", window.describe()))
+                        }
+                        (FocusEvent::Lost(window), _) => on_message(format!(
+                            "Focus moved to {}, paused until it comes back.",
+                            window.describe()
+                        )),
+                        (FocusEvent::Returned, _) => {
+                            on_message("Focus is back, resuming.".to_string())
+                        }
+                    },
+                )
+            });
+
+        Ok(Guards {
+            focus,
+            _blacklist: blacklist,
+            _takeover: takeover,
+            _lock: lock,
+        })
+    }
+}
+
+impl Guards {
+    /// The watcher of the window typed into, if the focus is watched.
+    pub fn focus(&self) -> Option<&FocusWatcher> {
+        self.focus.as_ref()
+    }
+}
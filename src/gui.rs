@@ -0,0 +1,340 @@
+use crate::backend::create_backend;
+use crate::config::{Config, HookSettings};
+use crate::directives::Script;
+use crate::guards::GuardSettings;
+use crate::hooks::{run_hook, HookEvent, HookPayload, HookWorker};
+use crate::hotkeys::TypingControls;
+use crate::typist::{HumanTypist, HumanTypistBuilder, TypingOutcome};
+use crate::window::{activate_window, focused_window, list_windows};
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Time to switch to the target window when none is picked in the window.
+const COUNTDOWN: Duration = Duration::from_secs(3);
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Called with the controls of each run as it starts, e.g. to listen for the hotkeys.
+pub type StartCallback<'a> =
+    Box<dyn FnMut(&Arc<TypingControls>) -> Result<(), Box<dyn std::error::Error>> + 'a>;
+
+/// Where the run started from the window is at, shared with its typing thread.
+#[derive(Default)]
+struct RunStatus {
+    running: bool,
+    position: usize,
+    total: usize,
+    message: String,
+}
+
+/// A window with a text box, sliders for the speed and the mistakes, a target window
+/// picker and a button to start and stop typing, for those who would rather not use
+/// the terminal.
+struct TyperApp<'a> {
+    config: &'a Config,
+    on_start: StartCallback<'a>,
+    text: String,
+    /// Speed factor, changed on the fly while typing.
+    speed: f64,
+    /// Mistakes made, as a percentage of those of the config.
+    mistakes: u32,
+    windows: Vec<String>,
+    target: Option<String>,
+    controls: Option<Arc<TypingControls>>,
+    status: Arc<Mutex<RunStatus>>,
+}
+
+/// Opens the window with the settings of `config` and blocks until it is closed.
+pub fn run(config: &Config, on_start: StartCallback) -> Result<(), Box<dyn std::error::Error>> {
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default().with_inner_size([560.0, 520.0]),
+        ..Default::default()
+    };
+    eframe::run_native(
+        "typer",
+        options,
+        Box::new(|_| {
+            Ok(Box::new(TyperApp {
+                config,
+                on_start,
+                text: String::new(),
+                speed: 1.0,
+                mistakes: 100,
+                windows: list_windows().unwrap_or_default(),
+                target: None,
+                controls: None,
+                status: Arc::new(Mutex::new(RunStatus::default())),
+            }))
+        }),
+    )
+    .map_err(|e| format!("Failed to open the window: {}", e))?;
+    Ok(())
+}
+
+impl TyperApp<'_> {
+    fn start(&mut self, ctx: &egui::Context) -> Result<(), Box<dyn std::error::Error>> {
+        if self.text.trim().is_empty() {
+            return Err("Nothing to type.".into());
+        }
+        let script = Script::parse(&self.text)?;
+        let mut typing_config = self.config.to_typing_config()?;
+        match self.mistakes {
+            0 => typing_config.exact = true,
+            percent => {
+                typing_config.mistake_probability = typing_config
+                    .mistake_probability
+                    .scaled(100.0 / percent as f64)
+            }
+        }
+        let keyboard = self.config.keyboard_layout()?;
+        let backend_kind = self.config.keyboard.backend;
+
+        let guards = GuardSettings::new(self.config, false)?;
+        let hooks = self.config.hooks.clone();
+
+        let controls = TypingControls::new();
+        controls.set_speed(self.speed);
+        (self.on_start)(&controls)?;
+
+        let target = self.target.clone();
+        let status = Arc::clone(&self.status);
+        *status.lock().unwrap() = RunStatus {
+            running: true,
+            total: script.text.chars().count(),
+            ..RunStatus::default()
+        };
+        let (thread_controls, ctx) = (Arc::clone(&controls), ctx.clone());
+        thread::spawn(move || {
+            // The last warning of the typist, which tells why it stopped by itself
+            let warning = Arc::new(Mutex::new(None));
+            let run = GuiRun {
+                status: &status,
+                controls: &thread_controls,
+                guards: &guards,
+                hooks: &hooks,
+                ctx: &ctx,
+            };
+            let outcome = run.type_into(target, &script, || {
+                let progress_status = Arc::clone(&status);
+                let progress_ctx = ctx.clone();
+                let output = Arc::clone(&warning);
+                Ok(HumanTypist::builder()
+                    .config(typing_config)
+                    .keyboard(keyboard)
+                    .controls(Arc::clone(&thread_controls))
+                    .boxed_backend(create_backend(backend_kind)?)
+                    .on_progress(move |progress| {
+                        progress_status.lock().unwrap().position = progress.position;
+                        progress_ctx.request_repaint();
+                    })
+                    .on_output(move |line| *output.lock().unwrap() = Some(line.to_string())))
+            });
+            let message = match (outcome, warning.lock().unwrap().take()) {
+                (Ok(TypingOutcome::Completed), _) => "Done.".to_string(),
+                (Ok(TypingOutcome::Aborted), Some(warning)) => warning,
+                (Ok(TypingOutcome::Aborted), None) => "Stopped.".to_string(),
+                (Err(e), _) => format!("Error: {}", e),
+            };
+            let mut status = status.lock().unwrap();
+            status.running = false;
+            status.message = message;
+            ctx.request_repaint();
+        });
+        self.controls = Some(controls);
+        Ok(())
+    }
+}
+
+/// What a run started from the window needs on its typing thread.
+struct GuiRun<'a> {
+    status: &'a Arc<Mutex<RunStatus>>,
+    controls: &'a Arc<TypingControls>,
+    guards: &'a GuardSettings,
+    hooks: &'a HookSettings,
+    ctx: &'a egui::Context,
+}
+
+impl GuiRun<'_> {
+    /// Brings `target` to the front, or counts down for the user to focus a window, and
+    /// types `script` with the typist of the builder `build` makes, guarded and with the
+    /// hooks like a run in the terminal.
+    fn type_into<F>(
+        &self,
+        target: Option<String>,
+        script: &Script,
+        build: F,
+    ) -> Result<TypingOutcome, Box<dyn std::error::Error>>
+    where
+        F: FnOnce() -> Result<HumanTypistBuilder, Box<dyn std::error::Error>>,
+    {
+        match target {
+            Some(name) => {
+                self.status.lock().unwrap().message = format!("Switching to \"{}\"...", name);
+                activate_window(&name)?;
+            }
+            None => {
+                let started = Instant::now();
+                while started.elapsed() < COUNTDOWN {
+                    if self.controls.is_aborted() {
+                        return Ok(TypingOutcome::Aborted);
+                    }
+                    let left = (COUNTDOWN - started.elapsed()).as_secs() + 1;
+                    self.status.lock().unwrap().message =
+                        format!("Focus the window to type into. Starting in {}...", left);
+                    thread::sleep(REDRAW_INTERVAL);
+                }
+            }
+        }
+        let (status, ctx) = (Arc::clone(self.status), self.ctx.clone());
+        let _guards = self
+            .guards
+            .start(self.controls, focused_window(), move |message| {
+                status.lock().unwrap().message = message;
+                ctx.request_repaint();
+            })?;
+        self.status.lock().unwrap().message = "Typing...".to_string();
+
+        let total = self.status.lock().unwrap().total;
+        let payload = move |event, position, elapsed: Duration, mistakes| HookPayload {
+            event,
+            source: "typer window".to_string(),
+            position,
+            total,
+            elapsed_ms: elapsed.as_millis() as u64,
+            mistakes,
+        };
+        self.fire_hook(payload(HookEvent::Start, 0, Duration::ZERO, 0));
+        let hook_worker = self.hooks.on_mistake.as_ref().map(|_| {
+            let status = Arc::clone(self.status);
+            HookWorker::spawn(move |e| status.lock().unwrap().message = format!("Warning: {}", e))
+        });
+        let builder = match (&self.hooks.on_mistake, &hook_worker) {
+            (Some(hook), Some(worker)) => {
+                let (hook, queue) = (hook.clone(), worker.queue());
+                let mut mistakes = 0;
+                build()?.on_mistake(move |position, elapsed| {
+                    mistakes += 1;
+                    queue.send(
+                        hook.clone(),
+                        payload(HookEvent::Mistake, position, elapsed, mistakes),
+                    );
+                })
+            }
+            _ => build()?,
+        };
+        let mut typist = builder.build();
+        let outcome = typist.type_script(script);
+        drop(hook_worker);
+
+        let (event, position) = match outcome {
+            TypingOutcome::Completed => (HookEvent::Complete, total),
+            TypingOutcome::Aborted => (HookEvent::Abort, typist.position()),
+        };
+        let elapsed = typist.elapsed();
+        self.fire_hook(payload(event, position, elapsed, typist.stats().mistakes));
+        Ok(outcome)
+    }
+
+    /// Runs the hook of `payload.event`, if there is one, and waits for it to finish.
+    fn fire_hook(&self, payload: HookPayload) {
+        let Some(hook) = self.hooks.hook(payload.event) else {
+            return;
+        };
+        if let Err(e) = run_hook(hook, &payload) {
+            self.status.lock().unwrap().message = format!("Warning: {}", e);
+        }
+    }
+}
+
+impl eframe::App for TyperApp<'_> {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        let (running, position, total, message) = {
+            let status = self.status.lock().unwrap();
+            let message = status.message.clone();
+            (status.running, status.position, status.total, message)
+        };
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.label("Text to type:");
+            egui::ScrollArea::vertical()
+                .max_height(ui.available_height() - 180.0)
+                .show(ui, |ui| {
+                    ui.add_enabled(
+                        !running,
+                        egui::TextEdit::multiline(&mut self.text)
+                            .desired_rows(12)
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+            ui.add_space(8.0);
+
+            let speed = ui.add(
+                egui::Slider::new(&mut self.speed, 0.25..=4.0)
+                    .logarithmic(true)
+                    .custom_formatter(|speed, _| format!("{:.0}%", speed * 100.0))
+                    .text("Speed"),
+            );
+            if speed.changed() {
+                if let Some(controls) = &self.controls {
+                    controls.set_speed(self.speed);
+                }
+            }
+            ui.add_enabled(
+                !running,
+                egui::Slider::new(&mut self.mistakes, 0..=300)
+                    .suffix("%")
+                    .text("Mistakes"),
+            );
+
+            ui.add_enabled_ui(!running, |ui| {
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_label("Target window")
+                        .width(320.0)
+                        .selected_text(self.target.as_deref().unwrap_or("Focused after 3 s"))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.target, None, "Focused after 3 s");
+                            for window in &self.windows {
+                                ui.selectable_value(&mut self.target, Some(window.clone()), window);
+                            }
+                        });
+                    if ui.button("Refresh").clicked() {
+                        self.windows = list_windows().unwrap_or_default();
+                    }
+                });
+            });
+            ui.add_space(8.0);
+
+            let label = if running { "Stop" } else { "Start" };
+            let button = egui::Button::new(egui::RichText::new(label).size(28.0));
+            if ui.add_sized([ui.available_width(), 56.0], button).clicked() {
+                match &self.controls {
+                    Some(controls) if running => controls.abort(),
+                    _ => {
+                        if let Err(e) = self.start(ctx) {
+                            self.status.lock().unwrap().message = e.to_string();
+                        }
+                    }
+                }
+            }
+
+            if total > 0 {
+                ui.add(
+                    egui::ProgressBar::new(position as f32 / total as f32)
+                        .text(format!("{} of {} characters", position, total)),
+                );
+            }
+            ui.label(message);
+        });
+
+        if running {
+            ctx.request_repaint_after(REDRAW_INTERVAL);
+        }
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if let Some(controls) = &self.controls {
+            controls.abort();
+        }
+    }
+}
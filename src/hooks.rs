@@ -0,0 +1,119 @@
+use serde::Serialize;
+use std::process::Command;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+/// When a hook runs, see [`HookSettings`](crate::config::HookSettings).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HookEvent {
+    Start,
+    Mistake,
+    Complete,
+    Abort,
+}
+
+/// What a hook is told about the run, as JSON.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct HookPayload {
+    pub event: HookEvent,
+    /// Where the text being typed came from.
+    pub source: String,
+    /// Characters typed of all the texts of the run.
+    pub position: usize,
+    pub total: usize,
+    pub elapsed_ms: u64,
+    pub mistakes: usize,
+}
+
+/// Runs `hook` for `payload` and waits for it: a URL starting with `http://` or
+/// `https://` gets the payload POSTed as JSON, anything else is a shell command, run
+/// with the event in `TYPER_EVENT` and the payload in `TYPER_PAYLOAD`.
+pub fn run_hook(hook: &str, payload: &HookPayload) -> Result<(), Box<dyn std::error::Error>> {
+    let json = serde_json::to_string(payload)?;
+    if hook.starts_with("http://") || hook.starts_with("https://") {
+        ureq::post(hook)
+            .set("Content-Type", "application/json")
+            .send_string(&json)
+            .map_err(|e| format!("Failed to post to {}: {}", hook, e))?;
+        return Ok(());
+    }
+
+    let event = serde_json::to_value(payload.event)?;
+    let status = shell(hook)
+        .env("TYPER_EVENT", event.as_str().unwrap_or_default())
+        .env("TYPER_PAYLOAD", &json)
+        .status()
+        .map_err(|e| format!("Failed to run {:?}: {}", hook, e))?;
+    if !status.success() {
+        return Err(format!("{:?} failed with {}", hook, status).into());
+    }
+    Ok(())
+}
+
+/// Runs hooks one after the other on a thread of its own, so typing goes on while they
+/// run and a burst of them never starts more than one process at a time.
+pub struct HookWorker {
+    queue: HookQueue,
+    thread: Option<JoinHandle<()>>,
+}
+
+/// Where hooks are queued for a [`HookWorker`], with `None` for it to stop.
+#[derive(Clone)]
+pub struct HookQueue(Sender<Option<(String, HookPayload)>>);
+
+impl HookWorker {
+    /// Calls `on_error` with the error of each hook that fails.
+    pub fn spawn<F>(on_error: F) -> Self
+    where
+        F: Fn(String) + Send + 'static,
+    {
+        let (sender, receiver) = mpsc::channel::<Option<(String, HookPayload)>>();
+        let thread = thread::spawn(move || {
+            for (hook, payload) in receiver.iter().map_while(|queued| queued) {
+                if let Err(e) = run_hook(&hook, &payload) {
+                    on_error(e.to_string());
+                }
+            }
+        });
+        HookWorker {
+            queue: HookQueue(sender),
+            thread: Some(thread),
+        }
+    }
+
+    pub fn queue(&self) -> HookQueue {
+        self.queue.clone()
+    }
+}
+
+impl HookQueue {
+    /// Queues `hook` to run with `payload` after those queued before it.
+    pub fn send(&self, hook: String, payload: HookPayload) {
+        let _ = self.0.send(Some((hook, payload)));
+    }
+}
+
+impl Drop for HookWorker {
+    /// Waits for the hooks queued so far, which are left out of any that come later.
+    fn drop(&mut self) {
+        let _ = self.queue.0.send(None);
+        if let Some(thread) = self.thread.take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("cmd");
+    shell.args(["/C", command]);
+    shell
+}
+
+#[cfg(not(windows))]
+fn shell(command: &str) -> Command {
+    let mut shell = Command::new("sh");
+    shell.args(["-c", command]);
+    shell
+}
@@ -0,0 +1,464 @@
+use crate::events::{Key, KeyAction};
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicU8, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+const ABORT_HOLD_KEY: Keycode = Keycode::Escape;
+const ABORT_HOLD_DURATION: Duration = Duration::from_secs(1);
+/// Bounds of the speed the speed hotkeys can set.
+const MIN_SPEED: f64 = 0.1;
+const MAX_SPEED: f64 = 10.0;
+
+/// Run-time state shared between the typing loop and whoever controls it.
+pub struct TypingControls {
+    // A bit for each `PauseReason` typing is paused for
+    paused: AtomicU8,
+    aborted: AtomicBool,
+    // Bits of the speed factor, as there is no atomic f64
+    speed: AtomicU64,
+    jump: Mutex<Option<Jump>>,
+    injected: Mutex<Injected>,
+}
+
+/// What typing is paused for, so that each watcher only resumes the pauses it made.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PauseReason {
+    /// The pause hotkey, the daemon or the TUI.
+    User,
+    /// Waiting for the user to go on, after a step or between files.
+    Step,
+    /// The window typed into lost focus.
+    Focus,
+    /// The screen got locked.
+    Lock,
+    /// The user typed or moved the mouse themselves.
+    Takeover,
+}
+
+impl PauseReason {
+    const fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// The pauses the pause hotkey, the daemon and the TUI take back.
+const USER_PAUSES: u8 =
+    PauseReason::User.bit() | PauseReason::Step.bit() | PauseReason::Takeover.bit();
+
+/// The input typer itself sends, told apart from the user's own.
+#[derive(Default)]
+struct Injected {
+    at: Option<Instant>,
+    // Keys pressed down and not yet released
+    held: Vec<Key>,
+}
+
+impl Default for TypingControls {
+    fn default() -> Self {
+        TypingControls {
+            paused: AtomicU8::new(0),
+            aborted: AtomicBool::new(false),
+            speed: AtomicU64::new(1.0f64.to_bits()),
+            jump: Mutex::new(None),
+            injected: Mutex::new(Injected::default()),
+        }
+    }
+}
+
+impl TypingControls {
+    pub fn new() -> Arc<Self> {
+        Arc::new(TypingControls::default())
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused.load(Ordering::SeqCst) != 0
+    }
+
+    pub fn is_paused_by(&self, reason: PauseReason) -> bool {
+        self.paused.load(Ordering::SeqCst) & reason.bit() != 0
+    }
+
+    pub fn pause(&self, reason: PauseReason) {
+        self.paused.fetch_or(reason.bit(), Ordering::SeqCst);
+    }
+
+    /// Takes back the pause for `reason`; typing stays paused while there are others.
+    pub fn resume(&self, reason: PauseReason) {
+        self.paused.fetch_and(!reason.bit(), Ordering::SeqCst);
+    }
+
+    /// Pauses for the user, or takes back the pauses that are the user's to take back:
+    /// their own, waiting for them after a step and stepping aside while they took
+    /// over. Pauses for the focus or the screen lock stay until their watchers resume.
+    pub fn set_paused(&self, paused: bool) {
+        match paused {
+            true => self.pause(PauseReason::User),
+            false => {
+                self.paused.fetch_and(!USER_PAUSES, Ordering::SeqCst);
+            }
+        }
+    }
+
+    /// Flips the pause state for the user, see [`TypingControls::set_paused`], and
+    /// returns whether the user's pause is on now.
+    pub fn toggle_pause(&self) -> bool {
+        let flipped = self
+            .paused
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |paused| {
+                Some(match paused & USER_PAUSES {
+                    0 => paused | PauseReason::User.bit(),
+                    _ => paused & !USER_PAUSES,
+                })
+            });
+        flipped.is_ok_and(|paused| paused & USER_PAUSES == 0)
+    }
+
+    pub fn is_aborted(&self) -> bool {
+        self.aborted.load(Ordering::SeqCst)
+    }
+
+    /// Stops the typing loop for good; it cannot be resumed afterwards.
+    pub fn abort(&self) {
+        self.aborted.store(true, Ordering::SeqCst);
+    }
+
+    /// How many times as fast as configured to type, 1.0 unless changed while typing.
+    pub fn speed(&self) -> f64 {
+        f64::from_bits(self.speed.load(Ordering::SeqCst))
+    }
+
+    pub fn set_speed(&self, speed: f64) {
+        self.speed.store(speed.to_bits(), Ordering::SeqCst);
+    }
+
+    /// Multiplies the speed by `factor`, within bounds, and returns the new speed.
+    pub fn change_speed(&self, factor: f64) -> f64 {
+        let speed = (self.speed() * factor).clamp(MIN_SPEED, MAX_SPEED);
+        self.set_speed(speed);
+        speed
+    }
+
+    /// Asks the typing loop to jump through the text before the next character.
+    pub fn request_jump(&self, jump: Jump) {
+        *self.jump.lock().unwrap() = Some(jump);
+    }
+
+    /// The jump requested since the last call, if any.
+    pub fn take_jump(&self) -> Option<Jump> {
+        self.jump.lock().unwrap().take()
+    }
+
+    /// Notes that the typing loop just sent `action`, see [`TypingControls::injecting`].
+    pub fn note_injected(&self, action: &KeyAction) {
+        let mut injected = self.injected.lock().unwrap();
+        injected.at = Some(Instant::now());
+        match *action {
+            KeyAction::Down { key } if !injected.held.contains(&key) => injected.held.push(key),
+            KeyAction::Up { key } => injected.held.retain(|&held| held != key),
+            _ => {}
+        }
+    }
+
+    /// Whether `pressed`, the keys down on the keyboard, and the mouse may be typer's own
+    /// doing: it sent something within the last `grace`, or holds down every one of the
+    /// `pressed` keys. Without any keys only the first counts.
+    pub fn injecting(&self, pressed: &[Keycode], grace: Duration) -> bool {
+        let injected = self.injected.lock().unwrap();
+        if injected.at.is_some_and(|at| at.elapsed() < grace) {
+            return true;
+        }
+        !pressed.is_empty()
+            && pressed.iter().all(|pressed| {
+                injected
+                    .held
+                    .iter()
+                    .any(|&key| keycodes(key).contains(pressed))
+            })
+    }
+}
+
+/// The keys the keyboard may report for `key`, none for keys only other layouts have.
+fn keycodes(key: Key) -> Vec<Keycode> {
+    let keycode = match key {
+        Key::Return => Keycode::Enter,
+        Key::Backspace => Keycode::Backspace,
+        Key::Tab => Keycode::Tab,
+        Key::Escape => Keycode::Escape,
+        Key::Delete => Keycode::Delete,
+        Key::Home => Keycode::Home,
+        Key::End => Keycode::End,
+        Key::PageUp => Keycode::PageUp,
+        Key::PageDown => Keycode::PageDown,
+        Key::Left => Keycode::Left,
+        Key::Right => Keycode::Right,
+        Key::Up => Keycode::Up,
+        Key::Down => Keycode::Down,
+        Key::Shift => return vec![Keycode::LShift, Keycode::RShift],
+        Key::Control => return vec![Keycode::LControl, Keycode::RControl],
+        Key::Alt => {
+            return vec![
+                Keycode::LAlt,
+                Keycode::RAlt,
+                Keycode::LOption,
+                Keycode::ROption,
+            ]
+        }
+        Key::Meta => return vec![Keycode::LMeta, Keycode::RMeta, Keycode::Command],
+        Key::Keypad(c) => match c {
+            '.' => Keycode::NumpadDecimal,
+            '+' => Keycode::NumpadAdd,
+            '-' => Keycode::NumpadSubtract,
+            '*' => Keycode::NumpadMultiply,
+            '/' => Keycode::NumpadDivide,
+            _ => {
+                return Keycode::from_str(&format!("Numpad{}", c))
+                    .into_iter()
+                    .collect()
+            }
+        },
+        Key::Char(c) => match c {
+            ' ' => Keycode::Space,
+            '-' => Keycode::Minus,
+            '=' => Keycode::Equal,
+            '`' => Keycode::Grave,
+            '[' => Keycode::LeftBracket,
+            ']' => Keycode::RightBracket,
+            '\\' => Keycode::BackSlash,
+            ';' => Keycode::Semicolon,
+            '\'' => Keycode::Apostrophe,
+            ',' => Keycode::Comma,
+            '.' => Keycode::Dot,
+            '/' => Keycode::Slash,
+            '0'..='9' => {
+                return Keycode::from_str(&format!("Key{}", c))
+                    .into_iter()
+                    .collect()
+            }
+            _ => {
+                let name = c.to_ascii_uppercase().to_string();
+                return Keycode::from_str(&name).into_iter().collect();
+            }
+        },
+    };
+    vec![keycode]
+}
+
+/// A jump through the text requested while typing, to get back in step with the target
+/// window, see [`TypingControls::request_jump`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Jump {
+    /// Skips the rest of the sentence.
+    Sentence,
+    /// Skips the rest of the paragraph.
+    Paragraph,
+    /// Goes on from the start of this line, counted from 1.
+    Line(usize),
+}
+
+impl Jump {
+    /// Where typing goes on after jumping from `i` in `chars`. The whitespace ending a
+    /// sentence or paragraph is still typed.
+    pub fn target(self, chars: &[char], i: usize) -> usize {
+        let ends_sentence = |k: usize| {
+            chars[k] == '\n'
+                || (".!?".contains(chars[k]) && chars.get(k + 1).is_none_or(|c| c.is_whitespace()))
+        };
+        match self {
+            Jump::Sentence => (i..chars.len())
+                .find(|&k| ends_sentence(k))
+                .map_or(chars.len(), |k| if chars[k] == '\n' { k } else { k + 1 }),
+            Jump::Paragraph => (i..chars.len())
+                .find(|&k| chars[k] == '\n' && chars.get(k + 1) == Some(&'\n'))
+                .unwrap_or(chars.len()),
+            // Line n starts after the (n - 1)th newline
+            Jump::Line(line) => match line.checked_sub(2) {
+                None => 0,
+                Some(newlines) => chars
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '\n')
+                    .nth(newlines)
+                    .map_or(chars.len(), |(k, _)| k + 1),
+            },
+        }
+    }
+}
+
+/// A key combination such as `F8` or `Ctrl+Shift+Q`.
+///
+/// Modifier names match either the left or the right key.
+#[derive(Clone, Debug)]
+pub struct Hotkey {
+    keys: Vec<Vec<Keycode>>,
+}
+
+impl Hotkey {
+    pub fn parse(name: &str) -> Result<Hotkey, String> {
+        let keys = name
+            .split('+')
+            .map(|part| match part.trim().to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => Ok(vec![Keycode::LControl, Keycode::RControl]),
+                "shift" => Ok(vec![Keycode::LShift, Keycode::RShift]),
+                "alt" => Ok(vec![Keycode::LAlt, Keycode::RAlt]),
+                "meta" | "super" | "cmd" => Ok(vec![Keycode::LMeta, Keycode::RMeta]),
+                _ => parse_key(part.trim()).map(|key| vec![key]),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Hotkey { keys })
+    }
+
+    /// Every key that makes up the hotkey, whichever of them are alternatives.
+    pub fn keys(&self) -> impl Iterator<Item = Keycode> + '_ {
+        self.keys.iter().flatten().copied()
+    }
+
+    fn is_down(&self, pressed: &[Keycode]) -> bool {
+        self.keys
+            .iter()
+            .all(|alternatives| alternatives.iter().any(|key| pressed.contains(key)))
+    }
+}
+
+pub enum HotkeyEvent {
+    Paused,
+    Resumed,
+    Aborted,
+    /// The speed was changed to this factor.
+    Speed(f64),
+    Jumped(Jump),
+}
+
+/// Polls the global keyboard state on a background thread and drives [`TypingControls`].
+///
+/// Holding Escape for a second always aborts, in addition to the configured abort hotkey.
+pub struct HotkeyListener {
+    pause: Hotkey,
+    abort: Hotkey,
+    speed: Option<SpeedHotkeys>,
+    skips: Vec<(Hotkey, Jump)>,
+}
+
+/// Hotkeys that change the speed while typing, by `step` each press.
+pub struct SpeedHotkeys {
+    pub slower: Hotkey,
+    pub faster: Hotkey,
+    /// Change per press, e.g. 0.25 to type 25% faster or slower.
+    pub step: f64,
+}
+
+impl HotkeyListener {
+    pub fn new(pause: Hotkey, abort: Hotkey) -> Self {
+        HotkeyListener {
+            pause,
+            abort,
+            speed: None,
+            skips: Vec::new(),
+        }
+    }
+
+    /// Also listens for `hotkey`, which requests `jump` from [`TypingControls`].
+    pub fn with_skip(mut self, hotkey: Hotkey, jump: Jump) -> Self {
+        self.skips.push((hotkey, jump));
+        self
+    }
+
+    /// Also listens for `speed`, which change the speed of [`TypingControls`].
+    pub fn with_speed(mut self, speed: SpeedHotkeys) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn spawn<F>(self, controls: Arc<TypingControls>, on_event: F) -> JoinHandle<()>
+    where
+        F: Fn(HotkeyEvent) + Send + 'static,
+    {
+        thread::spawn(move || {
+            let device_state = DeviceState::new();
+            let mut pause_was_down = false;
+            let mut speed_was_down = (false, false);
+            let mut skips_were_down = vec![false; self.skips.len()];
+            let mut escape_down_since: Option<Instant> = None;
+
+            while !controls.is_aborted() {
+                let keys = device_state.get_keys();
+
+                // Only react to the press itself, not to the key being held
+                let pause_down = self.pause.is_down(&keys);
+                if pause_down && !pause_was_down {
+                    if controls.toggle_pause() {
+                        on_event(HotkeyEvent::Paused);
+                    } else {
+                        on_event(HotkeyEvent::Resumed);
+                    }
+                }
+                pause_was_down = pause_down;
+
+                if let Some(speed) = &self.speed {
+                    let down = (speed.slower.is_down(&keys), speed.faster.is_down(&keys));
+                    let factor = match down {
+                        (true, _) if !speed_was_down.0 => Some(1.0 / (1.0 + speed.step)),
+                        (_, true) if !speed_was_down.1 => Some(1.0 + speed.step),
+                        _ => None,
+                    };
+                    if let Some(factor) = factor {
+                        on_event(HotkeyEvent::Speed(controls.change_speed(factor)));
+                    }
+                    speed_was_down = down;
+                }
+
+                for ((hotkey, jump), was_down) in self.skips.iter().zip(&mut skips_were_down) {
+                    let down = hotkey.is_down(&keys);
+                    if down && !*was_down {
+                        controls.request_jump(*jump);
+                        on_event(HotkeyEvent::Jumped(*jump));
+                    }
+                    *was_down = down;
+                }
+
+                escape_down_since = match escape_down_since {
+                    Some(since) if keys.contains(&ABORT_HOLD_KEY) => Some(since),
+                    _ if keys.contains(&ABORT_HOLD_KEY) => Some(Instant::now()),
+                    _ => None,
+                };
+                let escape_held =
+                    escape_down_since.is_some_and(|since| since.elapsed() >= ABORT_HOLD_DURATION);
+
+                if self.abort.is_down(&keys) || escape_held {
+                    controls.abort();
+                    on_event(HotkeyEvent::Aborted);
+                }
+
+                thread::sleep(POLL_INTERVAL);
+            }
+        })
+    }
+}
+
+/// Blocks until one of `hotkeys` is pressed and returns its index, or `None` once
+/// `controls` are aborted. Hotkeys already held down when called must be released first.
+pub fn wait_for_hotkey(hotkeys: &[Hotkey], controls: &TypingControls) -> Option<usize> {
+    let device_state = DeviceState::new();
+    let mut was_down = vec![true; hotkeys.len()];
+
+    while !controls.is_aborted() {
+        let keys = device_state.get_keys();
+        for (index, hotkey) in hotkeys.iter().enumerate() {
+            let down = hotkey.is_down(&keys);
+            if down && !was_down[index] {
+                return Some(index);
+            }
+            was_down[index] = down;
+        }
+        thread::sleep(POLL_INTERVAL);
+    }
+    None
+}
+
+pub fn parse_key(name: &str) -> Result<Keycode, String> {
+    Keycode::from_str(name).map_err(|_| format!("Unknown key name: {}", name))
+}
@@ -0,0 +1,174 @@
+use crate::daemon::{check_settable, Request, Response};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use std::thread::{self, JoinHandle};
+use tiny_http::{Header, Server};
+
+/// What a text posted to `/text` is reported as coming from.
+const POSTED_TEXT: &str = "text sent over HTTP";
+
+/// The only content type requests changing anything may have.
+const JSON: &str = "application/json";
+
+/// Listens on `addr` and answers the REST API of `typer daemon --serve` with `handle`,
+/// on a thread of its own. Requests must carry `token` as `Authorization: Bearer <token>`
+/// and come from no web page.
+pub fn spawn<F>(
+    addr: &str,
+    token: String,
+    handle: F,
+) -> Result<JoinHandle<()>, Box<dyn std::error::Error>>
+where
+    F: Fn(Request) -> Response + Send + 'static,
+{
+    let server = Server::http(addr).map_err(|e| format!("Failed to listen at {}: {}", addr, e))?;
+    Ok(thread::spawn(move || {
+        for mut request in server.incoming_requests() {
+            let header = |name: &str| {
+                request
+                    .headers()
+                    .iter()
+                    .find(|header| header.field.as_str().as_str().eq_ignore_ascii_case(name))
+                    .map(|header| header.value.as_str().to_string())
+            };
+            let (authorization, origin) = (header("Authorization"), header("Origin"));
+            let content_type = header("Content-Type");
+            let mut body = String::new();
+            let (code, response) =
+                match authorize(authorization.as_deref(), origin.as_deref(), &token) {
+                    Err((code, error)) => (code, Response::error(error)),
+                    Ok(()) if request.as_reader().read_to_string(&mut body).is_err() => {
+                        (400, Response::error("The request body is not UTF-8 text"))
+                    }
+                    Ok(()) => {
+                        let method = request.method().as_str();
+                        match route(method, request.url(), content_type.as_deref(), &body) {
+                            Ok(command) => {
+                                let response = handle(command);
+                                (if response.ok { 200 } else { 409 }, response)
+                            }
+                            Err((code, error)) => (code, Response::error(error)),
+                        }
+                    }
+                };
+
+            let json = serde_json::to_string(&response).unwrap_or_default();
+            let header =
+                Header::from_bytes("Content-Type", "application/json").expect("valid header");
+            let answer = tiny_http::Response::from_string(json)
+                .with_status_code(code)
+                .with_header(header);
+            let _ = request.respond(answer);
+        }
+    }))
+}
+
+/// A random token for when `typer daemon` is given none.
+pub fn generate_token() -> String {
+    let mut bytes = [0u8; 16];
+    OsRng.fill_bytes(&mut bytes);
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Whether `given` is `token`, taking as long wherever they differ so the time taken
+/// gives no hint of how much of a guess is right.
+pub fn same_token(given: &str, token: &str) -> bool {
+    let (given, token) = (given.as_bytes(), token.as_bytes());
+    let differences = (0..token.len()).fold(given.len() ^ token.len(), |differences, i| {
+        differences | usize::from(given.get(i).copied().unwrap_or(0) ^ token[i])
+    });
+    differences == 0
+}
+
+/// Checks the `Authorization` and `Origin` headers of a request, returning the status
+/// code and error to answer when it is refused. Browsers send an `Origin` with every
+/// request a web page makes, and no web page may drive the daemon.
+pub fn authorize(
+    authorization: Option<&str>,
+    origin: Option<&str>,
+    token: &str,
+) -> Result<(), (u16, String)> {
+    if origin.is_some() {
+        return Err((403, "Web pages may not use this API".to_string()));
+    }
+    let given = authorization.and_then(|value| value.strip_prefix("Bearer "));
+    match given {
+        Some(given) if same_token(given, token) => Ok(()),
+        _ => Err((401, "Missing or wrong token".to_string())),
+    }
+}
+
+/// The daemon request for an HTTP request, or the status code and error to answer.
+/// Requests that change anything must be `application/json`, which no web page can send
+/// to another site without asking it first.
+///
+/// - `GET /status` tells what the daemon is doing
+/// - `POST /text` loads the text to type from a body like `"Hello"` or `{"text":"Hello"}`
+/// - `POST /start`, `/pause`, `/resume` and `/abort` control the run
+/// - `PUT /speed` sets the speed factor from a body like `1.5` or `{"speed":1.5}`
+/// - `PATCH /config` changes settings from a body like `{"mistakes.probability":20}`
+pub fn route(
+    method: &str,
+    url: &str,
+    content_type: Option<&str>,
+    body: &str,
+) -> Result<Request, (u16, String)> {
+    let path = url.split('?').next().unwrap_or(url).trim_end_matches('/');
+    let expected = match path {
+        "/status" => "GET",
+        "/text" | "/start" | "/pause" | "/resume" | "/abort" => "POST",
+        "/speed" => "PUT",
+        "/config" => "PATCH",
+        _ => return Err((404, format!("No such endpoint: {}", path))),
+    };
+    if !method.eq_ignore_ascii_case(expected) {
+        return Err((405, format!("{} takes {} requests", path, expected)));
+    }
+    let media_type = content_type.map(|value| value.split(';').next().unwrap_or(value).trim());
+    if expected != "GET" && !media_type.is_some_and(|media| media.eq_ignore_ascii_case(JSON)) {
+        return Err((415, format!("{} takes {} bodies", path, JSON)));
+    }
+
+    let json = || {
+        serde_json::from_str::<serde_json::Value>(body)
+            .map_err(|e| (400, format!("Invalid JSON body: {}", e)))
+    };
+    Ok(match path {
+        "/status" => Request::Status,
+        "/text" => {
+            let value = json()?;
+            let text = value.get("text").unwrap_or(&value).as_str();
+            Request::Load {
+                text: text
+                    .ok_or((400, "Expected a text like \"Hello\"".to_string()))?
+                    .to_string(),
+                origin: POSTED_TEXT.to_string(),
+            }
+        }
+        "/start" => Request::Start,
+        "/pause" => Request::Pause,
+        "/resume" => Request::Resume,
+        "/abort" => Request::Abort,
+        "/speed" => {
+            let value = json()?;
+            let speed = value.get("speed").unwrap_or(&value).as_f64();
+            Request::Speed {
+                speed: speed.ok_or((400, "Expected a speed like 1.5".to_string()))?,
+            }
+        }
+        _ => {
+            let serde_json::Value::Object(settings) = json()? else {
+                return Err((400, "Expected an object of settings".to_string()));
+            };
+            let overrides: Vec<(String, String)> = settings
+                .into_iter()
+                .map(|(setting, value)| match value {
+                    serde_json::Value::String(value) => (setting, value),
+                    value => (setting, value.to_string()),
+                })
+                .collect();
+            check_settable(&overrides).map_err(|e| (403, e))?;
+            Request::Set { overrides }
+        }
+    })
+}
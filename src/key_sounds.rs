@@ -0,0 +1,205 @@
+use crate::events::KeyAction;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::f32::consts::TAU;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+
+/// Sample rate of the clicks made up by [`synthesize_click`].
+pub const CLICK_RATE: u32 = 44_100;
+
+/// A sound to play for a keystroke, as interleaved samples between -1 and 1.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeySample {
+    pub channels: u16,
+    pub rate: u32,
+    pub data: Vec<f32>,
+}
+
+/// How loud the clicks are and how much they differ from one another, in percent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClickVariation {
+    pub volume: u32,
+    /// How far the volume of a click may stray from `volume`, either way.
+    pub volume_variation: u32,
+    /// How far a click may be played faster or slower than the sample, which raises
+    /// or lowers its pitch.
+    pub pitch_variation: u32,
+}
+
+impl ClickVariation {
+    /// Picks the volume and playback speed of the next click, as factors.
+    pub fn pick(&self, rng: &mut impl Rng) -> (f32, f32) {
+        let volume = self.volume as f32 / 100.0 * (1.0 + jitter(rng, self.volume_variation));
+        let speed = 1.0 + jitter(rng, self.pitch_variation);
+        (volume.max(0.0), speed.max(0.1))
+    }
+}
+
+fn jitter(rng: &mut impl Rng, percent: u32) -> f32 {
+    match percent {
+        0 => 0.0,
+        _ => rng.gen_range(-1.0..=1.0) * percent as f32 / 100.0,
+    }
+}
+
+/// Whether a key event makes a click: keys going down do, keys coming up and the
+/// mouse do not.
+pub fn makes_click(action: &KeyAction) -> bool {
+    matches!(
+        action,
+        KeyAction::Type { .. } | KeyAction::Click { .. } | KeyAction::Down { .. }
+    )
+}
+
+/// Makes up the sound of a mechanical key, different for every `seed`: a sharp tick as
+/// the switch actuates and a duller thud as the key bottoms out.
+pub fn synthesize_click(seed: u64) -> KeySample {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let thud_at = rng.gen_range(0.006..0.012);
+    let thud_pitch = rng.gen_range(120.0..220.0);
+    let length = CLICK_RATE as usize * 40 / 1000;
+    let data = (0..length)
+        .map(|i| {
+            let t = i as f32 / CLICK_RATE as f32;
+            let tick = rng.gen_range(-1.0..1.0) * (-t / 0.0015).exp();
+            let after = t - thud_at;
+            let thud = match after >= 0.0 {
+                true => {
+                    (after * thud_pitch * TAU).sin() * (-after / 0.006).exp() * 0.6
+                        + rng.gen_range(-0.3..0.3) * (-after / 0.002).exp()
+                }
+                false => 0.0,
+            };
+            ((tick * 0.7 + thud) * 0.8).clamp(-1.0, 1.0)
+        })
+        .collect();
+    KeySample {
+        channels: 1,
+        rate: CLICK_RATE,
+        data,
+    }
+}
+
+/// The sound files among `paths`, with directories replaced by the `.wav` and `.ogg`
+/// files in them, by name.
+pub fn sample_files(paths: &[PathBuf]) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut files = Vec::new();
+    for path in paths {
+        if !path.is_dir() {
+            files.push(path.clone());
+            continue;
+        }
+        let entries =
+            fs::read_dir(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        let mut found = Vec::new();
+        for entry in entries {
+            let file = entry?.path();
+            let sound = file
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| {
+                    ext.eq_ignore_ascii_case("wav") || ext.eq_ignore_ascii_case("ogg")
+                });
+            if sound {
+                found.push(file);
+            }
+        }
+        if found.is_empty() {
+            return Err(format!("No .wav or .ogg files in {}", path.display()).into());
+        }
+        found.sort();
+        files.extend(found);
+    }
+    Ok(files)
+}
+
+/// Plays a click for every keystroke it is told about, on a thread of its own so typing
+/// never waits for it.
+pub struct KeySounds {
+    clicks: Sender<()>,
+}
+
+impl KeySounds {
+    /// Opens the default audio output and loads the sound files among `samples`, see
+    /// [`sample_files`], or makes up a few clicks when there are none.
+    #[cfg(feature = "sounds")]
+    pub fn start(
+        samples: &[PathBuf],
+        variation: ClickVariation,
+    ) -> Result<KeySounds, Box<dyn std::error::Error>> {
+        use rodio::buffer::SamplesBuffer;
+        use rodio::{OutputStream, Source};
+        use std::sync::mpsc;
+        use std::thread;
+
+        let samples = match samples.is_empty() {
+            true => (0..4).map(synthesize_click).collect(),
+            false => sample_files(samples)?
+                .iter()
+                .map(|file| decode(file))
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        let (clicks, rx) = mpsc::channel::<()>();
+        let (ready_tx, ready_rx) = mpsc::channel();
+        thread::spawn(move || {
+            // The output stream has to stay on the thread it was opened on
+            let (_stream, output) = match OutputStream::try_default() {
+                Ok(opened) => {
+                    let _ = ready_tx.send(Ok(()));
+                    opened
+                }
+                Err(e) => {
+                    let _ = ready_tx.send(Err(format!("Failed to open the audio output: {}", e)));
+                    return;
+                }
+            };
+            let mut rng = StdRng::from_entropy();
+            for () in rx {
+                let sample = &samples[rng.gen_range(0..samples.len())];
+                let (volume, speed) = variation.pick(&mut rng);
+                let click = SamplesBuffer::new(sample.channels, sample.rate, sample.data.clone())
+                    .amplify(volume)
+                    .speed(speed);
+                let _ = output.play_raw(click);
+            }
+        });
+        ready_rx
+            .recv()
+            .map_err(|_| "The audio thread ended before opening the output")??;
+        Ok(KeySounds { clicks })
+    }
+
+    #[cfg(not(feature = "sounds"))]
+    pub fn start(
+        _samples: &[PathBuf],
+        _variation: ClickVariation,
+    ) -> Result<KeySounds, Box<dyn std::error::Error>> {
+        Err("Key sounds need typer built with --features sounds".into())
+    }
+
+    /// Plays a click if `action` makes one, see [`makes_click`].
+    pub fn play(&self, action: &KeyAction) {
+        if makes_click(action) {
+            let _ = self.clicks.send(());
+        }
+    }
+}
+
+#[cfg(feature = "sounds")]
+fn decode(path: &std::path::Path) -> Result<KeySample, Box<dyn std::error::Error>> {
+    use rodio::Source;
+    use std::fs::File;
+    use std::io::BufReader;
+
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let decoder = rodio::Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+    let (channels, rate) = (decoder.channels(), decoder.sample_rate());
+    Ok(KeySample {
+        channels,
+        rate,
+        data: decoder.convert_samples().collect(),
+    })
+}
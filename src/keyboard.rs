@@ -0,0 +1,238 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+// Rows from the number row down, as printed on the unshifted keys
+const QWERTY_ROWS: [&str; 4] = ["1234567890-=", "qwertyuiop[]", "asdfghjkl;'", "zxcvbnm,./"];
+const DVORAK_ROWS: [&str; 4] = ["1234567890[]", "',.pyfgcrl/=", "aoeuidhtns-", ";qjkxbmwvz"];
+const COLEMAK_ROWS: [&str; 4] = ["1234567890-=", "qwfpgjluy;[]", "arstdhneio'", "zxcvbkm,./"];
+const AZERTY_ROWS: [&str; 4] = ["&é\"'(-è_çà)=", "azertyuiop^$", "qsdfghjklmù", "wxcvbn,;:!"];
+const QWERTZ_ROWS: [&str; 4] = ["1234567890ß", "qwertzuiopü+", "asdfghjklöä#", "yxcvbnm,.-"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hand {
+    Left,
+    Right,
+}
+
+/// The finger that touch-types a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Finger {
+    pub hand: Hand,
+    /// 0 for the index finger up to 3 for the pinky.
+    pub index: u8,
+}
+
+impl Finger {
+    /// Touch-typing assignment for a column of a staggered row.
+    fn for_column(column: usize) -> Finger {
+        let (hand, index) = match column {
+            0 => (Hand::Left, 3),
+            1 => (Hand::Left, 2),
+            2 => (Hand::Left, 1),
+            3 | 4 => (Hand::Left, 0),
+            5 | 6 => (Hand::Right, 0),
+            7 => (Hand::Right, 1),
+            8 => (Hand::Right, 2),
+            _ => (Hand::Right, 3),
+        };
+        Finger { hand, index }
+    }
+}
+
+/// Delay multipliers based on which fingers type two consecutive keys.
+#[derive(Clone, Debug)]
+pub struct FingerTimingConfig {
+    /// Two different keys typed with the same finger, e.g. "ed" on QWERTY.
+    pub same_finger_factor: f64,
+    /// Two keys typed with different hands.
+    pub hand_alternation_factor: f64,
+}
+
+impl Default for FingerTimingConfig {
+    fn default() -> Self {
+        FingerTimingConfig {
+            same_finger_factor: 1.4,
+            hand_alternation_factor: 0.85,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct KeyboardLayout {
+    nearby_keys: HashMap<char, Vec<char>>,
+    fingers: HashMap<char, Finger>,
+}
+
+/// On-disk format of a custom layout, in JSON or TOML.
+///
+/// `rows` are expanded like [`KeyboardLayout::from_rows`], then `nearby` entries
+/// (a key mapped to a string of its neighbours) are added on top, replacing any
+/// neighbours generated from the rows for that key.
+#[derive(Deserialize)]
+struct LayoutFile {
+    #[serde(default)]
+    rows: Vec<String>,
+    #[serde(default)]
+    nearby: HashMap<char, String>,
+}
+
+impl KeyboardLayout {
+    /// Looks up a built-in layout: `qwerty`, `dvorak`, `colemak`, `azerty` or `qwertz`.
+    pub fn named(name: &str) -> Result<Self, String> {
+        match name.to_ascii_lowercase().as_str() {
+            "qwerty" => Ok(KeyboardLayout::new()),
+            "dvorak" => Ok(KeyboardLayout::from_rows(&DVORAK_ROWS)),
+            "colemak" => Ok(KeyboardLayout::from_rows(&COLEMAK_ROWS)),
+            "azerty" => Ok(KeyboardLayout::from_rows(&AZERTY_ROWS)),
+            "qwertz" => Ok(KeyboardLayout::from_rows(&QWERTZ_ROWS)),
+            _ => Err(format!("Unknown keyboard layout: {}", name)),
+        }
+    }
+
+    /// Builds the adjacency map of a staggered keyboard from its rows, top row first.
+    ///
+    /// Each row is shifted half a key to the right of the one above it, so a key touches
+    /// its left and right neighbours, two keys above and two keys below.
+    pub fn from_rows(rows: &[&str]) -> Self {
+        let rows: Vec<Vec<char>> = rows.iter().map(|row| row.chars().collect()).collect();
+        let mut layout = HashMap::new();
+        let mut fingers = HashMap::new();
+
+        for (r, row) in rows.iter().enumerate() {
+            for (i, &key) in row.iter().enumerate() {
+                let mut nearby = Vec::new();
+                let mut add = |row: &Vec<char>, i: Option<usize>| {
+                    if let Some(&c) = i.and_then(|i| row.get(i)) {
+                        nearby.push(c);
+                    }
+                };
+
+                add(row, i.checked_sub(1));
+                add(row, Some(i + 1));
+                if let Some(above) = r.checked_sub(1).map(|r| &rows[r]) {
+                    add(above, Some(i));
+                    add(above, Some(i + 1));
+                }
+                if let Some(below) = rows.get(r + 1) {
+                    add(below, i.checked_sub(1));
+                    add(below, Some(i));
+                }
+
+                layout.insert(key, nearby);
+                fingers.insert(key, Finger::for_column(i));
+            }
+        }
+
+        KeyboardLayout {
+            nearby_keys: layout,
+            fingers,
+        }
+    }
+
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read layout {}: {}", path.display(), e))?;
+        let file: LayoutFile = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)?
+        } else {
+            serde_json::from_str(&content)?
+        };
+
+        let rows: Vec<&str> = file.rows.iter().map(String::as_str).collect();
+        let mut layout = KeyboardLayout::from_rows(&rows);
+        for (key, nearby) in file.nearby {
+            layout.nearby_keys.insert(key, nearby.chars().collect());
+        }
+
+        if layout.nearby_keys.is_empty() {
+            return Err(format!("Layout {} does not define any keys", path.display()).into());
+        }
+        Ok(layout)
+    }
+
+    pub fn new() -> Self {
+        let mut layout = HashMap::new();
+        layout.insert('a', vec!['s', 'q', 'w', 'z']);
+        layout.insert('b', vec!['v', 'n', 'h', 'g']);
+        layout.insert('c', vec!['x', 'v', 'd', 'f']);
+        layout.insert('d', vec!['s', 'f', 'e', 'r']);
+        layout.insert('e', vec!['w', 'r', 'd', 'f']);
+        layout.insert('f', vec!['d', 'g', 'r', 't']);
+        layout.insert('g', vec!['f', 'h', 't', 'y']);
+        layout.insert('h', vec!['g', 'j', 'y', 'u']);
+        layout.insert('i', vec!['u', 'o', 'k', 'l']);
+        layout.insert('j', vec!['h', 'k', 'u', 'i']);
+        layout.insert('k', vec!['j', 'l', 'i', 'o']);
+        layout.insert('l', vec!['k', ';', 'o', 'p']);
+        layout.insert('m', vec!['n', ',', 'j', 'k']);
+        layout.insert('n', vec!['b', 'm', 'h', 'j']);
+        layout.insert('o', vec!['i', 'p', 'k', 'l']);
+        layout.insert('p', vec!['o', '[', 'l', ';']);
+        layout.insert('q', vec!['w', 'a', '1', '2']);
+        layout.insert('r', vec!['e', 't', 'd', 'f']);
+        layout.insert('s', vec!['a', 'd', 'w', 'e']);
+        layout.insert('t', vec!['r', 'y', 'f', 'g']);
+        layout.insert('u', vec!['y', 'i', 'h', 'j']);
+        layout.insert('v', vec!['c', 'b', 'f', 'g']);
+        layout.insert('w', vec!['q', 'e', 'a', 's']);
+        layout.insert('x', vec!['z', 'c', 's', 'd']);
+        layout.insert('y', vec!['t', 'u', 'g', 'h']);
+        layout.insert('z', vec!['a', 'x', 's', 'd']);
+
+        KeyboardLayout {
+            nearby_keys: layout,
+            fingers: KeyboardLayout::from_rows(&QWERTY_ROWS).fingers,
+        }
+    }
+
+    pub fn finger(&self, c: char) -> Option<Finger> {
+        let c_lower = c.to_lowercase().next().unwrap_or(c);
+        self.fingers.get(&c_lower).copied()
+    }
+
+    /// Delay multiplier for typing `second` right after `first`.
+    pub fn finger_factor(&self, config: &FingerTimingConfig, first: char, second: char) -> f64 {
+        if first.to_lowercase().eq(second.to_lowercase()) {
+            return 1.0;
+        }
+
+        match (self.finger(first), self.finger(second)) {
+            (Some(a), Some(b)) if a == b => config.same_finger_factor,
+            (Some(a), Some(b)) if a.hand != b.hand => config.hand_alternation_factor,
+            _ => 1.0,
+        }
+    }
+
+    pub fn get_nearby_key<R: Rng>(&self, c: char, rng: &mut R) -> char {
+        let c_lower = single_char(c.to_lowercase(), c);
+        if let Some(nearby) = self.nearby_keys.get(&c_lower) {
+            let result = *nearby.choose(rng).unwrap_or(&c_lower);
+            if c.is_uppercase() {
+                single_char(result.to_uppercase(), result)
+            } else {
+                result
+            }
+        } else {
+            c
+        }
+    }
+}
+
+/// The case conversion `converted` of `c` when it is a single character, like 'ü' for
+/// 'Ü', or `c` as it is when it is not, like "SS" for 'ß'.
+fn single_char(mut converted: impl Iterator<Item = char>, c: char) -> char {
+    match (converted.next(), converted.next()) {
+        (Some(one), None) => one,
+        _ => c,
+    }
+}
+
+impl Default for KeyboardLayout {
+    fn default() -> Self {
+        KeyboardLayout::new()
+    }
+}
@@ -0,0 +1,79 @@
+pub mod apps;
+pub mod autosave;
+pub mod backend;
+pub mod blacklist;
+pub mod breaks;
+pub mod calibrate;
+pub mod chat;
+pub mod checkpoint;
+pub mod code;
+pub mod config;
+pub mod daemon;
+pub mod dataset;
+pub mod diff;
+pub mod directives;
+pub mod distraction;
+pub mod events;
+pub mod export;
+pub mod fatigue;
+pub mod form;
+pub mod guards;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod hooks;
+pub mod hotkeys;
+pub mod http;
+pub mod key_sounds;
+pub mod keyboard;
+pub mod lock;
+pub mod mishaps;
+pub mod notifications;
+#[cfg(feature = "overlay")]
+pub mod overlay;
+pub mod pacing;
+pub mod paste;
+pub mod paths;
+pub mod permissions;
+pub mod profile;
+pub mod reading;
+pub mod report;
+pub mod screen;
+pub mod sheet;
+pub mod shell;
+pub mod snippets;
+pub mod takeover;
+pub mod template;
+pub mod text;
+pub mod time_of_day;
+pub mod timing;
+#[cfg(all(feature = "tray", target_os = "linux"))]
+pub mod tray;
+pub mod triggers;
+#[cfg(feature = "tui")]
+pub mod tui;
+pub mod typist;
+pub mod typos;
+pub mod viewport;
+pub mod websocket;
+pub mod window;
+
+pub use autosave::AutosaveConfig;
+pub use backend::{BackendKind, KeyboardBackend, MockBackend};
+pub use breaks::{BreakConfig, BreakEvent};
+pub use chat::ChatConfig;
+pub use code::{AutoIndent, CodeModeConfig, Language};
+pub use config::{Config, CorrectionStyle, MistakeRates, NewlineKey, Step, TypingConfig};
+pub use distraction::{DistractionAction, DistractionConfig};
+pub use fatigue::{FatigueConfig, WarmUpConfig};
+pub use hotkeys::TypingControls;
+pub use keyboard::KeyboardLayout;
+pub use mishaps::{Mishap, MishapConfig};
+pub use paste::PasteConfig;
+pub use profile::PersonalProfile;
+pub use reading::ReadAheadConfig;
+pub use shell::ShellConfig;
+pub use text::TextSource;
+pub use time_of_day::TimeOfDayConfig;
+pub use timing::{BurstConfig, Delay, DelayDistribution, KeyHoldConfig};
+pub use typist::{HumanTypist, HumanTypistBuilder, Progress, TypingOutcome, TypingStats};
+pub use viewport::{ViewportAction, ViewportConfig};
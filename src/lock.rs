@@ -0,0 +1,196 @@
+use crate::config::LockAction;
+use crate::hotkeys::{PauseReason, TypingControls};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+/// Whether the session typer runs in takes keystrokes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SessionState {
+    Active,
+    /// The screen is locked.
+    Locked,
+    /// Another session is in front, after switching users or disconnecting.
+    Inactive,
+}
+
+impl SessionState {
+    pub fn describe(self) -> &'static str {
+        match self {
+            SessionState::Active => "the session is active",
+            SessionState::Locked => "the screen is locked",
+            SessionState::Inactive => "the session is no longer active",
+        }
+    }
+}
+
+/// The state of the session typer runs in, or `None` when it cannot be told.
+#[cfg(target_os = "linux")]
+pub fn session_state() -> Option<SessionState> {
+    let session = std::env::var("XDG_SESSION_ID").unwrap_or_else(|_| "self".to_string());
+    let output = std::process::Command::new("loginctl")
+        .args([
+            "show-session",
+            &session,
+            "--property=Type",
+            "--property=LockedHint",
+        ])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_loginctl(&String::from_utf8_lossy(&output.stdout))
+}
+
+#[cfg(target_os = "macos")]
+pub fn session_state() -> Option<SessionState> {
+    let output = std::process::Command::new("ioreg")
+        .args(["-n", "Root", "-d1"])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let uid = std::process::Command::new("id").arg("-u").output().ok()?;
+    let uid = String::from_utf8_lossy(&uid.stdout).trim().parse().ok()?;
+    parse_ioreg(&String::from_utf8_lossy(&output.stdout), uid)
+}
+
+#[cfg(windows)]
+pub fn session_state() -> Option<SessionState> {
+    use windows_sys::Win32::System::StationsAndDesktops::{
+        CloseDesktop, GetUserObjectInformationW, OpenInputDesktop, DESKTOP_READOBJECTS, UOI_NAME,
+    };
+
+    // Locked, the input desktop is Winlogon's, which the session is not even let open
+    let desktop = unsafe { OpenInputDesktop(0, 0, DESKTOP_READOBJECTS) };
+    if desktop.is_null() {
+        return Some(SessionState::Locked);
+    }
+    let mut name = [0u16; 64];
+    let mut needed = 0;
+    let read = unsafe {
+        GetUserObjectInformationW(
+            desktop,
+            UOI_NAME,
+            name.as_mut_ptr().cast(),
+            std::mem::size_of_val(&name) as u32,
+            &mut needed,
+        )
+    } != 0;
+    unsafe { CloseDesktop(desktop) };
+    if !read {
+        return None;
+    }
+    let length = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+    // Any other desktop in front, like the secure one of a UAC prompt, takes no keys
+    Some(
+        match String::from_utf16_lossy(&name[..length]).eq_ignore_ascii_case("Default") {
+            true => SessionState::Active,
+            false => SessionState::Locked,
+        },
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub fn session_state() -> Option<SessionState> {
+    None
+}
+
+/// Reads the `Type` and `LockedHint` properties printed by `loginctl show-session`,
+/// telling nothing for sessions without a screen.
+pub fn parse_loginctl(output: &str) -> Option<SessionState> {
+    let mut locked = None;
+    for line in output.lines() {
+        match line.trim().split_once('=') {
+            // Over SSH or on a text console there is no screen to lock, and such
+            // sessions never count as active either
+            Some(("Type", kind)) if !["x11", "wayland", "mir"].contains(&kind) => return None,
+            Some(("LockedHint", value)) => locked = Some(value == "yes"),
+            _ => {}
+        }
+    }
+    match locked? {
+        true => Some(SessionState::Locked),
+        false => Some(SessionState::Active),
+    }
+}
+
+/// Reads the properties of the console user with `uid` from the IORegistry root, as
+/// printed by `ioreg -n Root -d1`, leaving out the other users logged in.
+pub fn parse_ioreg(output: &str, uid: u32) -> Option<SessionState> {
+    let users = output
+        .lines()
+        .find(|line| line.contains("\"IOConsoleUsers\""))?;
+    let id = format!("\"kCGSSessionUserIDKey\"={}", uid);
+    let user = users
+        .split('}')
+        .find(|user| user.split([',', '{']).any(|property| property.trim() == id))?;
+    if user.contains("\"CGSSessionScreenIsLocked\"=Yes") {
+        Some(SessionState::Locked)
+    } else if user.contains("\"kCGSSessionOnConsoleKey\"=No") {
+        Some(SessionState::Inactive)
+    } else {
+        Some(SessionState::Active)
+    }
+}
+
+/// Polls the session state on a background thread and aborts or pauses typing as soon
+/// as the screen locks or the session becomes inactive. A paused run goes on once the
+/// session is active again. Stops when dropped.
+pub struct LockWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl LockWatcher {
+    /// Calls `on_change` with the state the session went into, after acting on it: when
+    /// it stops being active and, for a paused run, when it is active again.
+    pub fn spawn<F>(
+        action: LockAction,
+        interval: Duration,
+        controls: Arc<TypingControls>,
+        on_change: F,
+    ) -> Self
+    where
+        F: Fn(SessionState) + Send + 'static,
+    {
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watched = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let mut was_locked = false;
+            loop {
+                thread::sleep(interval);
+                if watched.load(Ordering::SeqCst) || controls.is_aborted() {
+                    return;
+                }
+                // A session whose state cannot be told is taken to be as it was
+                let Some(state) = session_state() else {
+                    continue;
+                };
+                let locked = state != SessionState::Active;
+                if locked && !was_locked {
+                    match action {
+                        LockAction::Abort => controls.abort(),
+                        LockAction::Pause => controls.pause(PauseReason::Lock),
+                        LockAction::Ignore => {}
+                    }
+                    on_change(state);
+                } else if !locked && was_locked && action == LockAction::Pause {
+                    controls.resume(PauseReason::Lock);
+                    on_change(state);
+                }
+                was_locked = locked;
+            }
+        });
+
+        LockWatcher { stopped }
+    }
+}
+
+impl Drop for LockWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
@@ -1,294 +1,1585 @@
-use std::{thread, time::Duration};
-use std::io::{self, Write};
+mod cli;
+
+use clap::{Args, Parser, Subcommand};
+use cli::hooks::with_hooks;
+use cli::session::{count_down, guard, type_texts, with_key_sounds, Session, SnippetSession};
+use cli::texts::{fill_placeholders, read_texts, sheet_texts};
+use cli::{ask, check_permissions, load_run_config, spawn_hotkey_listener};
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::terminal;
+use indicatif::ProgressBar;
+use notify::{RecursiveMode, Watcher};
+use std::collections::HashMap;
 use std::fs;
-use std::path::PathBuf;
-use rand::Rng;
-use rand::seq::SliceRandom;
-use enigo::*;
-use serde::{Serialize, Deserialize};
-
-type Range<T> = std::ops::Range<T>;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use typer::backend::create_backend;
+use typer::calibrate::{Calibration, Keystroke};
+use typer::checkpoint::{get_checkpoint_path, Checkpoint};
+use typer::code::TypingMode;
+use typer::config::{
+    format_config, get_config_path, load_config, parse_override, save_config, Config, Sloppiness,
+};
+use typer::daemon::{self, DaemonState, Request};
+use typer::dataset;
+use typer::diff::{
+    added_lines, diff_lines, git_patch, parse_patch, plan, type_diff, DiffStep, LineChange,
+};
+use typer::directives::{strip_directives, Script};
+use typer::events::read_keystroke_log;
+use typer::export::{export_script, ScriptFormat};
+use typer::form::{fill_form, read_record};
+use typer::hotkeys::{wait_for_hotkey, Hotkey};
+#[cfg(feature = "overlay")]
+use typer::overlay::Overlay;
+use typer::pacing::parse_budget;
+use typer::paths;
+use typer::snippets::{rank, SnippetLibrary};
+use typer::template::{parse_var, read_vars_file};
+use typer::text::{
+    read_playlist, read_text_file, ClipboardSource, DefaultTextFile, FileSource, PatchSource,
+    SelectedSource, Selection, StdinSource, TextSource, UrlSource,
+};
+#[cfg(all(feature = "tray", target_os = "linux"))]
+use typer::tray::{TrayAction, TrayIcon};
+use typer::triggers::StreamDeckPlugin;
+use typer::{HumanTypist, MockBackend, TypingControls, TypingOutcome};
+
+const CALIBRATION_SAMPLE: &str = "The best way to learn how someone types is to watch them \
+    do it. Type this paragraph at your usual pace, without rushing and without slowing down \
+    on purpose. If you make a mistake, fix it the way you always do, or leave it if that is \
+    what you would normally do. Short words, long words, commas, and the occasional number \
+    like 42 all help to paint the picture.";
+const WATCH_POLL_INTERVAL: Duration = Duration::from_millis(200);
+const WATCH_SETTLE_TIME: Duration = Duration::from_millis(300);
+const WATCH_COUNTDOWN_SECS: u64 = 3;
+/// Time to get back to the window to type into after picking from the tray menu.
+#[cfg(all(feature = "tray", target_os = "linux"))]
+const TRAY_COUNTDOWN_SECS: u64 = 2;
+
+#[derive(Parser)]
+#[command(name = "typer", version, about = "Realistic typer for x11")]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Keep the config and text files next to the executable
+    #[arg(long, global = true)]
+    portable: bool,
+}
 
-struct TypingConfig {
-    base_delay: Range<u64>,
-    thinking_delay: Range<u64>,
-    mistake_probability: u32,
-    correction_delay: Range<u64>,
-    long_pause_probability: u32,
-    long_pause_delay: Range<u64>,
+#[derive(Subcommand)]
+enum Command {
+    /// Type the text into the focused window
+    Run(RunArgs),
+    /// Show, locate or reset the config file
+    Config {
+        #[command(subcommand)]
+        action: Option<ConfigAction>,
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Answer a few questions and write a config tuned to them
+    Init {
+        /// Config file to write instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Take a typing test and save your own timings and mistakes as a profile
+    Calibrate {
+        /// Text file with the paragraph to type instead of the built-in one
+        #[arg(long)]
+        text: Option<PathBuf>,
+        /// Name of the profile to save the results as
+        #[arg(long, default_value = "calibrated")]
+        profile: String,
+        /// Config file to save the profile to instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Build a personal profile from a keystroke-dynamics dataset (CSV of press and release times)
+    ImportDataset {
+        /// CSV, TSV or semicolon separated file of key presses
+        file: PathBuf,
+        /// Profile file to write
+        #[arg(short, long, default_value = "profile.json")]
+        output: PathBuf,
+        /// Name stored in the profile; the file name when omitted
+        #[arg(long)]
+        name: Option<String>,
+        /// Times in the file are in seconds instead of milliseconds
+        #[arg(long)]
+        seconds: bool,
+    },
+    /// Continue the last typing run that was aborted or crashed
+    Resume {
+        /// Seconds to wait before typing starts (prompts when omitted)
+        #[arg(long)]
+        countdown: Option<u64>,
+        /// Print the simulated keystrokes and pauses instead of typing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Play back a keystroke log with its exact timings
+    Replay(ReplayArgs),
+    /// Write the keystrokes of a typing run to a script that plays them back without typer
+    Export(ExportArgs),
+    /// Type the values of a JSON or CSV record into the fields of a form
+    Form(FormArgs),
+    /// Type the lines a git commit or patch adds, like a programmer writing them
+    Git(RunArgs),
+    /// Edit a document from one version of a text into another, typing only what changed
+    Diff(DiffArgs),
+    /// Manage the snippet library and type snippets by name or hotkey
+    Snippets {
+        #[command(subcommand)]
+        action: SnippetAction,
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Open a window to type text from, with no need for the terminal
+    #[cfg(feature = "gui")]
+    Gui {
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Named profile from the config file to apply
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// Sit in the system tray: click to type the clipboard, or pick a snippet, a profile
+    /// or abort from the menu
+    #[cfg(all(feature = "tray", target_os = "linux"))]
+    Tray {
+        #[command(flatten)]
+        typing: SnippetTypingArgs,
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Stay in the background and type the text loaded with `typer ctl`, for other tools
+    /// and hotkey managers to drive
+    Daemon {
+        #[command(flatten)]
+        typing: SnippetTypingArgs,
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+        /// Also answer a REST API at this address, e.g. 127.0.0.1:7777
+        #[arg(long, value_name = "ADDRESS")]
+        serve: Option<String>,
+        /// Also stream progress to WebSocket clients and take commands from them at this
+        /// address, e.g. 127.0.0.1:7778
+        #[arg(long, value_name = "ADDRESS")]
+        websocket: Option<String>,
+        /// Token REST API and WebSocket clients must send as `Authorization: Bearer <TOKEN>`,
+        /// instead of a random one printed at startup
+        #[arg(long)]
+        token: Option<String>,
+        /// Web page origin, like https://dashboard.example, allowed to connect to the
+        /// WebSocket; pages from anywhere else are refused
+        #[arg(long = "allow-origin", value_name = "ORIGIN")]
+        allow_origins: Vec<String>,
+    },
+    /// Turn the MIDI notes and controllers bound in `triggers.bindings` into commands for
+    /// `typer daemon`
+    Triggers {
+        /// Config file to use instead of the default one
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Send a command to `typer daemon`
+    Ctl {
+        #[command(subcommand)]
+        action: CtlAction,
+    },
+    /// Print the text that would be typed without typing it
+    Preview {
+        #[command(flatten)]
+        source: SourceArgs,
+    },
 }
 
-impl Default for TypingConfig {
-    fn default() -> Self {
-        TypingConfig {
-            base_delay: 20..100,
-            thinking_delay: 500..1500,
-            mistake_probability: 10,
-            correction_delay: 300..700,
-            long_pause_probability: 5,
-            long_pause_delay: 1000..3000,
+/// Where to read the text from; the default text file when none is given.
+#[derive(Args, Default)]
+#[group(skip)]
+struct SourceArgs {
+    /// Text files to type one after the other
+    #[arg(group = "source")]
+    files: Vec<PathBuf>,
+    /// File listing the text files to type, one per line
+    #[arg(long, group = "source")]
+    playlist: Option<PathBuf>,
+    /// Text file to use instead of the default one
+    #[arg(long, group = "source")]
+    text: Option<PathBuf>,
+    /// Use the clipboard contents instead of a text file
+    #[arg(long, group = "source")]
+    from_clipboard: bool,
+    /// Read the text from standard input
+    #[arg(long, group = "source")]
+    from_stdin: bool,
+    /// Download the text from a URL
+    #[arg(long, group = "source")]
+    url: Option<String>,
+    /// Type the lines a git commit adds, file by file, e.g. HEAD
+    #[arg(long, value_name = "REVISION", group = "source")]
+    commit: Option<String>,
+    /// Type the lines a patch file adds, file by file
+    #[arg(long, group = "source")]
+    patch: Option<PathBuf>,
+    /// Type only these lines of each text, counted from 1, e.g. 40..120
+    #[arg(long, value_name = "FIRST..LAST", value_parser = Selection::parse_range, conflicts_with = "chars")]
+    lines: Option<(usize, Option<usize>)>,
+    /// Type only these characters of each text, counted from 1, e.g. 1000..2500
+    #[arg(long, value_name = "FIRST..LAST", value_parser = Selection::parse_range)]
+    chars: Option<(usize, Option<usize>)>,
+}
+
+impl SourceArgs {
+    fn into_sources(self) -> Result<Vec<Box<dyn TextSource>>, Box<dyn std::error::Error>> {
+        let selection = match (self.lines, self.chars) {
+            (Some((first, last)), _) => Some(Selection::Lines(first, last)),
+            (_, Some((first, last))) => Some(Selection::Chars(first, last)),
+            _ => None,
+        };
+        let sources = self.sources()?;
+        Ok(match selection {
+            Some(selection) => sources
+                .into_iter()
+                .map(|source| Box::new(SelectedSource { source, selection }) as Box<dyn TextSource>)
+                .collect(),
+            None => sources,
+        })
+    }
+
+    fn sources(self) -> Result<Vec<Box<dyn TextSource>>, Box<dyn std::error::Error>> {
+        let files = match self.playlist {
+            Some(playlist) => read_playlist(&playlist)?,
+            None => self.files,
+        };
+        if !files.is_empty() {
+            return Ok(files
+                .into_iter()
+                .map(|path| Box::new(FileSource { path }) as Box<dyn TextSource>)
+                .collect());
+        }
+
+        if let Some(revision) = self.commit {
+            return patch_sources(revision.clone(), &git_patch(&revision)?);
         }
+        if let Some(path) = self.patch {
+            let patch = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+            return patch_sources(path.display().to_string(), &patch);
+        }
+
+        let source: Box<dyn TextSource> = if let Some(path) = self.text {
+            Box::new(FileSource { path })
+        } else if let Some(url) = self.url {
+            Box::new(UrlSource { url })
+        } else if self.from_clipboard {
+            Box::new(ClipboardSource)
+        } else if self.from_stdin {
+            Box::new(StdinSource)
+        } else {
+            Box::new(DefaultTextFile)
+        };
+        Ok(vec![source])
+    }
+}
+
+/// A source for each file `patch` adds lines to.
+fn patch_sources(
+    origin: String,
+    patch: &str,
+) -> Result<Vec<Box<dyn TextSource>>, Box<dyn std::error::Error>> {
+    let files = parse_patch(patch);
+    if files.is_empty() {
+        return Err(format!("{} adds no lines to type", origin).into());
     }
+    Ok(files
+        .into_iter()
+        .map(|file| {
+            Box::new(PatchSource {
+                origin: origin.clone(),
+                file,
+            }) as Box<dyn TextSource>
+        })
+        .collect())
 }
 
-#[derive(Serialize, Deserialize)]
-struct Config {
-    base_delay_min: u64,
-    base_delay_max: u64,
-    thinking_delay_min: u64,
-    thinking_delay_max: u64,
-    mistake_probability: u32,
-    correction_delay_min: u64,
-    correction_delay_max: u64,
-    long_pause_probability: u32,
-    long_pause_delay_min: u64,
-    long_pause_delay_max: u64,
+/// Values for the `{{name}}` placeholders of the texts, which are only filled when the
+/// texts are templates.
+#[derive(Args, Clone, Default)]
+struct VarArgs {
+    /// Fill the `{{NAME}}` placeholders with VALUE, e.g. `--var name=Alice` (repeatable)
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_var)]
+    vars: Vec<(String, String)>,
+    /// File with placeholder values: NAME=VALUE lines, or a JSON or TOML table
+    #[arg(long, value_name = "FILE")]
+    vars_file: Option<PathBuf>,
+    /// Fill the `{{NAME}}` placeholders of the texts, asking for any value not given;
+    /// implied by --var and --vars-file
+    #[arg(long)]
+    template: bool,
 }
 
-impl Default for Config {
-    fn default() -> Self {
-        Config {
-            base_delay_min: 20,
-            base_delay_max: 100,
-            thinking_delay_min: 500,
-            thinking_delay_max: 1500,
-            mistake_probability: 10,
-            correction_delay_min: 300,
-            correction_delay_max: 700,
-            long_pause_probability: 5,
-            long_pause_delay_min: 1000,
-            long_pause_delay_max: 3000,
+impl VarArgs {
+    /// The values given on the command line, which win over those of the vars file, or
+    /// `None` when the texts are to be typed as they are.
+    fn to_vars(&self) -> Result<Option<HashMap<String, String>>, Box<dyn std::error::Error>> {
+        if !self.template && self.vars.is_empty() && self.vars_file.is_none() {
+            return Ok(None);
         }
+        let mut vars = match &self.vars_file {
+            Some(path) => read_vars_file(path)?,
+            None => HashMap::new(),
+        };
+        vars.extend(self.vars.iter().cloned());
+        Ok(Some(vars))
+    }
+}
+
+#[derive(Args, Default)]
+struct RunArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    #[command(flatten)]
+    vars: VarArgs,
+    /// Config file to use instead of the default one
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Named profile from the config file to apply
+    #[arg(long)]
+    profile: Option<String>,
+    /// Override a config setting, e.g. `--set mistakes.probability=20` (repeatable)
+    #[arg(long = "set", value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// What is being typed: text, code to type like a programmer (language by file
+    /// extension), chat messages separated by blank lines, terminal commands, one per
+    /// line, or a CSV table to type into a spreadsheet
+    #[arg(long, default_value = "text")]
+    mode: TypingMode,
+    /// Seconds to wait before typing starts (prompts when omitted)
+    #[arg(long)]
+    countdown: Option<u64>,
+    /// Print the simulated keystrokes and pauses instead of typing them
+    #[arg(long)]
+    dry_run: bool,
+    /// Only start typing if the focused window's title or app name contains this
+    #[arg(long)]
+    target: Option<String>,
+    /// Type even into windows on the blacklist, such as password managers
+    #[arg(long)]
+    ignore_blacklist: bool,
+    /// Activate the window whose title or app name contains this before typing,
+    /// without a countdown unless one is given
+    #[arg(long, value_name = "NAME")]
+    focus_window: Option<String>,
+    /// Write the end-of-run session report to this JSON file
+    #[arg(long, value_name = "FILE")]
+    report: Option<PathBuf>,
+    /// Log every key event with its timing to this JSON Lines file
+    #[arg(long, value_name = "FILE")]
+    keystroke_log: Option<PathBuf>,
+    /// Type the text file again every time it changes
+    #[arg(long)]
+    watch: bool,
+    /// Pace typing to be done within this time, e.g. 45m or 1h30m
+    #[arg(long, value_name = "DURATION", value_parser = parse_budget)]
+    finish_in: Option<Duration>,
+    /// Stop after every line or paragraph until the pause hotkey is pressed, to type in
+    /// front of an audience one chunk at a time
+    #[arg(long, value_parser = ["line", "paragraph"])]
+    step: Option<String>,
+    /// Show progress, speed and pause state in a small always-on-top window
+    #[cfg(feature = "overlay")]
+    #[arg(long)]
+    overlay: bool,
+    /// Show the text, progress, speed and pauses in a control panel in the terminal
+    #[cfg(feature = "tui")]
+    #[arg(long)]
+    tui: bool,
+}
+
+#[derive(Args)]
+struct ReplayArgs {
+    /// Keystroke log written by `typer run --keystroke-log`
+    log: PathBuf,
+    /// Config file with the hotkeys to use instead of the default one
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Seconds to wait before the replay starts (prompts when omitted)
+    #[arg(long)]
+    countdown: Option<u64>,
+    /// Print the key events instead of sending them
+    #[arg(long)]
+    dry_run: bool,
+    /// Type even into windows on the blacklist, such as password managers
+    #[arg(long)]
+    ignore_blacklist: bool,
+}
+
+#[derive(Args)]
+struct ExportArgs {
+    #[command(flatten)]
+    source: SourceArgs,
+    #[command(flatten)]
+    vars: VarArgs,
+    /// Config file to use instead of the default one
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Named profile from the config file to apply
+    #[arg(long)]
+    profile: Option<String>,
+    /// Override a config setting, e.g. `--set mistakes.probability=20` (repeatable)
+    #[arg(long = "set", value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// What is being typed: text, code to type like a programmer (language by file
+    /// extension), chat messages separated by blank lines, terminal commands, one per
+    /// line, or a CSV table to type into a spreadsheet
+    #[arg(long, default_value = "text")]
+    mode: TypingMode,
+    /// Script format: ahk (AutoHotkey v2) or xdotool (shell script)
+    #[arg(long)]
+    format: ScriptFormat,
+    /// Script file to write
+    #[arg(long, short)]
+    output: PathBuf,
+    /// Seconds the script waits before typing starts
+    #[arg(long, default_value_t = 5)]
+    countdown: u64,
+}
+
+#[derive(Args)]
+struct FormArgs {
+    /// JSON or CSV file with the record to type
+    record: PathBuf,
+    /// Which record of the file to type, counting from 1
+    #[arg(long, default_value_t = 1)]
+    row: usize,
+    /// Config file to use instead of the default one
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Named profile from the config file to apply
+    #[arg(long)]
+    profile: Option<String>,
+    /// Override a config setting, e.g. `--set form.next_key=down` (repeatable)
+    #[arg(long = "set", value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// Seconds to wait before typing starts (prompts when omitted)
+    #[arg(long)]
+    countdown: Option<u64>,
+    /// Print the simulated keystrokes and pauses instead of typing them
+    #[arg(long)]
+    dry_run: bool,
+    /// Type even into windows on the blacklist, such as password managers
+    #[arg(long)]
+    ignore_blacklist: bool,
+}
+
+#[derive(Args)]
+struct DiffArgs {
+    /// Text the document holds now
+    old: PathBuf,
+    /// Text to turn it into
+    new: PathBuf,
+    /// Type only the added lines at the cursor instead of moving around and deleting
+    #[arg(long)]
+    added_only: bool,
+    /// Config file to use instead of the default one
+    #[arg(long)]
+    config: Option<PathBuf>,
+    /// Named profile from the config file to apply
+    #[arg(long)]
+    profile: Option<String>,
+    /// Override a config setting, e.g. `--set code.enabled=true` (repeatable)
+    #[arg(long = "set", value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// Seconds to wait before typing starts (prompts when omitted)
+    #[arg(long)]
+    countdown: Option<u64>,
+    /// Print the simulated keystrokes and pauses instead of typing them
+    #[arg(long)]
+    dry_run: bool,
+    /// Type even into windows on the blacklist, such as password managers
+    #[arg(long)]
+    ignore_blacklist: bool,
+}
+
+#[derive(Subcommand)]
+enum SnippetAction {
+    /// List the snippets in the library
+    List,
+    /// Save a snippet from a file, the clipboard or standard input
+    Add {
+        /// Name to save the snippet as
+        name: String,
+        /// Text file with the snippet; read from standard input when omitted
+        #[arg(long, conflicts_with = "from_clipboard")]
+        text: Option<PathBuf>,
+        /// Use the clipboard contents
+        #[arg(long)]
+        from_clipboard: bool,
+    },
+    /// Delete a snippet
+    Remove { name: String },
+    /// Pick a snippet by a fuzzy search of its name and type it
+    Type {
+        /// Part of the snippet name; lists all snippets to pick from when omitted
+        query: Option<String>,
+        #[command(flatten)]
+        typing: SnippetTypingArgs,
+    },
+    /// Type the snippet bound to a hotkey in `snippets.bindings` whenever it is pressed
+    Listen {
+        #[command(flatten)]
+        typing: SnippetTypingArgs,
+    },
+}
+
+#[derive(Args, Clone)]
+struct SnippetTypingArgs {
+    #[command(flatten)]
+    vars: VarArgs,
+    /// Named profile from the config file to apply
+    #[arg(long)]
+    profile: Option<String>,
+    /// Override a config setting, e.g. `--set mistakes.probability=20` (repeatable)
+    #[arg(long = "set", value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+    overrides: Vec<(String, String)>,
+    /// Seconds to wait before typing starts (prompts when omitted)
+    #[arg(long)]
+    countdown: Option<u64>,
+    /// Print the simulated keystrokes and pauses instead of typing them
+    #[arg(long)]
+    dry_run: bool,
+}
+
+#[derive(Subcommand)]
+enum CtlAction {
+    /// Load the text to type on `start`, from a file or else standard input
+    Load {
+        /// Text file to load
+        #[arg(conflicts_with = "from_clipboard")]
+        file: Option<PathBuf>,
+        /// Load the clipboard contents
+        #[arg(long)]
+        from_clipboard: bool,
+    },
+    /// Start typing the loaded text into the focused window
+    Start,
+    /// Pause typing
+    Pause,
+    /// Resume typing after a pause
+    Resume,
+    /// Stop typing for good
+    Abort,
+    /// Set how many times as fast as configured to type, e.g. 1.5
+    Speed { speed: f64 },
+    /// Change config settings of how typing goes for the runs started from now on, e.g.
+    /// `mistakes.probability=20`
+    Set {
+        #[arg(required = true, value_name = "SECTION.FIELD=VALUE", value_parser = parse_override)]
+        overrides: Vec<(String, String)>,
+    },
+    /// Print what the daemon is doing
+    Status,
+}
+
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Print the config file contents
+    Show,
+    /// Print the config file location
+    Path,
+    /// Overwrite the config file with the defaults
+    Reset,
+}
+
+fn main() {
+    if let Err(e) = try_main() {
+        eprintln!("Error: {}", e);
+        process::exit(1);
     }
 }
 
-impl Config {
-    fn to_typing_config(&self) -> TypingConfig {
-        TypingConfig {
-            base_delay: self.base_delay_min..self.base_delay_max,
-            thinking_delay: self.thinking_delay_min..self.thinking_delay_max,
-            mistake_probability: self.mistake_probability,
-            correction_delay: self.correction_delay_min..self.correction_delay_max,
-            long_pause_probability: self.long_pause_probability,
-            long_pause_delay: self.long_pause_delay_min..self.long_pause_delay_max,
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    // The Stream Deck app starts its plugins with arguments of its own
+    if let Some(plugin) = StreamDeckPlugin::from_args(&std::env::args().collect::<Vec<_>>()) {
+        return cli::daemon::stream_deck(&plugin);
+    }
+    let cli = Cli::parse();
+    paths::set_portable(cli.portable);
+
+    match cli.command.unwrap_or(Command::Run(RunArgs::default())) {
+        Command::Run(args) => run(args),
+        Command::Config { action, config } => {
+            config_command(action.unwrap_or(ConfigAction::Show), config)
         }
+        Command::Init { config } => init(config),
+        Command::Calibrate {
+            text,
+            profile,
+            config,
+        } => calibrate(text, &profile, config),
+        Command::ImportDataset {
+            file,
+            output,
+            name,
+            seconds,
+        } => import_dataset(&file, &output, name, seconds),
+        Command::Resume { countdown, dry_run } => resume(countdown, dry_run),
+        Command::Replay(args) => replay(args),
+        Command::Export(args) => export(args),
+        Command::Form(args) => form(args),
+        Command::Diff(args) => diff(args),
+        Command::Git(args) => git(args),
+        Command::Snippets { action, config } => snippets(action, config),
+        #[cfg(feature = "gui")]
+        Command::Gui { config, profile } => gui(config, profile),
+        #[cfg(all(feature = "tray", target_os = "linux"))]
+        Command::Tray { typing, config } => tray(typing, config),
+        Command::Daemon {
+            typing,
+            config,
+            serve,
+            websocket,
+            token,
+            allow_origins,
+        } => cli::daemon::run(typing, config, serve, websocket, token, allow_origins),
+        Command::Triggers { config } => cli::daemon::triggers(config),
+        Command::Ctl { action } => ctl(action),
+        Command::Preview { source } => preview(source),
     }
 }
 
-fn get_config_path() -> PathBuf {
-    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.push("config.json");
-    path
+/// Turns on the settings of `mode` through an override, so `typer resume` keeps it.
+fn add_mode_override(mode: TypingMode, overrides: &mut Vec<(String, String)>) {
+    match mode {
+        TypingMode::Text => {}
+        TypingMode::Code => overrides.push(("code.enabled".to_string(), "true".to_string())),
+        TypingMode::Chat => overrides.push(("chat.enabled".to_string(), "true".to_string())),
+        TypingMode::Shell => overrides.push(("shell.enabled".to_string(), "true".to_string())),
+        TypingMode::Sheet => overrides.push(("sheet.enabled".to_string(), "true".to_string())),
+    }
 }
 
-fn get_text_file_path() -> PathBuf {
-    let mut path = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
-    path.push("typethis.txt");
-    path
+fn run(mut args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = args.config.unwrap_or_else(get_config_path);
+    add_mode_override(args.mode, &mut args.overrides);
+    if let Some(step) = args.step {
+        // As an override, so `typer resume` keeps stepping
+        args.overrides.push(("pauses.step".to_string(), step));
+    }
+    let config = load_run_config(&config_path, args.profile.as_deref(), &args.overrides)?;
+    let sources = args.source.into_sources()?;
+    let watched = match (args.watch, sources.as_slice()) {
+        (false, _) => None,
+        (true, [source]) => Some(
+            source
+                .watch_path()
+                .ok_or("--watch only works with a text file")?,
+        ),
+        (true, _) => return Err("--watch only works with a single text file".into()),
+    };
+    let mut vars = args.vars.to_vars()?;
+    let texts = sheet_texts(
+        &config,
+        fill_placeholders(read_texts(&sources)?, &mut vars)?,
+    )?;
+    let keyboard = config.keyboard_layout()?;
+
+    println!("Config file location: {}", config_path.display());
+    if let Some(profile) = &args.profile {
+        println!("Profile: {}", profile);
+    }
+    for (text, text_source) in &texts {
+        println!("\nText source: {}", text_source);
+        println!("Text to type:");
+        println!("{}", text);
+    }
+
+    let controls = TypingControls::new();
+    if !args.dry_run {
+        spawn_hotkey_listener(&config, &controls)?;
+    }
+
+    #[cfg(feature = "overlay")]
+    let overlay = match args.overlay && !args.dry_run {
+        true => Some(Overlay::spawn(controls.clone())?),
+        false => None,
+    };
+
+    let session = Session {
+        config: &config,
+        keyboard: &keyboard,
+        controls: &controls,
+        countdown: args.countdown,
+        dry_run: args.dry_run,
+        target: args.target,
+        focus_window: args.focus_window,
+        ignore_blacklist: args.ignore_blacklist,
+        report: args.report,
+        keystroke_log: args.keystroke_log,
+        finish_in: args.finish_in,
+        on_progress: None,
+        #[cfg(feature = "overlay")]
+        overlay,
+        #[cfg(feature = "tui")]
+        tui: args.tui,
+    };
+    let run = Checkpoint::new(texts, config_path, args.profile, args.overrides);
+    match watched {
+        Some(path) => watch(&session, sources[0].as_ref(), &path, run, vars),
+        None => session.start(run).map(|_| ()),
+    }
 }
 
-fn ensure_config_exists() -> Result<Config, Box<dyn std::error::Error>> {
-    let config_path = get_config_path();
+fn resume(countdown: Option<u64>, dry_run: bool) -> Result<(), Box<dyn std::error::Error>> {
+    let checkpoint_path = get_checkpoint_path();
+    let checkpoint = Checkpoint::load(&checkpoint_path)?
+        .ok_or("Nothing to resume, no typing run was interrupted")?;
+    let config = load_run_config(
+        &checkpoint.config,
+        checkpoint.profile.as_deref(),
+        &checkpoint.overrides,
+    )?;
+    let keyboard = config.keyboard_layout()?;
 
-    if (!config_path.exists()) {
-        let config = Config::default();
-        let config_str = serde_json::to_string_pretty(&config)?;
-        fs::write(&config_path, config_str)?;
-        return Ok(config);
+    let (text, text_source) = &checkpoint.texts[checkpoint.current];
+    let text = strip_directives(text);
+    let total = text.chars().count();
+    println!(
+        "Resuming {} at character {} of {}.",
+        text_source, checkpoint.position, total
+    );
+    println!("Remaining text:");
+    println!(
+        "{}",
+        text.chars().skip(checkpoint.position).collect::<String>()
+    );
+    if checkpoint.erase > 0 {
+        println!(
+            "The last {} typed characters were wrong and will be erased first.",
+            checkpoint.erase
+        );
     }
+    println!("Make sure the cursor is right after the text typed so far.");
+
+    let controls = TypingControls::new();
+    if !dry_run {
+        spawn_hotkey_listener(&config, &controls)?;
+    }
+
+    let session = Session {
+        config: &config,
+        keyboard: &keyboard,
+        controls: &controls,
+        countdown,
+        dry_run,
+        target: None,
+        focus_window: None,
+        ignore_blacklist: false,
+        report: None,
+        keystroke_log: None,
+        finish_in: None,
+        on_progress: None,
+        #[cfg(feature = "overlay")]
+        overlay: None,
+        #[cfg(feature = "tui")]
+        tui: false,
+    };
+    session.start(checkpoint).map(|_| ())
+}
+
+fn replay(args: ReplayArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = args.config.unwrap_or_else(get_config_path);
+    let config = load_run_config(&config_path, None, &[])?;
+    let events = read_keystroke_log(&args.log)?;
+    let duration = events.last().map_or(0, |event| event.at_ms);
+    println!(
+        "Replaying {} key events ({:.1}s) from {}",
+        events.len(),
+        duration as f64 / 1000.0,
+        args.log.display()
+    );
 
-    let config_str = fs::read_to_string(&config_path)?;
-    match serde_json::from_str(&config_str) {
-        Ok(config) => Ok(config),
-        Err(_) => {
-            // If there's an error loading the config, create a new one
-            println!("Warning: Invalid or outdated config file. Creating new config...");
-            let config = Config::default();
-            let config_str = serde_json::to_string_pretty(&config)?;
-            fs::write(&config_path, config_str)?;
-            Ok(config)
+    let controls = TypingControls::new();
+    let mut builder = HumanTypist::builder().controls(controls.clone());
+    let outcome = match args.dry_run {
+        true => {
+            println!("\nDry run:");
+            builder.dry_run(true).build().replay(&events)
+        }
+        false => {
+            check_permissions()?;
+            builder = builder.boxed_backend(create_backend(config.keyboard.backend)?);
+            builder = with_key_sounds(builder, &config)?;
+            spawn_hotkey_listener(&config, &controls)?;
+            count_down(args.countdown, &config)?;
+            let _guards = guard(&config, &controls, args.ignore_blacklist)?;
+            let source = args.log.display().to_string();
+            with_hooks(&config, &source, events.len(), builder, |typist| {
+                typist.replay(&events)
+            })
         }
+    };
+    if outcome == TypingOutcome::Aborted {
+        println!("Replay aborted.");
     }
+    Ok(())
 }
 
-fn ensure_text_file_exists() -> Result<String, Box<dyn std::error::Error>> {
-    let text_path = get_text_file_path();
+fn form(args: FormArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = args.config.unwrap_or_else(get_config_path);
+    let config = load_run_config(&config_path, args.profile.as_deref(), &args.overrides)?;
+    let record = read_record(&args.record, args.row.saturating_sub(1))?;
+    let fields = config.form.fields(record)?;
+    let next = config.form.next_key()?;
+    let submit = config.form.submit_key()?;
 
-    if (!text_path.exists()) {
-        let default_text = "Type your text here.\nType your text here.";
-        fs::write(&text_path, default_text)?;
-        return Ok(default_text.to_string());
+    println!(
+        "Filling {} fields from {}:",
+        fields.len(),
+        args.record.display()
+    );
+    for field in &fields {
+        println!("  {}: {}", field.name, field.value);
     }
 
-    let content = fs::read_to_string(&text_path)?;
-    if content.trim().is_empty() {
-        let default_text = "Type your text here.\nType your text here.";
-        fs::write(&text_path, default_text)?;
-        Ok(default_text.to_string())
-    } else {
-        // Normalize line endings and ensure proper text handling
-        let normalized = content.replace("\r\n", "\n");
-        if normalized.ends_with('\n') {
-            Ok(normalized[..normalized.len()-1].to_string())
-        } else {
-            Ok(normalized)
-        }
-    }
-}
-
-struct KeyboardLayout {
-    nearby_keys: std::collections::HashMap<char, Vec<char>>,
-}
-
-impl KeyboardLayout {
-    fn new() -> Self {
-        let mut layout = std::collections::HashMap::new();
-        layout.insert('a', vec!['s', 'q', 'w', 'z']);
-        layout.insert('b', vec!['v', 'n', 'h', 'g']);
-        layout.insert('c', vec!['x', 'v', 'd', 'f']);
-        layout.insert('d', vec!['s', 'f', 'e', 'r']);
-        layout.insert('e', vec!['w', 'r', 'd', 'f']);
-        layout.insert('f', vec!['d', 'g', 'r', 't']);
-        layout.insert('g', vec!['f', 'h', 't', 'y']);
-        layout.insert('h', vec!['g', 'j', 'y', 'u']);
-        layout.insert('i', vec!['u', 'o', 'k', 'l']);
-        layout.insert('j', vec!['h', 'k', 'u', 'i']);
-        layout.insert('k', vec!['j', 'l', 'i', 'o']);
-        layout.insert('l', vec!['k', ';', 'o', 'p']);
-        layout.insert('m', vec!['n', ',', 'j', 'k']);
-        layout.insert('n', vec!['b', 'm', 'h', 'j']);
-        layout.insert('o', vec!['i', 'p', 'k', 'l']);
-        layout.insert('p', vec!['o', '[', 'l', ';']);
-        layout.insert('q', vec!['w', 'a', '1', '2']);
-        layout.insert('r', vec!['e', 't', 'd', 'f']);
-        layout.insert('s', vec!['a', 'd', 'w', 'e']);
-        layout.insert('t', vec!['r', 'y', 'f', 'g']);
-        layout.insert('u', vec!['y', 'i', 'h', 'j']);
-        layout.insert('v', vec!['c', 'b', 'f', 'g']);
-        layout.insert('w', vec!['q', 'e', 'a', 's']);
-        layout.insert('x', vec!['z', 'c', 's', 'd']);
-        layout.insert('y', vec!['t', 'u', 'g', 'h']);
-        layout.insert('z', vec!['a', 'x', 's', 'd']);
-
-        KeyboardLayout { nearby_keys: layout }
-    }
-
-    fn get_nearby_key(&self, c: char) -> char {
-        let c_lower = c.to_ascii_lowercase();
-        if let Some(nearby) = self.nearby_keys.get(&c_lower) {
-            let result = *nearby.choose(&mut rand::thread_rng()).unwrap_or(&c_lower);
-            if c.is_uppercase() {
-                result.to_ascii_uppercase()
-            } else {
-                result
-            }
+    let controls = TypingControls::new();
+    let mut builder = HumanTypist::builder()
+        .config(config.to_typing_config()?)
+        .keyboard(config.keyboard_layout()?)
+        .controls(controls.clone());
+    let outcome = match args.dry_run {
+        true => {
+            println!("\nDry run:");
+            let mut typist = builder.dry_run(true).build();
+            fill_form(&mut typist, &fields, &next, submit.as_ref())
+        }
+        false => {
+            check_permissions()?;
+            builder = builder.boxed_backend(create_backend(config.keyboard.backend)?);
+            builder = with_key_sounds(builder, &config)?;
+            spawn_hotkey_listener(&config, &controls)?;
+            count_down(args.countdown, &config)?;
+            let _guards = guard(&config, &controls, args.ignore_blacklist)?;
+            let source = args.record.display().to_string();
+            let total = fields.iter().map(|field| field.value.chars().count()).sum();
+            with_hooks(&config, &source, total, builder, |typist| {
+                fill_form(typist, &fields, &next, submit.as_ref())
+            })
+        }
+    };
+    if outcome == TypingOutcome::Aborted {
+        println!("Form filling aborted.");
+    }
+    Ok(())
+}
+
+/// `typer run` in code mode for the lines of the last commit, or of the commit or patch
+/// given.
+fn git(mut args: RunArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let source = &mut args.source;
+    if source.patch.is_none() && source.commit.is_none() {
+        let other = !source.files.is_empty()
+            || source.playlist.is_some()
+            || source.text.is_some()
+            || source.from_clipboard
+            || source.from_stdin
+            || source.url.is_some();
+        if other {
+            return Err("typer git types a commit or a patch, pass --commit or --patch".into());
+        }
+        source.commit = Some("HEAD".to_string());
+    }
+    if args.mode == TypingMode::Text {
+        args.mode = TypingMode::Code;
+    }
+    run(args)
+}
+
+#[cfg(feature = "gui")]
+fn gui(config: Option<PathBuf>, profile: Option<String>) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config.unwrap_or_else(get_config_path);
+    let config = load_run_config(&config_path, profile.as_deref(), &[])?;
+    typer::gui::run(
+        &config,
+        Box::new(|controls| {
+            check_permissions()?;
+            spawn_hotkey_listener(&config, controls)
+        }),
+    )
+}
+
+fn diff(args: DiffArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = args.config.unwrap_or_else(get_config_path);
+    let config = load_run_config(&config_path, args.profile.as_deref(), &args.overrides)?;
+    let read = |path: &Path| {
+        fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))
+    };
+    let (old, new) = (read(&args.old)?, read(&args.new)?);
+    let changes = diff_lines(&old, &new);
+    let count = |kind: fn(&LineChange) -> bool| changes.iter().filter(|&c| kind(c)).count();
+    let added = count(|change| matches!(change, LineChange::Add(_)));
+    let removed = count(|change| matches!(change, LineChange::Remove(_)));
+    if added == 0 && removed == 0 {
+        println!(
+            "{} and {} are the same.",
+            args.old.display(),
+            args.new.display()
+        );
+        return Ok(());
+    }
+
+    let steps = match args.added_only {
+        true => vec![DiffStep::Type(added_lines(&changes))],
+        false => plan(&changes),
+    };
+    println!("{} lines added and {} removed:", added, removed);
+    for step in &steps {
+        println!("  {}", step);
+    }
+
+    let controls = TypingControls::new();
+    let mut builder = HumanTypist::builder()
+        .config(config.to_typing_config()?)
+        .keyboard(config.keyboard_layout()?)
+        .controls(controls.clone());
+    let outcome = match args.dry_run {
+        true => {
+            println!("\nDry run:");
+            type_diff(&mut builder.dry_run(true).build(), &steps)
+        }
+        false => {
+            check_permissions()?;
+            builder = builder.boxed_backend(create_backend(config.keyboard.backend)?);
+            builder = with_key_sounds(builder, &config)?;
+            spawn_hotkey_listener(&config, &controls)?;
+            count_down(args.countdown, &config)?;
+            let _guards = guard(&config, &controls, args.ignore_blacklist)?;
+            let source = args.new.display().to_string();
+            let total = steps
+                .iter()
+                .map(|step| match step {
+                    DiffStep::Type(text) => text.chars().count(),
+                    DiffStep::Press(..) => 0,
+                })
+                .sum();
+            with_hooks(&config, &source, total, builder, |typist| {
+                type_diff(typist, &steps)
+            })
+        }
+    };
+    if outcome == TypingOutcome::Aborted {
+        println!("Editing aborted.");
+    }
+    Ok(())
+}
+
+/// Types the text of `source` again each time the file at `path` changes, until aborted.
+fn watch(
+    session: &Session,
+    source: &dyn TextSource,
+    path: &Path,
+    run: Checkpoint,
+    mut vars: Option<HashMap<String, String>>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let file_name = path.file_name().ok_or("--watch needs a file path")?;
+    // Editors often replace the file rather than writing to it, so watch its directory
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx)?;
+    watcher.watch(dir, RecursiveMode::NonRecursive)?;
+    println!(
+        "\nWatching {} for changes. Press {} to stop.",
+        path.display(),
+        if session.dry_run {
+            "Ctrl+C"
         } else {
-            c
-        }
-    }
-}
-
-struct HumanTypist {
-    config: TypingConfig,
-    keyboard: KeyboardLayout,
-    rng: rand::rngs::ThreadRng,
-    enigo: Enigo,
-    mistake_buffer: Vec<char>,
-}
-
-impl HumanTypist {
-    fn new() -> Self {
-        HumanTypist {
-            config: TypingConfig::default(),
-            keyboard: KeyboardLayout::new(),
-            rng: rand::thread_rng(),
-            enigo: Enigo::new(),
-            mistake_buffer: Vec::new(),
-        }
-    }
-
-    fn type_text(&mut self, text: &str) {
-        for c in text.chars() {
-            match c {
-                '\n' => {
-                    self.enigo.key_click(Key::Return);
-                    thread::sleep(Duration::from_millis(
-                        self.rng.gen_range(self.config.thinking_delay.clone()),
-                    ));
-                },
-                '\r' => continue, // Skip carriage returns
-                _ => {
-                    // Thinking pause on whitespace
-                    if self.rng.gen_ratio(1, 100) && c.is_whitespace() {
-                        thread::sleep(Duration::from_millis(
-                            self.rng.gen_range(self.config.thinking_delay.clone()),
-                        ));
-                    }
-
-                    self.type_character(c);
-
-                    // Long pause after punctuation (after typing the character)
-                    if self.rng.gen_ratio(self.config.long_pause_probability, 100)
-                        && ".,?!;:".contains(c) {
-                        thread::sleep(Duration::from_millis(
-                            self.rng.gen_range(self.config.long_pause_delay.clone()),
-                        ));
-                    }
-                }
+            &session.config.hotkeys.abort
+        }
+    );
+
+    // Without a countdown every change would prompt for one
+    let session = Session {
+        countdown: Some(session.countdown.unwrap_or(WATCH_COUNTDOWN_SECS)),
+        ..session.clone()
+    };
+    let mut last_text = run.texts[0].0.clone();
+
+    while !session.controls.is_aborted() {
+        let event = match rx.recv_timeout(WATCH_POLL_INTERVAL) {
+            Ok(event) => event?,
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+        let changed = (event.kind.is_modify() || event.kind.is_create())
+            && event
+                .paths
+                .iter()
+                .any(|path| path.file_name() == Some(file_name));
+        if !changed {
+            continue;
+        }
+
+        // Let the editor finish writing before reading the file
+        thread::sleep(WATCH_SETTLE_TIME);
+        while rx.try_recv().is_ok() {}
+
+        let text = match source.read().and_then(|text| {
+            let texts = fill_placeholders(vec![(text, source.describe())], &mut vars)?;
+            let texts = sheet_texts(session.config, texts)?;
+            Ok(texts
+                .into_iter()
+                .next()
+                .map(|(text, _)| text)
+                .unwrap_or_default())
+        }) {
+            Ok(text) => text,
+            Err(e) => {
+                println!("Could not read {}: {}", source.describe(), e);
+                continue;
             }
+        };
+        if text == last_text {
+            continue;
+        }
 
-            thread::sleep(Duration::from_millis(
-                self.rng.gen_range(self.config.base_delay.clone()),
-            ));
+        println!("\n{} changed:", source.describe());
+        println!("{}", text);
+        session.start(Checkpoint {
+            texts: vec![(text.clone(), source.describe())],
+            ..run.clone()
+        })?;
+        last_text = text;
+        if !session.controls.is_aborted() {
+            println!("\nWatching {} for changes.", path.display());
         }
     }
 
-    fn type_character(&mut self, intended_char: char) {
-        if self.rng.gen_ratio(1, self.config.mistake_probability) {
-            // Make a simple mistake
-            let mistake_char = self.keyboard.get_nearby_key(intended_char);
-            self.enigo.key_sequence(&mistake_char.to_string());
+    Ok(())
+}
 
-            // Wait a bit before correcting
-            thread::sleep(Duration::from_millis(
-                self.rng.gen_range(self.config.correction_delay.clone()),
-            ));
+fn config_command(
+    action: ConfigAction,
+    config_path: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
 
-            // Correct the mistake
-            self.enigo.key_click(Key::Backspace);
-            self.enigo.key_sequence(&intended_char.to_string());
-        } else {
-            self.enigo.key_sequence(&intended_char.to_string());
+    match action {
+        ConfigAction::Show => {
+            let config = load_config(&config_path)?;
+            println!("{}", format_config(&config_path, &config)?.trim_end());
+        }
+        ConfigAction::Path => println!("{}", config_path.display()),
+        ConfigAction::Reset => {
+            save_config(&config_path, &Config::default())?;
+            println!("Config reset to defaults: {}", config_path.display());
         }
     }
+
+    Ok(())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let config = ensure_config_exists()?;
-    let text = ensure_text_file_exists()?;
+fn init(config_path: Option<PathBuf>) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+
+    if config_path.exists() {
+        let answer = ask(
+            &format!("{} already exists. Overwrite it?", config_path.display()),
+            "y/N",
+        )?;
+        if !answer.eq_ignore_ascii_case("y") {
+            println!("Keeping the existing config.");
+            return Ok(());
+        }
+    }
+
+    let wpm = loop {
+        match ask("Target typing speed in words per minute", "60")?.parse::<u32>() {
+            Ok(wpm) if (1..=300).contains(&wpm) => break wpm,
+            _ => println!("Please enter a number between 1 and 300."),
+        }
+    };
+
+    let sloppiness = loop {
+        match ask("How sloppy? careful, normal or sloppy", "normal")?
+            .to_ascii_lowercase()
+            .as_str()
+        {
+            "careful" => break Sloppiness::Careful,
+            "normal" => break Sloppiness::Normal,
+            "sloppy" => break Sloppiness::Sloppy,
+            _ => println!("Please answer careful, normal or sloppy."),
+        }
+    };
+
+    let layout = loop {
+        let answer = ask(
+            "Language or keyboard layout (english, french, german, qwerty, azerty, qwertz, dvorak, colemak)",
+            "english",
+        )?
+        .to_ascii_lowercase();
+        match answer.as_str() {
+            "english" => break "qwerty".to_string(),
+            "french" => break "azerty".to_string(),
+            "german" => break "qwertz".to_string(),
+            "qwerty" | "azerty" | "qwertz" | "dvorak" | "colemak" => break answer,
+            _ => println!("Unknown language or layout: {}", answer),
+        }
+    };
+
+    save_config(&config_path, &Config::tuned(wpm, sloppiness, &layout))?;
+    println!("Config written to {}", config_path.display());
 
-    println!("Text file location: {}", get_text_file_path().display());
-    println!("Config file location: {}", get_config_path().display());
-    println!("\nText to type:");
-    println!("{}", text);
+    Ok(())
+}
 
-    print!("\nEnter the number of seconds to wait before starting: ");
-    io::stdout().flush()?;
+fn export(mut args: ExportArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = args.config.unwrap_or_else(get_config_path);
+    add_mode_override(args.mode, &mut args.overrides);
+    let config = load_run_config(&config_path, args.profile.as_deref(), &args.overrides)?;
+    let mut vars = args.vars.to_vars()?;
+    let texts = fill_placeholders(read_texts(&args.source.into_sources()?)?, &mut vars)?;
+    let texts = sheet_texts(&config, texts)?;
 
-    let mut delay_secs = String::new();
-    io::stdin().read_line(&mut delay_secs)?;
-    let delay_secs: u64 = delay_secs.trim().parse().unwrap_or(5);
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&events);
+    let mut typist = HumanTypist::builder()
+        .config(config.to_typing_config()?)
+        .keyboard(config.keyboard_layout()?)
+        .backend(MockBackend::new())
+        .instant(true)
+        .on_key_event(move |event| recorded.lock().unwrap().push(event.clone()))
+        .build();
+    let run = Mutex::new(Checkpoint::new(
+        texts,
+        config_path,
+        args.profile,
+        args.overrides,
+    ));
+    // Nothing is typed for real, so there is no one to wait for between files
+    type_texts(
+        &mut typist,
+        &run,
+        &config,
+        true,
+        &ProgressBar::hidden(),
+        None,
+    );
 
-    println!("\nStarting in...");
-    for i in (1..=delay_secs).rev() {
-        println!("{i}...");
-        thread::sleep(Duration::from_secs(1));
+    let events = events.lock().unwrap();
+    let script = export_script(&events, args.format, args.countdown * 1000);
+    fs::write(&args.output, script)
+        .map_err(|e| format!("Failed to write {}: {}", args.output.display(), e))?;
+    #[cfg(unix)]
+    if args.format == ScriptFormat::Xdotool {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&args.output, fs::Permissions::from_mode(0o755))?;
     }
-    println!("Go!");
 
-    let mut typist = HumanTypist::new();
-    typist.config = config.to_typing_config();
-    typist.type_text(&text);
+    println!(
+        "Wrote {} key events ({:.1}s of typing) to {}",
+        events.len(),
+        typist.elapsed().as_secs_f64(),
+        args.output.display()
+    );
+    Ok(())
+}
+
+fn calibrate(
+    text: Option<PathBuf>,
+    profile: &str,
+    config_path: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+    // Enter ends the test, so the sample is typed as a single line
+    let sample = match text {
+        Some(path) => read_text_file(&path)?
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" "),
+        None => CALIBRATION_SAMPLE.to_string(),
+    };
+
+    println!("Type the text below as you normally would, mistakes and corrections included.");
+    println!("Press Enter when you are done or Escape to cancel.\n");
+    println!("{}\n", sample);
+    let Some(keystrokes) = record_typing()? else {
+        println!("Calibration cancelled.");
+        return Ok(());
+    };
+
+    let calibration = Calibration::fit(&sample, &keystrokes)?;
+    calibration.print();
+
+    let mut config = load_config(&config_path)?;
+    config
+        .profiles
+        .insert(profile.to_string(), calibration.to_profile());
+    config.with_profile(profile)?.validate()?;
+    save_config(&config_path, &config)?;
+    println!(
+        "\nSaved as profile {:?} in {}. Use it with `typer run --profile {}`.",
+        profile,
+        config_path.display(),
+        profile
+    );
+    Ok(())
+}
+
+fn import_dataset(
+    file: &Path,
+    output: &Path,
+    name: Option<String>,
+    seconds: bool,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let presses = dataset::read_dataset(file, if seconds { 1000.0 } else { 1.0 })?;
+    let name = name.unwrap_or_else(|| {
+        file.file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    let profile = dataset::fit_profile(&name, &presses)?;
+    profile.validate()?;
+    profile.save(output)?;
 
+    println!(
+        "Read {} key presses from {}.",
+        presses.len(),
+        file.display()
+    );
+    if let Some(keystroke) = &profile.keystroke {
+        println!(
+            "  Keystroke delay: {:.0} ms on average (±{:.0})",
+            keystroke.mean, keystroke.std_dev
+        );
+    }
+    println!("  Bigrams:         {} measured", profile.bigrams.len());
+    if let Some(rate) = profile.error_rates.letter {
+        println!("  Letter errors:   {:.1}%", rate);
+    }
+    println!(
+        "\nSaved the profile to {}. Use it by setting `file` in the [personal] config section.",
+        output.display()
+    );
     Ok(())
-}
\ No newline at end of file
+}
+
+/// Records the keys typed in the terminal with the time since the first one, until
+/// Enter is pressed. Returns `None` if cancelled.
+fn record_typing() -> io::Result<Option<Vec<(Duration, Keystroke)>>> {
+    terminal::enable_raw_mode()?;
+    let recorded = read_keystrokes();
+    terminal::disable_raw_mode()?;
+    println!();
+    recorded
+}
+
+fn read_keystrokes() -> io::Result<Option<Vec<(Duration, Keystroke)>>> {
+    let mut keystrokes = Vec::new();
+    let mut started = None;
+    let mut stdout = io::stdout();
+
+    loop {
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        let at = started.get_or_insert_with(Instant::now).elapsed();
+
+        match key.code {
+            KeyCode::Enter => return Ok(Some(keystrokes)),
+            KeyCode::Esc => return Ok(None),
+            // Raw mode swallows the interrupt signal
+            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return Ok(None),
+            KeyCode::Char(c) => {
+                keystrokes.push((at, Keystroke::Char(c)));
+                print!("{}", c);
+            }
+            KeyCode::Backspace => {
+                keystrokes.push((at, Keystroke::Backspace));
+                print!("\x08 \x08");
+            }
+            _ => {}
+        }
+        stdout.flush()?;
+    }
+}
+
+fn snippets(
+    action: SnippetAction,
+    config_path: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+    let library = SnippetLibrary::new(load_config(&config_path)?.snippets.dir());
+
+    match action {
+        SnippetAction::List => {
+            let names = library.names()?;
+            if names.is_empty() {
+                println!("No snippets in {} yet.", library.dir.display());
+            }
+            for name in names {
+                println!("{}", name);
+            }
+        }
+        SnippetAction::Add {
+            name,
+            text,
+            from_clipboard,
+        } => {
+            let source: Box<dyn TextSource> = match (text, from_clipboard) {
+                (Some(path), _) => Box::new(FileSource { path }),
+                (None, true) => Box::new(ClipboardSource),
+                (None, false) => Box::new(StdinSource),
+            };
+            let path = library.add(&name, &source.read()?)?;
+            println!("Saved snippet {:?} to {}", name, path.display());
+        }
+        SnippetAction::Remove { name } => {
+            library.remove(&name)?;
+            println!("Removed snippet {:?}", name);
+        }
+        SnippetAction::Type { query, typing } => {
+            let Some(name) = pick_snippet(&library, query.as_deref().unwrap_or(""))? else {
+                return Ok(());
+            };
+            let session = SnippetSession::new(&config_path, typing)?;
+            session.type_snippet(&library, &name, &mut session.typing.vars.to_vars()?)?;
+        }
+        SnippetAction::Listen { mut typing } => {
+            // The hotkey is pressed in the window to type into
+            typing.countdown.get_or_insert(0);
+            let session = SnippetSession::new(&config_path, typing)?;
+            let (hotkeys, names): (Vec<Hotkey>, Vec<&String>) = session
+                .config
+                .snippets
+                .bindings
+                .iter()
+                .map(|(hotkey, name)| Ok((Hotkey::parse(hotkey)?, name)))
+                .collect::<Result<Vec<_>, String>>()?
+                .into_iter()
+                .unzip();
+            if hotkeys.is_empty() {
+                return Err("No hotkeys bound to snippets, add some to snippets.bindings".into());
+            }
+            // Better found out now than when the key is pressed in front of an audience
+            for name in &names {
+                library
+                    .read(name)
+                    .map_err(|e| format!("snippets.bindings: {}", e))?;
+            }
+
+            for (hotkey, name) in &session.config.snippets.bindings {
+                println!("{} types {}", hotkey, name);
+            }
+            println!("Press {} to stop listening.", session.config.hotkeys.abort);
+            let mut vars = session.typing.vars.to_vars()?;
+            while let Some(index) = wait_for_hotkey(&hotkeys, &session.controls) {
+                if let Err(e) = session.type_snippet(&library, names[index], &mut vars) {
+                    println!("Could not type {}: {}", names[index], e);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds the snippet matching `query`, asking which one when several do and none is an
+/// exact match. Returns `None` when the choice is cancelled.
+fn pick_snippet(
+    library: &SnippetLibrary,
+    query: &str,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    let matches = library.search(query)?;
+    match matches.as_slice() {
+        [] if query.is_empty() => Err(format!("No snippets in {}", library.dir.display()).into()),
+        [] => Err(format!("No snippet matches {:?}", query).into()),
+        [only] => Ok(Some(only.clone())),
+        [best, ..] if best.eq_ignore_ascii_case(query) => Ok(Some(best.clone())),
+        _ => {
+            for (number, name) in matches.iter().enumerate() {
+                println!("{:>3}. {}", number + 1, name);
+            }
+            loop {
+                let answer = ask("Snippet to type, by number or name (empty to cancel)", "")?;
+                if answer.is_empty() {
+                    return Ok(None);
+                }
+                let picked = match answer.parse::<usize>() {
+                    Ok(number) => matches.get(number.wrapping_sub(1)).cloned(),
+                    Err(_) => rank(&answer, matches.clone()).into_iter().next(),
+                };
+                match picked {
+                    Some(name) => return Ok(Some(name)),
+                    None => println!("No snippet {:?} in the list.", answer),
+                }
+            }
+        }
+    }
+}
+
+/// Waits for what is asked for from the tray icon and types it, one run at a time.
+#[cfg(all(feature = "tray", target_os = "linux"))]
+fn tray(
+    mut typing: SnippetTypingArgs,
+    config_path: Option<PathBuf>,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let config_path = config_path.unwrap_or_else(get_config_path);
+    let config = load_config(&config_path)?;
+    let library = SnippetLibrary::new(config.snippets.dir());
+    let profiles = config.profiles.keys().cloned().collect();
+    // The menu is picked from in the window to type into
+    typing.countdown.get_or_insert(TRAY_COUNTDOWN_SECS);
+    let vars = typing.vars.to_vars()?;
+
+    let (icon, actions) = TrayIcon::spawn(
+        SnippetLibrary::new(config.snippets.dir()),
+        profiles,
+        typing.profile.clone(),
+    )?;
+    let icon = Arc::new(icon);
+    println!("typer is in the system tray: click it to type the clipboard.");
+
+    let mut running: Option<(Arc<TypingControls>, thread::JoinHandle<()>)> = None;
+    for action in actions {
+        let source: Box<dyn TextSource + Send> = match action {
+            TrayAction::TypeClipboard => Box::new(ClipboardSource),
+            TrayAction::TypeSnippet(name) => match library.path(&name) {
+                Ok(path) => Box::new(FileSource { path }),
+                Err(e) => {
+                    println!("Could not type: {}", e);
+                    continue;
+                }
+            },
+            TrayAction::Profile(profile) => {
+                println!("Profile: {}", profile.as_deref().unwrap_or("none"));
+                typing.profile = profile;
+                continue;
+            }
+            TrayAction::Abort => {
+                if let Some((controls, _)) = &running {
+                    controls.abort();
+                }
+                continue;
+            }
+            TrayAction::Quit => break,
+        };
+        if running.as_ref().is_some_and(|(_, run)| !run.is_finished()) {
+            println!("Still typing; abort first to type something else.");
+            continue;
+        }
+
+        // Loaded for every run, so changes to the config apply without a restart
+        let session = match SnippetSession::new(&config_path, typing.clone()) {
+            Ok(session) => session,
+            Err(e) => {
+                println!("Could not type: {}", e);
+                continue;
+            }
+        };
+        let controls = Arc::clone(&session.controls);
+        let (icon, mut vars) = (Arc::clone(&icon), vars.clone());
+        icon.set_typing(true);
+        let run = thread::spawn(move || {
+            if let Err(e) = session.type_source(source, &mut vars) {
+                println!("Could not type: {}", e);
+            }
+            icon.set_typing(false);
+        });
+        running = Some((controls, run));
+    }
+
+    if let Some((controls, run)) = running {
+        controls.abort();
+        let _ = run.join();
+    }
+    Ok(())
+}
+
+fn ctl(action: CtlAction) -> Result<(), Box<dyn std::error::Error>> {
+    let request = match action {
+        CtlAction::Load {
+            file,
+            from_clipboard,
+        } => {
+            let source: Box<dyn TextSource> = match (file, from_clipboard) {
+                (Some(path), _) => Box::new(FileSource { path }),
+                (None, true) => Box::new(ClipboardSource),
+                (None, false) => Box::new(StdinSource),
+            };
+            let text = source.read()?;
+            // Malformed directives are reported now rather than on `start`
+            Script::parse(&text).map_err(|e| format!("{}: {}", source.describe(), e))?;
+            Request::Load {
+                text,
+                origin: source.describe(),
+            }
+        }
+        CtlAction::Start => Request::Start,
+        CtlAction::Pause => Request::Pause,
+        CtlAction::Resume => Request::Resume,
+        CtlAction::Abort => Request::Abort,
+        CtlAction::Speed { speed } => Request::Speed { speed },
+        CtlAction::Set { overrides } => Request::Set { overrides },
+        CtlAction::Status => Request::Status,
+    };
+
+    let response = daemon::send(&daemon::socket_path(), &request)?;
+    if let Some(error) = response.error {
+        return Err(error.into());
+    }
+    if let Some(status) = response.status {
+        let state = match status.state {
+            DaemonState::Idle => "Idle",
+            DaemonState::Typing => "Typing",
+            DaemonState::Paused => "Paused",
+        };
+        match &status.loaded {
+            Some(loaded) => println!(
+                "{}: {} of {} characters of {}, speed {:.0}%",
+                state,
+                status.position,
+                status.total,
+                loaded,
+                status.speed * 100.0
+            ),
+            None => println!("{}, no text loaded", state),
+        }
+    }
+    Ok(())
+}
+
+fn preview(source: SourceArgs) -> Result<(), Box<dyn std::error::Error>> {
+    for (text, text_source) in read_texts(&source.into_sources()?)? {
+        let text = strip_directives(&text);
+        println!("Text source: {}", text_source);
+        println!(
+            "{} characters, {} words, {} lines\n",
+            text.chars().count(),
+            text.split_whitespace().count(),
+            text.lines().count()
+        );
+        println!("{}\n", text);
+    }
+
+    Ok(())
+}
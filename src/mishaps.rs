@@ -0,0 +1,63 @@
+use crate::events::Chord;
+use crate::timing::Delay;
+use serde::{Deserialize, Serialize};
+
+/// A shortcut pressed by mistake, each one with a way to take it back.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Mishap {
+    /// Ctrl+Z undoes the last few words, then the redo key brings them back.
+    Undo,
+    /// Ctrl+B turns bold on, then pressing it again turns it off.
+    Bold,
+    /// Ctrl+I turns italics on, then pressing it again turns them off.
+    Italic,
+}
+
+impl Mishap {
+    /// The shortcut pressed by mistake.
+    pub fn chord(self) -> Chord {
+        match self {
+            Mishap::Undo => Chord::ctrl('z'),
+            Mishap::Bold => Chord::ctrl('b'),
+            Mishap::Italic => Chord::ctrl('i'),
+        }
+    }
+}
+
+/// Now and then presses a wrong shortcut between two words, stops for a moment, and
+/// takes it back.
+///
+/// Only the mishaps in `allowed` happen, so the ones an application does something
+/// else with can be left out.
+#[derive(Clone, Debug)]
+pub struct MishapConfig {
+    /// One in this many words ends in a mishap.
+    pub one_in_words: u32,
+    pub allowed: Vec<Mishap>,
+    /// Takes back [`Mishap::Undo`], like Ctrl+Y or Ctrl+Shift+Z.
+    pub redo: Chord,
+    /// Flustered pause between the mishap and taking it back.
+    pub pause: Delay,
+}
+
+impl Default for MishapConfig {
+    fn default() -> Self {
+        MishapConfig {
+            one_in_words: 500,
+            allowed: vec![Mishap::Undo],
+            redo: Chord::ctrl('y'),
+            pause: Delay::Uniform(800..2500),
+        }
+    }
+}
+
+impl MishapConfig {
+    /// The shortcut that takes `mishap` back.
+    pub fn recovery(&self, mishap: Mishap) -> Chord {
+        match mishap {
+            Mishap::Undo => self.redo.clone(),
+            other => other.chord(),
+        }
+    }
+}
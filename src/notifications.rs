@@ -0,0 +1,87 @@
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// Shows `message` as a desktop notification, returning as soon as it is on its way.
+#[cfg(target_os = "linux")]
+pub fn notify_desktop(message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    run_notifier("notify-send", &["typer", message])
+}
+
+#[cfg(target_os = "windows")]
+pub fn notify_desktop(message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let script = format!(
+        "Add-Type -AssemblyName System.Windows.Forms; \
+         $icon = New-Object System.Windows.Forms.NotifyIcon; \
+         $icon.Icon = [System.Drawing.SystemIcons]::Information; \
+         $icon.Visible = $true; \
+         $icon.ShowBalloonTip(5000, 'typer', '{}', 'Info'); \
+         Start-Sleep -Seconds 5; $icon.Dispose()",
+        message.replace('\'', "''")
+    );
+    run_notifier("powershell", &["-NoProfile", "-Command", &script])
+}
+
+#[cfg(target_os = "macos")]
+pub fn notify_desktop(message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let script = format!(
+        "display notification \"{}\" with title \"typer\"",
+        message.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    run_notifier("osascript", &["-e", &script])
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn notify_desktop(_message: &str) -> Result<(), Box<dyn std::error::Error>> {
+    Err("Desktop notifications are not supported on this platform".into())
+}
+
+/// Starts `program` without waiting for it, as notifiers can take seconds to finish
+/// and are called while typing.
+#[cfg(any(target_os = "linux", target_os = "windows", target_os = "macos"))]
+fn run_notifier(program: &str, args: &[&str]) -> Result<(), Box<dyn std::error::Error>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    // Reaped once done, so it leaves no zombie behind
+    thread::spawn(move || child.wait());
+    Ok(())
+}
+
+/// Plays the sound file at `file`, or the system's notification sound, returning as
+/// soon as it starts.
+#[cfg(target_os = "linux")]
+pub fn play_sound(file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let file = file.unwrap_or(Path::new(
+        "/usr/share/sounds/freedesktop/stereo/complete.oga",
+    ));
+    run_notifier("paplay", &[&file.to_string_lossy()])
+}
+
+#[cfg(target_os = "windows")]
+pub fn play_sound(file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let script = match file {
+        Some(file) => format!(
+            "(New-Object System.Media.SoundPlayer '{}').PlaySync()",
+            file.to_string_lossy().replace('\'', "''")
+        ),
+        // System sounds play in the background, so wait for it before exiting
+        None => "[System.Media.SystemSounds]::Asterisk.Play(); Start-Sleep -Seconds 1".to_string(),
+    };
+    run_notifier("powershell", &["-NoProfile", "-Command", &script])
+}
+
+#[cfg(target_os = "macos")]
+pub fn play_sound(file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    let file = file.unwrap_or(Path::new("/System/Library/Sounds/Glass.aiff"));
+    run_notifier("afplay", &[&file.to_string_lossy()])
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+pub fn play_sound(_file: Option<&Path>) -> Result<(), Box<dyn std::error::Error>> {
+    Err("Playing sounds is not supported on this platform".into())
+}
@@ -0,0 +1,138 @@
+use crate::hotkeys::TypingControls;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use x11rb::connection::Connection;
+use x11rb::protocol::xproto::{
+    ConnectionExt, CreateGCAux, CreateWindowAux, EventMask, Gcontext, Window, WindowClass,
+};
+use x11rb::rust_connection::RustConnection;
+use x11rb::COPY_DEPTH_FROM_PARENT;
+
+const WIDTH: u16 = 280;
+const HEIGHT: u16 = 44;
+const MARGIN: i16 = 16;
+const REDRAW_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the overlay shows.
+#[derive(Clone, Debug, Default)]
+pub struct OverlayStatus {
+    pub position: usize,
+    pub total: usize,
+    pub wpm: Option<f64>,
+}
+
+/// A small always-on-top X11 window in the top right corner of the screen showing the
+/// typing progress, speed and pause state.
+///
+/// The window never takes the focus, so it can stay up while typing into another app.
+#[derive(Clone)]
+pub struct Overlay {
+    status: Arc<Mutex<OverlayStatus>>,
+}
+
+impl Overlay {
+    /// Opens the overlay on its own thread and X connection.
+    pub fn spawn(controls: Arc<TypingControls>) -> Result<Overlay, Box<dyn std::error::Error>> {
+        let (conn, screen_num) = x11rb::connect(None)?;
+        let status = Arc::new(Mutex::new(OverlayStatus::default()));
+        let window = OverlayWindow::create(conn, screen_num)?;
+
+        let shown = Arc::clone(&status);
+        thread::spawn(move || {
+            while !controls.is_aborted() {
+                let status = shown.lock().unwrap().clone();
+                if window.draw(&status, controls.is_paused()).is_err() {
+                    break;
+                }
+                thread::sleep(REDRAW_INTERVAL);
+            }
+        });
+
+        Ok(Overlay { status })
+    }
+
+    pub fn update(&self, status: OverlayStatus) {
+        *self.status.lock().unwrap() = status;
+    }
+}
+
+struct OverlayWindow {
+    conn: RustConnection,
+    window: Window,
+    gc: Gcontext,
+}
+
+impl OverlayWindow {
+    fn create(conn: RustConnection, screen_num: usize) -> Result<Self, Box<dyn std::error::Error>> {
+        let screen = &conn.setup().roots[screen_num];
+        let x = screen.width_in_pixels as i16 - WIDTH as i16 - MARGIN;
+        let (root, black, white) = (screen.root, screen.black_pixel, screen.white_pixel);
+
+        let window = conn.generate_id()?;
+        // Override-redirect keeps the window manager from decorating, focusing or
+        // stacking it below other windows
+        conn.create_window(
+            COPY_DEPTH_FROM_PARENT,
+            window,
+            root,
+            x,
+            MARGIN,
+            WIDTH,
+            HEIGHT,
+            1,
+            WindowClass::INPUT_OUTPUT,
+            0,
+            &CreateWindowAux::new()
+                .background_pixel(black)
+                .border_pixel(white)
+                .override_redirect(1)
+                .event_mask(EventMask::EXPOSURE),
+        )?;
+
+        let font = conn.generate_id()?;
+        conn.open_font(font, b"fixed")?;
+        let gc = conn.generate_id()?;
+        conn.create_gc(
+            gc,
+            window,
+            &CreateGCAux::new()
+                .foreground(white)
+                .background(black)
+                .font(font),
+        )?;
+        conn.close_font(font)?;
+
+        conn.map_window(window)?;
+        conn.flush()?;
+        Ok(OverlayWindow { conn, window, gc })
+    }
+
+    fn draw(&self, status: &OverlayStatus, paused: bool) -> Result<(), Box<dyn std::error::Error>> {
+        // Drain events, everything gets redrawn anyway
+        while self.conn.poll_for_event()?.is_some() {}
+
+        let percent = if status.total == 0 {
+            100.0
+        } else {
+            status.position as f64 / status.total as f64 * 100.0
+        };
+        let progress = format!(
+            "{}/{} chars ({:.0}%)",
+            status.position, status.total, percent
+        );
+        let state = match (paused, status.wpm) {
+            (true, _) => "Paused".to_string(),
+            (false, Some(wpm)) => format!("Typing at {:.0} WPM", wpm),
+            (false, None) => "Typing".to_string(),
+        };
+
+        self.conn.clear_area(false, self.window, 0, 0, 0, 0)?;
+        self.conn
+            .image_text8(self.window, self.gc, 10, 18, progress.as_bytes())?;
+        self.conn
+            .image_text8(self.window, self.gc, 10, 34, state.as_bytes())?;
+        self.conn.flush()?;
+        Ok(())
+    }
+}
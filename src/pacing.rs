@@ -0,0 +1,141 @@
+use std::time::Duration;
+
+/// How much longer than the keystroke delays a text takes with its pauses and
+/// corrections, for the first plan before any typing has been measured.
+const PAUSE_ALLOWANCE: f64 = 1.4;
+/// Characters the first plan counts for when blended with the measured speed.
+const PLAN_WEIGHT: f64 = 50.0;
+/// Bounds of the factor delays are scaled by; slowing down further is done with pauses.
+const MIN_FACTOR: f64 = 0.25;
+const MAX_STRETCH: f64 = 1.5;
+
+/// A plan to finish typing within a time budget, see
+/// [`HumanTypist::finish_in`](crate::HumanTypist::finish_in).
+///
+/// The plan scales every delay and pause by one factor, worked out from the time and
+/// characters left and the speed measured so far, and checked again at every word.
+/// Delays are stretched by at most half; when there is more time than that, the rest
+/// is spent as extra pauses at the ends of sentences.
+#[derive(Clone, Debug)]
+pub struct Pacing {
+    deadline: Duration,
+    total: usize,
+    done: usize,
+    // Index in the current text at the last check, and the time it was made
+    last_index: usize,
+    last_check: Duration,
+    // Milliseconds per character expected before any typing was measured
+    planned_rate: f64,
+    // Time the characters done would have taken without pacing, in milliseconds
+    natural: f64,
+    factor: f64,
+    // Milliseconds still to be spent on pauses at sentence ends
+    slack: f64,
+}
+
+impl Pacing {
+    /// Plans to type `total` characters by `budget` after `now`, taking
+    /// `keystroke_millis` between keys on average without pacing.
+    pub fn new(now: Duration, budget: Duration, total: usize, keystroke_millis: f64) -> Self {
+        let planned_rate = keystroke_millis * PAUSE_ALLOWANCE;
+        let mut pacing = Pacing {
+            deadline: now + budget,
+            total,
+            done: 0,
+            last_index: 0,
+            last_check: now,
+            planned_rate,
+            natural: 0.0,
+            factor: 1.0,
+            slack: 0.0,
+        };
+        pacing.replan(now);
+        pacing
+    }
+
+    /// Starts counting characters of a new text from index `start`.
+    pub fn start_text(&mut self, start: usize) {
+        self.last_index = start;
+    }
+
+    /// Factor delays and pauses are scaled by.
+    pub fn factor(&self) -> f64 {
+        self.factor.clamp(MIN_FACTOR, MAX_STRETCH)
+    }
+
+    /// Counts the characters typed up to `index` of the current text at `now` and
+    /// works out the factor for the rest.
+    pub fn update(&mut self, index: usize, now: Duration) {
+        let advanced = index.saturating_sub(self.last_index);
+        if advanced == 0 {
+            return;
+        }
+        let spent = now.saturating_sub(self.last_check).as_secs_f64() * 1000.0;
+        self.natural += spent / self.factor();
+        self.done += advanced;
+        self.last_index = index;
+        self.last_check = now;
+
+        self.replan(now);
+        // What the characters just typed would have taken at the full factor
+        let stretch = (self.factor - self.factor()).max(0.0);
+        self.slack += self.rate() * stretch * advanced as f64;
+    }
+
+    /// Takes up to the pause time saved up, in milliseconds, for a pause at the end of
+    /// a sentence. `share` is the part of it to take, between 0 and 1.
+    pub fn take_slack(&mut self, share: f64) -> u64 {
+        let millis = self.slack * share.clamp(0.0, 1.0);
+        self.slack -= millis;
+        // The pause is not typing, so it does not count towards the measured speed
+        self.last_check += Duration::from_secs_f64(millis / 1000.0);
+        millis as u64
+    }
+
+    /// Milliseconds per character without pacing, the first plan blended with what has
+    /// been measured.
+    fn rate(&self) -> f64 {
+        (self.natural + self.planned_rate * PLAN_WEIGHT) / (self.done as f64 + PLAN_WEIGHT)
+    }
+
+    fn replan(&mut self, now: Duration) {
+        let left = self.total.saturating_sub(self.done);
+        if left == 0 {
+            return;
+        }
+        let remaining = self.deadline.saturating_sub(now).as_secs_f64() * 1000.0;
+        // Time for the pauses still to come is not there to be spent again
+        let needed = (remaining - self.slack).max(0.0) / left as f64;
+        self.factor = (needed / self.rate()).max(MIN_FACTOR);
+    }
+}
+
+/// Reads a time budget like `45m`, `1h30m` or `90s`.
+pub fn parse_budget(arg: &str) -> Result<Duration, String> {
+    let invalid = || {
+        format!(
+            "Invalid duration {:?}, expected one like 45m, 1h30m or 90s",
+            arg
+        )
+    };
+    let mut seconds = 0;
+    let mut number = String::new();
+    for c in arg.trim().chars() {
+        if c.is_ascii_digit() {
+            number.push(c);
+            continue;
+        }
+        let unit = match c {
+            'h' => 3600,
+            'm' => 60,
+            's' => 1,
+            _ => return Err(invalid()),
+        };
+        seconds += number.parse::<u64>().map_err(|_| invalid())? * unit;
+        number.clear();
+    }
+    if !number.is_empty() || seconds == 0 {
+        return Err(invalid());
+    }
+    Ok(Duration::from_secs(seconds))
+}
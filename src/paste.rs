@@ -0,0 +1,30 @@
+use crate::timing::Delay;
+
+/// Pastes blocks of boilerplate instead of typing them, after a pause to find them
+/// elsewhere, the way people bring in licence headers or long imports.
+///
+/// Blocks between `{{paste}}` and `{{/paste}}` are always pasted; with
+/// `min_block_chars`, so are paragraphs at least that long.
+#[derive(Clone, Debug)]
+pub struct PasteConfig {
+    pub min_block_chars: Option<usize>,
+    /// Pause to find the block and copy it, before pasting it.
+    pub find_delay: Delay,
+}
+
+impl Default for PasteConfig {
+    fn default() -> Self {
+        PasteConfig {
+            min_block_chars: None,
+            find_delay: Delay::Uniform(1500..4000),
+        }
+    }
+}
+
+/// Where the paragraph starting at `i` ends: at the blank line after it, or at the end
+/// of the text.
+pub fn block_end(chars: &[char], i: usize) -> usize {
+    (i..chars.len())
+        .find(|&k| chars[k] == '\n' && chars.get(k + 1) == Some(&'\n'))
+        .unwrap_or(chars.len())
+}
@@ -0,0 +1,38 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static PORTABLE: AtomicBool = AtomicBool::new(false);
+
+/// Keeps the config and text files next to the executable instead of the user directories.
+pub fn set_portable(portable: bool) {
+    PORTABLE.store(portable, Ordering::SeqCst);
+}
+
+pub fn is_portable() -> bool {
+    PORTABLE.load(Ordering::SeqCst)
+}
+
+/// Directory for the config file, e.g. `~/.config/typer`.
+pub fn config_dir() -> PathBuf {
+    user_dir(dirs::config_dir())
+}
+
+/// Directory for the default text file, e.g. `~/.local/share/typer`.
+pub fn data_dir() -> PathBuf {
+    user_dir(dirs::data_dir())
+}
+
+fn user_dir(base: Option<PathBuf>) -> PathBuf {
+    match base {
+        Some(base) if !is_portable() => base.join("typer"),
+        _ => executable_dir(),
+    }
+}
+
+fn executable_dir() -> PathBuf {
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(PathBuf::from))
+        .unwrap_or_else(|| PathBuf::from("."))
+}
@@ -0,0 +1,55 @@
+#[cfg(target_os = "macos")]
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+const ACCESSIBILITY_SETTINGS: &str =
+    "x-apple.systempreferences:com.apple.preference.security?Privacy_Accessibility";
+
+#[cfg(target_os = "macos")]
+#[link(name = "ApplicationServices", kind = "framework")]
+extern "C" {
+    fn AXIsProcessTrusted() -> bool;
+}
+
+/// Whether typer is allowed to send keystrokes to other apps.
+///
+/// Only macOS asks for a permission, without which keystrokes are silently dropped.
+pub fn can_send_input() -> bool {
+    #[cfg(target_os = "macos")]
+    {
+        // SAFETY: AXIsProcessTrusted takes no arguments and only queries the process
+        unsafe { AXIsProcessTrusted() }
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        true
+    }
+}
+
+/// Step-by-step instructions for granting the missing permission.
+pub fn permission_instructions() -> String {
+    [
+        "typer needs the Accessibility permission to type into other apps:",
+        "  1. Open System Settings > Privacy & Security > Accessibility.",
+        "  2. Turn on the switch next to the terminal app you run typer from,",
+        "     or add it with the + button if it is not listed.",
+        "  3. Quit and reopen the terminal, then run typer again.",
+    ]
+    .join("\n")
+}
+
+/// Opens the settings pane where the permission is granted.
+pub fn open_permission_settings() -> Result<(), Box<dyn std::error::Error>> {
+    #[cfg(target_os = "macos")]
+    {
+        Command::new("open")
+            .arg(ACCESSIBILITY_SETTINGS)
+            .status()
+            .map_err(|e| format!("Failed to open System Settings: {}", e))?;
+        Ok(())
+    }
+    #[cfg(not(target_os = "macos"))]
+    {
+        Err("No permission settings to open on this platform".into())
+    }
+}
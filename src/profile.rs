@@ -0,0 +1,221 @@
+use crate::config::{CorrectionStyle, TypingConfig};
+use crate::timing::Delay;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+/// Measured typing of one person, e.g. from `profile.json`.
+///
+/// Where the profile has data it takes precedence over the generic delay and mistake
+/// settings; everything it leaves out falls back to them.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PersonalProfile {
+    pub name: String,
+    /// Delay before the second key of a pair, by lowercase pair, e.g. `"th"`.
+    pub bigrams: BTreeMap<String, Latency>,
+    /// Delay between keystrokes whose pair is not in `bigrams`.
+    pub keystroke: Option<Latency>,
+    /// How long keys are held down, used when `hold.enabled` is set.
+    pub hold: Option<Latency>,
+    pub error_rates: ErrorRates,
+    pub corrections: CorrectionHabits,
+}
+
+/// A keystroke delay in milliseconds, normally distributed.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+pub struct Latency {
+    pub mean: f64,
+    #[serde(default)]
+    pub std_dev: f64,
+}
+
+impl From<Latency> for Delay {
+    fn from(latency: Latency) -> Delay {
+        Delay::Normal {
+            mean: latency.mean,
+            std_dev: latency.std_dev,
+        }
+    }
+}
+
+/// Percentage of characters of each class that get typed wrong.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ErrorRates {
+    pub letter: Option<f64>,
+    pub digit: Option<f64>,
+    pub punctuation: Option<f64>,
+    pub symbol: Option<f64>,
+    pub whitespace: Option<f64>,
+}
+
+/// How mistakes get corrected, overriding the matching mistake settings.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct CorrectionHabits {
+    /// Percentage of mistakes that get corrected.
+    pub probability: Option<u32>,
+    pub delay_min: Option<u64>,
+    pub delay_max: Option<u64>,
+    pub lag_chars_min: Option<usize>,
+    pub lag_chars_max: Option<usize>,
+    pub style: Option<CorrectionStyle>,
+}
+
+/// Kinds of characters that are typed with different accuracy.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    Letter,
+    Digit,
+    /// Sentence punctuation: `.,;:!?'"-`.
+    Punctuation,
+    /// Everything else that is not whitespace, e.g. `@#{}`.
+    Symbol,
+    Whitespace,
+}
+
+impl CharClass {
+    pub fn of(c: char) -> CharClass {
+        if c.is_alphabetic() {
+            CharClass::Letter
+        } else if c.is_numeric() {
+            CharClass::Digit
+        } else if c.is_whitespace() {
+            CharClass::Whitespace
+        } else if ".,;:!?'\"-".contains(c) {
+            CharClass::Punctuation
+        } else {
+            CharClass::Symbol
+        }
+    }
+}
+
+impl ErrorRates {
+    pub fn get(&self, class: CharClass) -> Option<f64> {
+        match class {
+            CharClass::Letter => self.letter,
+            CharClass::Digit => self.digit,
+            CharClass::Punctuation => self.punctuation,
+            CharClass::Symbol => self.symbol,
+            CharClass::Whitespace => self.whitespace,
+        }
+    }
+}
+
+impl PersonalProfile {
+    /// Loads a profile from a JSON or TOML file, picked by extension.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read profile {}: {}", path.display(), e))?;
+        let profile: PersonalProfile = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)?
+        } else {
+            serde_json::from_str(&content)?
+        };
+        profile
+            .validate()
+            .map_err(|e| format!("Invalid profile {}: {}", path.display(), e))?;
+        Ok(profile)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        for (bigram, latency) in &self.bigrams {
+            if bigram.chars().count() != 2 {
+                return Err(format!("{:?} is not a pair of characters", bigram));
+            }
+            check_latency(&format!("bigrams.{}", bigram), latency)?;
+        }
+        if let Some(latency) = &self.keystroke {
+            check_latency("keystroke", latency)?;
+        }
+        if let Some(latency) = &self.hold {
+            check_latency("hold", latency)?;
+        }
+
+        let rates = &self.error_rates;
+        for (name, rate) in [
+            ("letter", rates.letter),
+            ("digit", rates.digit),
+            ("punctuation", rates.punctuation),
+            ("symbol", rates.symbol),
+            ("whitespace", rates.whitespace),
+        ] {
+            if let Some(rate) = rate.filter(|rate| !(0.0..=100.0).contains(rate)) {
+                return Err(format!(
+                    "error_rates.{name} ({rate}) must be between 0 and 100"
+                ));
+            }
+        }
+
+        let habits = &self.corrections;
+        if habits.probability.is_some_and(|p| p > 100) {
+            return Err("corrections.probability must be at most 100".to_string());
+        }
+        if let (Some(min), Some(max)) = (habits.delay_min, habits.delay_max) {
+            if min >= max {
+                return Err("corrections.delay_min must be less than delay_max".to_string());
+            }
+        }
+        if let (Some(min), Some(max)) = (habits.lag_chars_min, habits.lag_chars_max) {
+            if min > max {
+                return Err("corrections.lag_chars_min must not exceed lag_chars_max".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    /// The measured delay between typing `first` and `second`, if there is one.
+    pub fn latency(&self, first: char, second: char) -> Option<Latency> {
+        let pair: String = [first, second]
+            .iter()
+            .flat_map(|c| c.to_lowercase())
+            .collect();
+        self.bigrams.get(&pair).or(self.keystroke.as_ref()).copied()
+    }
+
+    /// Chance between 0 and 1 that `c` gets typed wrong, if the profile knows it.
+    pub fn error_rate(&self, c: char) -> Option<f64> {
+        self.error_rates
+            .get(CharClass::of(c))
+            .map(|rate| rate / 100.0)
+    }
+
+    /// Replaces the correction settings of `config` with the profile's habits.
+    pub fn apply_corrections(&self, config: &mut TypingConfig) {
+        let habits = &self.corrections;
+        if let Some(probability) = habits.probability {
+            config.correction_probability = probability;
+        }
+        let delay = &mut config.correction_delay;
+        *delay = habits.delay_min.unwrap_or(delay.start)..habits.delay_max.unwrap_or(delay.end);
+        if delay.start >= delay.end {
+            delay.end = delay.start + 1;
+        }
+        let lag = &config.correction_lag_chars;
+        let (min, max) = (
+            habits.lag_chars_min.unwrap_or(*lag.start()),
+            habits.lag_chars_max.unwrap_or(*lag.end()),
+        );
+        config.correction_lag_chars = min..=max.max(min);
+        if let Some(style) = habits.style {
+            config.correction_style = style;
+        }
+    }
+}
+
+fn check_latency(name: &str, latency: &Latency) -> Result<(), String> {
+    if !(latency.mean > 0.0 && latency.std_dev >= 0.0) {
+        return Err(format!(
+            "{name} needs a positive mean and a non-negative std_dev"
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,69 @@
+use rand::Rng;
+
+/// Parameters of the reading-ahead hesitation: before a word that is hard to take in
+/// at a glance, the typist pauses to read it from the source, longer the more complex
+/// the word is.
+#[derive(Clone, Debug)]
+pub struct ReadAheadConfig {
+    /// Pause per point of complexity above `threshold`, in milliseconds.
+    pub ms_per_point: f64,
+    /// Words of at most this complexity are typed without hesitating.
+    pub threshold: f64,
+    /// Longest pause before a single word, in milliseconds.
+    pub max_pause: u64,
+}
+
+impl Default for ReadAheadConfig {
+    fn default() -> Self {
+        ReadAheadConfig {
+            ms_per_point: 40.0,
+            threshold: 8.0,
+            max_pause: 1500,
+        }
+    }
+}
+
+/// Words common enough to be read without a second look.
+const COMMON_WORDS: &[&str] = &[
+    "a", "about", "after", "all", "also", "an", "and", "any", "are", "as", "at", "be", "because",
+    "been", "but", "by", "can", "come", "could", "day", "do", "even", "first", "for", "from",
+    "get", "give", "go", "good", "had", "has", "have", "he", "her", "here", "him", "his", "how",
+    "i", "if", "in", "into", "is", "it", "its", "just", "know", "like", "look", "make", "man",
+    "many", "me", "more", "most", "my", "new", "no", "not", "now", "of", "on", "one", "only", "or",
+    "other", "our", "out", "over", "people", "say", "see", "she", "so", "some", "take", "than",
+    "that", "the", "their", "them", "then", "there", "these", "they", "thing", "think", "this",
+    "those", "time", "to", "two", "up", "us", "use", "very", "want", "was", "way", "we", "well",
+    "were", "what", "when", "which", "who", "will", "with", "work", "would", "year", "you", "your",
+];
+
+/// How hard `word` is to read ahead: its length, plus extra points for being
+/// uncommon, for digits and symbols, and for capitals inside the word.
+pub fn word_complexity(word: &str) -> f64 {
+    let bare = word.trim_matches(|c: char| ".,;:!?'\"()".contains(c));
+    let mut score = bare.chars().count() as f64;
+
+    let lowercase = bare.to_lowercase();
+    if !lowercase.is_empty() && !COMMON_WORDS.contains(&lowercase.as_str()) {
+        score += 2.0;
+    }
+    for c in bare.chars() {
+        if c.is_numeric() {
+            score += 1.5;
+        } else if !c.is_alphabetic() {
+            score += 2.5;
+        }
+    }
+    // camelCase and ALLCAPS take a second look
+    score += bare.chars().skip(1).filter(|c| c.is_uppercase()).count() as f64;
+    score
+}
+
+/// The pause before typing `word`, if it is complex enough to need one.
+pub fn hesitation<R: Rng>(config: &ReadAheadConfig, word: &str, rng: &mut R) -> Option<u64> {
+    let excess = word_complexity(word) - config.threshold;
+    if excess <= 0.0 {
+        return None;
+    }
+    let millis = excess * config.ms_per_point * rng.gen_range(0.5..1.5);
+    Some((millis as u64).min(config.max_pause))
+}
@@ -0,0 +1,88 @@
+use crate::typist::TypingStats;
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use std::time::Duration;
+
+/// Summary of a typing session, printed at the end of a run and optionally saved as JSON.
+#[derive(Serialize, Debug)]
+pub struct SessionReport {
+    pub total_seconds: f64,
+    pub characters: usize,
+    pub words: usize,
+    /// Words per minute over the whole session, pauses and corrections included.
+    pub effective_wpm: f64,
+    pub mistakes: usize,
+    pub corrections: usize,
+    pub long_pauses: usize,
+    pub thinking_pauses: usize,
+    pub lines_fixed: usize,
+    pub mishaps: usize,
+    pub distractions: usize,
+    pub breaks: usize,
+}
+
+impl SessionReport {
+    /// Builds the report for `typed`, the text that ended up being typed.
+    pub fn new(typed: &str, stats: &TypingStats, elapsed: Duration) -> Self {
+        let characters = typed.chars().count();
+        let minutes = elapsed.as_secs_f64() / 60.0;
+        // A word is five characters by convention
+        let effective_wpm = if minutes > 0.0 {
+            characters as f64 / 5.0 / minutes
+        } else {
+            0.0
+        };
+
+        SessionReport {
+            total_seconds: elapsed.as_secs_f64(),
+            characters,
+            words: typed.split_whitespace().count(),
+            effective_wpm,
+            mistakes: stats.mistakes,
+            corrections: stats.corrections,
+            long_pauses: stats.long_pauses,
+            thinking_pauses: stats.thinking_pauses,
+            lines_fixed: stats.lines_fixed,
+            mishaps: stats.mishaps,
+            distractions: stats.distractions,
+            breaks: stats.breaks,
+        }
+    }
+
+    pub fn print(&self) {
+        println!("Session report:");
+        println!("  Time:            {:.1}s", self.total_seconds);
+        println!(
+            "  Typed:           {} characters, {} words",
+            self.characters, self.words
+        );
+        println!("  Effective speed: {:.0} WPM", self.effective_wpm);
+        println!(
+            "  Mistakes:        {} ({} corrected)",
+            self.mistakes, self.corrections
+        );
+        println!(
+            "  Pauses:          {} long, {} thinking",
+            self.long_pauses, self.thinking_pauses
+        );
+        if self.lines_fixed > 0 {
+            println!("  Lines fixed:     {}", self.lines_fixed);
+        }
+        if self.mishaps > 0 {
+            println!("  Mishaps:         {}", self.mishaps);
+        }
+        if self.distractions > 0 {
+            println!("  Distractions:    {}", self.distractions);
+        }
+        if self.breaks > 0 {
+            println!("  Breaks:          {}", self.breaks);
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .map_err(|e| format!("Failed to write report {}: {}", path.display(), e))?;
+        Ok(())
+    }
+}
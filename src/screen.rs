@@ -0,0 +1,69 @@
+/// Takes a snapshot of the pixels of a part of the screen, or returns `None` once the
+/// screen can no longer be read.
+pub type ScreenCapture = Box<dyn FnMut() -> Option<Vec<u8>> + Send>;
+
+/// A rectangle of the screen, in pixels from its top left corner.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScreenRegion {
+    pub x: i16,
+    pub y: i16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl ScreenRegion {
+    /// Reads a region written as `x,y,width,height`, e.g. `0,900,1920,180`.
+    pub fn parse(spec: &str) -> Result<ScreenRegion, String> {
+        let invalid = || {
+            format!(
+                "Invalid screen region {:?}, expected x,y,width,height",
+                spec
+            )
+        };
+        let parts: Vec<&str> = spec.split(',').map(str::trim).collect();
+        let [x, y, width, height] = parts.as_slice() else {
+            return Err(invalid());
+        };
+        let region = ScreenRegion {
+            x: x.parse().map_err(|_| invalid())?,
+            y: y.parse().map_err(|_| invalid())?,
+            width: width.parse().map_err(|_| invalid())?,
+            height: height.parse().map_err(|_| invalid())?,
+        };
+        if region.width == 0 || region.height == 0 {
+            return Err(invalid());
+        }
+        Ok(region)
+    }
+}
+
+/// Reads `region` of the X11 screen.
+#[cfg(feature = "screen")]
+pub fn region_capture(region: ScreenRegion) -> Result<ScreenCapture, Box<dyn std::error::Error>> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{ConnectionExt, ImageFormat};
+
+    let (conn, screen_num) = x11rb::connect(None)?;
+    let root = conn.setup().roots[screen_num].root;
+    Ok(Box::new(move || {
+        let image = conn
+            .get_image(
+                ImageFormat::Z_PIXMAP,
+                root,
+                region.x,
+                region.y,
+                region.width,
+                region.height,
+                !0,
+            )
+            .ok()?
+            .reply()
+            .ok()?;
+        Some(image.data)
+    }))
+}
+
+#[cfg(not(feature = "screen"))]
+pub fn region_capture(_region: ScreenRegion) -> Result<ScreenCapture, Box<dyn std::error::Error>> {
+    Err("Watching the screen needs typer built with --features screen".into())
+}
@@ -0,0 +1,135 @@
+use crate::events::Chord;
+use crate::form::parse_csv_records;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Letter case a column is typed in.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Case {
+    Upper,
+    Lower,
+}
+
+/// How the cells of one column are typed.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(default)]
+pub struct ColumnRule {
+    /// Leaves the cells of the column empty, moving past them.
+    pub skip: bool,
+    pub case: Option<Case>,
+    /// Rounds numbers to this many decimals; other values are typed as they are.
+    pub decimals: Option<usize>,
+    /// Types the decimal point of numbers as a comma, for locales that use one.
+    pub decimal_comma: bool,
+}
+
+impl ColumnRule {
+    /// `value` formatted by this rule.
+    pub fn format(&self, value: &str) -> String {
+        let mut value = value.trim().to_string();
+        if let Some(decimals) = self.decimals {
+            if let Ok(number) = value.parse::<f64>() {
+                value = format!("{:.*}", decimals, number);
+            }
+        }
+        if self.decimal_comma && value.parse::<f64>().is_ok() {
+            value = value.replace('.', ",");
+        }
+        match self.case {
+            Some(Case::Upper) => value.to_uppercase(),
+            Some(Case::Lower) => value.to_lowercase(),
+            None => value,
+        }
+    }
+}
+
+/// How a table is typed into a spreadsheet: which keys move between cells and rows and
+/// how each column is formatted.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SheetLayout {
+    /// Moves to the next cell of the row, like Tab or Right.
+    pub next_cell: Chord,
+    /// Moves to the next row at the end of one, like Enter or Down.
+    pub next_row: Chord,
+    /// Pressed after `next_row` to get back to the first column, like Home, for
+    /// navigation keys that do not return there by themselves.
+    pub row_start: Option<Chord>,
+    /// Types newlines inside a cell, like Alt+Enter.
+    pub cell_newline: Chord,
+    /// Types the header row along with the data rows.
+    pub header: bool,
+    /// Rules by column name, as in the header row.
+    pub columns: BTreeMap<String, ColumnRule>,
+}
+
+impl Default for SheetLayout {
+    fn default() -> Self {
+        SheetLayout {
+            next_cell: Chord::parse("tab").expect("valid key"),
+            next_row: Chord::parse("enter").expect("valid key"),
+            row_start: None,
+            cell_newline: Chord::parse("alt+enter").expect("valid key"),
+            header: true,
+            columns: BTreeMap::new(),
+        }
+    }
+}
+
+impl SheetLayout {
+    /// Turns a CSV table into text with key directives between the cells, so it types
+    /// the table cell by cell with everything that applies to texts.
+    pub fn to_text(&self, csv: &str) -> Result<String, String> {
+        let records = parse_csv_records(csv)?;
+        let Some(first) = records.first() else {
+            return Ok(String::new());
+        };
+        let names: Vec<&str> = first.iter().map(|(name, _)| name.as_str()).collect();
+
+        let mut rows: Vec<Vec<String>> = Vec::new();
+        if self.header {
+            rows.push(names.iter().map(|name| name.to_string()).collect());
+        }
+        for record in &records {
+            rows.push(
+                record
+                    .iter()
+                    .map(|(name, value)| match self.columns.get(name) {
+                        Some(rule) if rule.skip => String::new(),
+                        Some(rule) => rule.format(value),
+                        None => value.trim().to_string(),
+                    })
+                    .collect(),
+            );
+        }
+
+        let directive = |chord: &Chord| format!("{{{{key:{}}}}}", chord_name(chord));
+        let mut text = String::new();
+        for (index, row) in rows.iter().enumerate() {
+            if index > 0 {
+                text.push_str(&directive(&self.next_row));
+                if let Some(row_start) = &self.row_start {
+                    text.push_str(&directive(row_start));
+                }
+            }
+            for (column, cell) in row.iter().enumerate() {
+                if column > 0 {
+                    text.push_str(&directive(&self.next_cell));
+                }
+                text.push_str(&cell.replace('\n', &directive(&self.cell_newline)));
+            }
+        }
+        Ok(text)
+    }
+}
+
+/// The name of `chord` as written in a key directive.
+fn chord_name(chord: &Chord) -> String {
+    chord
+        .modifiers
+        .iter()
+        .chain([&chord.key])
+        .map(|key| key.name())
+        .collect::<Vec<_>>()
+        .join("+")
+}
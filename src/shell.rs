@@ -0,0 +1,64 @@
+use crate::timing::Delay;
+use std::time::Duration;
+
+/// Types each line as a terminal command: the line is typed, run with Enter and given
+/// time to finish before the next one starts. A `{{wait:...}}` directive on a line
+/// waits that long after it instead.
+#[derive(Clone, Debug)]
+pub struct ShellConfig {
+    /// Time a command is given to finish, after pressing Enter on it.
+    pub command_delay: Delay,
+    /// Waits for the prompt to come back on screen instead of `command_delay` when set,
+    /// given a way to read the screen through
+    /// [`HumanTypistBuilder::screen`](crate::HumanTypistBuilder::screen).
+    pub prompt: Option<PromptDetection>,
+}
+
+/// Tells that a command has finished by watching part of the screen, such as the
+/// bottom lines of a terminal: once Enter is pressed the region changes with the
+/// output of the command, and when it stops changing the prompt is taken to be back.
+#[derive(Clone, Debug)]
+pub struct PromptDetection {
+    /// How long the region has to stay the same after changing.
+    pub settle: Duration,
+    /// Longest wait for a command, after which the next one is typed anyway.
+    pub timeout: Duration,
+}
+
+impl Default for PromptDetection {
+    fn default() -> Self {
+        PromptDetection {
+            settle: Duration::from_millis(800),
+            timeout: Duration::from_secs(60),
+        }
+    }
+}
+
+impl Default for ShellConfig {
+    fn default() -> Self {
+        ShellConfig {
+            command_delay: Delay::Uniform(1000..3000),
+            prompt: None,
+        }
+    }
+}
+
+/// Where the next command starts after the newline at `i`, past any blank lines, which
+/// would only bring up another prompt.
+pub fn next_command(chars: &[char], i: usize) -> usize {
+    let mut next = i + 1;
+    let mut k = next;
+    while let Some(&c) = chars.get(k) {
+        if c == '\n' {
+            next = k + 1;
+        } else if !c.is_whitespace() {
+            break;
+        }
+        k += 1;
+    }
+    if k == chars.len() {
+        chars.len()
+    } else {
+        next
+    }
+}
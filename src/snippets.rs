@@ -0,0 +1,145 @@
+use crate::paths;
+use crate::text::read_text_file;
+use std::fs;
+use std::path::PathBuf;
+
+/// Extension of the files snippets are stored in.
+const SNIPPET_EXTENSION: &str = "txt";
+
+/// Directory of the snippet library, e.g. `~/.local/share/typer/snippets`.
+pub fn default_snippets_dir() -> PathBuf {
+    paths::data_dir().join("snippets")
+}
+
+/// A directory of named snippets, one `NAME.txt` file each.
+pub struct SnippetLibrary {
+    pub dir: PathBuf,
+}
+
+impl SnippetLibrary {
+    pub fn new(dir: PathBuf) -> Self {
+        SnippetLibrary { dir }
+    }
+
+    /// Names of all snippets, sorted. A missing directory is an empty library.
+    pub fn names(&self) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        if !self.dir.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut names = Vec::new();
+        for entry in fs::read_dir(&self.dir)
+            .map_err(|e| format!("Failed to read {}: {}", self.dir.display(), e))?
+        {
+            let path = entry?.path();
+            if path.extension().is_some_and(|ext| ext == SNIPPET_EXTENSION) {
+                if let Some(stem) = path.file_stem() {
+                    names.push(stem.to_string_lossy().into_owned());
+                }
+            }
+        }
+        names.sort();
+        Ok(names)
+    }
+
+    /// Path of the file of the snippet called `name`, which cannot lead out of the
+    /// library.
+    pub fn path(&self, name: &str) -> Result<PathBuf, String> {
+        check_name(name)?;
+        Ok(self.dir.join(format!("{}.{}", name, SNIPPET_EXTENSION)))
+    }
+
+    /// Reads the snippet called `name`, normalized like any text file.
+    pub fn read(&self, name: &str) -> Result<String, Box<dyn std::error::Error>> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(format!("No snippet called {:?}", name).into());
+        }
+        read_text_file(&path)
+    }
+
+    /// Saves `text` as the snippet called `name`, replacing any snippet of that name.
+    pub fn add(&self, name: &str, text: &str) -> Result<PathBuf, Box<dyn std::error::Error>> {
+        let path = self.path(name)?;
+        fs::create_dir_all(&self.dir)?;
+        fs::write(&path, text).map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        Ok(path)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let path = self.path(name)?;
+        if !path.exists() {
+            return Err(format!("No snippet called {:?}", name).into());
+        }
+        fs::remove_file(&path)?;
+        Ok(())
+    }
+
+    /// Names of the snippets matching `query`, best match first; see [`fuzzy_score`].
+    pub fn search(&self, query: &str) -> Result<Vec<String>, Box<dyn std::error::Error>> {
+        Ok(rank(query, self.names()?))
+    }
+}
+
+/// Orders the `names` matching `query` from the best match to the worst, dropping the
+/// others. An exact match always comes first.
+pub fn rank(query: &str, names: Vec<String>) -> Vec<String> {
+    let mut scored: Vec<(i64, String)> = names
+        .into_iter()
+        .filter_map(|name| fuzzy_score(query, &name).map(|score| (score, name)))
+        .collect();
+    scored.sort_by(|(a_score, a), (b_score, b)| b_score.cmp(a_score).then_with(|| a.cmp(b)));
+    scored.into_iter().map(|(_, name)| name).collect()
+}
+
+/// How well `name` matches `query`, or `None` when the characters of `query` do not all
+/// appear in `name` in that order, ignoring case.
+///
+/// Runs of consecutive characters and matches at the start of a word score higher,
+/// and shorter names win ties, much like the pickers of common editors.
+pub fn fuzzy_score(query: &str, name: &str) -> Option<i64> {
+    if query.to_lowercase() == name.to_lowercase() {
+        return Some(i64::MAX);
+    }
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+    let name: Vec<char> = name.chars().collect();
+
+    let mut score = 0;
+    let mut wanted = query.iter().peekable();
+    let mut previous_matched = false;
+    for (i, c) in name.iter().enumerate() {
+        let Some(&&next) = wanted.peek() else {
+            break;
+        };
+        if c.to_lowercase().eq(next.to_lowercase()) {
+            score += 1;
+            if previous_matched {
+                score += 5;
+            }
+            if i == 0 || !name[i - 1].is_alphanumeric() {
+                score += 10;
+            }
+            wanted.next();
+            previous_matched = true;
+        } else {
+            previous_matched = false;
+        }
+    }
+
+    wanted
+        .peek()
+        .is_none()
+        .then(|| score * 100 - name.len() as i64)
+}
+
+fn check_name(name: &str) -> Result<(), String> {
+    if !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains("..")
+        && !name.contains(['/', '\\'])
+    {
+        Ok(())
+    } else {
+        Err(format!("Invalid snippet name {:?}", name))
+    }
+}
@@ -0,0 +1,101 @@
+use crate::hotkeys::{PauseReason, TypingControls};
+use crate::window::focused_window;
+use device_query::{DeviceQuery, DeviceState, Keycode};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// How long keys and the mouse may still show what typer sent before they count as the
+/// user's own.
+const INJECTION_GRACE: Duration = Duration::from_millis(60);
+
+/// When typing is paused for the user and picked up again afterwards.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TakeoverEvent {
+    Paused,
+    Resumed,
+}
+
+/// Polls the keyboard and mouse on a background thread and pauses typing as soon as
+/// the user types or moves the mouse themselves, resuming once they have left both
+/// alone for a while, back in the window typing was paused in. Stops when dropped.
+pub struct TakeoverWatcher {
+    stopped: Arc<AtomicBool>,
+}
+
+impl TakeoverWatcher {
+    /// Resumes after `idle` without any input, unless typing was resumed by other
+    /// means already. Keys among `ignored`, such as the hotkeys, and with `mouse` off
+    /// the mouse, do not count as taking over, and neither does Escape, which aborts
+    /// when held.
+    pub fn spawn<F>(
+        idle: Duration,
+        mut ignored: Vec<Keycode>,
+        mouse: bool,
+        controls: Arc<TypingControls>,
+        on_event: F,
+    ) -> Self
+    where
+        F: Fn(TakeoverEvent) + Send + 'static,
+    {
+        ignored.push(Keycode::Escape);
+        let stopped = Arc::new(AtomicBool::new(false));
+        let watched = Arc::clone(&stopped);
+        thread::spawn(move || {
+            let device_state = DeviceState::new();
+            let mut last_mouse = device_state.get_mouse();
+            let mut last_input = Instant::now();
+            // The window typing was paused in, while it is paused by this watcher
+            let mut paused = None;
+
+            loop {
+                thread::sleep(POLL_INTERVAL);
+                if watched.load(Ordering::SeqCst) || controls.is_aborted() {
+                    return;
+                }
+
+                let keys: Vec<Keycode> = device_state
+                    .get_keys()
+                    .into_iter()
+                    .filter(|key| !ignored.contains(key))
+                    .collect();
+                let typed = !keys.is_empty() && !controls.injecting(&keys, INJECTION_GRACE);
+                let state = device_state.get_mouse();
+                let moved = state.coords != last_mouse.coords
+                    || state.button_pressed.iter().any(|&pressed| pressed);
+                let moved = mouse && moved && !controls.injecting(&[], INJECTION_GRACE);
+                last_mouse = state;
+
+                if typed || moved {
+                    last_input = Instant::now();
+                    if !controls.is_paused() {
+                        controls.pause(PauseReason::Takeover);
+                        paused = Some(focused_window().map(|window| window.id));
+                        on_event(TakeoverEvent::Paused);
+                    }
+                } else if let Some(window) = &paused {
+                    if !controls.is_paused_by(PauseReason::Takeover) {
+                        // Resumed with the pause hotkey or from elsewhere
+                        paused = None;
+                    } else if last_input.elapsed() >= idle
+                        && focused_window().map(|window| window.id) == *window
+                    {
+                        controls.resume(PauseReason::Takeover);
+                        paused = None;
+                        on_event(TakeoverEvent::Resumed);
+                    }
+                }
+            }
+        });
+
+        TakeoverWatcher { stopped }
+    }
+}
+
+impl Drop for TakeoverWatcher {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::Path;
+
+/// Names between double braces that are directives rather than placeholders.
+const DIRECTIVE_NAMES: [&str; 9] = [
+    "pause",
+    "wait",
+    "key",
+    "speed",
+    "move",
+    "click",
+    "doubleclick",
+    "paste",
+    "stop",
+];
+
+/// Names of the `{{name}}` placeholders in `text`, in order of first appearance.
+///
+/// A placeholder name is made of letters, digits, `_` and `-`, so directives and
+/// other double braces, like `{{}}` in a format string, are not placeholders. Neither
+/// is a name after `\{{`, which [`fill`] turns into a plain `{{`.
+pub fn placeholders(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for_each_placeholder(text, |name| {
+        if !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+        None
+    });
+    names
+}
+
+/// Fills the placeholders of `text` with `vars`, failing on the first one without a value.
+pub fn fill(text: &str, vars: &HashMap<String, String>) -> Result<String, String> {
+    let mut missing = None;
+    let filled = for_each_placeholder(text, |name| {
+        let value = vars.get(name);
+        if value.is_none() && missing.is_none() {
+            missing = Some(name.to_string());
+        }
+        value.cloned()
+    });
+    match missing {
+        Some(name) => Err(format!("No value for the placeholder {{{{{}}}}}", name)),
+        None => Ok(filled),
+    }
+}
+
+/// Reads a `name=value` pair as given to `--var`.
+pub fn parse_var(arg: &str) -> Result<(String, String), String> {
+    match arg.split_once('=') {
+        Some((name, value)) if is_name(name.trim()) => {
+            Ok((name.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("Invalid variable {:?}, expected NAME=VALUE", arg)),
+    }
+}
+
+/// Reads a vars file: a JSON or TOML table of strings, picked by extension, or else
+/// `name=value` lines. Empty lines and lines starting with `#` are skipped.
+pub fn read_vars_file(path: &Path) -> Result<HashMap<String, String>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    let invalid = |e: String| format!("Invalid vars file {}: {}", path.display(), e);
+
+    let vars = match path.extension().and_then(|ext| ext.to_str()) {
+        Some("json") => serde_json::from_str::<BTreeMap<String, String>>(&content)
+            .map_err(|e| invalid(e.to_string()))?
+            .into_iter()
+            .collect(),
+        Some("toml") => toml::from_str::<BTreeMap<String, String>>(&content)
+            .map_err(|e| invalid(e.to_string()))?
+            .into_iter()
+            .collect(),
+        _ => content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|line| parse_var(line).map(|(name, value)| (name, value.trim().to_string())))
+            .collect::<Result<_, _>>()
+            .map_err(invalid)?,
+    };
+    Ok(vars)
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+        && !DIRECTIVE_NAMES.contains(&name.to_ascii_lowercase().as_str())
+}
+
+/// Calls `replace` with the name of every placeholder in `text` and returns the text
+/// with each replaced by the value returned, or kept as it is for `None`.
+fn for_each_placeholder(text: &str, mut replace: impl FnMut(&str) -> Option<String>) -> String {
+    let mut filled = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(open) = rest.find("{{") {
+        // `\{{` is a literal `{{`
+        if rest[..open].ends_with('\\') {
+            filled.push_str(&rest[..open - 1]);
+            filled.push_str("{{");
+            rest = &rest[open + 2..];
+            continue;
+        }
+        let Some(close) = rest[open + 2..].find("}}").map(|close| open + 2 + close) else {
+            break;
+        };
+        let name = &rest[open + 2..close];
+        if !is_name(name) {
+            filled.push_str(&rest[..open + 2]);
+            rest = &rest[open + 2..];
+            continue;
+        }
+
+        filled.push_str(&rest[..open]);
+        match replace(name) {
+            Some(value) => filled.push_str(&value),
+            None => filled.push_str(&rest[open..close + 2]),
+        }
+        rest = &rest[close + 2..];
+    }
+
+    filled.push_str(rest);
+    filled
+}
@@ -0,0 +1,318 @@
+use crate::diff::PatchFile;
+use crate::directives::directive_spans;
+use crate::paths;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+pub fn get_text_file_path() -> PathBuf {
+    paths::data_dir().join("typethis.txt")
+}
+
+pub fn ensure_text_file_exists() -> Result<String, Box<dyn std::error::Error>> {
+    let text_path = get_text_file_path();
+
+    if !text_path.exists() {
+        if let Some(dir) = text_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let default_text = "Type your text here.\nType your text here.";
+        fs::write(&text_path, default_text)?;
+        return Ok(default_text.to_string());
+    }
+
+    let content = fs::read_to_string(&text_path)?;
+    if content.trim().is_empty() {
+        let default_text = "Type your text here.\nType your text here.";
+        fs::write(&text_path, default_text)?;
+        Ok(default_text.to_string())
+    } else {
+        Ok(normalize_text(&content))
+    }
+}
+
+/// Reads a user-supplied text file without creating or rewriting it.
+pub fn read_text_file(path: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    Ok(normalize_text(&content))
+}
+
+pub fn normalize_text(content: &str) -> String {
+    // Normalize line endings and ensure proper text handling
+    let normalized = content.replace("\r\n", "\n");
+    if let Some(stripped) = normalized.strip_suffix('\n') {
+        stripped.to_string()
+    } else {
+        normalized
+    }
+}
+
+pub fn read_clipboard() -> Result<String, Box<dyn std::error::Error>> {
+    let content = arboard::Clipboard::new()?.get_text()?;
+    if content.trim().is_empty() {
+        return Err("Clipboard does not contain any text".into());
+    }
+    Ok(normalize_text(&content))
+}
+
+/// Reads a playlist file: one text file path per line, relative to the playlist itself.
+/// Empty lines and lines starting with `#` are skipped.
+pub fn read_playlist(path: &Path) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let content = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read playlist {}: {}", path.display(), e))?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    Ok(content
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| dir.join(line))
+        .collect())
+}
+
+/// Somewhere the text to type can be read from.
+pub trait TextSource {
+    /// Human-readable description of the source, such as a path or URL.
+    fn describe(&self) -> String;
+
+    /// Reads the text, normalized with [`normalize_text`].
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>>;
+
+    /// The file to watch for changes, for sources backed by one.
+    fn watch_path(&self) -> Option<PathBuf> {
+        None
+    }
+}
+
+/// The default `typethis.txt`, created with placeholder text when missing or empty.
+pub struct DefaultTextFile;
+
+impl TextSource for DefaultTextFile {
+    fn describe(&self) -> String {
+        get_text_file_path().display().to_string()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        ensure_text_file_exists()
+    }
+
+    fn watch_path(&self) -> Option<PathBuf> {
+        Some(get_text_file_path())
+    }
+}
+
+pub struct FileSource {
+    pub path: PathBuf,
+}
+
+impl TextSource for FileSource {
+    fn describe(&self) -> String {
+        self.path.display().to_string()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        read_text_file(&self.path)
+    }
+
+    fn watch_path(&self) -> Option<PathBuf> {
+        Some(self.path.clone())
+    }
+}
+
+pub struct ClipboardSource;
+
+impl TextSource for ClipboardSource {
+    fn describe(&self) -> String {
+        "clipboard".to_string()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        read_clipboard()
+    }
+}
+
+pub struct StdinSource;
+
+impl TextSource for StdinSource {
+    fn describe(&self) -> String {
+        "standard input".to_string()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let mut content = String::new();
+        io::stdin().read_to_string(&mut content)?;
+        Ok(normalize_text(&content))
+    }
+}
+
+/// Text handed over as it is, like the text loaded into `typer daemon`.
+pub struct InlineSource {
+    pub origin: String,
+    pub text: String,
+}
+
+impl TextSource for InlineSource {
+    fn describe(&self) -> String {
+        self.origin.clone()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(normalize_text(&self.text))
+    }
+}
+
+/// Fetches the text with an HTTP GET request.
+pub struct UrlSource {
+    pub url: String,
+}
+
+impl TextSource for UrlSource {
+    fn describe(&self) -> String {
+        self.url.clone()
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        let content = ureq::get(&self.url)
+            .call()
+            .map_err(|e| format!("Failed to fetch {}: {}", self.url, e))?
+            .into_string()?;
+        Ok(normalize_text(&content))
+    }
+}
+
+/// The lines one file gains in a commit or patch, see
+/// [`parse_patch`](crate::diff::parse_patch).
+pub struct PatchSource {
+    /// The commit or patch file the lines come from.
+    pub origin: String,
+    pub file: PatchFile,
+}
+
+impl TextSource for PatchSource {
+    fn describe(&self) -> String {
+        // The path stays last, where its file extension can be told
+        format!("{}: {}", self.origin, self.file.path)
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(normalize_text(&self.file.added))
+    }
+}
+
+/// A part of a text, by line or character numbers counted from 1 with both ends
+/// included, like `40..120`. Either end may be left out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Selection {
+    Lines(usize, Option<usize>),
+    Chars(usize, Option<usize>),
+}
+
+impl Selection {
+    /// Reads a range like `40..120`, `40..` or `..120`.
+    pub fn parse_range(arg: &str) -> Result<(usize, Option<usize>), String> {
+        let invalid = || format!("Invalid range {:?}, expected one like 40..120", arg);
+        let (start, end) = arg.split_once("..").ok_or_else(invalid)?;
+        let start = match start.trim() {
+            "" => 1,
+            start => start.parse().map_err(|_| invalid())?,
+        };
+        let end = match end.trim() {
+            "" => None,
+            end => Some(end.parse().map_err(|_| invalid())?),
+        };
+        if start == 0 {
+            return Err(format!("Invalid range {:?}, numbers start at 1", arg));
+        }
+        if end.is_some_and(|end| end < start) {
+            return Err(format!(
+                "Invalid range {:?}, the end is before the start",
+                arg
+            ));
+        }
+        Ok((start, end))
+    }
+
+    /// The selected part of `text`. A selection starting past the end of the text, or
+    /// one of characters that would cut a directive in two, is an error.
+    pub fn apply(&self, text: &str) -> Result<String, String> {
+        match *self {
+            Selection::Lines(start, end) => {
+                let lines = text.split('\n').count();
+                if start > lines {
+                    return Err(format!(
+                        "Cannot type {}, the text has only {} lines",
+                        self.describe(),
+                        lines
+                    ));
+                }
+                Ok(text
+                    .split('\n')
+                    .skip(start - 1)
+                    .take(end.map_or(usize::MAX, |end| end + 1 - start))
+                    .collect::<Vec<_>>()
+                    .join("\n"))
+            }
+            Selection::Chars(start, end) => {
+                let chars: Vec<char> = text.chars().collect();
+                if start > chars.len() {
+                    return Err(format!(
+                        "Cannot type {}, the text has only {} characters",
+                        self.describe(),
+                        chars.len()
+                    ));
+                }
+                let end = end.map_or(chars.len(), |end| end.min(chars.len()));
+                let cut = directive_spans(text).into_iter().find(|span| {
+                    [start - 1, end]
+                        .iter()
+                        .any(|&at| span.start < at && at < span.end)
+                });
+                if let Some(span) = cut {
+                    return Err(format!(
+                        "Cannot type {}, that would cut the directive {} in two",
+                        self.describe(),
+                        chars[span].iter().collect::<String>()
+                    ));
+                }
+                Ok(chars[start - 1..end].iter().collect())
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let (unit, start, end) = match *self {
+            Selection::Lines(start, end) => ("lines", start, end),
+            Selection::Chars(start, end) => ("characters", start, end),
+        };
+        match end {
+            Some(end) => format!("{} {} to {}", unit, start, end),
+            None => format!("{} {} on", unit, start),
+        }
+    }
+}
+
+/// Only part of the text of another source, see [`Selection`].
+pub struct SelectedSource {
+    pub source: Box<dyn TextSource>,
+    pub selection: Selection,
+}
+
+impl TextSource for SelectedSource {
+    fn describe(&self) -> String {
+        // The source stays last, where its file extension can be told
+        format!(
+            "{} of {}",
+            self.selection.describe(),
+            self.source.describe()
+        )
+    }
+
+    fn read(&self) -> Result<String, Box<dyn std::error::Error>> {
+        Ok(self.selection.apply(&self.source.read()?)?)
+    }
+
+    fn watch_path(&self) -> Option<PathBuf> {
+        self.source.watch_path()
+    }
+}
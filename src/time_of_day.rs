@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+/// Slows typing down and adds mistakes depending on the local time, by a multiplier
+/// for each hour of the day that is interpolated in between.
+#[derive(Clone, Debug)]
+pub struct TimeOfDayConfig {
+    /// Multipliers of delays and mistake rates by hour of the day, from 0.0 up to
+    /// 24.0, sorted by hour.
+    pub curve: Vec<(f64, f64)>,
+    /// Local time typing started at, in hours since midnight.
+    pub start_hour: f64,
+}
+
+impl Default for TimeOfDayConfig {
+    fn default() -> Self {
+        TimeOfDayConfig {
+            curve: default_curve(),
+            start_hour: 12.0,
+        }
+    }
+}
+
+impl TimeOfDayConfig {
+    /// The multiplier after typing for `elapsed`.
+    pub fn factor(&self, elapsed: Duration) -> f64 {
+        let hour = (self.start_hour + elapsed.as_secs_f64() / 3600.0) % 24.0;
+        interpolate(&self.curve, hour)
+    }
+}
+
+/// Slower after dark and slowest in the small hours.
+pub fn default_curve() -> Vec<(f64, f64)> {
+    vec![
+        (0.0, 1.25),
+        (3.0, 1.35),
+        (7.0, 1.1),
+        (10.0, 1.0),
+        (14.0, 1.05),
+        (18.0, 1.0),
+        (22.0, 1.15),
+    ]
+}
+
+/// The value of `curve` at `hour`, going around midnight from the last hour to the first.
+fn interpolate(curve: &[(f64, f64)], hour: f64) -> f64 {
+    let (Some(&first), Some(&last)) = (curve.first(), curve.last()) else {
+        return 1.0;
+    };
+    let before = curve
+        .iter()
+        .rev()
+        .find(|&&(at, _)| at <= hour)
+        .copied()
+        .unwrap_or((last.0 - 24.0, last.1));
+    let after = curve
+        .iter()
+        .find(|&&(at, _)| at > hour)
+        .copied()
+        .unwrap_or((first.0 + 24.0, first.1));
+    if after.0 <= before.0 {
+        return before.1;
+    }
+    let t = (hour - before.0) / (after.0 - before.0);
+    before.1 + (after.1 - before.1) * t
+}
@@ -0,0 +1,216 @@
+use rand::Rng;
+use rand_distr::{Distribution, LogNormal, Normal};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::ops::{Range, RangeInclusive};
+use std::path::Path;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum DelayDistribution {
+    #[default]
+    Uniform,
+    Normal,
+    LogNormal,
+}
+
+/// A random delay in milliseconds.
+#[derive(Clone, Debug)]
+pub enum Delay {
+    Uniform(Range<u64>),
+    /// Bell curve around the mean, cut off at zero.
+    Normal {
+        mean: f64,
+        std_dev: f64,
+    },
+    /// Skewed towards short delays with a long tail, like real typing.
+    LogNormal {
+        mean: f64,
+        std_dev: f64,
+    },
+}
+
+impl Delay {
+    pub fn new(
+        distribution: DelayDistribution,
+        range: Range<u64>,
+        mean: f64,
+        std_dev: f64,
+    ) -> Self {
+        match distribution {
+            DelayDistribution::Uniform => Delay::Uniform(range),
+            DelayDistribution::Normal => Delay::Normal { mean, std_dev },
+            DelayDistribution::LogNormal => Delay::LogNormal { mean, std_dev },
+        }
+    }
+
+    pub fn sample<R: Rng>(&self, rng: &mut R) -> u64 {
+        let millis = match self {
+            Delay::Uniform(range) => return rng.gen_range(range.clone()),
+            Delay::Normal { mean, std_dev } => Normal::new(*mean, *std_dev)
+                .map(|normal| normal.sample(rng))
+                .unwrap_or(*mean),
+            Delay::LogNormal { mean, std_dev } => {
+                // Pick the underlying normal so the result has the requested mean and spread
+                let sigma_squared = (1.0 + (std_dev / mean).powi(2)).ln();
+                let mu = mean.ln() - sigma_squared / 2.0;
+                LogNormal::new(mu, sigma_squared.sqrt())
+                    .map(|log_normal| log_normal.sample(rng))
+                    .unwrap_or(*mean)
+            }
+        };
+        millis.max(0.0).round() as u64
+    }
+
+    /// The average delay, in milliseconds.
+    pub fn mean(&self) -> f64 {
+        match self {
+            Delay::Uniform(range) => (range.start + range.end) as f64 / 2.0,
+            Delay::Normal { mean, .. } | Delay::LogNormal { mean, .. } => *mean,
+        }
+    }
+}
+
+impl From<Range<u64>> for Delay {
+    fn from(range: Range<u64>) -> Self {
+        Delay::Uniform(range)
+    }
+}
+
+/// Typing in bursts: runs of quick keystrokes separated by short pauses.
+#[derive(Clone, Debug)]
+pub struct BurstConfig {
+    /// Number of keystrokes in a burst.
+    pub length: RangeInclusive<usize>,
+    /// Multiplier applied to the base delay inside a burst, e.g. 0.6 for 40% faster.
+    pub speed_factor: f64,
+    /// Pause between two bursts, in milliseconds.
+    pub pause: Range<u64>,
+}
+
+impl Default for BurstConfig {
+    fn default() -> Self {
+        BurstConfig {
+            length: 5..=15,
+            speed_factor: 0.6,
+            pause: 150..450,
+        }
+    }
+}
+
+/// How long keys stay down when typing with separate key down and up events.
+#[derive(Clone, Debug)]
+pub struct KeyHoldConfig {
+    /// Time between pressing and releasing a key, in milliseconds.
+    pub duration: Delay,
+    /// Percentage of keys that are still down when the next key is pressed.
+    pub rollover_probability: u32,
+    /// Schedules every release on its own instead of releasing keys in order, so keys
+    /// overlap whenever a hold outlasts the delay to the next key.
+    pub timeline: bool,
+    /// Percentage of letter pairs that come out swapped in timeline mode, on top of
+    /// the transposition probability.
+    pub swap_probability: u32,
+}
+
+impl Default for KeyHoldConfig {
+    fn default() -> Self {
+        KeyHoldConfig {
+            duration: Delay::Uniform(50..130),
+            rollover_probability: 10,
+            timeline: false,
+            swap_probability: 1,
+        }
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct Bursts {
+    remaining: usize,
+}
+
+impl Bursts {
+    /// Counts a keystroke and returns `true` when it ends the current burst, in which
+    /// case a pause is due before the next one starts.
+    pub(crate) fn keystroke<R: Rng>(&mut self, config: &BurstConfig, rng: &mut R) -> bool {
+        if self.remaining == 0 {
+            self.remaining = rng.gen_range(config.length.clone()).max(1);
+        }
+        self.remaining -= 1;
+        self.remaining == 0
+    }
+}
+
+// Frequent English digraphs are typed noticeably faster than average
+const FAST_BIGRAMS: [&str; 40] = [
+    "th", "he", "in", "er", "an", "re", "on", "at", "en", "nd", "ti", "es", "or", "te", "of", "ed",
+    "is", "it", "al", "ar", "st", "to", "nt", "ng", "se", "ha", "as", "ou", "io", "le", "ve", "co",
+    "me", "de", "hi", "ri", "ro", "ic", "ne", "ea",
+];
+const FAST_BIGRAM_FACTOR: f64 = 0.7;
+
+// Rare pairs that need an awkward reach or the same finger twice
+const SLOW_BIGRAMS: [&str; 16] = [
+    "pz", "zp", "qz", "zq", "xq", "qx", "bz", "zb", "mz", "zm", "yq", "qy", "pq", "qp", "vq", "qv",
+];
+const SLOW_BIGRAM_FACTOR: f64 = 1.5;
+
+/// Delay multipliers for pairs of consecutive characters, e.g. 0.7 for "th".
+///
+/// Pairs are looked up in lowercase; pairs that are not in the table keep the base delay.
+#[derive(Clone, Debug)]
+pub struct BigramTable {
+    factors: HashMap<(char, char), f64>,
+}
+
+impl BigramTable {
+    pub fn builtin() -> Self {
+        let mut table = BigramTable {
+            factors: HashMap::new(),
+        };
+        for bigram in FAST_BIGRAMS {
+            table.insert(bigram, FAST_BIGRAM_FACTOR);
+        }
+        for bigram in SLOW_BIGRAMS {
+            table.insert(bigram, SLOW_BIGRAM_FACTOR);
+        }
+        table
+    }
+
+    /// Loads overrides from a JSON or TOML map of bigram to factor, on top of the
+    /// built-in table.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read bigrams {}: {}", path.display(), e))?;
+        let overrides: HashMap<String, f64> = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)?
+        } else {
+            serde_json::from_str(&content)?
+        };
+
+        let mut table = BigramTable::builtin();
+        for (bigram, factor) in overrides {
+            if bigram.chars().count() != 2 {
+                return Err(format!("Invalid bigram {:?} in {}", bigram, path.display()).into());
+            }
+            table.insert(&bigram, factor);
+        }
+        Ok(table)
+    }
+
+    fn insert(&mut self, bigram: &str, factor: f64) {
+        let mut chars = bigram.chars().flat_map(char::to_lowercase);
+        if let (Some(first), Some(second)) = (chars.next(), chars.next()) {
+            self.factors.insert((first, second), factor);
+        }
+    }
+
+    pub fn factor(&self, first: char, second: char) -> f64 {
+        let key = (
+            first.to_lowercase().next().unwrap_or(first),
+            second.to_lowercase().next().unwrap_or(second),
+        );
+        self.factors.get(&key).copied().unwrap_or(1.0)
+    }
+}
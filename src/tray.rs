@@ -0,0 +1,180 @@
+use crate::snippets::SnippetLibrary;
+use ksni::blocking::{Handle, TrayMethods};
+use ksni::menu::{MenuItem, RadioGroup, RadioItem, StandardItem, SubMenu};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// What was asked for from the tray icon.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrayAction {
+    /// Type the clipboard contents; the icon was clicked.
+    TypeClipboard,
+    /// Type the snippet of this name.
+    TypeSnippet(String),
+    /// Type with this profile from now on, or with the config as it is.
+    Profile(Option<String>),
+    Abort,
+    Quit,
+}
+
+/// The state the icon and its menu are drawn from.
+struct TrayMenu {
+    library: SnippetLibrary,
+    profiles: Vec<String>,
+    profile: Option<String>,
+    typing: bool,
+    actions: Sender<TrayAction>,
+}
+
+impl TrayMenu {
+    fn send(&self, action: TrayAction) {
+        // Nothing is listening any more once typer is quitting
+        let _ = self.actions.send(action);
+    }
+}
+
+impl ksni::Tray for TrayMenu {
+    fn id(&self) -> String {
+        "typer".to_string()
+    }
+
+    fn title(&self) -> String {
+        match self.typing {
+            true => "typer: typing".to_string(),
+            false => "typer".to_string(),
+        }
+    }
+
+    fn icon_name(&self) -> String {
+        "input-keyboard".to_string()
+    }
+
+    fn activate(&mut self, _x: i32, _y: i32) {
+        self.send(TrayAction::TypeClipboard);
+    }
+
+    fn menu(&self) -> Vec<MenuItem<Self>> {
+        // Listed anew each time the menu opens, so snippets added meanwhile show up
+        let snippets: Vec<MenuItem<Self>> = match self.library.names() {
+            Ok(names) if !names.is_empty() => names
+                .into_iter()
+                .map(|name| {
+                    StandardItem {
+                        label: name.replace('_', "__"),
+                        enabled: !self.typing,
+                        activate: Box::new(move |tray: &mut Self| {
+                            tray.send(TrayAction::TypeSnippet(name.clone()))
+                        }),
+                        ..Default::default()
+                    }
+                    .into()
+                })
+                .collect(),
+            _ => vec![StandardItem {
+                label: "No snippets".to_string(),
+                enabled: false,
+                ..Default::default()
+            }
+            .into()],
+        };
+
+        let selected = self
+            .profile
+            .as_ref()
+            .and_then(|profile| self.profiles.iter().position(|name| name == profile))
+            .map_or(0, |index| index + 1);
+        let profiles = std::iter::once("No profile".to_string())
+            .chain(self.profiles.iter().map(|name| name.replace('_', "__")))
+            .map(|label| RadioItem {
+                label,
+                ..Default::default()
+            })
+            .collect();
+
+        vec![
+            StandardItem {
+                label: "Type the clipboard".to_string(),
+                enabled: !self.typing,
+                activate: Box::new(|tray: &mut Self| tray.send(TrayAction::TypeClipboard)),
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: "Snippets".to_string(),
+                submenu: snippets,
+                ..Default::default()
+            }
+            .into(),
+            SubMenu {
+                label: "Profile".to_string(),
+                submenu: vec![RadioGroup {
+                    selected,
+                    select: Box::new(|tray: &mut Self, index: usize| {
+                        tray.profile = index.checked_sub(1).map(|i| tray.profiles[i].clone());
+                        tray.send(TrayAction::Profile(tray.profile.clone()));
+                    }),
+                    options: profiles,
+                }
+                .into()],
+                ..Default::default()
+            }
+            .into(),
+            StandardItem {
+                label: "Abort".to_string(),
+                enabled: self.typing,
+                activate: Box::new(|tray: &mut Self| tray.send(TrayAction::Abort)),
+                ..Default::default()
+            }
+            .into(),
+            MenuItem::Separator,
+            StandardItem {
+                label: "Quit".to_string(),
+                activate: Box::new(|tray: &mut Self| tray.send(TrayAction::Quit)),
+                ..Default::default()
+            }
+            .into(),
+        ]
+    }
+}
+
+/// An icon in the system tray of desktops that support StatusNotifierItem, like KDE
+/// Plasma, or GNOME with the AppIndicator extension.
+///
+/// A click on the icon asks to type the clipboard; its menu offers the snippets of
+/// `library`, a choice of profile and a way to abort. Whatever is asked for comes out of
+/// the receiver returned by [`TrayIcon::spawn`], to be acted on by its owner.
+pub struct TrayIcon {
+    handle: Handle<TrayMenu>,
+}
+
+impl TrayIcon {
+    /// Puts the icon in the tray, with `profile` of `profiles` selected.
+    pub fn spawn(
+        library: SnippetLibrary,
+        profiles: Vec<String>,
+        profile: Option<String>,
+    ) -> Result<(TrayIcon, Receiver<TrayAction>), Box<dyn std::error::Error>> {
+        let (actions, received) = mpsc::channel();
+        let menu = TrayMenu {
+            library,
+            profiles,
+            profile,
+            typing: false,
+            actions,
+        };
+        let handle = menu
+            .spawn()
+            .map_err(|e| format!("Failed to add the tray icon: {}", e))?;
+        Ok((TrayIcon { handle }, received))
+    }
+
+    /// Shows whether typing is going on, which enables aborting instead of starting.
+    pub fn set_typing(&self, typing: bool) {
+        self.handle.update(|menu| menu.typing = typing);
+    }
+}
+
+impl Drop for TrayIcon {
+    fn drop(&mut self) {
+        self.handle.shutdown().wait();
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Deserialize;
+use std::net::TcpStream;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+
+/// What a trigger bound in `triggers.bindings` does, e.g. `snippet intro` or `speed 1.5`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum TriggerAction {
+    /// Types the snippet with this name.
+    Snippet(String),
+    /// Pauses typing, or resumes it when paused.
+    Pause,
+    Abort,
+    /// Sets the speed factor.
+    Speed(f64),
+}
+
+impl TriggerAction {
+    pub fn parse(action: &str) -> Result<TriggerAction, String> {
+        let action = action.trim();
+        let (verb, argument) = action
+            .split_once(char::is_whitespace)
+            .map_or((action, ""), |(verb, argument)| (verb, argument.trim()));
+        match (verb.to_ascii_lowercase().as_str(), argument) {
+            ("snippet", "") => Err("snippet needs a snippet name".to_string()),
+            ("snippet", name) => Ok(TriggerAction::Snippet(name.to_string())),
+            ("pause", "") => Ok(TriggerAction::Pause),
+            ("abort", "") => Ok(TriggerAction::Abort),
+            ("speed", speed) => match speed.parse::<f64>() {
+                Ok(speed) if speed.is_finite() && speed > 0.0 => Ok(TriggerAction::Speed(speed)),
+                _ => Err(format!(
+                    "Invalid speed {:?}, expected e.g. speed 1.5",
+                    speed
+                )),
+            },
+            _ => Err(format!(
+                "Unknown action {:?}, expected snippet NAME, pause, abort or speed FACTOR",
+                action
+            )),
+        }
+    }
+}
+
+/// Checks a trigger name of `triggers.bindings`: `note N` or `cc N` for a MIDI note or
+/// controller number from 0 to 127, or `deck COLUMN,ROW` for a Stream Deck key.
+pub fn check_trigger(trigger: &str) -> Result<(), String> {
+    let invalid = || {
+        format!(
+            "Invalid trigger {:?}, expected note N, cc N or deck COLUMN,ROW",
+            trigger
+        )
+    };
+    let (kind, number) = trigger.split_once(' ').ok_or_else(invalid)?;
+    let valid = match kind {
+        "note" | "cc" => number.parse::<u8>().is_ok_and(|number| number < 128),
+        "deck" => number.split_once(',').is_some_and(|(column, row)| {
+            column.parse::<u32>().is_ok() && row.parse::<u32>().is_ok()
+        }),
+        _ => false,
+    };
+    valid.then_some(()).ok_or_else(invalid)
+}
+
+/// The trigger a MIDI message sets off: `note N` for a note played, on any channel, and
+/// `cc N` for a controller, like a pad or button, going from off to on.
+pub fn midi_trigger(message: &[u8]) -> Option<String> {
+    match *message {
+        // A note on with no velocity is how many devices send a note off
+        [status, note, velocity] if status & 0xF0 == 0x90 && velocity > 0 => {
+            Some(format!("note {}", note))
+        }
+        [status, controller, value] if status & 0xF0 == 0xB0 && value >= 64 => {
+            Some(format!("cc {}", controller))
+        }
+        _ => None,
+    }
+}
+
+/// Listens to the first MIDI input whose name contains `port`, or the first one when
+/// `None`, and calls `on_trigger` for every trigger it sets off. Listens for as long as
+/// the returned connection is kept.
+#[cfg(feature = "midi")]
+pub fn listen_midi<F>(
+    port: Option<&str>,
+    mut on_trigger: F,
+) -> Result<(String, midir::MidiInputConnection<()>), Box<dyn std::error::Error>>
+where
+    F: FnMut(String) + Send + 'static,
+{
+    let input = midir::MidiInput::new("typer")?;
+    let ports = input.ports();
+    let found = ports
+        .iter()
+        .filter_map(|p| Some((p, input.port_name(p).ok()?)))
+        .find(|(_, name)| port.is_none_or(|port| name.contains(port)));
+    let Some((found, name)) = found else {
+        return Err(match port {
+            Some(port) => format!("No MIDI input called {:?}", port),
+            None => "No MIDI input found".to_string(),
+        }
+        .into());
+    };
+    let connection = input
+        .connect(
+            found,
+            "typer",
+            move |_, message, _| {
+                if let Some(trigger) = midi_trigger(message) {
+                    on_trigger(trigger);
+                }
+            },
+            (),
+        )
+        .map_err(|e| format!("Failed to connect to {}: {}", name, e))?;
+    Ok((name, connection))
+}
+
+/// How the Stream Deck app starts a plugin: `-port`, `-pluginUUID`, `-registerEvent` and
+/// `-info` arguments, with the port of its WebSocket server.
+#[derive(Clone, Debug, PartialEq)]
+pub struct StreamDeckPlugin {
+    pub port: u16,
+    pub uuid: String,
+    pub register_event: String,
+}
+
+impl StreamDeckPlugin {
+    /// The plugin arguments among `args`, if typer was started as a Stream Deck plugin.
+    pub fn from_args(args: &[String]) -> Option<StreamDeckPlugin> {
+        let value = |name: &str| {
+            let at = args.iter().position(|arg| arg == name)?;
+            args.get(at + 1).cloned()
+        };
+        Some(StreamDeckPlugin {
+            port: value("-port")?.parse().ok()?,
+            uuid: value("-pluginUUID")?,
+            register_event: value("-registerEvent")?,
+        })
+    }
+
+    /// Registers with the Stream Deck app and calls `on_trigger` with `deck COLUMN,ROW`
+    /// for every key pressed, until the app closes the connection.
+    pub fn listen<F>(&self, mut on_trigger: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnMut(String),
+    {
+        let url = format!("ws://127.0.0.1:{}", self.port);
+        let (mut socket, _) = tungstenite::connect(&url)
+            .map_err(|e| format!("Failed to connect to the Stream Deck app: {}", e))?;
+        let register = serde_json::json!({ "event": self.register_event, "uuid": self.uuid });
+        socket.send(Message::text(register.to_string()))?;
+
+        loop {
+            let Some(message) = read(&mut socket)? else {
+                return Ok(());
+            };
+            let Ok(event) = serde_json::from_str::<DeckEvent>(&message) else {
+                continue;
+            };
+            let at = event.payload.and_then(|payload| payload.coordinates);
+            if let (true, Some(at)) = (event.event == "keyDown", at) {
+                on_trigger(format!("deck {},{}", at.column, at.row));
+            }
+        }
+    }
+}
+
+/// The next text message, or `None` once the connection is closed.
+fn read(
+    socket: &mut WebSocket<MaybeTlsStream<TcpStream>>,
+) -> Result<Option<String>, Box<dyn std::error::Error>> {
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => return Ok(Some(text)),
+            Ok(_) => {}
+            Err(tungstenite::Error::ConnectionClosed) => return Ok(None),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+/// The part of the events of the Stream Deck app that tells which key was pressed.
+#[derive(Deserialize)]
+struct DeckEvent {
+    event: String,
+    payload: Option<DeckPayload>,
+}
+
+#[derive(Deserialize)]
+struct DeckPayload {
+    coordinates: Option<DeckCoordinates>,
+}
+
+#[derive(Deserialize)]
+struct DeckCoordinates {
+    column: u32,
+    row: u32,
+}
@@ -0,0 +1,310 @@
+use crate::directives::{Directive, Script};
+use crate::hotkeys::TypingControls;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use ratatui::backend::CrosstermBackend;
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::{Frame, Terminal};
+use std::io::{self, Stdout};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const REDRAW_INTERVAL: Duration = Duration::from_millis(100);
+/// Number of `{{pause}}` and `{{wait}}` directives listed ahead of the cursor.
+const UPCOMING_PAUSES: usize = 3;
+
+/// What the control panel shows besides the text.
+#[derive(Clone, Debug, Default)]
+pub struct TuiStatus {
+    pub position: usize,
+    pub wpm: Option<f64>,
+}
+
+#[derive(Default)]
+struct State {
+    status: TuiStatus,
+    // Reason of the pause being taken, how long it is and when it started
+    pause: Option<(String, Duration, Instant)>,
+    // The last message of the run
+    message: String,
+}
+
+/// A full-terminal control panel showing the text with the position typed up to, the
+/// speed, the pause being taken and those coming up, with keys to pause, abort and
+/// change the speed.
+///
+/// The keys only reach the panel while the terminal has the focus; the global hotkeys
+/// work as always.
+pub struct Tui {
+    state: Arc<Mutex<State>>,
+    stop: Arc<AtomicBool>,
+    thread: Mutex<Option<JoinHandle<()>>>,
+}
+
+impl Tui {
+    /// Takes over the terminal and shows `texts`, which are typed one after the other,
+    /// until [`Tui::close`]. `speed_step` is the change of speed per key press.
+    pub fn spawn(
+        controls: Arc<TypingControls>,
+        texts: &[String],
+        speed_step: f64,
+    ) -> Result<Tui, Box<dyn std::error::Error>> {
+        let panel = Panel::new(texts);
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+        let state = Arc::new(Mutex::new(State::default()));
+        let stop = Arc::new(AtomicBool::new(false));
+        let (shown, stopped) = (Arc::clone(&state), Arc::clone(&stop));
+        let thread = thread::spawn(move || {
+            panel.run(terminal, &shown, &stopped, &controls, speed_step);
+            let _ = terminal::disable_raw_mode();
+            let _ = execute!(io::stdout(), LeaveAlternateScreen);
+        });
+
+        Ok(Tui {
+            state,
+            stop,
+            thread: Mutex::new(Some(thread)),
+        })
+    }
+
+    pub fn update(&self, status: TuiStatus) {
+        self.state.lock().unwrap().status = status;
+    }
+
+    /// Shows that a pause of `duration` starts now.
+    pub fn pausing(&self, reason: &str, duration: Duration) {
+        self.state.lock().unwrap().pause = Some((reason.to_string(), duration, Instant::now()));
+    }
+
+    /// Shows `message` below the status line, in place of the one before.
+    pub fn say(&self, message: &str) {
+        self.state.lock().unwrap().message = message.to_string();
+    }
+
+    /// Gives the terminal back.
+    pub fn close(&self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(thread) = self.thread.lock().unwrap().take() {
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for Tui {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+/// The texts as shown, with where their lines start and their pauses.
+struct Panel {
+    chars: Vec<char>,
+    line_starts: Vec<usize>,
+    // Character index and length of each `{{pause}}` and `{{wait}}`, in order
+    pauses: Vec<(usize, Duration)>,
+}
+
+impl Panel {
+    fn new(texts: &[String]) -> Self {
+        let mut chars = Vec::new();
+        let mut pauses = Vec::new();
+        for text in texts {
+            let script = Script::parse(text).unwrap_or_else(|_| Script::plain(text));
+            let offset = chars.len();
+            pauses.extend(
+                script
+                    .directives
+                    .iter()
+                    .filter_map(|(at, directive)| match directive {
+                        Directive::Pause(millis) | Directive::Wait(millis) => {
+                            Some((offset + at, Duration::from_millis(*millis)))
+                        }
+                        _ => None,
+                    }),
+            );
+            chars.extend(script.text.chars());
+        }
+        let line_starts = std::iter::once(0)
+            .chain(
+                chars
+                    .iter()
+                    .enumerate()
+                    .filter(|&(_, &c)| c == '\n')
+                    .map(|(i, _)| i + 1),
+            )
+            .collect();
+        Panel {
+            chars,
+            line_starts,
+            pauses,
+        }
+    }
+
+    fn run(
+        &self,
+        mut terminal: Terminal<CrosstermBackend<Stdout>>,
+        state: &Mutex<State>,
+        stop: &AtomicBool,
+        controls: &TypingControls,
+        speed_step: f64,
+    ) {
+        let mut shown = (false, 1.0);
+        while !stop.load(Ordering::SeqCst) {
+            // Messages printed by the hotkeys come with a change of state and get
+            // wiped out with it
+            let now = (controls.is_paused(), controls.speed());
+            if now != shown {
+                let _ = terminal.clear();
+                shown = now;
+            }
+            let drawn = terminal.draw(|frame| self.draw(frame, &state.lock().unwrap(), controls));
+            if drawn.is_err() {
+                break;
+            }
+
+            let Ok(true) = event::poll(REDRAW_INTERVAL) else {
+                continue;
+            };
+            let Ok(Event::Key(key)) = event::read() else {
+                continue;
+            };
+            if key.kind != KeyEventKind::Press {
+                continue;
+            }
+            match key.code {
+                KeyCode::Char(' ') | KeyCode::Char('p') => {
+                    controls.toggle_pause();
+                }
+                KeyCode::Char('+') | KeyCode::Char('=') => {
+                    controls.change_speed(1.0 + speed_step);
+                }
+                KeyCode::Char('-') => {
+                    controls.change_speed(1.0 / (1.0 + speed_step));
+                }
+                KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    controls.abort()
+                }
+                KeyCode::Char('q') | KeyCode::Esc => controls.abort(),
+                _ => {}
+            }
+        }
+    }
+
+    fn draw(&self, frame: &mut Frame, state: &State, controls: &TypingControls) {
+        let [text, status, upcoming, message, help] = Layout::vertical([
+            Constraint::Min(3),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        self.draw_text(frame, text, state.status.position);
+
+        let position = state.status.position.min(self.chars.len());
+        let percent = match self.chars.len() {
+            0 => 100.0,
+            total => position as f64 / total as f64 * 100.0,
+        };
+        let wpm = state
+            .status
+            .wpm
+            .map_or("-- WPM".to_string(), |wpm| format!("{:.0} WPM", wpm));
+        let doing = match &state.pause {
+            _ if controls.is_aborted() => "aborting".to_string(),
+            _ if controls.is_paused() => "paused".to_string(),
+            Some((reason, duration, started)) if started.elapsed() < *duration => format!(
+                "{} {:.1} s",
+                reason,
+                (*duration - started.elapsed()).as_secs_f64()
+            ),
+            _ => "typing".to_string(),
+        };
+        frame.render_widget(
+            Paragraph::new(format!(
+                " {:.0}%  {} of {} characters  {}  speed {:.0}%  {}",
+                percent,
+                position,
+                self.chars.len(),
+                wpm,
+                controls.speed() * 100.0,
+                doing
+            ))
+            .style(Style::new().add_modifier(Modifier::BOLD)),
+            status,
+        );
+
+        let next: Vec<String> = self
+            .pauses
+            .iter()
+            .filter(|&&(at, _)| at >= position)
+            .take(UPCOMING_PAUSES)
+            .map(|&(at, duration)| {
+                format!(
+                    "{:.1} s in {} characters",
+                    duration.as_secs_f64(),
+                    at - position
+                )
+            })
+            .collect();
+        let next = match next.is_empty() {
+            true => " No pauses coming up".to_string(),
+            false => format!(" Next pauses: {}", next.join(", ")),
+        };
+        frame.render_widget(Paragraph::new(next), upcoming);
+        frame.render_widget(Paragraph::new(format!(" {}", state.message)), message);
+        frame.render_widget(
+            Paragraph::new(" Space pause/resume   + faster   - slower   q abort")
+                .style(Style::new().fg(Color::DarkGray)),
+            help,
+        );
+    }
+
+    /// The lines around `position`, with the text before it in green and the character
+    /// at it highlighted.
+    fn draw_text(&self, frame: &mut Frame, area: Rect, position: usize) {
+        let block = Block::default().borders(Borders::ALL).title(" typer ");
+        let height = block.inner(area).height as usize;
+        let current = self.line_starts.partition_point(|&start| start <= position) - 1;
+        let first = current.saturating_sub(height / 2);
+
+        let typed = Style::new().fg(Color::Green);
+        let cursor = Style::new().add_modifier(Modifier::REVERSED);
+        let lines: Vec<Line> = self.line_starts[first..]
+            .iter()
+            .take(height)
+            .map(|&start| {
+                let end = self.chars[start..]
+                    .iter()
+                    .position(|&c| c == '\n')
+                    .map_or(self.chars.len(), |len| start + len);
+                let split = position.clamp(start, end);
+                let mut spans = vec![Span::styled(
+                    self.chars[start..split].iter().collect::<String>(),
+                    typed,
+                )];
+                if (start..=end).contains(&position) {
+                    // The end of a line shows the cursor as a space
+                    let at = self.chars.get(position).filter(|&&c| c != '\n');
+                    spans.push(Span::styled(at.unwrap_or(&' ').to_string(), cursor));
+                    let rest = (position + 1).min(end);
+                    spans.push(Span::raw(self.chars[rest..end].iter().collect::<String>()));
+                } else {
+                    spans.push(Span::raw(self.chars[split..end].iter().collect::<String>()));
+                }
+                Line::from(spans)
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
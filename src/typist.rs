@@ -0,0 +1,1962 @@
+use crate::apps::AppSwitch;
+use crate::backend::{EnigoBackend, KeyboardBackend};
+use crate::breaks::BreakEvent;
+use crate::chat;
+use crate::code::{self, AutoClose, AutoIndent};
+use crate::config::{CorrectionStyle, NewlineKey, Step, TypingConfig};
+use crate::directives::{Directive, Script};
+use crate::distraction::DistractionAction;
+use crate::events::{Chord, Key, KeyAction, KeyEvent};
+use crate::fatigue::Fatigue;
+use crate::hotkeys::{Jump, PauseReason, TypingControls};
+use crate::keyboard::KeyboardLayout;
+use crate::pacing::Pacing;
+use crate::paste;
+use crate::profile::CharClass;
+use crate::reading;
+use crate::shell::{self, PromptDetection};
+use crate::timing::{Bursts, Delay};
+use crate::typos;
+use crate::viewport::ViewportAction;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::Serialize;
+use std::io::{self, Write};
+use std::ops::{Range, RangeInclusive};
+use std::sync::Arc;
+use std::{thread, time::Duration};
+
+const PAUSE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+const OMISSION_NOTICE_CHARS: RangeInclusive<usize> = 2..=5;
+const BACKSPACE_BURST_DELAY: Range<u64> = 15..40;
+/// How long both keys are down when the previous one is released late.
+const ROLLOVER_OVERLAP: Range<u64> = 5..30;
+/// Time between the two keys of a pair pressed almost at once, in timeline mode.
+const SWAP_GAP: Range<u64> = 3..20;
+/// Time the hand takes from the keyboard to the mouse, or between moving and clicking.
+const MOUSE_DELAY: Range<u64> = 250..600;
+/// Time between the two clicks of a double-click.
+const DOUBLE_CLICK_GAP: Range<u64> = 60..140;
+/// Time between two looks at the screen while waiting for a prompt.
+const PROMPT_POLL_INTERVAL: u64 = 100;
+/// Time for a copied selection to reach the clipboard.
+const CLIPBOARD_DELAY: Range<u64> = 80..160;
+/// Time between the small moves of a mouse wiggle, and how far each one goes.
+const WIGGLE_STEP_DELAY: Range<u64> = 30..90;
+const WIGGLE_STEP: RangeInclusive<i32> = -25..=25;
+/// Characters typed on a single key of a US keyboard, without Shift.
+const UNSHIFTED_KEYS: &str = "abcdefghijklmnopqrstuvwxyz0123456789 `-=[]\\;',./";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TypingOutcome {
+    Completed,
+    Aborted,
+}
+
+pub struct HumanTypist {
+    pub config: TypingConfig,
+    keyboard: KeyboardLayout,
+    rng: StdRng,
+    // Skips waiting for delays, which are still counted in `elapsed`
+    instant: bool,
+    // `None` in dry-run mode, where keystrokes are printed instead of sent
+    backend: Option<Box<dyn KeyboardBackend>>,
+    controls: Arc<TypingControls>,
+    position: usize,
+    elapsed: Duration,
+    // Characters typed since a mistake that has not been noticed yet
+    mistake_buffer: Vec<char>,
+    pending: Option<PendingMistake>,
+    // Text before this index has been typed once already and is retyped without mistakes
+    retype_until: usize,
+    fatigue: Fatigue,
+    bursts: Bursts,
+    // Text before this index is being retyped in a quick burst after a word-level correction
+    quick_retype_until: usize,
+    on_progress: Option<ProgressCallback>,
+    on_key_event: Option<KeyEventCallback>,
+    on_break: Option<BreakCallback>,
+    on_pause: Option<PauseCallback>,
+    on_mistake: Option<MistakeCallback>,
+    on_output: Option<OutputCallback>,
+    stats: TypingStats,
+    keystroke_log: Option<Box<dyn Write + Send>>,
+    // Time of the last key event, for the delays in the keystroke log
+    last_event_at: Duration,
+    // Set while erasing a mistake
+    correcting: bool,
+    // Key left down to roll over into the next one
+    held: Option<Key>,
+    // Time already spent holding the last key, taken off the next keystroke delay
+    hold_credit: u64,
+    // Keys that are down in timeline mode, with the time they get released
+    releases: Vec<(Duration, Key)>,
+    // Which characters of the text are inside a string literal, in code mode
+    literals: Vec<bool>,
+    // Closers the editor inserted after the cursor by itself, innermost last
+    auto_closed: Vec<(char, AutoClose)>,
+    clipboard: Option<ClipboardReader>,
+    clipboard_writer: Option<ClipboardWriter>,
+    screen: Option<ScreenReader>,
+    // Directives of the script being typed and the index of the next one to run
+    directives: Vec<(usize, Directive)>,
+    next_directive: usize,
+    // Speed factor set by the last speed directive
+    speed: f64,
+    // Lines typed since the view was last scrolled along
+    lines_since_scroll: usize,
+    // Paragraphs typed since the last save, and the time it happened
+    paragraphs_since_save: usize,
+    last_save_at: Duration,
+    // Time of the next distraction, picked once the first one is due to be planned
+    next_distraction_at: Option<Duration>,
+    // Time the last break ended
+    last_break_at: Duration,
+    pacing: Option<Pacing>,
+    app_switch: Option<Arc<AppSwitch>>,
+    // The typist's own newline key, auto-indent and exactness, kept while an app
+    // profile replaces them
+    app_base: Option<(Option<NewlineKey>, AutoIndent, bool)>,
+    // Speed factor of the app being typed into
+    app_speed: f64,
+}
+
+/// How far [`HumanTypist::type_text`] got, reported through
+/// [`HumanTypistBuilder::on_progress`] before each character.
+#[derive(Clone, Copy, Debug)]
+pub struct Progress {
+    /// Number of characters of the text that are on screen correctly.
+    pub position: usize,
+    /// Number of wrong characters typed after those, which still need to be erased.
+    pub erase: usize,
+    /// Number of characters in the whole text.
+    pub total: usize,
+    pub elapsed: Duration,
+}
+
+type ProgressCallback = Box<dyn FnMut(&Progress) + Send>;
+type KeyEventCallback = Box<dyn FnMut(&KeyEvent) + Send>;
+type BreakCallback = Box<dyn FnMut(BreakEvent) + Send>;
+type PauseCallback = Box<dyn FnMut(&str, Duration) + Send>;
+type MistakeCallback = Box<dyn FnMut(usize, Duration) + Send>;
+type OutputCallback = Box<dyn FnMut(&str) + Send>;
+type ClipboardReader = Box<dyn FnMut() -> Option<String> + Send>;
+type ClipboardWriter = Box<dyn FnMut(&str) -> bool + Send>;
+type ScreenReader = Box<dyn FnMut() -> Option<Vec<u8>> + Send>;
+
+/// Counts of what happened while typing, accumulated over all texts typed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct TypingStats {
+    pub mistakes: usize,
+    pub corrections: usize,
+    pub long_pauses: usize,
+    pub thinking_pauses: usize,
+    /// Lines retyped because copying them showed they did not match the source.
+    pub lines_fixed: usize,
+    /// Wrong shortcuts pressed and taken back, see [`MishapConfig`](crate::mishaps::MishapConfig).
+    pub mishaps: usize,
+    /// Times the typist looked away for a while, see
+    /// [`DistractionConfig`](crate::distraction::DistractionConfig).
+    pub distractions: usize,
+    /// Breaks taken, see [`BreakConfig`](crate::breaks::BreakConfig).
+    pub breaks: usize,
+}
+
+/// A mistake the typist has made but not noticed yet.
+struct PendingMistake {
+    /// Index in the text where the output started to go wrong.
+    start: usize,
+    /// How many characters end up in the mistake buffer before it is noticed.
+    notice_after: usize,
+}
+
+/// Builds a [`HumanTypist`] with a custom configuration or keyboard layout.
+#[derive(Default)]
+pub struct HumanTypistBuilder {
+    config: Option<TypingConfig>,
+    keyboard: Option<KeyboardLayout>,
+    controls: Option<Arc<TypingControls>>,
+    dry_run: bool,
+    seed: Option<u64>,
+    instant: bool,
+    backend: Option<Box<dyn KeyboardBackend>>,
+    on_progress: Option<ProgressCallback>,
+    on_key_event: Option<KeyEventCallback>,
+    on_break: Option<BreakCallback>,
+    on_pause: Option<PauseCallback>,
+    on_mistake: Option<MistakeCallback>,
+    on_output: Option<OutputCallback>,
+    keystroke_log: Option<Box<dyn Write + Send>>,
+    clipboard: Option<ClipboardReader>,
+    clipboard_writer: Option<ClipboardWriter>,
+    screen: Option<ScreenReader>,
+    app_switch: Option<Arc<AppSwitch>>,
+}
+
+impl HumanTypistBuilder {
+    pub fn config(mut self, config: TypingConfig) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    pub fn keyboard(mut self, keyboard: KeyboardLayout) -> Self {
+        self.keyboard = Some(keyboard);
+        self
+    }
+
+    pub fn controls(mut self, controls: Arc<TypingControls>) -> Self {
+        self.controls = Some(controls);
+        self
+    }
+
+    /// Prints the simulated keystrokes and pauses instead of typing them, or hands them
+    /// to [`HumanTypistBuilder::on_output`].
+    ///
+    /// Delays are not actually waited for, so a dry run finishes immediately.
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Makes every random choice reproducible for the same `seed`.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Sends keystrokes without waiting in between, e.g. to run a [`MockBackend`] in
+    /// tests. Delays still count towards [`HumanTypist::elapsed`].
+    ///
+    /// [`MockBackend`]: crate::backend::MockBackend
+    pub fn instant(mut self, instant: bool) -> Self {
+        self.instant = instant;
+        self
+    }
+
+    /// Sends keystrokes through `backend` instead of enigo.
+    pub fn backend<B: KeyboardBackend + 'static>(mut self, backend: B) -> Self {
+        self.backend = Some(Box::new(backend));
+        self
+    }
+
+    /// Like [`HumanTypistBuilder::backend`], for a backend picked at run time.
+    pub fn boxed_backend(mut self, backend: Box<dyn KeyboardBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+
+    /// Calls `callback` with the typing progress before each character and once more
+    /// when the text is done.
+    pub fn on_progress<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&Progress) + Send + 'static,
+    {
+        self.on_progress = Some(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` with every key event right after it is sent.
+    pub fn on_key_event<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&KeyEvent) + Send + 'static,
+    {
+        self.on_key_event = Some(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` when a break starts and when it ends, see
+    /// [`BreakConfig`](crate::breaks::BreakConfig).
+    pub fn on_break<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(BreakEvent) + Send + 'static,
+    {
+        self.on_break = Some(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` with the reason and length of every pause as it starts, such as
+    /// a thinking pause or a `{{pause}}` directive.
+    pub fn on_pause<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&str, Duration) + Send + 'static,
+    {
+        self.on_pause = Some(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` with the position in the text of every mistake as it is made,
+    /// whether it gets corrected or not, and the time spent typing until then.
+    pub fn on_mistake<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(usize, Duration) + Send + 'static,
+    {
+        self.on_mistake = Some(Box::new(callback));
+        self
+    }
+
+    /// Calls `callback` with every line of the dry run and every warning, such as a
+    /// keystroke that could not be sent, instead of printing them to standard output.
+    pub fn on_output<F>(mut self, callback: F) -> Self
+    where
+        F: FnMut(&str) + Send + 'static,
+    {
+        self.on_output = Some(Box::new(callback));
+        self
+    }
+
+    /// Switches to the app profiles handed over through `switch`, see
+    /// [`AppWatcher`](crate::apps::AppWatcher).
+    pub fn app_switch(mut self, switch: Arc<AppSwitch>) -> Self {
+        self.app_switch = Some(switch);
+        self
+    }
+
+    /// Writes every key event to `log` as a line of JSON, see [`KeyEvent`].
+    pub fn keystroke_log<W: Write + Send + 'static>(mut self, log: W) -> Self {
+        self.keystroke_log = Some(Box::new(log));
+        self
+    }
+
+    /// Reads the clipboard with `reader`, to check the lines typed in code mode when
+    /// [`CodeModeConfig::verify_lines`] is set.
+    ///
+    /// [`CodeModeConfig::verify_lines`]: crate::code::CodeModeConfig::verify_lines
+    pub fn clipboard<F>(mut self, reader: F) -> Self
+    where
+        F: FnMut() -> Option<String> + Send + 'static,
+    {
+        self.clipboard = Some(Box::new(reader));
+        self
+    }
+
+    /// Puts text on the clipboard with `writer`, which returns whether it worked, to
+    /// paste blocks instead of typing them, see [`PasteConfig`]. Without one every
+    /// block is typed.
+    ///
+    /// [`PasteConfig`]: crate::paste::PasteConfig
+    pub fn clipboard_writer<F>(mut self, writer: F) -> Self
+    where
+        F: FnMut(&str) -> bool + Send + 'static,
+    {
+        self.clipboard_writer = Some(Box::new(writer));
+        self
+    }
+
+    /// Takes snapshots of the screen with `reader`, to see when the prompt is back in
+    /// shell mode when [`ShellConfig::prompt`] is set.
+    ///
+    /// [`ShellConfig::prompt`]: crate::shell::ShellConfig::prompt
+    pub fn screen<F>(mut self, reader: F) -> Self
+    where
+        F: FnMut() -> Option<Vec<u8>> + Send + 'static,
+    {
+        self.screen = Some(Box::new(reader));
+        self
+    }
+
+    pub fn build(self) -> HumanTypist {
+        let mut config = self.config.unwrap_or_default();
+        if let Some(personal) = config.personal.clone() {
+            personal.apply_corrections(&mut config);
+        }
+
+        HumanTypist {
+            config,
+            keyboard: self.keyboard.unwrap_or_default(),
+            rng: match self.seed {
+                Some(seed) => StdRng::seed_from_u64(seed),
+                None => StdRng::from_entropy(),
+            },
+            instant: self.instant,
+            backend: if self.dry_run {
+                None
+            } else {
+                Some(
+                    self.backend
+                        .unwrap_or_else(|| Box::new(EnigoBackend::new())),
+                )
+            },
+            controls: self.controls.unwrap_or_default(),
+            position: 0,
+            elapsed: Duration::ZERO,
+            mistake_buffer: Vec::new(),
+            pending: None,
+            retype_until: 0,
+            quick_retype_until: 0,
+            fatigue: Fatigue::default(),
+            bursts: Bursts::default(),
+            on_progress: self.on_progress,
+            on_key_event: self.on_key_event,
+            on_break: self.on_break,
+            on_pause: self.on_pause,
+            on_mistake: self.on_mistake,
+            on_output: self.on_output,
+            stats: TypingStats::default(),
+            keystroke_log: self.keystroke_log,
+            last_event_at: Duration::ZERO,
+            correcting: false,
+            held: None,
+            hold_credit: 0,
+            releases: Vec::new(),
+            literals: Vec::new(),
+            auto_closed: Vec::new(),
+            clipboard: self.clipboard,
+            clipboard_writer: self.clipboard_writer,
+            screen: self.screen,
+            directives: Vec::new(),
+            next_directive: 0,
+            speed: 1.0,
+            lines_since_scroll: 0,
+            paragraphs_since_save: 0,
+            last_save_at: Duration::ZERO,
+            next_distraction_at: None,
+            last_break_at: Duration::ZERO,
+            pacing: None,
+            app_switch: self.app_switch,
+            app_base: None,
+            app_speed: 1.0,
+        }
+    }
+}
+
+impl HumanTypist {
+    pub fn new() -> Self {
+        HumanTypist::builder().build()
+    }
+
+    pub fn builder() -> HumanTypistBuilder {
+        HumanTypistBuilder::default()
+    }
+
+    pub fn controls(&self) -> Arc<TypingControls> {
+        Arc::clone(&self.controls)
+    }
+
+    /// Index of the character currently being typed by [`HumanTypist::type_text`].
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// Total time spent in typing delays so far, simulated or real.
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn stats(&self) -> &TypingStats {
+        &self.stats
+    }
+
+    /// Paces the texts typed from now on, `chars` characters in all, to be done within
+    /// `budget`, see [`Pacing`].
+    pub fn finish_in(&mut self, budget: Duration, chars: usize) {
+        let keystroke = self.config.base_delay.mean();
+        self.pacing = Some(Pacing::new(self.elapsed, budget, chars, keystroke));
+    }
+
+    /// Presses Alt+Tab to switch to the previously focused window.
+    pub fn switch_window(&mut self) {
+        self.press_key_down(Key::Alt);
+        self.sleep_random(BACKSPACE_BURST_DELAY);
+        self.press_key(Key::Tab);
+        self.sleep_random(BACKSPACE_BURST_DELAY);
+        self.press_key_up(Key::Alt);
+    }
+
+    /// Presses Backspace `count` times in a quick burst.
+    pub fn erase(&mut self, count: usize) {
+        let correcting = std::mem::replace(&mut self.correcting, true);
+        for _ in 0..count {
+            self.press_key(Key::Backspace);
+            self.sleep_random(BACKSPACE_BURST_DELAY);
+        }
+        self.correcting = correcting;
+    }
+
+    pub fn type_text(&mut self, text: &str) -> TypingOutcome {
+        self.type_text_from(text, 0)
+    }
+
+    /// Types `text` starting at character index `start`, assuming everything before it
+    /// is already on screen.
+    pub fn type_text_from(&mut self, text: &str, start: usize) -> TypingOutcome {
+        self.type_script_from(&Script::plain(text), start)
+    }
+
+    /// Types the text of `script`, running its directives along the way.
+    pub fn type_script(&mut self, script: &Script) -> TypingOutcome {
+        self.type_script_from(script, 0)
+    }
+
+    /// Like [`HumanTypist::type_text_from`] for a script. Speed directives before
+    /// `start` still apply; when resuming mid-text, those right at `start` are taken as
+    /// already run.
+    pub fn type_script_from(&mut self, script: &Script, start: usize) -> TypingOutcome {
+        let chars: Vec<char> = script.text.chars().collect();
+        let mut i = start;
+        self.directives = script.directives.clone();
+        self.next_directive = self
+            .directives
+            .iter()
+            .take_while(|&&(at, _)| at < start || (start > 0 && at == start))
+            .count();
+        self.speed = 1.0;
+        for (_, directive) in &self.directives[..self.next_directive] {
+            if let Directive::Speed(factor) = directive {
+                self.speed = *factor;
+            }
+        }
+        if let Some(pacing) = &mut self.pacing {
+            pacing.start_text(start);
+        }
+        self.mistake_buffer.clear();
+        self.pending = None;
+        self.retype_until = 0;
+        self.quick_retype_until = 0;
+        self.auto_closed.clear();
+        self.literals = match &self.config.code {
+            Some(code) => code::string_literals(&chars, code.language),
+            None => Vec::new(),
+        };
+
+        while i < chars.len() {
+            if let Some(jump) = self.controls.take_jump() {
+                i = self.jump(&chars, i, jump);
+                continue;
+            }
+            self.switch_app();
+            if self.directive_due(i) {
+                // A directive may move the cursor, so mistakes get fixed before it runs
+                if self.pending.is_some() {
+                    i = self.correct_pending(&chars, i - 1);
+                    continue;
+                }
+                if let Some(end) = self.run_directives(i) {
+                    i = self.paste(&chars, i, end);
+                    continue;
+                }
+            }
+            if i > start && chars[i - 1] == '\n' && self.pending.is_none() {
+                i = self.fix_indent(&chars, i);
+            }
+            if let Some(end) = self.long_block(&chars, i) {
+                let pasted_to = self.paste(&chars, i, end);
+                if pasted_to > i {
+                    i = pasted_to;
+                    continue;
+                }
+            }
+            let Some(&c) = chars.get(i) else {
+                break;
+            };
+            self.position = i;
+            self.wait_while_paused();
+            self.report_progress(chars.len());
+
+            if self.controls.is_aborted() {
+                self.release_modifiers();
+                return TypingOutcome::Aborted;
+            }
+
+            match c {
+                '\n' => {
+                    self.verify_line(&chars, i);
+                    if !self.auto_closed.is_empty() {
+                        // Enter replaces the closers left after the cursor, which the
+                        // text types on a later line anyway
+                        self.press_key_down(Key::Shift);
+                        self.press_key(Key::End);
+                        self.press_key_up(Key::Shift);
+                        self.auto_closed.clear();
+                    }
+                    let message_delay = self
+                        .config
+                        .chat
+                        .as_ref()
+                        .map(|chat| chat.message_delay.clone());
+                    let next_message = message_delay
+                        .as_ref()
+                        .and_then(|_| chat::next_message(&chars, i));
+                    let wait = self.line_wait(&chars, i);
+                    let line_end = i.checked_sub(1).is_some_and(|k| chars[k] != '\n');
+                    let paragraph_end = chars.get(i + 1) == Some(&'\n')
+                        && i.checked_sub(1).is_none_or(|k| chars[k] != '\n');
+                    match (message_delay, next_message) {
+                        _ if self.config.shell.is_some() || wait.is_some() => {
+                            let shell = self.config.shell.clone();
+                            let prompt = shell.as_ref().and_then(|shell| shell.prompt.clone());
+                            // The screen from before Enter, so even a quick command shows
+                            // up as a change
+                            let before = match (&prompt, wait) {
+                                (Some(_), None) => self.capture_screen(),
+                                _ => None,
+                            };
+                            self.press_key(Key::Return);
+                            match (wait, shell, prompt.zip(before)) {
+                                (Some(millis), _, _) => self.pause(millis, "wait"),
+                                (None, _, Some((prompt, before))) => {
+                                    self.wait_for_prompt(&prompt, before)
+                                }
+                                (None, Some(shell), None) => {
+                                    self.pause_random(shell.command_delay, "command delay")
+                                }
+                                (None, None, None) => {}
+                            }
+                            if self.config.shell.is_some() {
+                                // Blank lines would only bring up another prompt
+                                i = shell::next_command(&chars, i) - 1;
+                            }
+                        }
+                        (Some(delay), Some(next)) => {
+                            self.press_key(Key::Return);
+                            self.pause_random(delay, "next message");
+                            // The blank lines between messages are not typed
+                            i = next - 1;
+                        }
+                        (delay, _) => {
+                            // In a chat message Enter would send it before it is finished
+                            let newline = self.config.newline_key.unwrap_or(match delay {
+                                Some(_) => NewlineKey::ShiftEnter,
+                                None => NewlineKey::Enter,
+                            });
+                            let chord = newline.chord();
+                            for &modifier in &chord.modifiers {
+                                self.press_key_down(modifier);
+                            }
+                            self.press_key(chord.key);
+                            for &modifier in chord.modifiers.iter().rev() {
+                                self.press_key_up(modifier);
+                            }
+                            self.stats.thinking_pauses += 1;
+                            let next_line: String =
+                                chars[i + 1..].iter().take_while(|&&c| c != '\n').collect();
+                            match &self.config.code {
+                                Some(code) if code.language.starts_function(&next_line) => {
+                                    self.pause_random(code.function_pause.clone(), "function pause")
+                                }
+                                _ => self.pause_random(
+                                    self.config.thinking_delay.clone(),
+                                    "thinking pause",
+                                ),
+                            }
+                        }
+                    }
+                    self.follow_viewport();
+                    self.autosave(paragraph_end);
+                    self.keep_pace(&chars, i);
+                    if paragraph_end {
+                        self.take_break(&chars, i);
+                    }
+                    self.step(&chars, i, line_end, paragraph_end);
+                }
+                '\r' => {
+                    // Skip carriage returns
+                    i += 1;
+                    continue;
+                }
+                _ => {
+                    let starts_word = !c.is_whitespace()
+                        && i.checked_sub(1).is_none_or(|k| chars[k].is_whitespace());
+                    if starts_word && self.pending.is_none() && i >= self.retype_until {
+                        self.read_ahead(&chars[i..]);
+                    }
+
+                    // Thinking pause on whitespace
+                    if self.rng.gen_ratio(1, 100) && c.is_whitespace() {
+                        self.stats.thinking_pauses += 1;
+                        self.pause_random(self.config.thinking_delay.clone(), "thinking pause");
+                    }
+
+                    let closed = self.close_auto_pair(&chars, i);
+                    if let Some(extra) = closed {
+                        i += extra;
+                    } else if self.pending.is_some() {
+                        self.press_char(c, false);
+                        self.mistake_buffer.push(c);
+                    } else if i < self.retype_until || self.types_exactly(&chars, i) {
+                        self.press_char(c, false);
+                    } else if let Some(len) = self.substitute_word(&chars, i) {
+                        i += len - 1;
+                    } else if self.should_omit(c) {
+                        // Skip the letter and only notice a few characters later
+                        if self.notices_mistake() {
+                            let lag = self.rng.gen_range(OMISSION_NOTICE_CHARS);
+                            self.start_pending(i, &[], lag);
+                        }
+                    } else {
+                        match chars.get(i + 1) {
+                            Some(&next) if self.should_transpose(c, next) => {
+                                self.type_transposed(c, next);
+                                i += 1;
+                            }
+                            Some(&next) if self.should_slip_shift(c, next) => {
+                                self.type_shift_slip(c, next);
+                                i += 1;
+                            }
+                            _ => self.type_character(c),
+                        }
+                    }
+                    if closed.is_none() && self.pending.is_none() {
+                        self.open_auto_pair(&chars, i);
+                    }
+
+                    // Long pause after punctuation (after typing the character)
+                    if self.rng.gen_ratio(self.config.long_pause_probability, 100)
+                        && ".,?!;:".contains(chars[i])
+                    {
+                        self.stats.long_pauses += 1;
+                        self.pause_random(self.config.long_pause_delay.clone(), "long pause");
+                    }
+                    if chars[i] == ' ' {
+                        self.mishap(&chars, i);
+                        self.keep_pace(&chars, i);
+                        self.distraction();
+                        self.autosave(false);
+                    }
+                }
+            }
+
+            if i < self.quick_retype_until {
+                let millis = self.config.base_delay.sample(&mut self.rng) / 2;
+                self.sleep(millis);
+            } else {
+                let pair = chars.get(i + 1).map(|&next| (chars[i], next));
+                self.sleep_keystroke(pair);
+            }
+
+            // Mistakes are always noticed before the end of the line
+            if let Some(pending) = &self.pending {
+                let line_ends = matches!(chars.get(i + 1), None | Some('\n'));
+                if self.mistake_buffer.len() >= pending.notice_after || line_ends {
+                    i = self.correct_pending(&chars, i);
+                    continue;
+                }
+            }
+
+            i += 1;
+        }
+
+        if !self.controls.is_aborted() {
+            self.verify_line(&chars, chars.len());
+            let unsent = self.config.chat.is_some() || self.config.shell.is_some();
+            if unsent && chat::ends_unsent(&chars) {
+                self.press_key(Key::Return);
+            }
+            // A paste block cannot start at the very end, it would be empty
+            let _ = self.run_directives(chars.len());
+        }
+        if let Some(log) = &mut self.keystroke_log {
+            let _ = log.flush();
+        }
+        // Aborted after the last character was checked, which may not have gone out
+        if self.controls.is_aborted() {
+            self.release_modifiers();
+            return TypingOutcome::Aborted;
+        }
+        self.release_held();
+        self.finish_releases();
+        self.position = chars.len();
+        self.report_progress(chars.len());
+        TypingOutcome::Completed
+    }
+
+    /// Presses a key or chord, such as `Chord::ctrl('s')` or `Key::Escape`: its
+    /// modifiers go down one after the other, then the key, and the modifiers come up
+    /// again in reverse order.
+    pub fn press(&mut self, chord: impl Into<Chord>) {
+        let chord = chord.into();
+        for &modifier in &chord.modifiers {
+            self.press_key_down(modifier);
+            self.sleep_random(BACKSPACE_BURST_DELAY);
+        }
+        self.press_key(chord.key);
+        for &modifier in chord.modifiers.iter().rev() {
+            self.sleep_random(BACKSPACE_BURST_DELAY);
+            self.press_key_up(modifier);
+        }
+        self.sleep_keystroke(None);
+    }
+
+    /// Moves on to where `jump` from `i` lands, after fixing any pending mistake, and
+    /// returns the index to type from.
+    fn jump(&mut self, chars: &[char], i: usize, jump: Jump) -> usize {
+        let i = match self.pending {
+            Some(_) => self.correct_pending(chars, i - 1),
+            None => i,
+        };
+        let target = jump.target(chars, i);
+        if self.backend.is_none() {
+            self.output(format!(
+                "{} jump to {}",
+                format_timestamp(self.elapsed),
+                target
+            ));
+        }
+        // Directives skipped over do not run, but the speed they set still applies
+        self.next_directive = self
+            .directives
+            .iter()
+            .take_while(|&&(at, _)| at < target)
+            .count();
+        self.speed = self.directives[..self.next_directive]
+            .iter()
+            .rev()
+            .find_map(|(_, directive)| match directive {
+                Directive::Speed(factor) => Some(*factor),
+                _ => None,
+            })
+            .unwrap_or(1.0);
+        self.retype_until = 0;
+        self.quick_retype_until = 0;
+        self.auto_closed.clear();
+        if let Some(pacing) = &mut self.pacing {
+            pacing.start_text(target);
+        }
+        target
+    }
+
+    fn directive_due(&self, i: usize) -> bool {
+        self.directives
+            .get(self.next_directive)
+            .is_some_and(|&(at, _)| at <= i)
+    }
+
+    /// Runs the directives that come before the character at `i`, up to a paste
+    /// directive, whose end it returns.
+    fn run_directives(&mut self, i: usize) -> Option<usize> {
+        while self.directive_due(i) && !self.controls.is_aborted() {
+            let (_, directive) = self.directives[self.next_directive].clone();
+            self.next_directive += 1;
+            match directive {
+                Directive::Paste { end } => return Some(end),
+                Directive::Pause(millis) => self.pause(millis, "pause"),
+                // Waited for once the line is done, see `line_wait`
+                Directive::Wait(_) => {}
+                Directive::Key(chord) => {
+                    self.press(chord);
+                    // Whatever the key did, the editor's closers are not where they were
+                    self.auto_closed.clear();
+                }
+                Directive::Speed(factor) => self.speed = factor,
+                Directive::Move(x, y) => {
+                    self.sleep_random(MOUSE_DELAY);
+                    self.emit(KeyAction::MouseMove { x, y }, false);
+                }
+                Directive::Click { at, double } => {
+                    self.sleep_random(MOUSE_DELAY);
+                    if let Some((x, y)) = at {
+                        self.emit(KeyAction::MouseMove { x, y }, false);
+                        self.sleep_random(MOUSE_DELAY);
+                    }
+                    self.emit(KeyAction::MouseClick, false);
+                    if double {
+                        self.sleep_random(DOUBLE_CLICK_GAP);
+                        self.emit(KeyAction::MouseClick, false);
+                    }
+                    // The click puts the cursor somewhere the editor's closers are not
+                    self.auto_closed.clear();
+                    self.sleep_random(MOUSE_DELAY);
+                }
+            }
+        }
+        None
+    }
+
+    /// The end of the paragraph starting at `i`, if it is long enough to be pasted.
+    fn long_block(&self, chars: &[char], i: usize) -> Option<usize> {
+        let min = self.config.paste.min_block_chars?;
+        let starts_block = i == 0 || (i >= 2 && chars[i - 1] == '\n' && chars[i - 2] == '\n');
+        if !starts_block || i >= chars.len() || self.pending.is_some() {
+            return None;
+        }
+        let end = paste::block_end(chars, i);
+        (end - i >= min).then_some(end)
+    }
+
+    /// Pastes the characters from `start` to `end` after a pause to find them and
+    /// returns `end`, or returns `start` to type them after all when the clipboard
+    /// cannot be written.
+    fn paste(&mut self, chars: &[char], start: usize, end: usize) -> usize {
+        if self.clipboard_writer.is_none() || end <= start {
+            return start;
+        }
+        let block: String = chars[start..end].iter().filter(|&&c| c != '\r').collect();
+        self.pause_random(self.config.paste.find_delay.clone(), "finding the snippet");
+        let written = self
+            .clipboard_writer
+            .as_mut()
+            .is_some_and(|write| write(&block));
+        if !written {
+            return start;
+        }
+        self.sleep_random(CLIPBOARD_DELAY);
+        self.press(Chord::ctrl('v'));
+        // The pasted text has no closers waiting after it
+        self.auto_closed.clear();
+        while self.directive_due(end.saturating_sub(1)) {
+            self.next_directive += 1;
+        }
+        end
+    }
+
+    /// Presses the save key once enough paragraphs were typed, or enough time went by,
+    /// since the last save. `paragraph_end` tells that a paragraph was just finished.
+    fn autosave(&mut self, paragraph_end: bool) {
+        let Some(autosave) = self.config.autosave.clone() else {
+            return;
+        };
+        if paragraph_end {
+            self.paragraphs_since_save += 1;
+        }
+        let paragraphs = autosave
+            .every_paragraphs
+            .is_some_and(|every| self.paragraphs_since_save >= every.max(1));
+        let time = autosave
+            .every
+            .is_some_and(|every| self.elapsed - self.last_save_at >= every);
+        // Nobody saves halfway through fixing a typo
+        if !(paragraphs || time) || self.pending.is_some() {
+            return;
+        }
+        self.press(autosave.chord);
+        self.pause_random(autosave.pause, "save");
+        self.paragraphs_since_save = 0;
+        self.last_save_at = self.elapsed;
+    }
+
+    /// Now and then presses a wrong shortcut after the word before `i`, pauses, and
+    /// takes it back.
+    fn mishap(&mut self, chars: &[char], i: usize) {
+        let Some(mishaps) = self.config.mishaps.clone() else {
+            return;
+        };
+        let after_word = i.checked_sub(1).is_some_and(|k| chars[k].is_alphanumeric());
+        if !after_word
+            || mishaps.allowed.is_empty()
+            || self.pending.is_some()
+            || !self.rng.gen_ratio(1, mishaps.one_in_words.max(1))
+        {
+            return;
+        }
+        let mishap = mishaps.allowed[self.rng.gen_range(0..mishaps.allowed.len())];
+        self.stats.mishaps += 1;
+        self.press(mishap.chord());
+        self.pause_random(mishaps.pause.clone(), "mishap");
+        self.press(mishaps.recovery(mishap));
+    }
+
+    /// Takes a break after the paragraph ending at the newline at `i` once one is due,
+    /// reporting the progress up to it first so it is kept if typing stops there.
+    fn take_break(&mut self, chars: &[char], i: usize) {
+        let Some(breaks) = self.config.breaks.clone() else {
+            return;
+        };
+        if self.elapsed - self.last_break_at < breaks.every || self.pending.is_some() {
+            return;
+        }
+        self.stats.breaks += 1;
+        self.position = i + 1;
+        self.report_progress(chars.len());
+        let millis = breaks.duration.sample(&mut self.rng);
+        if let Some(callback) = &mut self.on_break {
+            callback(BreakEvent::Started(Duration::from_millis(millis)));
+        }
+        self.pause(millis, "break");
+        if let Some(callback) = &mut self.on_break {
+            callback(BreakEvent::Ended);
+        }
+        self.last_break_at = self.elapsed;
+    }
+
+    /// Pauses after the line ending at `i` when it ends a step, until resumed through the
+    /// controls. Dry runs only note where they would have stopped.
+    fn step(&mut self, chars: &[char], i: usize, line_end: bool, paragraph_end: bool) {
+        let stop = match self.config.step {
+            None => false,
+            Some(Step::Line) => line_end,
+            Some(Step::Paragraph) => paragraph_end,
+        };
+        // Nothing left to show after the last line
+        let rest_blank = chars[i + 1..].iter().all(|c| c.is_whitespace());
+        if !stop || rest_blank || self.pending.is_some() {
+            return;
+        }
+        self.position = i + 1;
+        self.report_progress(chars.len());
+        if self.backend.is_none() {
+            self.output(format!("{} step", format_timestamp(self.elapsed)));
+            return;
+        }
+        self.controls.pause(PauseReason::Step);
+        self.wait_while_paused();
+    }
+
+    /// Checks the pace after the word before `i`, pausing at the end of a sentence if
+    /// there is time to spare.
+    fn keep_pace(&mut self, chars: &[char], i: usize) {
+        let Some(pacing) = &mut self.pacing else {
+            return;
+        };
+        pacing.update(i + 1, self.elapsed);
+        let sentence_end =
+            chars[i] == '\n' || i.checked_sub(1).is_some_and(|k| ".!?".contains(chars[k]));
+        if sentence_end && self.pending.is_none() {
+            let millis = pacing.take_slack(self.rng.gen_range(0.6..1.0));
+            if millis > 0 {
+                self.pause(millis, "pacing pause");
+            }
+        }
+    }
+
+    /// Looks away from the text for a while once the next distraction is due.
+    fn distraction(&mut self) {
+        let Some(distraction) = self.config.distraction.clone() else {
+            return;
+        };
+        if distraction.per_hour <= 0.0 || self.pending.is_some() {
+            return;
+        }
+        let due = match self.next_distraction_at {
+            Some(due) => due,
+            None => self.elapsed + self.distraction_gap(distraction.per_hour),
+        };
+        self.next_distraction_at = Some(due);
+        if self.elapsed < due {
+            return;
+        }
+
+        self.stats.distractions += 1;
+        let action = distraction
+            .actions
+            .choose(&mut self.rng)
+            .copied()
+            .unwrap_or(DistractionAction::Idle);
+        let millis = distraction.duration.sample(&mut self.rng);
+        match action {
+            DistractionAction::Idle => self.pause(millis, "distraction"),
+            DistractionAction::Wiggle => {
+                self.wiggle_mouse();
+                self.pause(millis, "distraction");
+                self.wiggle_mouse();
+            }
+            DistractionAction::SwitchWindow => {
+                self.switch_window();
+                self.pause(millis, "distraction");
+                self.switch_window();
+                self.sleep_random(MOUSE_DELAY);
+            }
+        }
+        self.next_distraction_at = Some(self.elapsed + self.distraction_gap(distraction.per_hour));
+    }
+
+    /// Time until the next distraction. They come at random, so the gaps between them
+    /// are exponential.
+    fn distraction_gap(&mut self, per_hour: f64) -> Duration {
+        let hours = -(1.0 - self.rng.gen::<f64>()).ln() / per_hour;
+        Duration::from_secs_f64(hours * 3600.0)
+    }
+
+    /// Moves the mouse a little back and forth.
+    fn wiggle_mouse(&mut self) {
+        for _ in 0..self.rng.gen_range(3..7) {
+            let dx = self.rng.gen_range(WIGGLE_STEP);
+            let dy = self.rng.gen_range(WIGGLE_STEP);
+            self.emit(KeyAction::MouseNudge { dx, dy }, false);
+            self.sleep_random(WIGGLE_STEP_DELAY);
+        }
+    }
+
+    /// Scrolls along once every `viewport.every_lines` lines, if set.
+    fn follow_viewport(&mut self) {
+        let Some(viewport) = self.config.viewport.clone() else {
+            return;
+        };
+        self.lines_since_scroll += 1;
+        if self.lines_since_scroll < viewport.every_lines.max(1) {
+            return;
+        }
+        self.lines_since_scroll = 0;
+        match viewport.action {
+            ViewportAction::Scroll => {
+                self.sleep_random(MOUSE_DELAY);
+                let lines = viewport.scroll_lines as i32;
+                self.emit(KeyAction::MouseScroll { lines }, false);
+                self.sleep_random(MOUSE_DELAY);
+            }
+            ViewportAction::CtrlEnd => self.press(Chord::key(Key::End).with(Key::Control)),
+        }
+    }
+
+    fn capture_screen(&mut self) -> Option<Vec<u8>> {
+        self.screen.as_mut().and_then(|capture| capture())
+    }
+
+    /// Waits until the screen has changed from `before` and then stayed the same for
+    /// `prompt.settle`, or for `prompt.timeout` at most.
+    fn wait_for_prompt(&mut self, prompt: &PromptDetection, before: Vec<u8>) {
+        if self.backend.is_none() {
+            self.output(format!(
+                "{} waiting for prompt",
+                format_timestamp(self.elapsed)
+            ));
+        }
+        self.release_held();
+        let started = self.elapsed;
+        let mut last = before;
+        let mut changed_at = None;
+        while !self.controls.is_aborted() && self.elapsed - started < prompt.timeout {
+            self.sleep(PROMPT_POLL_INTERVAL);
+            let Some(now) = self.capture_screen() else {
+                break;
+            };
+            if now != last {
+                changed_at = Some(self.elapsed);
+                last = now;
+            } else if changed_at.is_some_and(|at| self.elapsed - at >= prompt.settle) {
+                break;
+            }
+        }
+        if let Some(fatigue) = &self.config.fatigue {
+            self.fatigue.rest(fatigue, self.elapsed - started);
+        }
+    }
+
+    /// The time the last wait directive on the line ending with the newline at `i` asks
+    /// for after it.
+    fn line_wait(&self, chars: &[char], i: usize) -> Option<u64> {
+        let line_start = chars[..i]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |k| k + 1);
+        self.directives
+            .iter()
+            .filter(|&&(at, _)| (line_start..=i).contains(&at))
+            .rev()
+            .find_map(|(_, directive)| match directive {
+                Directive::Wait(millis) => Some(*millis),
+                _ => None,
+            })
+    }
+
+    /// Sends logged key events again at the same times they were first sent.
+    pub fn replay(&mut self, events: &[KeyEvent]) -> TypingOutcome {
+        for event in events {
+            let due = Duration::from_millis(event.at_ms);
+            self.sleep(due.saturating_sub(self.elapsed).as_millis() as u64);
+            self.wait_while_paused();
+
+            if self.controls.is_aborted() {
+                self.release_modifiers();
+                return TypingOutcome::Aborted;
+            }
+
+            self.correcting = event.correction;
+            self.emit(event.action, event.mistake);
+        }
+
+        self.correcting = false;
+        if self.controls.is_aborted() {
+            self.release_modifiers();
+            return TypingOutcome::Aborted;
+        }
+        TypingOutcome::Completed
+    }
+
+    /// Makes up for the indentation the editor adds by itself at the start of the line
+    /// at `i`, and returns the index to go on typing from.
+    fn fix_indent(&mut self, chars: &[char], i: usize) -> usize {
+        let indent = chars[i..]
+            .iter()
+            .take_while(|&&c| c == ' ' || c == '\t')
+            .count();
+        match self.config.auto_indent {
+            AutoIndent::Off => i,
+            // Strict whitespace cannot trust the editor's indentation
+            AutoIndent::Strip if !self.strict_whitespace() => i + indent,
+            AutoIndent::Strip | AutoIndent::Home => {
+                self.press_key_down(Key::Shift);
+                self.press_key(Key::Home);
+                self.press_key_up(Key::Shift);
+                self.sleep_keystroke(None);
+                if indent == 0 {
+                    // Typing over the selection clears it, even when the editor left none
+                    self.press_char(' ', false);
+                    self.sleep_keystroke(None);
+                    self.press_key(Key::Backspace);
+                    self.sleep_keystroke(None);
+                }
+                i
+            }
+        }
+    }
+
+    /// Remembers the closer the editor inserts after the opener just typed at `i`.
+    fn open_auto_pair(&mut self, chars: &[char], i: usize) {
+        let opener = chars[i];
+        let rule = self.config.auto_close.rule(opener);
+        let Some(closer) = code::closer(opener).filter(|_| rule != AutoClose::Off) else {
+            return;
+        };
+        // Editors do not pair a quote typed right after a word, like the one in "don't"
+        if closer == opener && i.checked_sub(1).is_some_and(|k| chars[k].is_alphanumeric()) {
+            return;
+        }
+        self.auto_closed.push((closer, rule));
+    }
+
+    /// Gets past the closer the editor inserted, if the character at `i` is that closer,
+    /// and returns how many more characters of the text that took care of.
+    fn close_auto_pair(&mut self, chars: &[char], i: usize) -> Option<usize> {
+        let &(closer, rule) = self.auto_closed.last()?;
+        if chars[i] != closer || self.pending.is_some() {
+            return None;
+        }
+        self.auto_closed.pop();
+
+        let line_rest = chars[i..].iter().take_while(|&&c| c != '\n').count();
+        let only_closers = chars[i + 1..i + line_rest].iter().eq(self
+            .auto_closed
+            .iter()
+            .rev()
+            .map(|(closer, _)| closer));
+        match rule {
+            AutoClose::Off | AutoClose::TypeOver => {
+                self.press_char(closer, false);
+                Some(0)
+            }
+            AutoClose::End if only_closers => {
+                self.press_key(Key::End);
+                self.auto_closed.clear();
+                Some(line_rest - 1)
+            }
+            AutoClose::Skip | AutoClose::End => {
+                self.press_key(Key::Right);
+                Some(0)
+            }
+        }
+    }
+
+    /// Whether the character at `i` is typed without any chance of a mistake: all of
+    /// them when typing exactly, and in code mode whitespace, string literals unless
+    /// configured not to and, with strict whitespace, the characters next to whitespace.
+    fn types_exactly(&self, chars: &[char], i: usize) -> bool {
+        if self.config.exact {
+            return true;
+        }
+        let Some(code) = &self.config.code else {
+            return false;
+        };
+        let next_to_whitespace = [i.checked_sub(1), Some(i + 1)]
+            .into_iter()
+            .flatten()
+            .any(|k| chars.get(k).is_some_and(|c| c.is_whitespace()));
+        self.literals
+            .get(i)
+            .is_some_and(|&literal| literal && !code.mistakes_in_strings)
+            || chars[i].is_whitespace()
+            || (next_to_whitespace && self.strict_whitespace())
+    }
+
+    /// Whether whitespace has to come out exactly as in the source, see
+    /// [`CodeModeConfig::strict_whitespace`](crate::code::CodeModeConfig::strict_whitespace).
+    fn strict_whitespace(&self) -> bool {
+        self.config
+            .code
+            .as_ref()
+            .is_some_and(|code| code.strict_whitespace || code.language.indentation_sensitive())
+    }
+
+    /// Selects and copies the line of `chars` that ends at `end` and, if it does not
+    /// match the source, retypes it over the selection. Only in strict whitespace mode
+    /// with line verification on and a clipboard to read.
+    fn verify_line(&mut self, chars: &[char], end: usize) {
+        let verify = self
+            .config
+            .code
+            .as_ref()
+            .is_some_and(|code| code.verify_lines);
+        if !verify || !self.strict_whitespace() || self.clipboard.is_none() {
+            return;
+        }
+        let start = chars[..end]
+            .iter()
+            .rposition(|&c| c == '\n')
+            .map_or(0, |k| k + 1);
+        let line: String = chars[start..end].iter().collect();
+        // Copying an empty selection copies the whole line in some editors
+        if line.trim().is_empty() {
+            return;
+        }
+
+        let mut copied = self.copy_to_line_start();
+        // Smart Home stops at the indentation first, pressing it again goes on to column 0
+        if copied.as_deref() == Some(line.trim_start()) && line.trim_start() != line {
+            copied = self.copy_to_line_start();
+        }
+        if copied.is_none_or(|copied| copied == line) {
+            self.press_key(Key::End);
+            self.sleep_keystroke(None);
+            return;
+        }
+
+        self.stats.lines_fixed += 1;
+        self.pause_random(self.config.correction_delay.clone(), "correction delay");
+        let correcting = std::mem::replace(&mut self.correcting, true);
+        self.press_key(Key::Backspace);
+        let mut previous = None;
+        for c in line.chars() {
+            self.sleep_keystroke(previous.map(|p| (p, c)));
+            self.press_char(c, false);
+            previous = Some(c);
+        }
+        self.correcting = correcting;
+    }
+
+    /// Extends the selection to the start of the line, copies it and returns what the
+    /// clipboard holds then.
+    fn copy_to_line_start(&mut self) -> Option<String> {
+        self.press_key_down(Key::Shift);
+        self.press_key(Key::Home);
+        self.press_key_up(Key::Shift);
+        self.sleep_keystroke(None);
+        self.press_key_down(Key::Control);
+        self.press_key(Key::Char('c'));
+        self.press_key_up(Key::Control);
+        self.sleep_random(CLIPBOARD_DELAY);
+        let copied = (self.clipboard.as_mut()?)()?;
+        Some(copied.trim_end_matches(['\r', '\n']).to_string())
+    }
+
+    /// Hesitates before the word at the start of `rest` if it takes reading ahead.
+    fn read_ahead(&mut self, rest: &[char]) {
+        let Some(config) = &self.config.read_ahead else {
+            return;
+        };
+        let word: String = rest.iter().take_while(|c| !c.is_whitespace()).collect();
+        if let Some(millis) = reading::hesitation(config, &word, &mut self.rng) {
+            self.pause(millis, "reading ahead");
+        }
+    }
+
+    /// Takes on the app profile the app switch asks for, if it asks for another one.
+    fn switch_app(&mut self) {
+        let Some(profile) = self.app_switch.as_ref().and_then(|switch| switch.take()) else {
+            return;
+        };
+        let (newline_key, auto_indent, exact) = *self.app_base.get_or_insert((
+            self.config.newline_key,
+            self.config.auto_indent,
+            self.config.exact,
+        ));
+        let profile = profile.unwrap_or_default();
+        self.config.newline_key = profile.newline_key.or(newline_key);
+        self.config.auto_indent = profile.auto_indent.unwrap_or(auto_indent);
+        self.config.exact = exact || profile.no_mistakes;
+        self.app_speed = profile.speed.unwrap_or(1.0);
+    }
+
+    fn report_progress(&mut self, total: usize) {
+        let Some(callback) = &mut self.on_progress else {
+            return;
+        };
+        // While a mistake is pending the output is only right up to where it started
+        let (position, erase) = match &self.pending {
+            Some(pending) => (pending.start, self.mistake_buffer.len()),
+            None => (self.position, 0),
+        };
+        callback(&Progress {
+            position,
+            erase,
+            total,
+            elapsed: self.elapsed,
+        });
+    }
+
+    fn start_pending(&mut self, start: usize, typed: &[char], lag: usize) {
+        self.mistake_buffer.extend_from_slice(typed);
+        self.pending = Some(PendingMistake {
+            start,
+            notice_after: typed.len() + lag,
+        });
+    }
+
+    /// Erases everything typed since the pending mistake, according to the correction
+    /// style, and returns the index to continue typing from. `last` is the index of the
+    /// last character typed.
+    fn correct_pending(&mut self, chars: &[char], last: usize) -> usize {
+        let start = match self.pending.take() {
+            Some(pending) => pending.start,
+            None => return last + 1,
+        };
+        self.stats.corrections += 1;
+        self.correcting = true;
+
+        self.pause_random(self.config.correction_delay.clone(), "correction delay");
+
+        // Word-level styles throw away the whole word the mistake is in
+        let word_start = (0..start)
+            .rev()
+            .find(|&k| chars[k].is_whitespace())
+            .map_or(0, |k| k + 1);
+        let resume_at = match self.config.correction_style {
+            CorrectionStyle::Backspace => {
+                for _ in 0..self.mistake_buffer.len() {
+                    self.press_key(Key::Backspace);
+                    self.sleep_keystroke(None);
+                }
+                start
+            }
+            CorrectionStyle::Word => {
+                self.erase(start - word_start + self.mistake_buffer.len());
+                word_start
+            }
+            CorrectionStyle::CtrlBackspace => {
+                let on_screen: String = chars[word_start..start]
+                    .iter()
+                    .chain(&self.mistake_buffer)
+                    .collect();
+                let words = on_screen.split_whitespace().count();
+                if words == 0 {
+                    // Ctrl+Backspace would take the previous word along with stray spaces
+                    self.erase(on_screen.chars().count());
+                }
+                for _ in 0..words {
+                    self.press_key_down(Key::Control);
+                    self.press_key(Key::Backspace);
+                    self.press_key_up(Key::Control);
+                    self.sleep_keystroke(None);
+                }
+                word_start
+            }
+            CorrectionStyle::SelectAndRetype => {
+                self.press_key_down(Key::Shift);
+                self.press_key(Key::Home);
+                self.press_key_up(Key::Shift);
+                self.sleep_keystroke(None);
+                (0..start)
+                    .rev()
+                    .find(|&k| chars[k] == '\n')
+                    .map_or(0, |k| k + 1)
+            }
+        };
+
+        self.correcting = false;
+        self.mistake_buffer.clear();
+        self.retype_until = last + 1;
+        if self.config.correction_style != CorrectionStyle::Backspace {
+            self.quick_retype_until = last + 1;
+        }
+        resume_at
+    }
+
+    fn wait_while_paused(&mut self) {
+        if self.controls.is_paused() {
+            self.release_held();
+            for (_, key) in std::mem::take(&mut self.releases) {
+                self.emit(KeyAction::Up { key }, false);
+            }
+        }
+        while self.controls.is_paused() && !self.controls.is_aborted() {
+            thread::sleep(PAUSE_POLL_INTERVAL);
+        }
+    }
+
+    /// Sleeps for a random duration, waking up early on abort.
+    fn sleep_random(&mut self, delay: impl Into<Delay>) -> u64 {
+        let millis = delay.into().sample(&mut self.rng);
+        self.sleep(millis);
+        millis
+    }
+
+    /// Waits between two keystrokes. `pair` holds the characters typed before and after
+    /// the delay, when they are known.
+    fn sleep_keystroke(&mut self, pair: Option<(char, char)>) {
+        // A measured delay for the pair already includes how easy it is to type
+        let measured = self
+            .config
+            .personal
+            .as_ref()
+            .and_then(|personal| match pair {
+                Some((first, second)) => personal.latency(first, second),
+                None => personal.keystroke,
+            });
+        let millis = match measured {
+            Some(latency) => Delay::from(latency).sample(&mut self.rng),
+            None => self.config.base_delay.sample(&mut self.rng),
+        };
+        let mut factor = self.delay_factor();
+        if let (Some(code), Some((_, second))) = (&self.config.code, pair) {
+            if matches!(
+                CharClass::of(second),
+                CharClass::Symbol | CharClass::Punctuation
+            ) {
+                factor *= code.symbol_factor;
+            }
+        }
+
+        if let (Some((first, second)), None) = (pair, measured) {
+            if let Some(bigrams) = &self.config.bigrams {
+                factor *= bigrams.factor(first, second);
+            }
+            if let Some(fingers) = &self.config.finger_timing {
+                factor *= self.keyboard.finger_factor(fingers, first, second);
+            }
+        }
+
+        let mut burst_pause = None;
+        if let Some(burst) = &self.config.burst {
+            if self.bursts.keystroke(burst, &mut self.rng) {
+                burst_pause = Some(burst.pause.clone());
+            }
+            factor *= burst.speed_factor;
+        }
+
+        let millis = (millis as f64 * factor) as u64;
+        self.fatigue.work(Duration::from_millis(millis));
+        // Part of the delay went by while the last key was held down
+        let held = std::mem::take(&mut self.hold_credit);
+        self.sleep(millis.saturating_sub(held));
+
+        if let Some(pause) = burst_pause {
+            self.pause_random(pause, "burst pause");
+        }
+    }
+
+    fn delay_factor(&self) -> f64 {
+        let fatigue = match &self.config.fatigue {
+            Some(fatigue) => self.fatigue.delay_factor(fatigue),
+            None => 1.0,
+        };
+        let warm_up = match &self.config.warm_up {
+            Some(warm_up) => warm_up.delay_factor(self.elapsed),
+            None => 1.0,
+        };
+        let time_of_day = match &self.config.time_of_day {
+            Some(time_of_day) => time_of_day.factor(self.elapsed),
+            None => 1.0,
+        };
+        let pace = self.pacing.as_ref().map_or(1.0, Pacing::factor);
+        fatigue * warm_up * time_of_day * pace
+            / (self.speed * self.app_speed * self.controls.speed())
+    }
+
+    /// Waits `millis` milliseconds, releasing the keys that are due in the meantime.
+    fn sleep(&mut self, millis: u64) {
+        let until = self.elapsed + Duration::from_millis(millis);
+        while let Some(index) = self.next_release(until) {
+            let (at, key) = self.releases.remove(index);
+            self.wait(at.saturating_sub(self.elapsed));
+            self.emit(KeyAction::Up { key }, false);
+        }
+        self.wait(until.saturating_sub(self.elapsed));
+    }
+
+    /// The release in `releases` that comes first, if it is due by `until`.
+    fn next_release(&self, until: Duration) -> Option<usize> {
+        (0..self.releases.len())
+            .min_by_key(|&i| self.releases[i].0)
+            .filter(|&i| self.releases[i].0 <= until)
+    }
+
+    fn wait(&mut self, duration: Duration) {
+        self.elapsed += duration;
+
+        if self.backend.is_some() && !self.instant {
+            let mut remaining = duration;
+            while !remaining.is_zero() && !self.controls.is_aborted() {
+                let step = remaining.min(PAUSE_POLL_INTERVAL);
+                thread::sleep(step);
+                remaining -= step;
+            }
+        }
+    }
+
+    /// Like [`HumanTypist::sleep_random`], but shows up in the dry-run output.
+    fn pause_random(&mut self, delay: impl Into<Delay>, reason: &str) {
+        let millis = delay.into().sample(&mut self.rng);
+        let pace = self.pacing.as_ref().map_or(1.0, Pacing::factor);
+        self.pause((millis as f64 * pace) as u64, reason);
+    }
+
+    /// Waits for `millis` milliseconds, letting fatigue recover. The dry run prints
+    /// the pause with `reason`.
+    pub fn pause(&mut self, millis: u64, reason: &str) {
+        // A key held through a pause would start repeating
+        self.release_held();
+        if self.backend.is_none() {
+            self.output(format!(
+                "{} {} {} ms",
+                format_timestamp(self.elapsed),
+                reason,
+                millis
+            ));
+        }
+        if let Some(callback) = &mut self.on_pause {
+            callback(reason, Duration::from_millis(millis));
+        }
+        self.sleep(millis);
+        if let Some(fatigue) = &self.config.fatigue {
+            self.fatigue.rest(fatigue, Duration::from_millis(millis));
+        }
+    }
+
+    fn press_char(&mut self, c: char, mistake: bool) {
+        if let Some(key) = Key::keypad(c).filter(|_| self.config.keypad) {
+            match self.config.key_hold {
+                Some(_) => self.hold_key(key, mistake, true),
+                None => self.emit(KeyAction::Click { key }, mistake),
+            }
+        } else if self.config.key_hold.is_some() && c.is_ascii_uppercase() {
+            self.press_key_down(Key::Shift);
+            self.hold_key(Key::Char(c.to_ascii_lowercase()), mistake, false);
+            self.press_key_up(Key::Shift);
+        } else if self.config.key_hold.is_some() && UNSHIFTED_KEYS.contains(c) {
+            self.hold_key(Key::Char(c), mistake, true);
+        } else {
+            self.emit(KeyAction::Type { char: c }, mistake);
+        }
+    }
+
+    /// Presses `key` and releases it after the hold duration. With `rollover` the key
+    /// may stay down until the next key is pressed.
+    fn hold_key(&mut self, key: Key, mistake: bool, rollover: bool) {
+        let Some(hold) = self.config.key_hold.clone() else {
+            return;
+        };
+        let duration = match self.config.personal.as_ref().and_then(|p| p.hold) {
+            Some(latency) => Delay::from(latency),
+            None => hold.duration,
+        };
+        let millis = duration.sample(&mut self.rng);
+
+        if hold.timeline {
+            // The key has to come up before it can go down again
+            if let Some(index) = self.releases.iter().position(|&(_, k)| k == key) {
+                self.releases.remove(index);
+                self.emit(KeyAction::Up { key }, false);
+            }
+            self.emit(KeyAction::Down { key }, mistake);
+            let release_at = self.elapsed + Duration::from_millis(millis);
+            self.releases.push((release_at, key));
+            return;
+        }
+
+        let previous = self.held.take();
+        self.emit(KeyAction::Down { key }, mistake);
+        let mut overlap = 0;
+        if let Some(previous) = previous {
+            overlap = self.rng.gen_range(ROLLOVER_OVERLAP).min(millis);
+            self.sleep(overlap);
+            self.emit(KeyAction::Up { key: previous }, false);
+        }
+
+        self.sleep(millis - overlap);
+        self.hold_credit = millis;
+        if rollover && self.rng.gen_ratio(hold.rollover_probability, 100) {
+            self.held = Some(key);
+        } else {
+            self.emit(KeyAction::Up { key }, mistake);
+        }
+    }
+
+    /// Releases a key left down for rollover.
+    fn release_held(&mut self) {
+        if let Some(key) = self.held.take() {
+            self.emit(KeyAction::Up { key }, false);
+        }
+    }
+
+    /// Releases every key still down in timeline mode, waiting until each is due.
+    fn finish_releases(&mut self) {
+        if let Some(last) = self.releases.iter().map(|&(at, _)| at).max() {
+            let millis = last.saturating_sub(self.elapsed).as_millis() as u64;
+            self.sleep(millis);
+        }
+    }
+
+    fn press_key(&mut self, key: Key) {
+        self.emit(KeyAction::Click { key }, false);
+    }
+
+    fn press_key_down(&mut self, key: Key) {
+        self.emit(KeyAction::Down { key }, false);
+    }
+
+    fn press_key_up(&mut self, key: Key) {
+        self.emit(KeyAction::Up { key }, false);
+    }
+
+    /// Types an ASCII letter on its own key while Shift is held down, instead of
+    /// relying on `key_sequence` to pick the case.
+    fn press_shifted_letter(&mut self, letter: char, mistake: bool) {
+        let key = Key::Char(letter.to_ascii_lowercase());
+        self.emit(KeyAction::Click { key }, mistake);
+    }
+
+    /// Sends `action`, or prints it in a dry run, and writes it to the keystroke log.
+    fn emit(&mut self, action: KeyAction, mistake: bool) {
+        // Only the next held key rolls over, anything else releases it first
+        self.release_held();
+        match &mut self.backend {
+            Some(backend) => {
+                let sent = match action {
+                    KeyAction::Type { char } => backend.send_char(char),
+                    KeyAction::Click { key } => backend.key_click(key),
+                    KeyAction::Down { key } => backend.key_down(key),
+                    KeyAction::Up { key } => backend.key_up(key),
+                    KeyAction::MouseMove { x, y } => backend.mouse_move(x, y),
+                    KeyAction::MouseClick => backend.mouse_click(),
+                    KeyAction::MouseScroll { lines } => backend.mouse_scroll(lines),
+                    KeyAction::MouseNudge { dx, dy } => backend.mouse_nudge(dx, dy),
+                };
+                if let Err(e) = sent {
+                    self.output(format!("Error: Could not send {}, aborting: {}", action, e));
+                    self.controls.abort();
+                }
+                self.controls.note_injected(&action);
+            }
+            None if mistake => self.output(format!(
+                "{} {} (mistake)",
+                format_timestamp(self.elapsed),
+                action
+            )),
+            None => self.output(format!("{} {}", format_timestamp(self.elapsed), action)),
+        }
+
+        let event = KeyEvent {
+            at_ms: self.elapsed.as_millis() as u64,
+            delay_ms: (self.elapsed - self.last_event_at).as_millis() as u64,
+            action,
+            mistake,
+            correction: self.correcting,
+        };
+        if let Some(callback) = &mut self.on_key_event {
+            callback(&event);
+        }
+        if let Some(log) = &mut self.keystroke_log {
+            let written = serde_json::to_writer(&mut *log, &event)
+                .map_err(io::Error::from)
+                .and_then(|_| log.write_all(b"\n"));
+            if let Err(e) = written {
+                self.output(format!(
+                    "Warning: Could not write keystroke log, disabling it: {}",
+                    e
+                ));
+                self.keystroke_log = None;
+            }
+        }
+        self.last_event_at = self.elapsed;
+    }
+
+    /// Hands `line` to the output callback, or prints it without one.
+    fn output(&mut self, line: String) {
+        match &mut self.on_output {
+            Some(callback) => callback(&line),
+            None => println!("{}", line),
+        }
+    }
+
+    /// Makes sure no modifier is left pressed when typing stops early.
+    fn release_modifiers(&mut self) {
+        let mut down: Vec<Key> = self.held.take().into_iter().collect();
+        down.extend(self.releases.drain(..).map(|(_, key)| key));
+        if let Some(backend) = &mut self.backend {
+            for key in down
+                .into_iter()
+                .chain([Key::Shift, Key::Control, Key::Alt, Key::Meta])
+            {
+                // Best effort, typing is over anyway
+                let _ = backend.key_up(key);
+            }
+        }
+    }
+
+    /// Whether a mistake that was just made gets corrected at all.
+    /// Called once for every mistake made.
+    fn notices_mistake(&mut self) -> bool {
+        self.stats.mistakes += 1;
+        if let Some(callback) = &mut self.on_mistake {
+            callback(self.position, self.elapsed);
+        }
+        self.rng.gen_ratio(self.config.correction_probability, 100)
+    }
+
+    /// Rolls for a mistake, which is more likely while warming up, as the typist tires
+    /// and late at night.
+    fn roll_mistake(&mut self, probability: f64) -> bool {
+        let fatigue = match &self.config.fatigue {
+            Some(fatigue) => self.fatigue.mistake_factor(fatigue),
+            None => 1.0,
+        };
+        let warm_up = match &self.config.warm_up {
+            Some(warm_up) => warm_up.mistake_factor(self.elapsed),
+            None => 1.0,
+        };
+        let time_of_day = match &self.config.time_of_day {
+            Some(time_of_day) => time_of_day.factor(self.elapsed),
+            None => 1.0,
+        };
+        self.rng
+            .gen_bool((probability * fatigue * warm_up * time_of_day).min(1.0))
+    }
+
+    /// Types a misspelling or a wrong word in place of the word starting at `i` if it
+    /// has one and the roll says so, and returns the length of the intended word.
+    fn substitute_word(&mut self, chars: &[char], i: usize) -> Option<usize> {
+        if i.checked_sub(1).is_some_and(|k| chars[k].is_alphabetic()) {
+            return None;
+        }
+        let word: String = chars[i..]
+            .iter()
+            .take_while(|&&c| c.is_alphabetic() || c == '\'')
+            .collect();
+        let word = word.trim_end_matches('\'');
+        let candidates: Vec<(Vec<String>, u32)> = [
+            (
+                &self.config.misspellings,
+                self.config.misspelling_probability,
+            ),
+            (&self.config.wrong_words, self.config.wrong_word_probability),
+        ]
+        .into_iter()
+        .filter_map(|(dictionary, probability)| {
+            let typos = dictionary.as_ref()?.misspellings(word);
+            (!typos.is_empty()).then(|| (typos.to_vec(), probability))
+        })
+        .collect();
+
+        for (typos, probability) in candidates {
+            let typo = &typos[self.rng.gen_range(0..typos.len())];
+            if !self.roll_mistake(probability as f64 / 100.0) {
+                continue;
+            }
+
+            let typed: Vec<char> = typos::match_case(word, typo).chars().collect();
+            for (k, &c) in typed.iter().enumerate() {
+                if k > 0 {
+                    self.sleep_keystroke(Some((typed[k - 1], c)));
+                }
+                self.press_char(c, true);
+            }
+            if self.notices_mistake() {
+                let lag = self.rng.gen_range(self.config.correction_lag_chars.clone());
+                self.start_pending(i, &typed, lag);
+            }
+            return Some(word.chars().count());
+        }
+        None
+    }
+
+    fn should_omit(&mut self, c: char) -> bool {
+        c.is_alphabetic() && self.roll_mistake(self.config.omission_probability as f64 / 100.0)
+    }
+
+    fn should_transpose(&mut self, first: char, second: char) -> bool {
+        let swap_probability = match &self.config.key_hold {
+            Some(hold) if hold.timeline => hold.swap_probability,
+            _ => 0,
+        };
+        first.is_alphabetic()
+            && second.is_alphabetic()
+            && self.roll_mistake(
+                (self.config.transposition_probability + swap_probability) as f64 / 100.0,
+            )
+    }
+
+    /// Types two characters in the wrong order ("teh"). In timeline mode both keys go
+    /// down almost at once, the way rolling over them swaps them.
+    fn type_transposed(&mut self, first: char, second: char) {
+        self.press_char(second, true);
+        if self
+            .config
+            .key_hold
+            .as_ref()
+            .is_some_and(|hold| hold.timeline)
+        {
+            let gap = self.rng.gen_range(SWAP_GAP);
+            self.sleep(gap);
+        } else {
+            self.sleep_keystroke(Some((second, first)));
+        }
+        self.press_char(first, true);
+
+        if self.notices_mistake() {
+            self.start_pending(self.position, &[second, first], 0);
+        }
+    }
+
+    fn should_slip_shift(&mut self, first: char, second: char) -> bool {
+        first.is_ascii_uppercase()
+            && second.is_ascii_lowercase()
+            && self.roll_mistake(self.config.case_mistake_probability as f64 / 100.0)
+    }
+
+    /// Holds Shift a little too long ("THe").
+    fn type_shift_slip(&mut self, first: char, second: char) {
+        self.press_key_down(Key::Shift);
+        self.press_shifted_letter(first, false);
+        self.sleep_keystroke(Some((first, second)));
+        self.press_shifted_letter(second, true);
+        self.press_key_up(Key::Shift);
+
+        if self.notices_mistake() {
+            self.start_pending(self.position + 1, &[second.to_ascii_uppercase()], 0);
+        }
+    }
+
+    fn type_character(&mut self, intended_char: char) {
+        let probability = self
+            .config
+            .personal
+            .as_ref()
+            .and_then(|personal| personal.error_rate(intended_char))
+            .unwrap_or(
+                1.0 / self
+                    .config
+                    .mistake_probability
+                    .get(CharClass::of(intended_char)) as f64,
+            );
+        if self.roll_mistake(probability) {
+            // Make a simple mistake
+            let mistake_char = self.keyboard.get_nearby_key(intended_char, &mut self.rng);
+            self.press_char(mistake_char, true);
+
+            if self.notices_mistake() {
+                // Possibly keep typing for a while before noticing it
+                let lag = self.rng.gen_range(self.config.correction_lag_chars.clone());
+                self.start_pending(self.position, &[mistake_char], lag);
+            }
+        } else if intended_char.is_ascii_uppercase()
+            && self.roll_mistake(self.config.case_mistake_probability as f64 / 100.0)
+        {
+            // Miss the Shift key
+            let lowercase = intended_char.to_ascii_lowercase();
+            self.press_char(lowercase, true);
+
+            if self.notices_mistake() {
+                self.start_pending(self.position, &[lowercase], 0);
+            }
+        } else if self.roll_mistake(self.config.double_letter_probability as f64 / 100.0) {
+            // Hit the key twice, then remove the extra copy
+            self.press_char(intended_char, false);
+            self.sleep_keystroke(Some((intended_char, intended_char)));
+            self.press_char(intended_char, true);
+
+            if self.notices_mistake() {
+                self.start_pending(self.position + 1, &[intended_char], 0);
+            }
+        } else {
+            self.press_char(intended_char, false);
+        }
+    }
+}
+
+impl Default for HumanTypist {
+    fn default() -> Self {
+        HumanTypist::new()
+    }
+}
+
+fn format_timestamp(elapsed: Duration) -> String {
+    format!("[{:>9.3}s]", elapsed.as_secs_f64())
+}
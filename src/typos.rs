@@ -0,0 +1,154 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+/// Common misspellings that people type out of habit rather than by slipping a key.
+const BUILTIN_MISSPELLINGS: &[(&str, &[&str])] = &[
+    ("the", &["teh", "hte"]),
+    ("and", &["adn", "nad"]),
+    ("receive", &["recieve"]),
+    ("believe", &["beleive"]),
+    ("definitely", &["definately", "definitly"]),
+    ("separate", &["seperate"]),
+    ("occurred", &["occured"]),
+    ("occurrence", &["occurence"]),
+    ("necessary", &["neccessary", "necesary"]),
+    ("accommodate", &["accomodate"]),
+    ("achieve", &["acheive"]),
+    ("address", &["adress"]),
+    ("beginning", &["begining"]),
+    ("calendar", &["calender"]),
+    ("colleague", &["collegue"]),
+    ("committee", &["commitee"]),
+    ("embarrass", &["embarass"]),
+    ("environment", &["enviroment"]),
+    ("existence", &["existance"]),
+    ("government", &["goverment"]),
+    ("guarantee", &["gaurantee"]),
+    ("immediately", &["immediatly"]),
+    ("independent", &["independant"]),
+    ("knowledge", &["knowlege"]),
+    ("library", &["libary"]),
+    ("maintenance", &["maintainance"]),
+    ("millennium", &["millenium"]),
+    ("noticeable", &["noticable"]),
+    ("occasionally", &["occasionaly", "ocasionally"]),
+    ("publicly", &["publically"]),
+    ("really", &["realy"]),
+    ("recommend", &["recomend", "reccommend"]),
+    ("restaurant", &["restaraunt"]),
+    ("rhythm", &["rythm"]),
+    ("success", &["sucess"]),
+    ("surprise", &["suprise"]),
+    ("tomorrow", &["tommorow", "tomorow"]),
+    ("truly", &["truely"]),
+    ("until", &["untill"]),
+    ("weird", &["wierd"]),
+    ("which", &["whcih", "wich"]),
+    ("with", &["wiht"]),
+    ("would", &["woudl"]),
+];
+
+/// Words that sound alike and get typed in place of each other.
+pub const BUILTIN_WRONG_WORDS: &[&[&str]] = &[
+    &["their", "there", "they're"],
+    &["its", "it's"],
+    &["your", "you're"],
+    &["whose", "who's"],
+    &["to", "too"],
+    &["then", "than"],
+    &["affect", "effect"],
+    &["lose", "loose"],
+    &["accept", "except"],
+    &["were", "where"],
+    &["weather", "whether"],
+    &["hear", "here"],
+];
+
+/// Whole-word misspellings by correctly spelled word, e.g. `"recieve"` for `"receive"`.
+#[derive(Clone, Debug)]
+pub struct TypoDictionary {
+    misspellings: HashMap<String, Vec<String>>,
+}
+
+impl TypoDictionary {
+    pub fn builtin() -> Self {
+        TypoDictionary {
+            misspellings: BUILTIN_MISSPELLINGS
+                .iter()
+                .map(|(word, typos)| {
+                    (
+                        word.to_string(),
+                        typos.iter().map(|typo| typo.to_string()).collect(),
+                    )
+                })
+                .collect(),
+        }
+    }
+
+    /// Confuses every word of a group with the others, e.g. "there" for "their".
+    pub fn from_groups(groups: &[Vec<String>]) -> Self {
+        let mut misspellings: HashMap<String, Vec<String>> = HashMap::new();
+        for group in groups {
+            for word in group {
+                let others = group.iter().filter(|other| *other != word).cloned();
+                misspellings
+                    .entry(word.to_lowercase())
+                    .or_default()
+                    .extend(others);
+            }
+        }
+        TypoDictionary { misspellings }
+    }
+
+    /// Loads a JSON or TOML map of word to its misspellings, on top of the built-in
+    /// dictionary. A word listed in the file replaces its built-in misspellings.
+    pub fn from_file(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read typos {}: {}", path.display(), e))?;
+        let entries: HashMap<String, Vec<String>> =
+            if path.extension().is_some_and(|ext| ext == "toml") {
+                toml::from_str(&content)?
+            } else {
+                serde_json::from_str(&content)?
+            };
+
+        let mut dictionary = TypoDictionary::builtin();
+        for (word, typos) in entries {
+            if typos
+                .iter()
+                .any(|typo| typo.is_empty() || typo.contains(char::is_whitespace))
+            {
+                return Err(format!(
+                    "Misspellings of {:?} in {} must be single words",
+                    word,
+                    path.display()
+                )
+                .into());
+            }
+            dictionary.misspellings.insert(word.to_lowercase(), typos);
+        }
+        Ok(dictionary)
+    }
+
+    /// The misspellings of `word`, whatever its case.
+    pub fn misspellings(&self, word: &str) -> &[String] {
+        self.misspellings
+            .get(&word.to_lowercase())
+            .map_or(&[], Vec::as_slice)
+    }
+}
+
+/// `typo` with the capitalization of `word`, so "Receive" becomes "Recieve".
+pub fn match_case(word: &str, typo: &str) -> String {
+    let mut letters = word.chars().filter(|c| c.is_alphabetic());
+    let first_upper = letters.next().is_some_and(char::is_uppercase);
+    if first_upper && word.chars().count() > 1 && letters.all(char::is_uppercase) {
+        return typo.to_uppercase();
+    }
+    let mut chars = typo.chars();
+    match chars.next() {
+        Some(first) if first_upper => first.to_uppercase().chain(chars).collect(),
+        _ => typo.to_string(),
+    }
+}
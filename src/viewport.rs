@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+
+/// How the typist keeps the caret in view in editors that do not scroll along by
+/// themselves.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ViewportAction {
+    /// Turns the mouse wheel down.
+    #[default]
+    Scroll,
+    /// Presses Ctrl+End, which jumps to the end of the document, so only for texts
+    /// typed at the end of one.
+    CtrlEnd,
+}
+
+/// Scrolls along every few lines of a long text, the way people glance down to keep
+/// what they type on screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ViewportConfig {
+    /// Lines typed between two scrolls.
+    pub every_lines: usize,
+    pub action: ViewportAction,
+    /// Lines the mouse wheel scrolls for [`ViewportAction::Scroll`].
+    pub scroll_lines: u32,
+}
+
+impl Default for ViewportConfig {
+    fn default() -> Self {
+        ViewportConfig {
+            every_lines: 30,
+            action: ViewportAction::Scroll,
+            scroll_lines: 10,
+        }
+    }
+}
@@ -0,0 +1,145 @@
+use crate::daemon::{Events, Request, Response};
+use crate::http::same_token;
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use tungstenite::handshake::server::{ErrorResponse, Request as Handshake, Response as Accepted};
+use tungstenite::http::{HeaderValue, StatusCode};
+use tungstenite::{Error, Message};
+
+/// How long to wait for a command before sending on the events that came in meanwhile.
+const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// The subprotocol browsers offer along with the token, as they cannot set headers.
+const PROTOCOL: &str = "typer";
+
+/// Listens on `addr` for WebSocket clients of `typer daemon --websocket`, each on a
+/// thread of its own. Every daemon event is sent to them as a JSON text message, and
+/// every message they send is a request, answered with `handle` as on the socket.
+/// Clients must send `token` as `Authorization: Bearer <token>` or, as browsers cannot,
+/// offer the `typer` subprotocol and the token as another, like
+/// `new WebSocket(url, ["typer", token])`. Web pages, which send an `Origin`, are only
+/// let in from `origins`.
+pub fn spawn<F>(
+    addr: &str,
+    token: String,
+    origins: Vec<String>,
+    events: Events,
+    handle: F,
+) -> Result<JoinHandle<()>, Box<dyn std::error::Error>>
+where
+    F: Fn(Request) -> Response + Send + Sync + 'static,
+{
+    let listener =
+        TcpListener::bind(addr).map_err(|e| format!("Failed to listen at {}: {}", addr, e))?;
+    let (token, origins, handle) = (Arc::new(token), Arc::new(origins), Arc::new(handle));
+    Ok(thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let (token, origins) = (Arc::clone(&token), Arc::clone(&origins));
+            let (events, handle) = (events.clone(), Arc::clone(&handle));
+            thread::spawn(move || follow(stream, &token, &origins, &events, &*handle));
+        }
+    }))
+}
+
+/// Streams events to the client on `stream` and answers its requests until it leaves.
+fn follow<F>(stream: TcpStream, token: &str, origins: &[String], events: &Events, handle: &F)
+where
+    F: Fn(Request) -> Response,
+{
+    // The handshake callback's error type is tungstenite's to pick
+    #[allow(clippy::result_large_err)]
+    let check = |request: &Handshake, mut accepted: Accepted| {
+        let refuse = |status, reason: &str| {
+            let mut refused = ErrorResponse::new(Some(reason.to_string()));
+            *refused.status_mut() = status;
+            Err(refused)
+        };
+        let origin = request.headers().get("Origin");
+        if origin.is_some_and(|origin| !origins.iter().any(|allowed| origin == allowed)) {
+            return refuse(
+                StatusCode::FORBIDDEN,
+                "Web pages from there may not connect",
+            );
+        }
+        match authorization(request, token) {
+            None => refuse(StatusCode::UNAUTHORIZED, "Missing or wrong token"),
+            Some(Authorization::Header) => Ok(accepted),
+            Some(Authorization::Protocol) => {
+                // Browsers drop connections that take up none of the offered subprotocols
+                let protocol = HeaderValue::from_static(PROTOCOL);
+                accepted
+                    .headers_mut()
+                    .insert("Sec-WebSocket-Protocol", protocol);
+                Ok(accepted)
+            }
+        }
+    };
+    let Ok(mut socket) = tungstenite::accept_hdr(stream, check) else {
+        return;
+    };
+    // Set only now, as a handshake cut short by it would fail
+    if socket
+        .get_ref()
+        .set_read_timeout(Some(POLL_INTERVAL))
+        .is_err()
+    {
+        return;
+    }
+
+    let events = events.subscribe();
+    loop {
+        let reply = match socket.read() {
+            Ok(Message::Text(text)) => Some(match serde_json::from_str(&text) {
+                Ok(request) => handle(request),
+                Err(e) => Response::error(format!("Invalid request: {}", e)),
+            }),
+            Ok(_) => None,
+            Err(Error::Io(e))
+                if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                None
+            }
+            Err(_) => return,
+        };
+        let replies = reply.into_iter().map(|reply| serde_json::to_string(&reply));
+        let events = events.try_iter().map(|event| serde_json::to_string(&event));
+        for json in replies.chain(events) {
+            let Ok(json) = json else {
+                continue;
+            };
+            if socket.send(Message::text(json)).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Where a client sent the right token.
+enum Authorization {
+    Header,
+    Protocol,
+}
+
+fn authorization(request: &Handshake, token: &str) -> Option<Authorization> {
+    let headers = request.headers();
+    let in_header = headers
+        .get("Authorization")
+        .and_then(|value| value.to_str().ok()?.strip_prefix("Bearer "))
+        .is_some_and(|given| same_token(given, token));
+    if in_header {
+        return Some(Authorization::Header);
+    }
+    let protocols: Vec<&str> = headers
+        .get_all("Sec-WebSocket-Protocol")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .map(str::trim)
+        .collect();
+    let in_protocols =
+        protocols.contains(&PROTOCOL) && protocols.iter().any(|given| same_token(given, token));
+    in_protocols.then_some(Authorization::Protocol)
+}
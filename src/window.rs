@@ -0,0 +1,271 @@
+use crate::config::FocusLoss;
+use crate::hotkeys::{PauseReason, TypingControls};
+use active_win_pos_rs::get_active_window;
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const ACTIVATE_TIMEOUT: Duration = Duration::from_secs(2);
+const ACTIVATE_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// The window that currently has the keyboard focus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FocusedWindow {
+    pub title: String,
+    pub app_name: String,
+    /// Platform-specific id that tells apart windows with the same title.
+    pub id: String,
+}
+
+impl FocusedWindow {
+    /// Whether `target` appears in the window title or app name, ignoring case.
+    pub fn matches(&self, target: &str) -> bool {
+        let target = target.to_lowercase();
+        self.title.to_lowercase().contains(&target)
+            || self.app_name.to_lowercase().contains(&target)
+    }
+
+    pub fn describe(&self) -> String {
+        if self.app_name.is_empty() || self.title.contains(&self.app_name) {
+            format!("\"{}\"", self.title)
+        } else {
+            format!("\"{}\" ({})", self.title, self.app_name)
+        }
+    }
+}
+
+/// Returns the focused window, or `None` when it cannot be detected.
+pub fn focused_window() -> Option<FocusedWindow> {
+    get_active_window().ok().map(|window| FocusedWindow {
+        title: window.title,
+        app_name: window.app_name,
+        id: window.window_id,
+    })
+}
+
+/// Brings the first window whose title or app name contains `name` to the front and
+/// waits for it to take the focus.
+pub fn activate_window(name: &str) -> Result<FocusedWindow, Box<dyn std::error::Error>> {
+    if !request_activation(name)? {
+        return Err(format!("No window matching \"{}\" found", name).into());
+    }
+
+    let started = Instant::now();
+    loop {
+        match focused_window() {
+            Some(window) if window.matches(name) => return Ok(window),
+            _ if started.elapsed() >= ACTIVATE_TIMEOUT => {
+                return Err(
+                    format!("The window matching \"{}\" did not take the focus", name).into(),
+                )
+            }
+            _ => thread::sleep(ACTIVATE_POLL_INTERVAL),
+        }
+    }
+}
+
+/// Asks the platform to activate a matching window, returning whether one was found.
+#[cfg(target_os = "linux")]
+fn request_activation(name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    // xdotool matches a regex against the title, then the window class, ignoring case
+    let mut pattern = String::new();
+    for c in name.chars() {
+        if c.is_ascii_punctuation() {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    for field in ["--name", "--class"] {
+        let status = Command::new("xdotool")
+            .args(["search", "--onlyvisible", field, &pattern])
+            .args(["windowactivate", "--sync"])
+            .output()
+            .map_err(|e| format!("Failed to run xdotool: {}", e))?
+            .status;
+        if status.success() {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+#[cfg(target_os = "windows")]
+fn request_activation(name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    // AppActivate takes a title prefix or a process name
+    let script = format!(
+        "if ((New-Object -ComObject WScript.Shell).AppActivate('{}')) {{ exit 0 }} exit 1",
+        name.replace('\'', "''")
+    );
+    let status = Command::new("powershell")
+        .args(["-NoProfile", "-Command", &script])
+        .status()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    Ok(status.success())
+}
+
+#[cfg(target_os = "macos")]
+fn request_activation(name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    let script = format!(
+        "tell application \"{}\" to activate",
+        name.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let status = Command::new("osascript")
+        .args(["-e", &script])
+        .status()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+    Ok(status.success())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn request_activation(_name: &str) -> Result<bool, Box<dyn std::error::Error>> {
+    Err("Activating windows is not supported on this platform".into())
+}
+
+/// Titles of the visible windows, or app names on macOS, to pick a window to type into.
+pub fn list_windows() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let mut names = window_names()?;
+    names.retain(|name| !name.trim().is_empty());
+    names.sort();
+    names.dedup();
+    Ok(names)
+}
+
+#[cfg(target_os = "linux")]
+fn window_names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let output = Command::new("xdotool")
+        .args(["search", "--onlyvisible", "--name", ""])
+        .output()
+        .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+    let mut names = Vec::new();
+    for id in String::from_utf8_lossy(&output.stdout).lines() {
+        let output = Command::new("xdotool")
+            .args(["getwindowname", id])
+            .output()
+            .map_err(|e| format!("Failed to run xdotool: {}", e))?;
+        if output.status.success() {
+            names.push(String::from_utf8_lossy(&output.stdout).trim().to_string());
+        }
+    }
+    Ok(names)
+}
+
+#[cfg(target_os = "windows")]
+fn window_names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    let script = "Get-Process | Where-Object { $_.MainWindowTitle } | \
+                  ForEach-Object { $_.MainWindowTitle }";
+    let output = Command::new("powershell")
+        .args(["-NoProfile", "-Command", script])
+        .output()
+        .map_err(|e| format!("Failed to run powershell: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .collect())
+}
+
+#[cfg(target_os = "macos")]
+fn window_names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    // Windows are activated by app name there, so the apps are listed instead
+    let script = "tell application \"System Events\" to get name of every process \
+                  whose background only is false";
+    let output = Command::new("osascript")
+        .args(["-e", script])
+        .output()
+        .map_err(|e| format!("Failed to run osascript: {}", e))?;
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .split(", ")
+        .map(|name| name.trim().to_string())
+        .collect())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "windows", target_os = "macos")))]
+fn window_names() -> Result<Vec<String>, Box<dyn std::error::Error>> {
+    Err("Listing windows is not supported on this platform".into())
+}
+
+pub enum FocusEvent {
+    Lost(FocusedWindow),
+    Returned,
+}
+
+#[derive(Default)]
+struct WatchState {
+    stopped: AtomicBool,
+    retarget: AtomicBool,
+}
+
+/// Polls the focused window on a background thread and pauses or aborts typing while
+/// it is not the target window. Stops when dropped.
+pub struct FocusWatcher {
+    state: Arc<WatchState>,
+}
+
+impl FocusWatcher {
+    pub fn spawn<F>(
+        target: FocusedWindow,
+        on_loss: FocusLoss,
+        interval: Duration,
+        controls: Arc<TypingControls>,
+        on_event: F,
+    ) -> Self
+    where
+        F: Fn(FocusEvent) + Send + 'static,
+    {
+        let state = Arc::new(WatchState::default());
+        let watched = Arc::clone(&state);
+
+        thread::spawn(move || {
+            let mut target = target;
+            let mut lost = false;
+
+            while !watched.stopped.load(Ordering::SeqCst) && !controls.is_aborted() {
+                thread::sleep(interval);
+                // Windows that cannot be detected for a moment don't count as lost
+                let Some(focused) = focused_window() else {
+                    continue;
+                };
+
+                if focused.id != target.id && watched.retarget.swap(false, Ordering::SeqCst) {
+                    target = focused;
+                    continue;
+                }
+
+                match (focused.id == target.id, lost) {
+                    (false, false) => {
+                        lost = true;
+                        match on_loss {
+                            FocusLoss::Pause => controls.pause(PauseReason::Focus),
+                            FocusLoss::Abort => controls.abort(),
+                            FocusLoss::Ignore => {}
+                        }
+                        on_event(FocusEvent::Lost(focused));
+                    }
+                    (true, true) => {
+                        lost = false;
+                        if on_loss == FocusLoss::Pause {
+                            controls.resume(PauseReason::Focus);
+                        }
+                        on_event(FocusEvent::Returned);
+                    }
+                    _ => {}
+                }
+            }
+        });
+
+        FocusWatcher { state }
+    }
+
+    /// Makes the next window to take the focus the new target, e.g. after typer
+    /// switched windows itself.
+    pub fn retarget(&self) {
+        self.state.retarget.store(true, Ordering::SeqCst);
+    }
+}
+
+impl Drop for FocusWatcher {
+    fn drop(&mut self) {
+        self.state.stopped.store(true, Ordering::SeqCst);
+    }
+}
@@ -0,0 +1,43 @@
+use typer::window::FocusedWindow;
+use typer::Config;
+
+fn window(title: &str, app_name: &str) -> FocusedWindow {
+    FocusedWindow {
+        title: title.to_string(),
+        app_name: app_name.to_string(),
+        id: "1".to_string(),
+    }
+}
+
+#[test]
+fn password_managers_are_refused_by_default() {
+    let blacklist = Config::default().blacklist.blacklist().unwrap();
+    let refused = blacklist
+        .check(Some(&window("Passwords.kdbx - KeePassXC", "keepassxc")))
+        .unwrap();
+    assert!(refused.contains("keepass"), "{}", refused);
+    assert!(blacklist
+        .check(Some(&window("Reset your password - Firefox", "firefox")))
+        .is_none());
+    assert!(blacklist
+        .check(Some(&window("notes.txt - gedit", "gedit")))
+        .is_none());
+    assert!(blacklist.check(None).is_none());
+}
+
+#[test]
+fn the_blacklist_can_be_extended_or_turned_off() {
+    let config: Config =
+        toml::from_str("[blacklist]\napps = [\"Terminal\", \"My Bank\"]\n").unwrap();
+    let blacklist = config.blacklist.blacklist().unwrap();
+    assert!(blacklist
+        .check(Some(&window("Accounts - My Bank - Chrome", "chrome")))
+        .is_some());
+    assert!(blacklist
+        .check(Some(&window("KeePassXC", "keepassxc")))
+        .is_none());
+
+    let overrides = [("blacklist.enabled".to_string(), "false".to_string())];
+    let config = config.with_overrides(&overrides).unwrap();
+    assert!(config.blacklist.blacklist().is_none());
+}
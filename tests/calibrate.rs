@@ -0,0 +1,56 @@
+use std::time::Duration;
+use typer::calibrate::{Calibration, Keystroke};
+
+/// Keystrokes for `keys` pressed `gap` milliseconds apart, with `#` for Backspace.
+fn keystrokes(keys: &str, gap: u64) -> Vec<(Duration, Keystroke)> {
+    keys.chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let keystroke = match c {
+                '#' => Keystroke::Backspace,
+                c => Keystroke::Char(c),
+            };
+            (Duration::from_millis(i as u64 * gap), keystroke)
+        })
+        .collect()
+}
+
+#[test]
+fn measures_speed_and_corrected_mistakes() {
+    let target = "the quick brown fox jumps over the lazy dog";
+    let typed = "the quick brpwn###own fox jumps over the lazy dog";
+    let calibration = Calibration::fit(target, &keystrokes(typed, 100)).unwrap();
+
+    assert_eq!(calibration.characters, target.len());
+    assert_eq!(calibration.keystroke.mean, 100.0);
+    assert_eq!(calibration.keystroke.std_dev, 0.0);
+    assert_eq!(calibration.mistakes, 1);
+    assert_eq!(calibration.corrections, 1);
+    assert_eq!(calibration.correction_lag, Some((2, 2)));
+    assert!(calibration.thinking.is_none());
+
+    let profile = calibration.to_profile();
+    assert_eq!(profile["mistakes"]["correction_probability"], 100);
+    assert_eq!(profile["delays"]["base_mean"], 100.0);
+}
+
+#[test]
+fn profile_is_a_valid_config_profile() {
+    let target = "pack my box with five dozen liquor jugs";
+    let typed = "pack my bix with five dozen liquor jugs";
+    let calibration = Calibration::fit(target, &keystrokes(typed, 150)).unwrap();
+    assert_eq!(calibration.corrections, 0);
+
+    let mut config = typer::Config::default();
+    config
+        .profiles
+        .insert("me".to_string(), calibration.to_profile());
+    let config = config.with_profile("me").unwrap();
+    config.validate().unwrap();
+    assert_eq!(config.mistakes.correction_probability, 0);
+}
+
+#[test]
+fn too_short_a_test_is_rejected() {
+    assert!(Calibration::fit("hello", &keystrokes("hello", 100)).is_err());
+}
@@ -0,0 +1,29 @@
+use std::fs;
+use std::process::{Command, Stdio};
+
+#[test]
+fn code_with_double_braces_is_typed_as_it_is() {
+    let dir = std::env::temp_dir().join(format!("typer-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let code = "fn main() {\n    println!(\"{{x}} {}\", format!(\"{{name}}\"));\n}\n";
+    fs::write(dir.join("main.rs"), code).unwrap();
+
+    // Piped in, a placeholder would have no one to ask for its value and fail the run
+    let output = Command::new(env!("CARGO_BIN_EXE_typer"))
+        .args(["run", "--dry-run", "--countdown", "0", "--mode", "code"])
+        .arg("--config")
+        .arg(dir.join("config.toml"))
+        .arg("--text")
+        .arg(dir.join("main.rs"))
+        .stdin(Stdio::null())
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(stdout.contains(&format!("Text to type:\n{}", code)));
+    fs::remove_dir_all(&dir).unwrap();
+}
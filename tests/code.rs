@@ -0,0 +1,192 @@
+use std::path::Path;
+use typer::code::{string_literals, AutoClose, AutoCloseRules, Language};
+use typer::events::{Key, KeyAction};
+use typer::{AutoIndent, CodeModeConfig, HumanTypist, MistakeRates, MockBackend, TypingConfig};
+
+const RUST: &str =
+    "// don't \"quote\" me\nfn main() {\n    let s = \"a 'b' c\";\n    let c = '\"';\n}";
+
+#[test]
+fn languages_are_detected_by_extension() {
+    assert_eq!(Language::detect(Path::new("src/main.rs")), Language::Rust);
+    assert_eq!(Language::detect(Path::new("script.PY")), Language::Python);
+    assert_eq!(Language::detect(Path::new("App.tsx")), Language::TypeScript);
+    assert_eq!(Language::detect(Path::new("notes.txt")), Language::Other);
+    assert_eq!(Language::detect(Path::new("clipboard")), Language::Other);
+}
+
+#[test]
+fn string_literals_skip_comments_and_char_literals() {
+    let chars: Vec<char> = RUST.chars().collect();
+    let inside = string_literals(&chars, Language::Rust);
+    let literals: String = chars
+        .iter()
+        .zip(&inside)
+        .map(|(&c, &inside)| if inside { c } else { '_' })
+        .collect();
+    let strings: Vec<&str> = literals.split('_').filter(|s| !s.is_empty()).collect();
+
+    assert_eq!(strings, ["\"a 'b' c\""]);
+}
+
+#[test]
+fn function_definitions_are_recognized() {
+    assert!(Language::Rust.starts_function("    pub(crate) async fn run() {"));
+    assert!(!Language::Rust.starts_function("    let f = fn_name();"));
+    assert!(Language::Python.starts_function("def main():"));
+    assert!(Language::C.starts_function("static int parse(const char *s) {"));
+    assert!(!Language::C.starts_function("    if (x) {"));
+    assert!(!Language::C.starts_function("    return parse(s);"));
+}
+
+#[test]
+fn code_mode_types_strings_and_whitespace_exactly() {
+    let text = "let s = \"abcdefghijklmnopqrstuvwxyz\";\n\tlet t = \"0123456789\";";
+    let chars: Vec<char> = text.chars().collect();
+    let inside = string_literals(&chars, Language::Rust);
+    for seed in 0..10 {
+        let backend = MockBackend::new();
+        let mut typist = HumanTypist::builder()
+            .config(TypingConfig {
+                mistake_probability: MistakeRates::uniform(1),
+                transposition_probability: 0,
+                double_letter_probability: 0,
+                omission_probability: 0,
+                correction_probability: 0,
+                code: Some(CodeModeConfig {
+                    language: Language::Rust,
+                    mistakes_in_strings: false,
+                    ..CodeModeConfig::default()
+                }),
+                ..TypingConfig::default()
+            })
+            .backend(backend.clone())
+            .seed(seed)
+            .instant(true)
+            .build();
+        typist.type_text(text);
+
+        let typed: Vec<char> = backend.typed().chars().collect();
+        assert_eq!(typed.len(), chars.len());
+        for (k, &c) in chars.iter().enumerate() {
+            if inside[k] || c.is_whitespace() {
+                assert_eq!(typed[k], c, "character {} with seed {}", k, seed);
+            }
+        }
+    }
+}
+
+const INDENTED: &str = "fn main() {\n    if ok {\n\t\tgo();\n\n    }\n}";
+
+fn careful_backend(config: TypingConfig, text: &str) -> MockBackend {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            transposition_probability: 0,
+            double_letter_probability: 0,
+            omission_probability: 0,
+            case_mistake_probability: 0,
+            ..config
+        })
+        .backend(backend.clone())
+        .seed(7)
+        .instant(true)
+        .build();
+    typist.type_text(text);
+    backend
+}
+
+fn type_indented(auto_indent: AutoIndent) -> String {
+    let config = TypingConfig {
+        auto_indent,
+        ..TypingConfig::default()
+    };
+    careful_backend(config, INDENTED).typed()
+}
+
+#[test]
+fn auto_indent_strips_or_retypes_indentation() {
+    let stripped: Vec<&str> = INDENTED.lines().map(str::trim_start).collect();
+    assert_eq!(type_indented(AutoIndent::Strip), stripped.join("\n"));
+    // Without an editor indenting by itself, Shift+Home selects nothing to type over
+    assert_eq!(type_indented(AutoIndent::Home), INDENTED);
+}
+
+#[test]
+fn auto_closed_pairs_are_stepped_over() {
+    let clicks = |backend: &MockBackend, key: Key| {
+        backend
+            .actions()
+            .iter()
+            .filter(|&&action| action == KeyAction::Click { key })
+            .count()
+    };
+    let rules = |rule| {
+        let mut rules = AutoCloseRules::new(rule);
+        rules.set('\'', AutoClose::Off).unwrap();
+        rules
+    };
+    let text = "say(\"hi\", list[0]);\nif x {\n}";
+
+    // The mock editor does not close anything, so the closers it was spared are missing
+    let config = TypingConfig {
+        auto_close: rules(AutoClose::Skip),
+        ..TypingConfig::default()
+    };
+    let backend = careful_backend(config, text);
+    assert_eq!(backend.typed(), "say(\"hi, list[0;\nif x {\n}");
+    assert_eq!(clicks(&backend, Key::Right), 3);
+    // Enter takes the closer of the brace along
+    assert_eq!(clicks(&backend, Key::End), 1);
+
+    let config = TypingConfig {
+        auto_close: rules(AutoClose::End),
+        ..TypingConfig::default()
+    };
+    let backend = careful_backend(config, "f(g(x))\ndon't");
+    assert_eq!(backend.typed(), "f(g(x\ndon't");
+    assert_eq!(clicks(&backend, Key::End), 1);
+    assert_eq!(clicks(&backend, Key::Right), 0);
+}
+
+const PYTHON: &str = "def main():\n    if ok:\n\tgo(1, 2)\n\n    return x";
+
+fn type_python(seed: u64, copy: fn(&str) -> String) -> (HumanTypist, String) {
+    let backend = MockBackend::new();
+    let screen = backend.clone();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(2),
+            code: Some(CodeModeConfig {
+                language: Language::Python,
+                verify_lines: true,
+                ..CodeModeConfig::default()
+            }),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(seed)
+        .instant(true)
+        .clipboard(move || Some(copy(&screen.typed())))
+        .build();
+    typist.type_text(PYTHON);
+    (typist, backend.typed())
+}
+
+#[test]
+fn strict_whitespace_verifies_lines() {
+    for seed in 0..10 {
+        // The mock selects from the line start, so the copy is the last line on screen
+        let (typist, typed) = type_python(seed, |screen| {
+            screen.rsplit('\n').next().unwrap().to_string()
+        });
+        assert_eq!(typed, PYTHON, "seed {}", seed);
+        assert_eq!(typist.stats().lines_fixed, 0);
+    }
+
+    // A line that does not copy back as typed gets retyped
+    let (typist, typed) = type_python(1, |_| "garbage".to_string());
+    assert_eq!(typed, PYTHON);
+    assert_eq!(typist.stats().lines_fixed, 4);
+}
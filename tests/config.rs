@@ -0,0 +1,126 @@
+use std::time::Duration;
+use typer::profile::CharClass;
+use typer::window::FocusedWindow;
+use typer::{Config, MistakeRates, NewlineKey, TimeOfDayConfig, ViewportAction};
+
+#[test]
+fn mistake_probability_is_a_number_or_a_table() {
+    let config: Config = serde_json::from_str(r#"{ "mistakes": { "probability": 20 } }"#).unwrap();
+    assert_eq!(config.mistakes.probability, MistakeRates::uniform(20));
+
+    let config: Config = toml::from_str("[mistakes.probability]\nsymbol = 2\ndigit = 6\n").unwrap();
+    let rates = config.mistakes.probability;
+    assert_eq!(rates.symbol, 2);
+    assert_eq!(rates.digit, 6);
+    assert_eq!(rates.letter, MistakeRates::default().letter);
+
+    let typing = config.to_typing_config().unwrap();
+    assert_eq!(typing.mistake_probability.get(CharClass::of('#')), 2);
+    assert_eq!(typing.mistake_probability.get(CharClass::of('7')), 6);
+}
+
+#[test]
+fn overrides_reach_into_the_mistake_table() {
+    let overrides = [
+        ("mistakes.probability.symbol".to_string(), "3".to_string()),
+        ("delays.base_min".to_string(), "30".to_string()),
+    ];
+    let config = Config::default().with_overrides(&overrides).unwrap();
+    assert_eq!(config.mistakes.probability.symbol, 3);
+    assert_eq!(config.delays.base_min, 30);
+
+    let overrides = [("mistakes.probability".to_string(), "25".to_string())];
+    let config = Config::default().with_overrides(&overrides).unwrap();
+    assert_eq!(config.mistakes.probability, MistakeRates::uniform(25));
+
+    let overrides = [("mistakes.probability.symbol".to_string(), "0".to_string())];
+    let config = Config::default().with_overrides(&overrides).unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn viewport_settings_apply_per_app() {
+    let config: Config = toml::from_str(
+        "[viewport]\nevery_lines = 20\n\n[viewport.apps.notepad]\naction = \"ctrl_end\"\n",
+    )
+    .unwrap();
+    let notepad = FocusedWindow {
+        title: "notes.txt - Notepad".to_string(),
+        app_name: "notepad.exe".to_string(),
+        id: "1".to_string(),
+    };
+
+    assert_eq!(config.viewport.config_for(None), None);
+    let viewport = config.viewport.config_for(Some(&notepad)).unwrap();
+    assert_eq!(viewport.action, ViewportAction::CtrlEnd);
+    assert_eq!(viewport.every_lines, 20);
+}
+
+#[test]
+fn time_of_day_curve_goes_around_midnight() {
+    let config: Config =
+        toml::from_str("[time_of_day.curve]\n\"0\" = 1.5\n\"12\" = 1.0\n").unwrap();
+    let curve = config.time_of_day.curve().unwrap();
+    assert_eq!(curve, vec![(0.0, 1.5), (12.0, 1.0)]);
+
+    let time_of_day = TimeOfDayConfig {
+        curve,
+        start_hour: 6.0,
+    };
+    assert_eq!(time_of_day.factor(Duration::ZERO), 1.25);
+    assert_eq!(time_of_day.factor(Duration::from_secs(6 * 3600)), 1.0);
+    assert_eq!(time_of_day.factor(Duration::from_secs(12 * 3600)), 1.25);
+
+    let config: Config = toml::from_str("[time_of_day.curve]\n\"25\" = 1.1\n").unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn notifications_are_off_unless_asked_for() {
+    let config = Config::default();
+    assert!(!config.notifications.desktop && !config.notifications.sound);
+    assert_eq!(config.notifications.min_seconds, 60);
+
+    let config: Config =
+        toml::from_str("[notifications]\nsound = true\nsound_file = \"done.wav\"\n").unwrap();
+    assert!(config.notifications.sound);
+    assert_eq!(
+        config.notifications.sound_file.as_deref(),
+        Some(std::path::Path::new("done.wav"))
+    );
+    assert_eq!(config.notifications.min_seconds, 60);
+}
+
+#[test]
+fn app_settings_are_found_by_window() {
+    let config: Config = toml::from_str(
+        "[apps.code]\nnewline_key = \"enter\"\nauto_indent = \"home\"\n\n[apps.slack]\nspeed = 1.5\nno_mistakes = true\n",
+    )
+    .unwrap();
+    let profiles = config.app_profiles();
+    let window = |title: &str, app_name: &str| FocusedWindow {
+        title: title.to_string(),
+        app_name: app_name.to_string(),
+        id: "1".to_string(),
+    };
+
+    let (name, profile) = profiles
+        .find(&window("main.rs - typer - Visual Studio Code", "Code"))
+        .unwrap();
+    assert_eq!(name, "code");
+    assert_eq!(profile.newline_key, Some(NewlineKey::Enter));
+    assert_eq!(profile.speed, None);
+
+    let (name, profile) = profiles.find(&window("general", "Slack")).unwrap();
+    assert_eq!(name, "slack");
+    assert_eq!(profile.speed, Some(1.5));
+    assert!(profile.no_mistakes);
+    assert!(profiles.find(&window("Inbox", "Firefox")).is_none());
+
+    let overrides = [("apps.slack.speed".to_string(), "0".to_string())];
+    assert!(config
+        .with_overrides(&overrides)
+        .unwrap()
+        .validate()
+        .is_err());
+}
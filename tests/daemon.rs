@@ -0,0 +1,62 @@
+use typer::daemon::{DaemonState, DaemonStatus, Request, Response};
+
+#[test]
+fn requests_are_tagged_by_command() {
+    let request: Request = serde_json::from_str(r#"{"command":"speed","speed":1.5}"#).unwrap();
+    assert_eq!(request, Request::Speed { speed: 1.5 });
+    assert_eq!(
+        serde_json::to_string(&Request::Pause).unwrap(),
+        r#"{"command":"pause"}"#
+    );
+    assert!(serde_json::from_str::<Request>(r#"{"command":"fly"}"#).is_err());
+}
+
+#[test]
+fn errors_leave_out_the_status() {
+    let json = serde_json::to_string(&Response::error("Not typing")).unwrap();
+    assert_eq!(json, r#"{"ok":false,"error":"Not typing"}"#);
+
+    let status = DaemonStatus {
+        state: DaemonState::Paused,
+        loaded: Some("notes.txt".to_string()),
+        position: 12,
+        total: 40,
+        speed: 1.0,
+    };
+    let response: Response =
+        serde_json::from_str(&serde_json::to_string(&Response::ok(status.clone())).unwrap())
+            .unwrap();
+    assert_eq!(response.status, Some(status));
+}
+
+#[cfg(unix)]
+#[test]
+fn answers_each_line_sent_to_the_socket() {
+    use std::thread;
+    use std::time::Duration;
+    use typer::daemon::{send, serve};
+
+    let path = std::env::temp_dir().join(format!("typer-test-{}.sock", std::process::id()));
+    let served = path.clone();
+    thread::spawn(move || {
+        let _ = serve(&served, |request| match request {
+            Request::Status => Response::ok(DaemonStatus::default()),
+            _ => Response::error("Not typing"),
+        });
+    });
+    let mut response = send(&path, &Request::Status);
+    for _ in 0..50 {
+        if response.is_ok() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(20));
+        response = send(&path, &Request::Status);
+    }
+
+    assert_eq!(response.unwrap(), Response::ok(DaemonStatus::default()));
+    assert_eq!(
+        send(&path, &Request::Abort).unwrap().error.as_deref(),
+        Some("Not typing")
+    );
+    let _ = std::fs::remove_file(&path);
+}
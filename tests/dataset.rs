@@ -0,0 +1,58 @@
+use typer::dataset::{fit_profile, parse_dataset, DatasetKey};
+
+/// A press-and-release dataset typing `text` every `gap` milliseconds, with `#` for Backspace.
+fn dataset(text: &str, gap: u64) -> String {
+    let mut csv = "key,press,release\n".to_string();
+    for (i, c) in text.chars().enumerate() {
+        let key = match c {
+            '#' => "BKSP".to_string(),
+            ' ' => "SPACE".to_string(),
+            c => c.to_string(),
+        };
+        let press = i as u64 * gap;
+        csv.push_str(&format!("{},{},{}\n", key, press, press + 60));
+    }
+    csv
+}
+
+#[test]
+fn reads_press_release_and_event_layouts() {
+    let rows = parse_dataset("Key;Press;Release\na;0.1;0.15\nb;0.3;0.4\n", 1000.0).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[1].key, DatasetKey::Char('b'));
+    assert_eq!(rows[1].press, 300.0);
+    assert_eq!(rows[1].release, Some(400.0));
+
+    let events = "key\tevent\ttime\nShift\tdown\t0\nH\tdown\t10\nShift\tup\t50\nH\tup\t80\n";
+    let rows = parse_dataset(events, 1.0).unwrap();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0].key, DatasetKey::Other);
+    assert_eq!(rows[0].release, Some(50.0));
+    assert_eq!(rows[1].key, DatasetKey::Char('H'));
+    assert_eq!(rows[1].release, Some(80.0));
+
+    assert!(parse_dataset("key,when\na,1\n", 1.0).is_err());
+}
+
+#[test]
+fn fits_bigrams_and_error_rates() {
+    let text = "the thin theme then the theory of the thaw and thx#e the thesis";
+    let presses = parse_dataset(&dataset(text, 120), 1.0).unwrap();
+    let profile = fit_profile("dataset", &presses).unwrap();
+
+    assert_eq!(profile.name, "dataset");
+    assert_eq!(profile.keystroke.unwrap().mean, 120.0);
+    assert!(profile.bigrams.contains_key("th"));
+    assert!(!profile.bigrams.contains_key("xe"));
+    let letter = profile.error_rates.letter.unwrap();
+    assert!(letter > 0.0 && letter < 5.0);
+    assert!(profile.validate().is_ok());
+}
+
+#[test]
+fn skips_breaks_between_phrases() {
+    let mut csv = dataset("a quick brown fox jumps over the lazy dog", 100);
+    csv.push_str("x,60000,60050\n");
+    let profile = fit_profile("", &parse_dataset(&csv, 1.0).unwrap()).unwrap();
+    assert_eq!(profile.keystroke.unwrap().mean, 100.0);
+}
@@ -0,0 +1,163 @@
+use typer::diff::{
+    added_lines, diff_lines, parse_patch, plan, type_diff, DiffStep, LineChange, PatchFile,
+};
+use typer::events::Key;
+use typer::{HumanTypist, MockBackend, TypingConfig};
+
+/// Runs a plan on a plain editor: the document, the cursor and the other end of the
+/// selection, all as character indices.
+fn edit(text: &str, steps: &[DiffStep]) -> String {
+    let mut doc: Vec<char> = text.chars().collect();
+    let (mut cursor, mut anchor) = (0, 0);
+    for step in steps {
+        match step {
+            DiffStep::Type(text) => {
+                for c in text.chars() {
+                    doc.insert(cursor, c);
+                    cursor += 1;
+                }
+                anchor = cursor;
+            }
+            DiffStep::Press(chord, count) => {
+                let shift = chord.modifiers.contains(&Key::Shift);
+                let ctrl = chord.modifiers.contains(&Key::Control);
+                for _ in 0..*count {
+                    match chord.key {
+                        Key::Home if ctrl => cursor = 0,
+                        Key::End if ctrl => cursor = doc.len(),
+                        Key::Down => {
+                            // Plans only go down from the start of a line
+                            cursor = match doc[cursor..].iter().position(|&c| c == '\n') {
+                                Some(newline) => cursor + newline + 1,
+                                None => doc.len(),
+                            }
+                        }
+                        Key::Delete if anchor != cursor => {
+                            let (start, end) = (anchor.min(cursor), anchor.max(cursor));
+                            doc.drain(start..end);
+                            cursor = start;
+                        }
+                        Key::Delete if cursor < doc.len() => {
+                            doc.remove(cursor);
+                        }
+                        Key::Backspace if cursor > 0 => {
+                            cursor -= 1;
+                            doc.remove(cursor);
+                        }
+                        Key::Delete | Key::Backspace => {}
+                        key => panic!("unexpected key {:?}", key),
+                    }
+                    if !shift {
+                        anchor = cursor;
+                    }
+                }
+            }
+        }
+    }
+    doc.into_iter().collect()
+}
+
+#[test]
+fn changed_lines_are_removed_before_their_replacements() {
+    let changes = diff_lines("a\nb\nc\n", "a\nB\nc\nd\n");
+    assert_eq!(
+        changes,
+        [
+            LineChange::Keep("a"),
+            LineChange::Remove("b"),
+            LineChange::Add("B"),
+            LineChange::Keep("c"),
+            LineChange::Add("d"),
+            LineChange::Keep(""),
+        ]
+    );
+    assert_eq!(added_lines(&changes), "B\nd\n");
+}
+
+#[test]
+fn plans_edit_the_old_text_into_the_new_one() {
+    let cases = [
+        ("a\nb\nc\n", "a\nB\nc\nd\n"),
+        ("one\ntwo\nthree", "one\ntwo\nthree\nfour"),
+        ("one\ntwo\nthree", "one"),
+        ("one\ntwo\nthree", "zero\nthree"),
+        ("", "hello\nworld"),
+        ("gone\nalso gone", ""),
+        ("x\ny\nz", "new\nz\nend"),
+        (
+            "fn main() {\n}\n",
+            "use std::io;\n\nfn main() {\n    run();\n}\n",
+        ),
+    ];
+    for (old, new) in cases {
+        let steps = plan(&diff_lines(old, new));
+        assert_eq!(edit(old, &steps), new, "editing {:?}: {:?}", old, steps);
+    }
+}
+
+#[test]
+fn patches_give_the_lines_added_to_each_file() {
+    let patch = "\
+diff --git a/src/main.rs b/src/main.rs
+index 1234567..89abcde 100644
+--- a/src/main.rs
++++ b/src/main.rs
+@@ -1,3 +1,4 @@
++use std::io;
+ fn main() {
+-    old();
++    new();
+ }
+@@ -10,2 +11,3 @@ fn helper() {
+     let x = 1;
++    let y = 2;
+ }
+diff --git a/gone.txt b/gone.txt
+deleted file mode 100644
+--- a/gone.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-bye
+diff --git a/notes.md b/notes.md
+new file mode 100644
+--- /dev/null
++++ b/notes.md
+@@ -0,0 +1,2 @@
++# Notes
+++1 for this
+";
+    assert_eq!(
+        parse_patch(patch),
+        [
+            PatchFile {
+                path: "src/main.rs".to_string(),
+                added: "use std::io;\n    new();\n\n    let y = 2;\n".to_string(),
+            },
+            PatchFile {
+                path: "notes.md".to_string(),
+                added: "# Notes\n+1 for this\n".to_string(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn only_changes_are_typed() {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            exact: true,
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(1)
+        .instant(true)
+        .build();
+    let steps = plan(&diff_lines(
+        "# Notes\n\nFirst point\n",
+        "# Notes\n\nFirst point\nSecond point\n",
+    ));
+    type_diff(&mut typist, &steps);
+
+    assert_eq!(backend.typed(), "Second point\n");
+}
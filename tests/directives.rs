@@ -0,0 +1,225 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use typer::directives::{strip_directives, Directive, Script};
+use typer::events::{Chord, Key, KeyAction};
+use typer::shell::PromptDetection;
+use typer::{Delay, HumanTypist, MistakeRates, MockBackend, ShellConfig, TypingConfig};
+
+#[test]
+fn directives_are_taken_out_of_the_text() {
+    let script = Script::parse("Hi{{pause:1.5s}} there{{key:ctrl+s}}{{speed:2x}}!").unwrap();
+
+    assert_eq!(script.text, "Hi there!");
+    assert_eq!(
+        script.directives,
+        [
+            (2, Directive::Pause(1500)),
+            (8, Directive::Key(Chord::parse("ctrl+s").unwrap())),
+            (8, Directive::Speed(2.0)),
+        ]
+    );
+}
+
+#[test]
+fn unknown_braces_stay_and_stop_ends_the_text() {
+    assert_eq!(
+        strip_directives("format!(\"{{}}\", x);{{stop}} not typed"),
+        "format!(\"{{}}\", x);"
+    );
+    assert!(Script::parse("{{pause:soon}}").is_err());
+    assert!(Script::parse("{{key:nope}}").is_err());
+}
+
+#[test]
+fn directives_press_keys_and_pause() {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(1)
+        .instant(true)
+        .build();
+    typist.type_script(&Script::parse("a{{key:tab}}b{{pause:60000}}").unwrap());
+
+    assert_eq!(backend.typed(), "a\tb");
+    assert!(backend
+        .actions()
+        .contains(&KeyAction::Click { key: Key::Tab }));
+    assert!(typist.elapsed() >= Duration::from_secs(60));
+}
+
+#[test]
+fn mouse_directives_move_and_click() {
+    let script = Script::parse("{{click:10,20}}Name{{move:5, 6}}{{doubleclick}}").unwrap();
+    assert_eq!(
+        script.directives,
+        [
+            (
+                0,
+                Directive::Click {
+                    at: Some((10, 20)),
+                    double: false
+                }
+            ),
+            (4, Directive::Move(5, 6)),
+            (
+                4,
+                Directive::Click {
+                    at: None,
+                    double: true
+                }
+            ),
+        ]
+    );
+    assert!(Script::parse("{{click:here}}").is_err());
+
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(1)
+        .instant(true)
+        .build();
+    typist.type_script(&script);
+
+    let mouse: Vec<KeyAction> = backend
+        .actions()
+        .into_iter()
+        .filter(|action| matches!(action, KeyAction::MouseMove { .. } | KeyAction::MouseClick))
+        .collect();
+    assert_eq!(
+        mouse,
+        [
+            KeyAction::MouseMove { x: 10, y: 20 },
+            KeyAction::MouseClick,
+            KeyAction::MouseMove { x: 5, y: 6 },
+            KeyAction::MouseClick,
+            KeyAction::MouseClick,
+        ]
+    );
+    assert_eq!(backend.typed(), "Name");
+}
+
+#[test]
+fn paste_blocks_are_pasted_instead_of_typed() {
+    let script = Script::parse("use a;\n{{paste}}// License: MIT\n{{/paste}}fn main").unwrap();
+    assert_eq!(script.text, "use a;\n// License: MIT\nfn main");
+    assert_eq!(script.directives, [(7, Directive::Paste { end: 23 })]);
+    assert!(Script::parse("{{paste}}never closed").is_err());
+
+    let clipboard = Arc::new(Mutex::new(Vec::new()));
+    let copied = Arc::clone(&clipboard);
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .clipboard_writer(move |text| {
+            copied.lock().unwrap().push(text.to_string());
+            true
+        })
+        .seed(1)
+        .instant(true)
+        .build();
+    typist.type_script(&script);
+
+    assert_eq!(*clipboard.lock().unwrap(), ["// License: MIT\n"]);
+    // Everything but the pasted block is typed
+    assert_eq!(backend.typed(), "use a;\nfn main");
+    assert!(backend.actions().contains(&KeyAction::Click {
+        key: Key::Char('v')
+    }));
+}
+
+#[test]
+fn shell_commands_wait_after_enter() {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            shell: Some(ShellConfig {
+                command_delay: Delay::Uniform(60_000..60_001),
+                prompt: None,
+            }),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(1)
+        .instant(true)
+        .build();
+    typist.type_script(&Script::parse("ls -la\n\n\nmake{{wait:120s}}\necho done").unwrap());
+
+    assert_eq!(backend.typed(), "ls -la\nmake\necho done\n");
+    // One command delay and the wait of `make`, nothing after the last command
+    assert!(typist.elapsed() >= Duration::from_secs(180));
+    assert!(typist.elapsed() < Duration::from_secs(240));
+}
+
+#[test]
+fn shell_commands_wait_for_the_prompt() {
+    // The region changes twice after Enter, then shows the prompt for good
+    let mut frames = [0, 1, 2].into_iter();
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(u32::MAX),
+            shell: Some(ShellConfig {
+                command_delay: Delay::Uniform(600_000..600_001),
+                prompt: Some(PromptDetection {
+                    settle: Duration::from_secs(2),
+                    timeout: Duration::from_secs(300),
+                }),
+            }),
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .screen(move || Some(vec![frames.next().unwrap_or(3)]))
+        .seed(1)
+        .instant(true)
+        .build();
+    typist.type_text("make\nls");
+
+    assert_eq!(backend.typed(), "make\nls\n");
+    assert!(typist.elapsed() >= Duration::from_secs(2));
+    assert!(typist.elapsed() < Duration::from_secs(60));
+}
+
+#[test]
+fn named_keys_and_chords_are_pressed() {
+    assert_eq!(Chord::parse("Ctrl+S").unwrap(), Chord::ctrl('s'));
+    assert_eq!(
+        Chord::parse("shift+tab").unwrap(),
+        Chord::key(Key::Tab).with(Key::Shift)
+    );
+    assert_eq!(Key::parse("Esc").unwrap(), Key::Escape);
+    assert_eq!(Key::parse("pgdn").unwrap(), Key::PageDown);
+
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .backend(backend.clone())
+        .instant(true)
+        .build();
+    typist.press(Chord::ctrl('s'));
+    typist.press(Key::Escape);
+
+    assert_eq!(
+        backend.actions(),
+        [
+            KeyAction::Down { key: Key::Control },
+            KeyAction::Click {
+                key: Key::Char('s')
+            },
+            KeyAction::Up { key: Key::Control },
+            KeyAction::Click { key: Key::Escape },
+        ]
+    );
+    assert_eq!(backend.typed(), "");
+}
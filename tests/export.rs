@@ -0,0 +1,92 @@
+use typer::events::{Key, KeyAction, KeyEvent};
+use typer::export::{export_script, ScriptFormat};
+
+fn event(delay_ms: u64, action: KeyAction) -> KeyEvent {
+    KeyEvent {
+        at_ms: 0,
+        delay_ms,
+        action,
+        mistake: false,
+        correction: false,
+    }
+}
+
+fn events() -> Vec<KeyEvent> {
+    vec![
+        event(0, KeyAction::Type { char: '"' }),
+        event(120, KeyAction::Type { char: '\'' }),
+        event(
+            80,
+            KeyAction::Click {
+                key: Key::Backspace,
+            },
+        ),
+        event(1500, KeyAction::Down { key: Key::Shift }),
+        event(
+            0,
+            KeyAction::Click {
+                key: Key::Char('a'),
+            },
+        ),
+        event(0, KeyAction::Up { key: Key::Shift }),
+    ]
+}
+
+#[test]
+fn ahk_script_sends_events_with_their_delays() {
+    let script = export_script(&events(), ScriptFormat::Ahk, 3000);
+    let body: Vec<&str> = script
+        .lines()
+        .skip_while(|line| *line != "Sleep 3000")
+        .collect();
+
+    assert_eq!(
+        body,
+        [
+            "Sleep 3000",
+            "SendText \"`\"\"",
+            "Sleep 120",
+            "SendText \"'\"",
+            "Sleep 80",
+            "Send \"{Backspace}\"",
+            "Sleep 1500",
+            "Send \"{Shift down}\"",
+            "Send \"{a}\"",
+            "Send \"{Shift up}\"",
+            "ExitApp",
+        ]
+    );
+}
+
+#[test]
+fn xdotool_script_quotes_typed_characters() {
+    let script = export_script(&events(), ScriptFormat::Xdotool, 3000);
+    let body: Vec<&str> = script
+        .lines()
+        .skip_while(|line| *line != "sleep 3.000")
+        .collect();
+
+    assert!(script.starts_with("#!/bin/sh\n"));
+    assert_eq!(
+        body,
+        [
+            "sleep 3.000",
+            "xdotool type --delay 0 -- '\"'",
+            "sleep 0.120",
+            "xdotool type --delay 0 -- ''\\'''",
+            "sleep 0.080",
+            "xdotool key -- BackSpace",
+            "sleep 1.500",
+            "xdotool keydown -- shift",
+            "xdotool key -- a",
+            "xdotool keyup -- shift",
+        ]
+    );
+}
+
+#[test]
+fn script_format_names() {
+    assert_eq!("ahk".parse(), Ok(ScriptFormat::Ahk));
+    assert_eq!("xdotool".parse(), Ok(ScriptFormat::Xdotool));
+    assert!("bat".parse::<ScriptFormat>().is_err());
+}
@@ -0,0 +1,109 @@
+use typer::events::{Chord, Key, KeyAction};
+use typer::form::{fill_form, parse_csv_records, parse_json_records, FieldOptions, FormField};
+use typer::guards::GuardSettings;
+use typer::window::FocusedWindow;
+use typer::{Config, HumanTypist, MistakeRates, MockBackend, TypingConfig, TypingControls};
+
+fn field(name: &str, value: &str, options: FieldOptions) -> FormField {
+    FormField {
+        name: name.to_string(),
+        value: value.to_string(),
+        options,
+    }
+}
+
+#[test]
+fn records_keep_the_field_order_of_the_file() {
+    let records = parse_json_records(r#"{ "name": "Ada", "age": 36, "email": null }"#).unwrap();
+    assert_eq!(
+        records,
+        [vec![
+            ("name".to_string(), "Ada".to_string()),
+            ("age".to_string(), "36".to_string()),
+            ("email".to_string(), String::new()),
+        ]]
+    );
+
+    let records = parse_csv_records("name;city\nAda;London\nAlan;Wilmslow\n").unwrap();
+    assert_eq!(records.len(), 2);
+    assert_eq!(
+        records[1],
+        [
+            ("name".to_string(), "Alan".to_string()),
+            ("city".to_string(), "Wilmslow".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn fields_are_typed_with_their_own_options() {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            mistake_probability: MistakeRates::uniform(1),
+            correction_probability: 0,
+            ..TypingConfig::default()
+        })
+        .backend(backend.clone())
+        .seed(3)
+        .instant(true)
+        .build();
+    let exact = FieldOptions {
+        mistakes: false,
+        ..FieldOptions::default()
+    };
+    let fields = [
+        field("email", "ada@example.com", exact.clone()),
+        field(
+            "amount",
+            "12.5",
+            FieldOptions {
+                keypad: true,
+                next: Some(Chord::key(Key::Down)),
+                ..exact
+            },
+        ),
+        field("note", "", FieldOptions::default()),
+    ];
+    fill_form(
+        &mut typist,
+        &fields,
+        &Chord::key(Key::Tab),
+        Some(&Chord::key(Key::Return)),
+    );
+
+    assert_eq!(backend.typed(), "ada@example.com\t12.5\n");
+    let actions = backend.actions();
+    assert!(actions.contains(&KeyAction::Click {
+        key: Key::Keypad('5')
+    }));
+    assert!(actions.contains(&KeyAction::Click { key: Key::Down }));
+}
+
+#[test]
+fn forms_are_not_filled_into_a_blacklisted_window() {
+    let config: Config = toml::from_str("[lock]\non_lock = \"ignore\"\n").unwrap();
+    let controls = TypingControls::new();
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .backend(backend.clone())
+        .controls(controls.clone())
+        .instant(true)
+        .build();
+    let fields = [field("password", "hunter2", FieldOptions::default())];
+    let window = FocusedWindow {
+        title: "Passwords.kdbx - KeePassXC".to_string(),
+        app_name: "keepassxc".to_string(),
+        id: "1".to_string(),
+    };
+
+    // The same steps as `typer form` once the countdown is over
+    let guards = GuardSettings::new(&config, false).unwrap();
+    let filled = guards
+        .start(&controls, Some(window), |_| {})
+        .map(|_guards| fill_form(&mut typist, &fields, &Chord::key(Key::Tab), None));
+
+    let refused = filled.err().unwrap();
+    assert!(refused.contains("keepass"), "{}", refused);
+    assert_eq!(backend.typed(), "");
+}
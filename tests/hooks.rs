@@ -0,0 +1,75 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread;
+use typer::hooks::{run_hook, HookEvent, HookPayload};
+
+fn payload(event: HookEvent) -> HookPayload {
+    HookPayload {
+        event,
+        source: "notes.txt".to_string(),
+        position: 120,
+        total: 500,
+        elapsed_ms: 30_000,
+        mistakes: 4,
+    }
+}
+
+#[cfg(unix)]
+#[test]
+fn commands_get_the_event_and_payload() {
+    let out = std::env::temp_dir().join(format!("typer-hook-{}.txt", std::process::id()));
+    let command = format!(
+        "printf '%s %s' \"$TYPER_EVENT\" \"$TYPER_PAYLOAD\" > {}",
+        out.display()
+    );
+    run_hook(&command, &payload(HookEvent::Complete)).unwrap();
+
+    let written = std::fs::read_to_string(&out).unwrap();
+    let _ = std::fs::remove_file(&out);
+    let (event, json) = written.split_once(' ').unwrap();
+    assert_eq!(event, "complete");
+    let json: serde_json::Value = serde_json::from_str(json).unwrap();
+    assert_eq!(json["source"], "notes.txt");
+    assert_eq!(json["mistakes"], 4);
+
+    assert!(run_hook("exit 3", &payload(HookEvent::Abort)).is_err());
+}
+
+#[test]
+fn webhooks_get_the_payload_posted() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/done", listener.local_addr().unwrap());
+    let server = thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        let mut request_line = String::new();
+        reader.read_line(&mut request_line).unwrap();
+        let mut length = 0;
+        loop {
+            let mut header = String::new();
+            reader.read_line(&mut header).unwrap();
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.eq_ignore_ascii_case("content-length") {
+                    length = value.trim().parse().unwrap();
+                }
+            }
+        }
+        let mut body = vec![0; length];
+        reader.read_exact(&mut body).unwrap();
+        stream
+            .write_all(b"HTTP/1.1 204 No Content\r\nContent-Length: 0\r\n\r\n")
+            .unwrap();
+        (request_line, String::from_utf8(body).unwrap())
+    });
+
+    run_hook(&url, &payload(HookEvent::Start)).unwrap();
+
+    let (request_line, body) = server.join().unwrap();
+    assert!(request_line.starts_with("POST /done "));
+    let json: serde_json::Value = serde_json::from_str(&body).unwrap();
+    assert_eq!(json["event"], "start");
+    assert_eq!(json["position"], 120);
+}
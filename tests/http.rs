@@ -0,0 +1,118 @@
+use typer::daemon::Request;
+use typer::http::{authorize, route, same_token};
+
+const JSON: Option<&str> = Some("application/json");
+
+#[test]
+fn routes_endpoints_to_daemon_requests() {
+    assert_eq!(route("GET", "/status", None, ""), Ok(Request::Status));
+    assert_eq!(route("post", "/abort/", JSON, ""), Ok(Request::Abort));
+    assert_eq!(
+        route("POST", "/text?from=raycast", JSON, r#""Hello""#),
+        Ok(Request::Load {
+            text: "Hello".to_string(),
+            origin: "text sent over HTTP".to_string(),
+        })
+    );
+    assert_eq!(
+        route(
+            "POST",
+            "/text",
+            Some("application/json; charset=utf-8"),
+            r#"{"text":"Hi"}"#
+        ),
+        Ok(Request::Load {
+            text: "Hi".to_string(),
+            origin: "text sent over HTTP".to_string(),
+        })
+    );
+    assert_eq!(
+        route("PUT", "/speed", JSON, r#"{"speed":1.5}"#),
+        Ok(Request::Speed { speed: 1.5 })
+    );
+    assert_eq!(
+        route("PUT", "/speed", JSON, "2"),
+        Ok(Request::Speed { speed: 2.0 })
+    );
+}
+
+#[test]
+fn config_changes_become_overrides() {
+    let request = route(
+        "PATCH",
+        "/config",
+        JSON,
+        r#"{"mistakes.probability":20,"keyboard.layout":"dvorak"}"#,
+    );
+    assert_eq!(
+        request,
+        Ok(Request::Set {
+            overrides: vec![
+                ("keyboard.layout".to_string(), "dvorak".to_string()),
+                ("mistakes.probability".to_string(), "20".to_string()),
+            ],
+        })
+    );
+}
+
+#[test]
+fn config_changes_cannot_run_commands_or_name_files() {
+    let refused = |body| route("PATCH", "/config", JSON, body).unwrap_err().0;
+    assert_eq!(refused(r#"{"hooks.on_start":"rm -rf ~"}"#), 403);
+    assert_eq!(refused(r#"{"keyboard.layout_file":"/etc/passwd"}"#), 403);
+    assert_eq!(refused(r#"{"notifications.sound_file":"x.wav"}"#), 403);
+    assert_eq!(refused(r#"{"profiles.code":"{}"}"#), 403);
+}
+
+#[test]
+fn rejects_unknown_endpoints_methods_and_bodies() {
+    assert_eq!(route("GET", "/nope", None, "").unwrap_err().0, 404);
+    assert_eq!(route("GET", "/start", None, "").unwrap_err().0, 405);
+    assert_eq!(route("PUT", "/speed", JSON, "fast").unwrap_err().0, 400);
+    assert_eq!(route("PATCH", "/config", JSON, "[1]").unwrap_err().0, 400);
+    assert_eq!(route("POST", "/text", JSON, "Hello").unwrap_err().0, 400);
+}
+
+#[test]
+fn changes_must_be_json() {
+    assert_eq!(route("POST", "/start", None, "").unwrap_err().0, 415);
+    let form = Some("application/x-www-form-urlencoded");
+    assert_eq!(route("POST", "/text", form, "Hello").unwrap_err().0, 415);
+    assert_eq!(
+        route("POST", "/text", Some("text/plain"), r#""Hi""#)
+            .unwrap_err()
+            .0,
+        415
+    );
+}
+
+#[test]
+fn requests_need_the_token_and_no_origin() {
+    assert_eq!(authorize(Some("Bearer secret"), None, "secret"), Ok(()));
+    assert_eq!(authorize(None, None, "secret").unwrap_err().0, 401);
+    assert_eq!(
+        authorize(Some("Bearer secre"), None, "secret")
+            .unwrap_err()
+            .0,
+        401
+    );
+    assert_eq!(
+        authorize(Some("secret"), None, "secret").unwrap_err().0,
+        401
+    );
+    let page = Some("https://example.com");
+    assert_eq!(
+        authorize(Some("Bearer secret"), page, "secret")
+            .unwrap_err()
+            .0,
+        403
+    );
+}
+
+#[test]
+fn tokens_match_only_when_equal() {
+    assert!(same_token("secret", "secret"));
+    assert!(!same_token("secreT", "secret"));
+    assert!(!same_token("secrets", "secret"));
+    assert!(!same_token("", "secret"));
+}
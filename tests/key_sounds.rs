@@ -0,0 +1,68 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use std::fs;
+use typer::events::{Key, KeyAction};
+use typer::key_sounds::{makes_click, sample_files, synthesize_click, ClickVariation, CLICK_RATE};
+
+#[test]
+fn made_up_clicks_differ_by_seed() {
+    let click = synthesize_click(1);
+    assert_eq!((click.channels, click.rate), (1, CLICK_RATE));
+    assert!(!click.data.is_empty());
+    assert!(click
+        .data
+        .iter()
+        .all(|sample| (-1.0..=1.0).contains(sample)));
+    assert_eq!(click, synthesize_click(1));
+    assert_ne!(click, synthesize_click(2));
+}
+
+#[test]
+fn clicks_vary_within_the_settings() {
+    let variation = ClickVariation {
+        volume: 50,
+        volume_variation: 20,
+        pitch_variation: 10,
+    };
+    let mut rng = StdRng::seed_from_u64(7);
+    for _ in 0..200 {
+        let (volume, speed) = variation.pick(&mut rng);
+        assert!((0.4..=0.6).contains(&volume), "volume {}", volume);
+        assert!((0.9..=1.1).contains(&speed), "speed {}", speed);
+    }
+
+    let steady = ClickVariation {
+        volume: 80,
+        volume_variation: 0,
+        pitch_variation: 0,
+    };
+    assert_eq!(steady.pick(&mut rng), (0.8, 1.0));
+}
+
+#[test]
+fn only_keys_going_down_click() {
+    assert!(makes_click(&KeyAction::Type { char: 'a' }));
+    assert!(makes_click(&KeyAction::Down { key: Key::Shift }));
+    assert!(makes_click(&KeyAction::Click { key: Key::Return }));
+    assert!(!makes_click(&KeyAction::Up { key: Key::Shift }));
+    assert!(!makes_click(&KeyAction::MouseClick));
+}
+
+#[test]
+fn directories_of_samples_are_expanded() {
+    let dir = std::env::temp_dir().join(format!("typer-clicks-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    for name in ["b.ogg", "a.WAV", "notes.txt"] {
+        fs::write(dir.join(name), "").unwrap();
+    }
+    let files = sample_files(&[dir.clone(), "space.wav".into()]).unwrap();
+    assert_eq!(
+        files,
+        [dir.join("a.WAV"), dir.join("b.ogg"), "space.wav".into()]
+    );
+
+    let empty = dir.join("empty");
+    fs::create_dir_all(&empty).unwrap();
+    assert!(sample_files(&[empty]).is_err());
+    fs::remove_dir_all(&dir).unwrap();
+}
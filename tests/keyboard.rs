@@ -0,0 +1,17 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use typer::KeyboardLayout;
+
+#[test]
+fn nearby_keys_of_umlauts_keep_their_case() {
+    let layout = KeyboardLayout::named("qwertz").unwrap();
+    let mut rng = StdRng::seed_from_u64(1);
+    let nearby: String = (0..50)
+        .map(|_| layout.get_nearby_key('Ü', &mut rng))
+        .collect();
+    // 'ß' has no single uppercase letter and stays as it is
+    assert!(nearby.chars().all(|c| "P+ßÖÄ".contains(c)), "{}", nearby);
+    assert!(nearby.contains('Ö') || nearby.contains('Ä'), "{}", nearby);
+
+    assert!("pßöä+".contains(layout.get_nearby_key('ü', &mut rng)));
+}
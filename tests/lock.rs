@@ -0,0 +1,53 @@
+use typer::lock::{parse_ioreg, parse_loginctl, SessionState};
+
+#[test]
+fn loginctl_tells_locked_graphical_sessions() {
+    assert_eq!(
+        parse_loginctl("Type=x11\nLockedHint=no\n"),
+        Some(SessionState::Active)
+    );
+    assert_eq!(
+        parse_loginctl("Type=wayland\nLockedHint=yes\n"),
+        Some(SessionState::Locked)
+    );
+    assert_eq!(
+        parse_loginctl("LockedHint=no\n"),
+        Some(SessionState::Active)
+    );
+    // Sessions without a screen, like SSH or tmux over SSH, tell nothing
+    assert_eq!(parse_loginctl("Type=tty\nLockedHint=no\n"), None);
+    assert_eq!(parse_loginctl("Type=unspecified\nLockedHint=yes\n"), None);
+    assert_eq!(parse_loginctl(""), None);
+}
+
+#[test]
+fn ioreg_tells_locked_and_inactive_sessions() {
+    let users = |properties: &str| {
+        format!(
+            "+-o Root  <class IORegistryEntry, id 0x100000100>\n    {{\n      \"IOConsoleUsers\" = ({{{}}})\n    }}\n",
+            properties
+        )
+    };
+    let active = users("\"kCGSSessionOnConsoleKey\"=Yes,\"kCGSSessionUserIDKey\"=501");
+    assert_eq!(parse_ioreg(&active, 501), Some(SessionState::Active));
+    let locked = users("\"CGSSessionScreenIsLocked\"=Yes,\"kCGSSessionUserIDKey\"=501");
+    assert_eq!(parse_ioreg(&locked, 501), Some(SessionState::Locked));
+    let switched = users("\"kCGSSessionOnConsoleKey\"=No,\"kCGSSessionUserIDKey\"=501");
+    assert_eq!(parse_ioreg(&switched, 501), Some(SessionState::Inactive));
+    assert_eq!(parse_ioreg(&active, 502), None);
+    assert_eq!(parse_ioreg("+-o Root\n", 501), None);
+}
+
+#[test]
+fn ioreg_reads_only_the_current_user() {
+    let output = concat!(
+        "    \"IOConsoleUsers\" = (",
+        "{\"kCGSSessionUserIDKey\"=501,\"kCGSSessionOnConsoleKey\"=No,",
+        "\"CGSSessionScreenIsLocked\"=Yes},",
+        "{\"kCGSSessionUserIDKey\"=5012,\"kCGSSessionOnConsoleKey\"=No},",
+        "{\"kCGSSessionOnConsoleKey\"=Yes,\"kCGSSessionUserIDKey\"=502})\n",
+    );
+    assert_eq!(parse_ioreg(output, 502), Some(SessionState::Active));
+    assert_eq!(parse_ioreg(output, 501), Some(SessionState::Locked));
+    assert_eq!(parse_ioreg(output, 5012), Some(SessionState::Inactive));
+}
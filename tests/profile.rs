@@ -0,0 +1,104 @@
+use std::sync::{Arc, Mutex};
+use typer::profile::{CharClass, Latency, PersonalProfile};
+use typer::{HumanTypist, KeyboardLayout, MistakeRates, MockBackend, TypingConfig};
+
+const PROFILE: &str = r#"{
+    "name": "me",
+    "bigrams": { "th": { "mean": 40 }, "he": { "mean": 90, "std_dev": 0 } },
+    "keystroke": { "mean": 150 },
+    "error_rates": { "letter": 0, "symbol": 100 },
+    "corrections": { "probability": 0, "lag_chars_min": 1, "lag_chars_max": 3 }
+}"#;
+
+fn profile() -> PersonalProfile {
+    let profile: PersonalProfile = serde_json::from_str(PROFILE).unwrap();
+    profile.validate().unwrap();
+    profile
+}
+
+/// Types `text` with `profile` and returns the delays between key events.
+fn delays(profile: PersonalProfile, text: &str) -> (Vec<u64>, String) {
+    let delays = Arc::new(Mutex::new(Vec::new()));
+    let recorded = Arc::clone(&delays);
+    let backend = MockBackend::new();
+    let config = TypingConfig {
+        mistake_probability: MistakeRates::uniform(1),
+        transposition_probability: 0,
+        double_letter_probability: 0,
+        omission_probability: 0,
+        case_mistake_probability: 0,
+        long_pause_probability: 0,
+        personal: Some(profile),
+        ..TypingConfig::default()
+    };
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .keyboard(KeyboardLayout::named("qwerty").unwrap())
+        .backend(backend.clone())
+        .on_key_event(move |event| recorded.lock().unwrap().push(event.delay_ms))
+        .seed(5)
+        .instant(true)
+        .build();
+    typist.type_text(text);
+
+    let delays = delays.lock().unwrap().clone();
+    (delays, backend.typed())
+}
+
+#[test]
+fn measured_bigram_delays_replace_the_base_delay() {
+    let (delays, typed) = delays(profile(), "the");
+    assert_eq!(typed, "the");
+    assert_eq!(delays, [0, 40, 90]);
+}
+
+#[test]
+fn other_pairs_use_the_keystroke_delay() {
+    let (delays, _) = delays(profile(), "xyz");
+    assert_eq!(delays, [0, 150, 150]);
+}
+
+#[test]
+fn error_rates_per_class_override_the_mistake_probability() {
+    let (_, typed) = delays(profile(), "abc");
+    assert_eq!(typed, "abc");
+
+    // Letters are now always typed wrong, and never corrected
+    let mut sloppy = profile();
+    sloppy.error_rates.letter = Some(100.0);
+    let (_, typed) = delays(sloppy, "abc");
+    assert!(
+        typed.chars().zip("abc".chars()).all(|(a, b)| a != b),
+        "{:?}",
+        typed
+    );
+}
+
+#[test]
+fn corrections_override_the_typing_config() {
+    let mut config = TypingConfig::default();
+    profile().apply_corrections(&mut config);
+    assert_eq!(config.correction_probability, 0);
+    assert_eq!(config.correction_lag_chars, 1..=3);
+}
+
+#[test]
+fn latency_lookup_ignores_case() {
+    let profile = profile();
+    assert_eq!(profile.latency('T', 'H').map(|l| l.mean), Some(40.0));
+    assert_eq!(
+        profile.latency('q', 'q'),
+        Some(Latency {
+            mean: 150.0,
+            std_dev: 0.0
+        })
+    );
+    assert_eq!(CharClass::of('#'), CharClass::Symbol);
+}
+
+#[test]
+fn invalid_profiles_are_rejected() {
+    let mut profile = profile();
+    profile.error_rates.digit = Some(150.0);
+    assert!(profile.validate().is_err());
+}
@@ -0,0 +1,25 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use typer::reading::{hesitation, word_complexity};
+use typer::ReadAheadConfig;
+
+#[test]
+fn rare_and_symbol_heavy_words_are_more_complex() {
+    assert!(word_complexity("the") < word_complexity("cat"));
+    assert!(word_complexity("house") < word_complexity("phosphorescence"));
+    assert!(word_complexity("version") < word_complexity("v2.4.1-rc"));
+    assert!(word_complexity("hashmap") < word_complexity("HashMap"));
+    // Surrounding punctuation does not count
+    assert_eq!(word_complexity("(the)."), word_complexity("the"));
+}
+
+#[test]
+fn only_complex_words_cause_hesitation() {
+    let config = ReadAheadConfig::default();
+    let mut rng = StdRng::seed_from_u64(1);
+
+    assert_eq!(hesitation(&config, "the", &mut rng), None);
+    assert_eq!(hesitation(&config, "because", &mut rng), None);
+    let pause = hesitation(&config, "std::collections::BTreeMap", &mut rng).unwrap();
+    assert!(pause > 0 && pause <= config.max_pause);
+}
@@ -0,0 +1,62 @@
+use std::collections::BTreeMap;
+use typer::events::Chord;
+use typer::sheet::{Case, ColumnRule, SheetLayout};
+
+#[test]
+fn cells_and_rows_are_separated_by_key_directives() {
+    let layout = SheetLayout::default();
+    let text = layout
+        .to_text("name,city\nAda,London\nAlan,\"Wilm\nslow\"\n")
+        .unwrap();
+    assert_eq!(
+        text,
+        "name{{key:tab}}city{{key:return}}\
+         Ada{{key:tab}}London{{key:return}}\
+         Alan{{key:tab}}Wilm{{key:alt+return}}slow"
+    );
+
+    let layout = SheetLayout {
+        next_cell: Chord::parse("right").unwrap(),
+        next_row: Chord::parse("down").unwrap(),
+        row_start: Some(Chord::parse("home").unwrap()),
+        header: false,
+        ..SheetLayout::default()
+    };
+    assert_eq!(
+        layout.to_text("a;b\n1;2\n3;4\n").unwrap(),
+        "1{{key:right}}2{{key:down}}{{key:home}}3{{key:right}}4"
+    );
+}
+
+#[test]
+fn columns_are_formatted_by_their_rules() {
+    let rule = ColumnRule {
+        decimals: Some(2),
+        decimal_comma: true,
+        ..ColumnRule::default()
+    };
+    assert_eq!(rule.format("3.14159"), "3,14");
+    assert_eq!(rule.format("n/a"), "n/a");
+
+    let rule = ColumnRule {
+        case: Some(Case::Upper),
+        ..ColumnRule::default()
+    };
+    assert_eq!(rule.format(" gb "), "GB");
+
+    let layout = SheetLayout {
+        header: false,
+        columns: BTreeMap::from([(
+            "id".to_string(),
+            ColumnRule {
+                skip: true,
+                ..ColumnRule::default()
+            },
+        )]),
+        ..SheetLayout::default()
+    };
+    assert_eq!(
+        layout.to_text("id,name\n7,Ada\n").unwrap(),
+        "{{key:tab}}Ada"
+    );
+}
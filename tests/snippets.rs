@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use typer::snippets::{fuzzy_score, rank, SnippetLibrary};
+
+#[test]
+fn letters_in_order_match() {
+    assert!(fuzzy_score("sig", "signature").is_some());
+    assert!(fuzzy_score("SGN", "signature").is_some());
+    assert!(fuzzy_score("gis", "signature").is_none());
+    assert!(fuzzy_score("sig", "signature") > fuzzy_score("sig", "design"));
+}
+
+#[test]
+fn exact_and_word_start_matches_rank_first() {
+    let names = ["address", "meeting-notes", "mention", "notes"].map(String::from);
+
+    assert_eq!(rank("notes", names.to_vec()), ["notes", "meeting-notes"]);
+    assert_eq!(
+        rank("", names.to_vec()),
+        ["notes", "address", "mention", "meeting-notes"]
+    );
+}
+
+#[test]
+fn names_cannot_lead_out_of_the_library() {
+    let library = SnippetLibrary::new(PathBuf::from("snippets"));
+    assert_eq!(
+        library.path("intro").unwrap(),
+        PathBuf::from("snippets").join("intro.txt")
+    );
+    for name in ["../secret", "..", "a/b", "a\\b", ".hidden", "x..", ""] {
+        assert!(library.path(name).is_err(), "{:?}", name);
+        assert!(library.read(name).is_err(), "{:?}", name);
+    }
+}
@@ -0,0 +1,40 @@
+use std::collections::HashMap;
+use typer::template::{fill, parse_var, placeholders};
+
+#[test]
+fn placeholders_are_found_once_and_directives_are_not_placeholders() {
+    assert_eq!(
+        placeholders("Dear {{name}},{{pause:500}} {{ not one }} {{}} {{name}} from {{team-lead}}"),
+        ["name", "team-lead"]
+    );
+    assert!(placeholders("{{stop}}{{key:tab}}").is_empty());
+}
+
+#[test]
+fn placeholders_are_filled() {
+    let vars = HashMap::from([("name".to_string(), "Alice".to_string())]);
+
+    assert_eq!(
+        fill("Hi {{name}}!{{pause:1s}} {{}}", &vars).unwrap(),
+        "Hi Alice!{{pause:1s}} {{}}"
+    );
+    assert!(fill("Hi {{nickname}}", &vars).is_err());
+    assert_eq!(
+        parse_var("name=Alice = Bob").unwrap(),
+        ("name".to_string(), "Alice = Bob".to_string())
+    );
+    assert!(parse_var("no value").is_err());
+}
+
+#[test]
+fn escaped_braces_are_not_placeholders() {
+    let text = r#"Hi {{name}}, println!("\{{name}}");"#;
+    assert_eq!(placeholders(text), ["name"]);
+
+    let vars = HashMap::from([("name".to_string(), "Alice".to_string())]);
+    assert_eq!(
+        fill(text, &vars).unwrap(),
+        r#"Hi Alice, println!("{{name}}");"#
+    );
+    assert_eq!(fill(r"\{{x}}", &HashMap::new()).unwrap(), "{{x}}");
+}
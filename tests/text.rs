@@ -0,0 +1,46 @@
+use typer::text::Selection;
+
+#[test]
+fn selections_count_from_one_and_include_both_ends() {
+    let text = "one\ntwo\nthree\nfour";
+    assert_eq!(Selection::parse_range("2..3"), Ok((2, Some(3))));
+    assert_eq!(
+        Selection::Lines(2, Some(3)).apply(text).unwrap(),
+        "two\nthree"
+    );
+    assert_eq!(
+        Selection::Lines(3, None).apply(text).unwrap(),
+        "three\nfour"
+    );
+    assert_eq!(Selection::Chars(5, Some(7)).apply(text).unwrap(), "two");
+    assert_eq!(Selection::Chars(15, Some(99)).apply(text).unwrap(), "four");
+
+    let (first, last) = Selection::parse_range("..2").unwrap();
+    assert_eq!(
+        Selection::Lines(first, last).apply(text).unwrap(),
+        "one\ntwo"
+    );
+
+    assert!(Selection::parse_range("0..2").is_err());
+    assert!(Selection::parse_range("40").is_err());
+    let reversed = Selection::parse_range("5..3").unwrap_err();
+    assert!(reversed.contains("end is before the start"), "{}", reversed);
+}
+
+#[test]
+fn selections_past_the_end_or_through_a_directive_are_refused() {
+    let text = "one\ntwo";
+    assert!(Selection::Lines(3, None).apply(text).is_err());
+    assert!(Selection::Chars(9, Some(12)).apply(text).is_err());
+
+    let text = "Hi{{pause:500}} there, {{x}} stays";
+    let cut = Selection::Chars(1, Some(6)).apply(text).unwrap_err();
+    assert!(cut.contains("{{pause:500}}"), "{}", cut);
+    assert!(Selection::Chars(5, None).apply(text).is_err());
+    assert_eq!(
+        Selection::Chars(1, Some(15)).apply(text).unwrap(),
+        "Hi{{pause:500}}"
+    );
+    // Braces around anything but a directive are just text
+    assert_eq!(Selection::Chars(27, None).apply(text).unwrap(), "}} stays");
+}
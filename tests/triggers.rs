@@ -0,0 +1,91 @@
+use std::net::TcpListener;
+use std::thread;
+use tungstenite::Message;
+use typer::triggers::{check_trigger, midi_trigger, StreamDeckPlugin, TriggerAction};
+use typer::Config;
+
+#[test]
+fn parses_actions() {
+    assert_eq!(
+        TriggerAction::parse("snippet code block"),
+        Ok(TriggerAction::Snippet("code block".to_string()))
+    );
+    assert_eq!(TriggerAction::parse(" Pause "), Ok(TriggerAction::Pause));
+    assert_eq!(
+        TriggerAction::parse("speed 1.5"),
+        Ok(TriggerAction::Speed(1.5))
+    );
+    assert!(TriggerAction::parse("snippet").is_err());
+    assert!(TriggerAction::parse("speed -1").is_err());
+    assert!(TriggerAction::parse("dance").is_err());
+}
+
+#[test]
+fn midi_notes_and_controllers_become_triggers() {
+    assert_eq!(midi_trigger(&[0x90, 36, 100]), Some("note 36".to_string()));
+    assert_eq!(midi_trigger(&[0x93, 36, 100]), Some("note 36".to_string()));
+    assert_eq!(midi_trigger(&[0x90, 36, 0]), None);
+    assert_eq!(midi_trigger(&[0x80, 36, 64]), None);
+    assert_eq!(midi_trigger(&[0xB0, 20, 127]), Some("cc 20".to_string()));
+    assert_eq!(midi_trigger(&[0xB0, 20, 0]), None);
+
+    assert!(check_trigger("note 36").is_ok());
+    assert!(check_trigger("deck 2,1").is_ok());
+    assert!(check_trigger("note 128").is_err());
+    assert!(check_trigger("pad 1").is_err());
+}
+
+#[test]
+fn bindings_are_validated() {
+    let config: Config =
+        toml::from_str("[triggers.bindings]\n\"note 36\" = \"snippet intro\"\n").unwrap();
+    assert!(config.validate().is_ok());
+
+    let config: Config = toml::from_str("[triggers.bindings]\n\"note 36\" = \"jump\"\n").unwrap();
+    assert!(config.validate().is_err());
+}
+
+#[test]
+fn registers_with_the_stream_deck_app_and_reports_keys() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port().to_string();
+    let app = thread::spawn(move || {
+        let (stream, _) = listener.accept().unwrap();
+        let mut socket = tungstenite::accept(stream).unwrap();
+        let register = socket.read().unwrap().into_text().unwrap();
+        for event in [
+            r#"{"event":"deviceDidConnect","device":"D1"}"#,
+            r#"{"event":"keyUp","payload":{"coordinates":{"column":1,"row":0}}}"#,
+            r#"{"event":"keyDown","payload":{"coordinates":{"column":2,"row":1}}}"#,
+        ] {
+            socket.send(Message::text(event)).unwrap();
+        }
+        socket.close(None).unwrap();
+        while socket.read().is_ok() {}
+        register
+    });
+
+    let args = [
+        "typer",
+        "-port",
+        &port,
+        "-pluginUUID",
+        "ABC",
+        "-registerEvent",
+        "registerPlugin",
+        "-info",
+        "{}",
+    ]
+    .map(String::from);
+    let plugin = StreamDeckPlugin::from_args(&args).unwrap();
+    let mut triggers = Vec::new();
+    plugin.listen(|trigger| triggers.push(trigger)).unwrap();
+
+    assert_eq!(triggers, ["deck 2,1"]);
+    let register: serde_json::Value = serde_json::from_str(&app.join().unwrap()).unwrap();
+    assert_eq!(
+        register,
+        serde_json::json!({ "event": "registerPlugin", "uuid": "ABC" })
+    );
+    assert_eq!(StreamDeckPlugin::from_args(&["typer".to_string()]), None);
+}
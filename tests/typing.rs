@@ -0,0 +1,799 @@
+use device_query::Keycode;
+use std::io::{self, Write};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use typer::apps::{AppProfile, AppSwitch};
+use typer::directives::Script;
+use typer::events::{Key, KeyAction, KeyEvent};
+use typer::hotkeys::{Jump, PauseReason};
+use typer::typos::TypoDictionary;
+use typer::{
+    AutosaveConfig, BreakConfig, BreakEvent, ChatConfig, CorrectionStyle, Delay, DistractionAction,
+    DistractionConfig, HumanTypist, KeyHoldConfig, Mishap, MishapConfig, MistakeRates, MockBackend,
+    NewlineKey, Step, TypingConfig, TypingControls, TypingOutcome, ViewportConfig, WarmUpConfig,
+};
+
+const TEXT: &str =
+    "The quick brown fox jumps over the lazy dog.\nPack my box with five dozen liquor jugs!";
+
+/// A keystroke log that can still be read after the typist took it.
+#[derive(Clone, Default)]
+struct SharedLog(Arc<Mutex<Vec<u8>>>);
+
+impl SharedLog {
+    fn events(&self) -> Vec<KeyEvent> {
+        let log = self.0.lock().unwrap();
+        String::from_utf8_lossy(&log)
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect()
+    }
+}
+
+impl Write for SharedLog {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+fn sloppy_config() -> TypingConfig {
+    TypingConfig {
+        mistake_probability: MistakeRates::uniform(5),
+        transposition_probability: 3,
+        double_letter_probability: 3,
+        omission_probability: 3,
+        case_mistake_probability: 10,
+        ..TypingConfig::default()
+    }
+}
+
+fn careful_config() -> TypingConfig {
+    TypingConfig {
+        mistake_probability: MistakeRates::uniform(u32::MAX),
+        transposition_probability: 0,
+        double_letter_probability: 0,
+        omission_probability: 0,
+        case_mistake_probability: 0,
+        long_pause_probability: 0,
+        ..TypingConfig::default()
+    }
+}
+
+fn type_with(config: TypingConfig, seed: u64, text: &str) -> (HumanTypist, MockBackend) {
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(backend.clone())
+        .seed(seed)
+        .instant(true)
+        .build();
+    assert_eq!(typist.type_text(text), TypingOutcome::Completed);
+    (typist, backend)
+}
+
+#[test]
+fn corrected_mistakes_leave_the_text_intact() {
+    for style in [
+        CorrectionStyle::Backspace,
+        CorrectionStyle::Word,
+        CorrectionStyle::CtrlBackspace,
+        CorrectionStyle::SelectAndRetype,
+    ] {
+        for seed in 0..20 {
+            let config = TypingConfig {
+                correction_style: style,
+                ..sloppy_config()
+            };
+            let (typist, backend) = type_with(config, seed, TEXT);
+
+            assert_eq!(backend.typed(), TEXT, "{:?} with seed {}", style, seed);
+            let stats = typist.stats();
+            assert!(stats.mistakes > 0);
+            assert_eq!(stats.corrections, stats.mistakes);
+        }
+    }
+}
+
+#[test]
+fn uncorrected_mistakes_stay_in_the_output() {
+    let config = TypingConfig {
+        correction_probability: 0,
+        ..sloppy_config()
+    };
+    let (typist, backend) = type_with(config, 1, TEXT);
+
+    assert_ne!(backend.typed(), TEXT);
+    assert!(typist.stats().mistakes > 0);
+    assert_eq!(typist.stats().corrections, 0);
+    assert!(!backend.actions().contains(&KeyAction::Click {
+        key: Key::Backspace
+    }));
+}
+
+#[test]
+fn every_mistake_is_reported_where_it_is_made() {
+    let positions = Arc::new(Mutex::new(Vec::new()));
+    let reported = Arc::clone(&positions);
+    let mut typist = HumanTypist::builder()
+        .config(sloppy_config())
+        .backend(MockBackend::new())
+        .seed(4)
+        .instant(true)
+        .on_mistake(move |position, elapsed| reported.lock().unwrap().push((position, elapsed)))
+        .build();
+    assert_eq!(typist.type_text(TEXT), TypingOutcome::Completed);
+
+    let positions = positions.lock().unwrap();
+    assert_eq!(positions.len(), typist.stats().mistakes);
+    assert!(positions.windows(2).all(|pair| pair[0] <= pair[1]));
+    assert!(positions.iter().all(|&(position, _)| position < TEXT.len()));
+    // Timed by the typist, so instant runs report when the mistake would have been made
+    assert!(positions[0].1 > Duration::ZERO);
+    assert!(positions
+        .iter()
+        .all(|&(_, elapsed)| elapsed <= typist.elapsed()));
+}
+
+#[test]
+fn careful_typist_makes_no_mistakes() {
+    let (typist, backend) = type_with(careful_config(), 3, TEXT);
+
+    assert_eq!(backend.typed(), TEXT);
+    assert_eq!(typist.stats().mistakes, 0);
+    let expected = TEXT.chars().filter(|&c| c != '\n').count() + 1;
+    assert_eq!(backend.actions().len(), expected);
+}
+
+#[test]
+fn same_seed_types_the_same_keystrokes() {
+    let (first, first_backend) = type_with(sloppy_config(), 42, TEXT);
+    let (second, second_backend) = type_with(sloppy_config(), 42, TEXT);
+
+    assert_eq!(first_backend.actions(), second_backend.actions());
+    assert_eq!(first.elapsed(), second.elapsed());
+}
+
+#[test]
+fn keystroke_delays_follow_the_config() {
+    let log = SharedLog::default();
+    let config = TypingConfig {
+        base_delay: Delay::Uniform(50..60),
+        thinking_delay: Delay::Uniform(500..600),
+        ..careful_config()
+    };
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(MockBackend::new())
+        .keystroke_log(log.clone())
+        .seed(7)
+        .instant(true)
+        .build();
+    typist.type_text("abcdefghijklmnopqrstuvwxyz");
+
+    let events = log.events();
+    assert_eq!(events.len(), 26);
+    assert_eq!(events[0].delay_ms, 0);
+    for event in &events[1..] {
+        assert!((50..60).contains(&event.delay_ms), "{:?}", event);
+        assert!(!event.mistake && !event.correction);
+    }
+}
+
+#[test]
+fn held_keys_type_the_text_with_rollover() {
+    for seed in 0..10 {
+        let config = TypingConfig {
+            key_hold: Some(KeyHoldConfig {
+                rollover_probability: 50,
+                ..KeyHoldConfig::default()
+            }),
+            ..sloppy_config()
+        };
+        let (_, backend) = type_with(config, seed, TEXT);
+        assert_eq!(backend.typed(), TEXT, "seed {}", seed);
+
+        // Every key goes up again, some only after the next one went down
+        let actions = backend.actions();
+        let mut down = Vec::new();
+        let mut rollovers = 0;
+        for action in &actions {
+            match *action {
+                KeyAction::Down { key } => {
+                    rollovers += usize::from(down.iter().any(|&k| matches!(k, Key::Char(_))));
+                    down.push(key);
+                }
+                KeyAction::Up { key } => down.retain(|&k| k != key),
+                _ => {}
+            }
+        }
+        assert!(down.is_empty(), "{:?} left down", down);
+        assert!(rollovers > 0);
+    }
+}
+
+#[test]
+fn keys_are_held_for_the_hold_duration() {
+    let log = SharedLog::default();
+    let config = TypingConfig {
+        base_delay: Delay::Uniform(150..160),
+        key_hold: Some(KeyHoldConfig {
+            duration: Delay::Uniform(80..90),
+            rollover_probability: 0,
+            ..KeyHoldConfig::default()
+        }),
+        ..careful_config()
+    };
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(MockBackend::new())
+        .keystroke_log(log.clone())
+        .seed(7)
+        .instant(true)
+        .build();
+    typist.type_text("hello world");
+
+    let events = log.events();
+    assert_eq!(events.len(), 22);
+    for pair in events.chunks(2) {
+        assert!(matches!(pair[0].action, KeyAction::Down { .. }));
+        assert!(matches!(pair[1].action, KeyAction::Up { .. }));
+        assert!((80..90).contains(&pair[1].delay_ms), "{:?}", pair[1]);
+    }
+    // The hold counts towards the delay until the next key
+    for event in events[2..].iter().step_by(2) {
+        assert!((60..80).contains(&event.delay_ms), "{:?}", event);
+    }
+}
+
+#[test]
+fn timeline_overlaps_keys_and_swaps_pairs() {
+    for seed in 0..10 {
+        let config = TypingConfig {
+            base_delay: Delay::Uniform(40..80),
+            key_hold: Some(KeyHoldConfig {
+                duration: Delay::Uniform(90..120),
+                timeline: true,
+                swap_probability: 20,
+                ..KeyHoldConfig::default()
+            }),
+            ..careful_config()
+        };
+        let (typist, backend) = type_with(config, seed, TEXT);
+        assert_eq!(backend.typed(), TEXT, "seed {}", seed);
+        assert!(typist.stats().mistakes > 0);
+
+        // Holds outlast the delays, so most keys are down together with the next one
+        let mut down = 0;
+        let mut overlapping = 0;
+        for action in backend.actions() {
+            match action {
+                KeyAction::Down { key: Key::Char(_) } => {
+                    overlapping += usize::from(down > 0);
+                    down += 1;
+                }
+                KeyAction::Up { key: Key::Char(_) } => down -= 1,
+                _ => {}
+            }
+        }
+        assert_eq!(down, 0);
+        assert!(overlapping > TEXT.len() / 2);
+    }
+}
+
+#[test]
+fn misspelled_words_get_corrected() {
+    let text = "I definitely believe The weird rhythm would receive praise.";
+    let misspelled = || TypingConfig {
+        misspellings: Some(TypoDictionary::builtin()),
+        misspelling_probability: 100,
+        ..careful_config()
+    };
+
+    for seed in 0..10 {
+        let (typist, backend) = type_with(misspelled(), seed, text);
+        assert_eq!(backend.typed(), text, "seed {}", seed);
+        // A word already half typed while noticing the previous mistake is retyped as is
+        assert!(typist.stats().mistakes >= 4);
+    }
+
+    let config = TypingConfig {
+        correction_probability: 0,
+        ..misspelled()
+    };
+    let (_, backend) = type_with(config, 1, text);
+    let typed = backend.typed();
+    assert!(typed.starts_with("I defin"), "{}", typed);
+    assert!(typed.contains(" praise."));
+    assert!(!typed.contains("believe") && !typed.contains("The "));
+}
+
+#[test]
+fn wrong_words_come_from_their_group() {
+    let text = "Their cat knows its way there.";
+    let groups = [vec!["their", "there", "they're"], vec!["its", "it's"]]
+        .map(|group| group.iter().map(|word| word.to_string()).collect());
+    let wrong = || TypingConfig {
+        wrong_words: Some(TypoDictionary::from_groups(&groups)),
+        wrong_word_probability: 100,
+        ..careful_config()
+    };
+
+    let (typist, backend) = type_with(wrong(), 5, text);
+    assert_eq!(backend.typed(), text);
+    assert!(typist.stats().mistakes > 0);
+
+    let config = TypingConfig {
+        correction_probability: 0,
+        ..wrong()
+    };
+    let (_, backend) = type_with(config, 5, text);
+    let typed = backend.typed();
+    assert!(
+        typed.starts_with("There ") || typed.starts_with("They're "),
+        "{}",
+        typed
+    );
+    assert!(typed.contains(" cat knows it's way "), "{}", typed);
+}
+
+#[test]
+fn chat_messages_are_sent_one_by_one() {
+    let config = TypingConfig {
+        chat: Some(ChatConfig {
+            message_delay: Delay::Uniform(60_000..60_001),
+        }),
+        ..careful_config()
+    };
+    let (typist, backend) = type_with(config, 1, "Hi all\nquick one\n\n\nThanks!");
+
+    assert_eq!(backend.typed(), "Hi all\nquick one\nThanks!\n");
+    let actions = backend.actions();
+    let enters: Vec<usize> = (0..actions.len())
+        .filter(|&i| actions[i] == KeyAction::Click { key: Key::Return })
+        .collect();
+    assert_eq!(enters.len(), 3);
+    // Only the newline inside the first message is typed with Shift
+    assert_eq!(actions[enters[0] - 1], KeyAction::Down { key: Key::Shift });
+    assert_ne!(actions[enters[1] - 1], KeyAction::Down { key: Key::Shift });
+    assert!(typist.elapsed().as_secs() >= 60);
+}
+
+#[test]
+fn newlines_are_typed_with_the_newline_key() {
+    let config = TypingConfig {
+        newline_key: Some(NewlineKey::CtrlEnter),
+        ..careful_config()
+    };
+    let (_, backend) = type_with(config, 1, "one\ntwo");
+
+    assert_eq!(backend.typed(), "one\ntwo");
+    let actions = backend.actions();
+    let enter = actions
+        .iter()
+        .position(|action| *action == KeyAction::Click { key: Key::Return })
+        .unwrap();
+    assert_eq!(actions[enter - 1], KeyAction::Down { key: Key::Control });
+    assert_eq!(actions[enter + 1], KeyAction::Up { key: Key::Control });
+}
+
+#[test]
+fn app_profiles_switch_settings_while_typing() {
+    let backend = MockBackend::new();
+    let switch = AppSwitch::new();
+    let mut typist = HumanTypist::builder()
+        .config(sloppy_config())
+        .backend(backend.clone())
+        .app_switch(Arc::clone(&switch))
+        .seed(3)
+        .instant(true)
+        .build();
+    let control_enter = |actions: &[KeyAction]| {
+        let enter = actions
+            .iter()
+            .position(|action| *action == KeyAction::Click { key: Key::Return })
+            .unwrap();
+        actions[enter - 1] == KeyAction::Down { key: Key::Control }
+    };
+
+    switch.switch(Some(AppProfile {
+        newline_key: Some(NewlineKey::CtrlEnter),
+        no_mistakes: true,
+        ..AppProfile::default()
+    }));
+    typist.type_text(TEXT);
+    assert_eq!(backend.typed(), TEXT);
+    assert!(control_enter(&backend.actions()));
+
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .backend(backend.clone())
+        .app_switch(Arc::clone(&switch))
+        .instant(true)
+        .build();
+    switch.switch(None);
+    typist.type_text(TEXT);
+    assert!(!control_enter(&backend.actions()));
+}
+
+#[test]
+fn long_texts_scroll_along_every_few_lines() {
+    let config = TypingConfig {
+        viewport: Some(ViewportConfig {
+            every_lines: 2,
+            scroll_lines: 4,
+            ..ViewportConfig::default()
+        }),
+        ..careful_config()
+    };
+    let (_, backend) = type_with(config, 1, "one\ntwo\nthree\nfour\nfive");
+
+    assert_eq!(backend.typed(), "one\ntwo\nthree\nfour\nfive");
+    let scrolls = backend
+        .actions()
+        .into_iter()
+        .filter(|action| *action == KeyAction::MouseScroll { lines: 4 })
+        .count();
+    assert_eq!(scrolls, 2);
+}
+
+#[test]
+fn work_is_saved_every_few_paragraphs() {
+    let config = TypingConfig {
+        autosave: Some(AutosaveConfig {
+            every_paragraphs: Some(2),
+            ..AutosaveConfig::default()
+        }),
+        ..careful_config()
+    };
+    let text = "First one.\n\nSecond one.\n\n\nThird one.\n\nFourth.";
+    let (_, backend) = type_with(config, 1, text);
+
+    assert_eq!(backend.typed(), text);
+    let saves = backend
+        .actions()
+        .into_iter()
+        .filter(|action| {
+            *action
+                == KeyAction::Click {
+                    key: Key::Char('s'),
+                }
+        })
+        .count();
+    assert_eq!(saves, 1);
+}
+
+#[test]
+fn mishaps_are_taken_back() {
+    let config = TypingConfig {
+        mishaps: Some(MishapConfig {
+            one_in_words: 1,
+            allowed: vec![Mishap::Bold],
+            ..MishapConfig::default()
+        }),
+        ..careful_config()
+    };
+    let (typist, backend) = type_with(config, 1, "one two, three");
+
+    assert_eq!(backend.typed(), "one two, three");
+    let bold = backend
+        .actions()
+        .into_iter()
+        .filter(|action| {
+            *action
+                == KeyAction::Click {
+                    key: Key::Char('b'),
+                }
+        })
+        .count();
+    // Not after the comma, which is not the end of a word
+    assert_eq!(bold, 2);
+    assert_eq!(typist.stats().mishaps, 1);
+}
+
+#[test]
+fn distractions_wiggle_the_mouse_between_words() {
+    let config = TypingConfig {
+        distraction: Some(DistractionConfig {
+            // Planned at the first word break, then due at every one after it
+            per_hour: 1e9,
+            duration: Delay::Uniform(1000..2000),
+            actions: vec![DistractionAction::Wiggle],
+        }),
+        ..careful_config()
+    };
+    let (typist, backend) = type_with(config, 1, "one two three");
+
+    assert_eq!(backend.typed(), "one two three");
+    assert_eq!(typist.stats().distractions, 1);
+    assert!(backend
+        .actions()
+        .iter()
+        .any(|action| matches!(action, KeyAction::MouseNudge { .. })));
+}
+
+#[test]
+fn breaks_come_at_paragraph_ends_after_the_progress() {
+    let config = TypingConfig {
+        breaks: Some(BreakConfig {
+            every: Duration::ZERO,
+            duration: Delay::Uniform(60_000..120_000),
+        }),
+        ..careful_config()
+    };
+    let position = Arc::new(Mutex::new(0));
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let (progress, breaks) = (Arc::clone(&position), Arc::clone(&events));
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(backend.clone())
+        .seed(1)
+        .instant(true)
+        .on_progress(move |p| *progress.lock().unwrap() = p.position)
+        .on_break(move |event| {
+            if matches!(event, BreakEvent::Started(_)) {
+                breaks.lock().unwrap().push(*position.lock().unwrap());
+            }
+        })
+        .build();
+    let text = "One.\n\nTwo.\nMore.\n\nThree.";
+    assert_eq!(typist.type_text(text), TypingOutcome::Completed);
+
+    assert_eq!(backend.typed(), text);
+    assert_eq!(typist.stats().breaks, 2);
+    assert_eq!(*events.lock().unwrap(), vec![5, 17]);
+    assert!(typist.elapsed() >= Duration::from_secs(120));
+}
+
+#[test]
+fn pauses_are_reported_as_they_start() {
+    let pauses = Arc::new(Mutex::new(Vec::new()));
+    let reported = Arc::clone(&pauses);
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .backend(MockBackend::new())
+        .seed(1)
+        .instant(true)
+        .on_pause(move |reason, duration| {
+            reported
+                .lock()
+                .unwrap()
+                .push((reason.to_string(), duration))
+        })
+        .build();
+    let script = Script::parse("Wait for it{{pause:1500}}\nThere.").unwrap();
+    assert_eq!(typist.type_script(&script), TypingOutcome::Completed);
+
+    let pauses = pauses.lock().unwrap();
+    assert!(pauses.contains(&("pause".to_string(), Duration::from_millis(1500))));
+    assert!(pauses.iter().any(|(reason, _)| reason == "thinking pause"));
+}
+
+#[test]
+fn aborting_on_the_last_character_is_not_a_completed_run() {
+    let controls = TypingControls::new();
+    let aborting = Arc::clone(&controls);
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .backend(MockBackend::new())
+        .controls(Arc::clone(&controls))
+        .seed(1)
+        .instant(true)
+        .on_key_event(move |event| {
+            if event.action == (KeyAction::Type { char: '!' }) {
+                aborting.abort();
+            }
+        })
+        .build();
+    assert_eq!(typist.type_text("Hi!"), TypingOutcome::Aborted);
+    assert!(typist.position() < 3);
+}
+
+#[test]
+fn dry_runs_hand_their_output_to_the_callback() {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let output = Arc::clone(&lines);
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .dry_run(true)
+        .seed(1)
+        .on_output(move |line| output.lock().unwrap().push(line.to_string()))
+        .build();
+    let script = Script::parse("Hi{{pause:700}}").unwrap();
+    assert_eq!(typist.type_script(&script), TypingOutcome::Completed);
+
+    let lines = lines.lock().unwrap();
+    assert!(lines.iter().any(|line| line.ends_with("pause 700 ms")));
+    assert!(lines.iter().any(|line| line.contains('H')));
+}
+
+#[test]
+fn paced_typing_ends_within_the_budget() {
+    let text = [TEXT; 8].join("\n\n");
+    let (typist, _) = type_with(TypingConfig::default(), 3, &text);
+    let natural = typist.elapsed();
+
+    for budget in [natural * 2, natural * 2 / 3] {
+        let backend = MockBackend::new();
+        let mut typist = HumanTypist::builder()
+            .backend(backend.clone())
+            .seed(3)
+            .instant(true)
+            .build();
+        typist.finish_in(budget, text.chars().count());
+        assert_eq!(typist.type_text(&text), TypingOutcome::Completed);
+
+        assert_eq!(backend.typed(), text);
+        let elapsed = typist.elapsed().as_secs_f64();
+        let budget = budget.as_secs_f64();
+        assert!(elapsed <= budget * 1.05, "{elapsed} > {budget}");
+        assert!(elapsed >= budget * 0.85, "{elapsed} < {budget}");
+    }
+}
+
+#[test]
+fn warm_up_starts_slow_and_reaches_full_speed() {
+    let log = SharedLog::default();
+    let config = TypingConfig {
+        base_delay: Delay::Uniform(50..60),
+        warm_up: Some(WarmUpConfig {
+            duration: Duration::from_secs(1),
+            slowdown: 1.0,
+            extra_mistakes: 0.0,
+        }),
+        ..careful_config()
+    };
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(MockBackend::new())
+        .keystroke_log(log.clone())
+        .seed(7)
+        .instant(true)
+        .build();
+    typist.type_text(&"abcdefghijklmnopqrstuvwxyz".repeat(2));
+
+    let events = log.events();
+    assert!(events[1].delay_ms >= 95, "{:?}", events[1]);
+    let last = events.last().unwrap();
+    assert!((50..60).contains(&last.delay_ms), "{:?}", last);
+}
+
+#[test]
+fn speed_set_while_typing_scales_the_delays() {
+    let log = SharedLog::default();
+    let controls = TypingControls::new();
+    controls.set_speed(2.0);
+    let config = TypingConfig {
+        base_delay: Delay::Uniform(50..60),
+        ..careful_config()
+    };
+    let mut typist = HumanTypist::builder()
+        .config(config)
+        .backend(MockBackend::new())
+        .keystroke_log(log.clone())
+        .controls(controls)
+        .seed(7)
+        .instant(true)
+        .build();
+    typist.type_text("abcdefghijklmnopqrstuvwxyz");
+
+    for event in &log.events()[1..] {
+        assert!((25..30).contains(&event.delay_ms), "{:?}", event);
+    }
+}
+
+#[test]
+fn sent_keys_are_told_apart_from_the_users() {
+    let controls = TypingControls::new();
+    assert!(!controls.injecting(&[], Duration::from_secs(60)));
+
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .backend(MockBackend::new())
+        .controls(Arc::clone(&controls))
+        .instant(true)
+        .build();
+    typist.type_text("Hi");
+    assert!(controls.injecting(&[], Duration::from_secs(60)));
+    assert!(!controls.injecting(&[], Duration::ZERO));
+
+    controls.note_injected(&KeyAction::Down { key: Key::Shift });
+    assert!(controls.injecting(&[Keycode::LShift], Duration::ZERO));
+    // The user typing while typer holds Shift down
+    assert!(!controls.injecting(&[Keycode::LShift, Keycode::A], Duration::ZERO));
+    assert!(!controls.injecting(&[], Duration::ZERO));
+    controls.note_injected(&KeyAction::Up { key: Key::Shift });
+    assert!(!controls.injecting(&[Keycode::LShift], Duration::ZERO));
+}
+
+#[test]
+fn pauses_are_only_taken_back_by_whoever_made_them() {
+    let controls = TypingControls::new();
+    controls.pause(PauseReason::Takeover);
+    controls.pause(PauseReason::Focus);
+    controls.resume(PauseReason::Takeover);
+    assert!(controls.is_paused());
+    assert!(!controls.is_paused_by(PauseReason::Takeover));
+    controls.resume(PauseReason::Focus);
+    assert!(!controls.is_paused());
+
+    // The user only takes back their own pauses, and pausing is theirs to take back
+    controls.pause(PauseReason::Lock);
+    controls.pause(PauseReason::Step);
+    assert!(!controls.toggle_pause());
+    assert!(controls.is_paused_by(PauseReason::Lock));
+    assert!(!controls.is_paused_by(PauseReason::Step));
+    assert!(controls.toggle_pause());
+    controls.resume(PauseReason::Lock);
+    controls.resume(PauseReason::Takeover);
+    assert!(controls.is_paused_by(PauseReason::User));
+    controls.pause(PauseReason::Focus);
+    controls.set_paused(false);
+    assert!(controls.is_paused_by(PauseReason::Focus));
+    assert!(!controls.is_paused_by(PauseReason::User));
+    controls.resume(PauseReason::Focus);
+    assert!(!controls.is_paused());
+}
+
+#[test]
+fn jumps_skip_ahead_through_the_text() {
+    let text = "One two. Three four.\nFive six\n\nSeven.";
+    let chars: Vec<char> = text.chars().collect();
+    assert_eq!(Jump::Sentence.target(&chars, 2), 8);
+    assert_eq!(Jump::Sentence.target(&chars, 10), 20);
+    assert_eq!(Jump::Paragraph.target(&chars, 2), 29);
+    assert_eq!(Jump::Line(1).target(&chars, 10), 0);
+    assert_eq!(Jump::Line(3).target(&chars, 0), 30);
+    assert_eq!(Jump::Line(9).target(&chars, 0), chars.len());
+
+    let controls = TypingControls::new();
+    controls.request_jump(Jump::Line(2));
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(careful_config())
+        .backend(backend.clone())
+        .controls(controls)
+        .seed(1)
+        .instant(true)
+        .build();
+    assert_eq!(typist.type_text(text), TypingOutcome::Completed);
+    assert_eq!(backend.typed(), "Five six\n\nSeven.");
+}
+
+#[test]
+fn steps_wait_to_be_resumed_after_each_paragraph() {
+    let controls = TypingControls::new();
+    let backend = MockBackend::new();
+    let mut typist = HumanTypist::builder()
+        .config(TypingConfig {
+            step: Some(Step::Paragraph),
+            ..careful_config()
+        })
+        .backend(backend.clone())
+        .controls(controls.clone())
+        .seed(1)
+        .instant(true)
+        .build();
+    let text = "First.\n\nSecond.\n\nThird.\n";
+    let typing = thread::spawn(move || typist.type_text(text));
+
+    // What was on screen at each stop
+    let mut shown = Vec::new();
+    while !typing.is_finished() {
+        if controls.is_paused() {
+            shown.push(backend.typed());
+            controls.set_paused(false);
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+    assert_eq!(typing.join().unwrap(), TypingOutcome::Completed);
+    // Not after the last paragraph, with nothing left to show
+    assert_eq!(shown, ["First.\n", "First.\n\nSecond.\n"]);
+    assert_eq!(backend.typed(), text);
+}
@@ -0,0 +1,115 @@
+use std::net::TcpListener;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::Message;
+use typer::daemon::{DaemonEvent, DaemonStatus, Events, Request, Response};
+
+/// A free local port to listen on.
+fn local_addr() -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().to_string()
+}
+
+fn next_json(socket: &mut tungstenite::WebSocket<impl std::io::Read + std::io::Write>) -> String {
+    loop {
+        if let Message::Text(text) = socket.read().unwrap() {
+            return text;
+        }
+    }
+}
+
+/// A handshake request to `addr` with `headers`.
+fn handshake(
+    addr: &str,
+    headers: &[(&'static str, &str)],
+) -> tungstenite::handshake::client::Request {
+    let mut request = format!("ws://{}/", addr).into_client_request().unwrap();
+    for (name, value) in headers {
+        request.headers_mut().insert(*name, value.parse().unwrap());
+    }
+    request
+}
+
+fn spawn_with_token(origins: Vec<String>) -> String {
+    let addr = local_addr();
+    typer::websocket::spawn(
+        &addr,
+        "s3cret".to_string(),
+        origins,
+        Events::default(),
+        |_| Response::ok(DaemonStatus::default()),
+    )
+    .unwrap();
+    addr
+}
+
+#[test]
+fn streams_events_and_answers_requests() {
+    let addr = local_addr();
+    let events = Events::default();
+    typer::websocket::spawn(
+        &addr,
+        "s3cret".to_string(),
+        Vec::new(),
+        events.clone(),
+        |request| match request {
+            Request::Status => Response::ok(DaemonStatus::default()),
+            _ => Response::error("Not typing"),
+        },
+    )
+    .unwrap();
+
+    let (mut socket, _) =
+        tungstenite::connect(handshake(&addr, &[("Authorization", "Bearer s3cret")])).unwrap();
+    socket
+        .send(Message::text(r#"{"command":"pause"}"#))
+        .unwrap();
+    let response: Response = serde_json::from_str(&next_json(&mut socket)).unwrap();
+    assert_eq!(response.error.as_deref(), Some("Not typing"));
+
+    events.send(DaemonEvent::Progress {
+        position: 3,
+        total: 10,
+    });
+    assert_eq!(
+        next_json(&mut socket),
+        r#"{"event":"progress","position":3,"total":10}"#
+    );
+}
+
+#[test]
+fn refuses_clients_without_the_token() {
+    let addr = spawn_with_token(Vec::new());
+
+    assert!(tungstenite::connect(handshake(&addr, &[])).is_err());
+    assert!(tungstenite::connect(format!("ws://{}/?token=s3cret", addr)).is_err());
+    let wrong = [("Authorization", "Bearer s3cre")];
+    assert!(tungstenite::connect(handshake(&addr, &wrong)).is_err());
+    // Offered without the subprotocol to answer with, browsers would drop the connection
+    let alone = [("Sec-WebSocket-Protocol", "s3cret")];
+    assert!(tungstenite::connect(handshake(&addr, &alone)).is_err());
+}
+
+#[test]
+fn takes_the_token_as_a_subprotocol() {
+    let addr = spawn_with_token(Vec::new());
+
+    let offered = [("Sec-WebSocket-Protocol", "typer, s3cret")];
+    let (_, response) = tungstenite::connect(handshake(&addr, &offered)).unwrap();
+    assert_eq!(response.headers()["Sec-WebSocket-Protocol"], "typer");
+}
+
+#[test]
+fn refuses_web_pages_from_other_origins() {
+    let addr = spawn_with_token(vec!["https://dashboard.example".to_string()]);
+
+    let elsewhere = [
+        ("Authorization", "Bearer s3cret"),
+        ("Origin", "https://evil.example"),
+    ];
+    assert!(tungstenite::connect(handshake(&addr, &elsewhere)).is_err());
+    let allowed = [
+        ("Sec-WebSocket-Protocol", "typer, s3cret"),
+        ("Origin", "https://dashboard.example"),
+    ];
+    assert!(tungstenite::connect(handshake(&addr, &allowed)).is_ok());
+}